@@ -0,0 +1,181 @@
+// Copyright 2018 Chris Pearce
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Micro-benchmarks of mining's hot paths, on generated transactions. Run
+// with cargo bench; pass a name such as fptree to run only those.
+
+extern crate arm;
+#[macro_use]
+extern crate criterion;
+extern crate rand;
+
+use arm::counter::{Counter, Item, ItemCounter, ItemId};
+use arm::internals::{
+    build_initial_fptree, fp_growth, fp_growth_parameters, generate_rules, rule_parameters,
+    split_out, union, FPTree, Index, TransactionIndex, TreeBuildOptions,
+};
+use criterion::{black_box, Criterion};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+const NUM_TRANSACTIONS: usize = 5000;
+const NUM_ITEMS: ItemId = 200;
+const MIN_COUNT: u32 = 250;
+
+// Transactions in which lower numbered items are more frequent, like the
+// long-tailed item frequencies of real data sets. Items are sorted by id.
+fn transactions() -> Vec<Vec<Item>> {
+    let mut rng = StdRng::seed_from_u64(1);
+    (0..NUM_TRANSACTIONS)
+        .map(|_| {
+            (1..NUM_ITEMS + 1)
+                .filter(|&id| rng.gen_bool(0.6 / (1.0 + id as f64 / 8.0)))
+                .map(Item::with_id)
+                .collect()
+        })
+        .collect()
+}
+
+fn item_count(transactions: &[Vec<Item>]) -> ItemCounter {
+    let mut item_count = ItemCounter::new();
+    for transaction in transactions {
+        for item in transaction {
+            item_count.add(item, 1);
+        }
+    }
+    item_count
+}
+
+// The transactions' frequent items, in descending order of frequency, as
+// they're inserted into an FPTree.
+fn tree_transactions(transactions: &[Vec<Item>]) -> Vec<Vec<Item>> {
+    let item_count = item_count(transactions);
+    transactions
+        .iter()
+        .map(|transaction| {
+            let mut transaction: Vec<Item> = transaction
+                .iter()
+                .cloned()
+                .filter(|item| item_count.get(item) >= MIN_COUNT)
+                .collect();
+            item_count.sort_descending(&mut transaction);
+            transaction
+        })
+        .collect()
+}
+
+fn build_tree(transactions: &[Vec<Item>]) -> FPTree {
+    let mut fptree = FPTree::new();
+    for transaction in transactions {
+        fptree.insert(transaction, 1);
+    }
+    fptree
+}
+
+fn bench_vec_sets(c: &mut Criterion) {
+    let evens: Vec<Item> = (0..100).map(|id| Item::with_id(id * 2)).collect();
+    let odds: Vec<Item> = (0..100).map(|id| Item::with_id(id * 2 + 1)).collect();
+    c.bench_function("vec_sets/union", |b| {
+        b.iter(|| union(black_box(&evens), black_box(&odds)))
+    });
+    let every_third: Vec<Item> = evens.iter().cloned().step_by(3).collect();
+    c.bench_function("vec_sets/split_out", |b| {
+        b.iter(|| split_out(black_box(&evens), black_box(&every_third)))
+    });
+}
+
+fn bench_index_count(c: &mut Criterion) {
+    let transactions = transactions();
+    let mut index = Index::new();
+    for transaction in &transactions {
+        index.insert(transaction, 1);
+    }
+    index.finish();
+    // Pairs of frequent items, whose tid-lists become bitmaps, and of
+    // infrequent items, which keep their tid-lists.
+    let frequent: Vec<Item> = vec![Item::with_id(1), Item::with_id(2), Item::with_id(3)];
+    let infrequent: Vec<Item> = vec![Item::with_id(150), Item::with_id(190)];
+    c.bench_function("index/count_frequent", |b| {
+        b.iter(|| index.count(black_box(&frequent)))
+    });
+    c.bench_function("index/count_infrequent", |b| {
+        b.iter(|| index.count(black_box(&infrequent)))
+    });
+}
+
+fn bench_fptree(c: &mut Criterion) {
+    let raw_transactions = transactions();
+    let transactions = tree_transactions(&raw_transactions);
+    c.bench_function("fptree/insert", |b| {
+        b.iter(|| build_tree(black_box(&transactions)))
+    });
+    // The whole build phase, from the transactions as they're read: filtering
+    // out infrequent items, sorting, and inserting, serially and from
+    // partial trees built in parallel.
+    let item_count = item_count(&raw_transactions);
+    for &parallel in &[false, true] {
+        let options = TreeBuildOptions {
+            parallel,
+            aggregate_duplicates: false,
+            item_order: None,
+        };
+        let name = if parallel {
+            "fptree/build_initial_parallel"
+        } else {
+            "fptree/build_initial"
+        };
+        c.bench_function(name, |b| {
+            b.iter(|| {
+                build_initial_fptree(
+                    raw_transactions.iter().map(|t| Ok((t.clone(), 1))),
+                    &item_count,
+                    MIN_COUNT - 1,
+                    &options,
+                    None,
+                )
+                .unwrap()
+            })
+        });
+    }
+    let fptree = build_tree(&transactions);
+    let item = Item::with_id(4);
+    c.bench_function("fptree/conditional_tree", |b| {
+        b.iter(|| fptree.construct_conditional_tree(black_box(item), MIN_COUNT))
+    });
+}
+
+fn bench_generate_rules(c: &mut Criterion) {
+    let transactions = tree_transactions(&transactions());
+    let fptree = build_tree(&transactions);
+    let num_transactions = NUM_TRANSACTIONS as u32;
+    let itemsets = fp_growth(
+        &fptree,
+        &fp_growth_parameters(MIN_COUNT),
+        &[],
+        num_transactions,
+    );
+    let params = rule_parameters(0.3);
+    c.bench_function("rules/generate", |b| {
+        b.iter(|| generate_rules(black_box(&itemsets), num_transactions, &params))
+    });
+}
+
+criterion_group!(
+    benches,
+    bench_vec_sets,
+    bench_index_count,
+    bench_fptree,
+    bench_generate_rules
+);
+criterion_main!(benches);
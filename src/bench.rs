@@ -0,0 +1,188 @@
+// Copyright 2018 Chris Pearce
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use cancellation::CancellationToken;
+use command_line_args::BenchArguments;
+use error::ArmError;
+use fptree::{fp_growth, FPGrowthParameters, ItemSet};
+use generate_rules::{generate_rules, RuleParameters};
+use index::{Index, TransactionIndex};
+use itemizer::Itemizer;
+use memory::{peak_rss_bytes, reset_peak_rss};
+use mining::{
+    build_initial_fptree, count_item_frequencies, duration_as_ms, min_count, TreeBuildOptions,
+};
+use rule::DEFAULT_LAPLACE_K;
+use std::time::Instant;
+use transaction_reader::TransactionReader;
+
+struct BenchResult {
+    first_pass_ms: u64,
+    build_tree_ms: u64,
+    fp_growth_ms: u64,
+    generate_rules_ms: u64,
+    total_ms: u64,
+    peak_rss: Option<u64>,
+    num_itemsets: usize,
+    num_rules: usize,
+    // Time to recount all itemsets using scalar and SIMD intersection.
+    recount_ms: Option<(u64, u64)>,
+}
+
+// Recounts the support of every itemset, and returns how long it took.
+fn time_recount(index: &Index, itemsets: &[ItemSet]) -> u64 {
+    let timer = Instant::now();
+    for itemset in itemsets {
+        assert_eq!(index.count(&itemset.items), itemset.count as usize);
+    }
+    duration_as_ms(&timer.elapsed())
+}
+
+// Runs the full mining pipeline, minus writing the output, and records
+// how long each phase took.
+fn bench_one(path: &str, min_support: f64, args: &BenchArguments) -> Result<BenchResult, ArmError> {
+    reset_peak_rss();
+    let start = Instant::now();
+
+    let timer = Instant::now();
+    let mut itemizer: Itemizer = Itemizer::new();
+    let (mut item_count, num_transactions) =
+        count_item_frequencies(TransactionReader::new(path, &mut itemizer)?)?;
+    itemizer.reorder_sorted(&mut item_count);
+    let first_pass_ms = duration_as_ms(&timer.elapsed());
+
+    let timer = Instant::now();
+    let min_count = min_count(min_support, num_transactions);
+    let mut index = if args.recount {
+        Some(Index::new())
+    } else {
+        None
+    };
+    let fptree = build_initial_fptree(
+        TransactionReader::new(path, &mut itemizer)?,
+        &item_count,
+        min_count,
+        &TreeBuildOptions {
+            parallel: args.parallel_tree_build,
+            aggregate_duplicates: args.aggregate_duplicates,
+            item_order: None,
+        },
+        index
+            .as_mut()
+            .map(|index| index as &mut dyn TransactionIndex),
+    )?;
+    let build_tree_ms = duration_as_ms(&timer.elapsed());
+
+    let timer = Instant::now();
+    let params = FPGrowthParameters {
+        min_count,
+        max_itemset_size: None,
+        cancellation: CancellationToken::new(),
+        cross_support: None,
+        item_min_counts: None,
+    };
+    let itemsets = fp_growth(&fptree, &params, &[], num_transactions as u32);
+    let fp_growth_ms = duration_as_ms(&timer.elapsed());
+
+    let timer = Instant::now();
+    let rules = generate_rules(
+        &itemsets,
+        num_transactions as u32,
+        &RuleParameters {
+            min_confidence: args.min_confidence,
+            min_confidence_by_length: None,
+            min_lift: args.min_lift,
+            max_lift: None,
+            min_confidence_lower_bound: None,
+            laplace_k: DEFAULT_LAPLACE_K,
+            min_laplace: None,
+            min_added_value: None,
+            min_gini_gain: None,
+            min_info_gain: None,
+            min_odds_ratio: None,
+            min_yules_q: None,
+            min_phi: None,
+            filter: None,
+            min_itemset_size: 1,
+            cancellation: CancellationToken::new(),
+            item_min_counts: None,
+            target_consequent: None,
+        },
+    );
+    let generate_rules_ms = duration_as_ms(&timer.elapsed());
+
+    let recount_ms = index.map(|mut index| {
+        index.set_simd(false);
+        let scalar_ms = time_recount(&index, &itemsets);
+        index.set_simd(true);
+        (scalar_ms, time_recount(&index, &itemsets))
+    });
+
+    Ok(BenchResult {
+        first_pass_ms,
+        build_tree_ms,
+        fp_growth_ms,
+        generate_rules_ms,
+        total_ms: duration_as_ms(&start.elapsed()),
+        peak_rss: peak_rss_bytes(),
+        num_itemsets: itemsets.len(),
+        num_rules: rules.iter().map(|x| x.len()).sum(),
+        recount_ms,
+    })
+}
+
+pub fn run_benchmarks(args: &BenchArguments) -> Result<(), ArmError> {
+    println!(
+        "{:<30} {:>9} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10}",
+        "Dataset",
+        "Support",
+        "Pass1 ms",
+        "Tree ms",
+        "FPG ms",
+        "Rules ms",
+        "Total ms",
+        "Peak MB",
+        "Itemsets",
+        "Rules"
+    );
+    for path in &args.dataset_paths {
+        for &min_support in &args.min_supports {
+            let result = bench_one(path, min_support, args)?;
+            let peak_mb = match result.peak_rss {
+                Some(bytes) => format!("{:.1}", bytes as f64 / 1_000_000.0),
+                None => String::from("?"),
+            };
+            println!(
+                "{:<30} {:>9} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10}",
+                path,
+                min_support,
+                result.first_pass_ms,
+                result.build_tree_ms,
+                result.fp_growth_ms,
+                result.generate_rules_ms,
+                result.total_ms,
+                peak_mb,
+                result.num_itemsets,
+                result.num_rules
+            );
+            if let Some((scalar_ms, simd_ms)) = result.recount_ms {
+                println!(
+                    "    Recounted {} itemsets in {} ms with scalar, {} ms with SIMD intersection.",
+                    result.num_itemsets, scalar_ms, simd_ms
+                );
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,90 @@
+// Copyright 2018 Chris Pearce
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+// Shared flag used to abort a long running mine. Clones share the same flag,
+// so a token can be handed to the miner and cancelled from another thread.
+// Cancelled work returns whatever results were found before the token was
+// checked, so callers should treat results as partial if is_cancelled().
+#[derive(Clone, Debug)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+    // Number of results counted, and the number above which the token is
+    // cancelled.
+    count: Arc<AtomicUsize>,
+    max_count: Arc<AtomicUsize>,
+    count_exceeded: Arc<AtomicBool>,
+}
+
+impl Default for CancellationToken {
+    fn default() -> CancellationToken {
+        CancellationToken {
+            cancelled: Arc::new(AtomicBool::new(false)),
+            count: Arc::new(AtomicUsize::new(0)),
+            max_count: Arc::new(AtomicUsize::new(usize::MAX)),
+            count_exceeded: Arc::new(AtomicBool::new(false)),
+        }
+    }
+}
+
+impl CancellationToken {
+    pub fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    // Cancels the token once timeout has elapsed, from a background thread.
+    pub fn cancel_after(&self, timeout: Duration) {
+        let token = self.clone();
+        thread::spawn(move || {
+            thread::sleep(timeout);
+            token.cancel();
+        });
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    // Cancels the token once more than max_count results have been counted
+    // by add_to_count, to bound the memory used by results.
+    pub fn cancel_above_count(&self, max_count: usize) {
+        self.max_count.store(max_count, Ordering::Relaxed);
+    }
+
+    // Counts results found by the cancellable work. Without a maximum count,
+    // nothing is counted, so that threads don't contend for the count.
+    pub fn add_to_count(&self, n: usize) {
+        let max_count = self.max_count.load(Ordering::Relaxed);
+        if max_count == usize::MAX {
+            return;
+        }
+        if self.count.fetch_add(n, Ordering::Relaxed) + n > max_count {
+            self.count_exceeded.store(true, Ordering::Relaxed);
+            self.cancel();
+        }
+    }
+
+    // Whether the token was cancelled for exceeding its maximum count.
+    pub fn is_count_exceeded(&self) -> bool {
+        self.count_exceeded.load(Ordering::Relaxed)
+    }
+}
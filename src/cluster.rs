@@ -0,0 +1,210 @@
+// Copyright 2018 Chris Pearce
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use command_line_args::ClusterArguments;
+use error::ArmError;
+use fnv::FnvHashMap;
+use output::{OutputFile, RULES_HEADER};
+use rule_file::{read_rules, RuleRecord};
+use std::cmp::Ordering;
+use std::io::Write;
+
+pub struct Clustering {
+    // Index of the representative rule of each cluster, in order of the
+    // representatives' confidence, lift and support.
+    pub representatives: Vec<usize>,
+    // Cluster of each rule, and its similarity to the cluster's
+    // representative, indexed by rule.
+    pub membership: Vec<(usize, f64)>,
+}
+
+fn jaccard(shared: usize, a: usize, b: usize) -> f64 {
+    shared as f64 / (a + b - shared) as f64
+}
+
+// Clusters rules by the Jaccard similarity of their items, antecedent and
+// consequent together. Rules are visited in descending order of confidence,
+// lift and support; each joins the most similar existing cluster whose
+// representative has similarity at least min_similarity to it, or else
+// becomes the representative of a new cluster. So each representative is
+// the strongest rule in its cluster.
+pub fn cluster_rules(rules: &[RuleRecord], min_similarity: f64) -> Clustering {
+    let mut ids: FnvHashMap<&str, usize> = FnvHashMap::default();
+    let itemsets: Vec<Vec<usize>> = rules
+        .iter()
+        .map(|rule| {
+            let mut items: Vec<usize> = rule
+                .antecedent
+                .iter()
+                .chain(rule.consequent.iter())
+                .map(|item| {
+                    let next_id = ids.len();
+                    *ids.entry(item.as_str()).or_insert(next_id)
+                })
+                .collect();
+            items.sort_unstable();
+            items.dedup();
+            items
+        })
+        .collect();
+
+    let mut order: Vec<usize> = (0..rules.len()).collect();
+    order.sort_by(|&a, &b| {
+        let (a, b) = (&rules[a], &rules[b]);
+        b.confidence
+            .partial_cmp(&a.confidence)
+            .unwrap_or(Ordering::Equal)
+            .then(b.lift.partial_cmp(&a.lift).unwrap_or(Ordering::Equal))
+            .then(b.support.partial_cmp(&a.support).unwrap_or(Ordering::Equal))
+    });
+
+    // Clusters whose representative contains each item, so only clusters
+    // sharing an item with a rule are compared with it.
+    let mut clusters_with_item: Vec<Vec<usize>> = vec![vec![]; ids.len()];
+    let mut representatives: Vec<usize> = vec![];
+    let mut membership: Vec<(usize, f64)> = vec![(0, 0.0); rules.len()];
+    let mut shared: FnvHashMap<usize, usize> = FnvHashMap::default();
+    for rule in order {
+        let items = &itemsets[rule];
+        shared.clear();
+        for &item in items {
+            for &cluster in &clusters_with_item[item] {
+                *shared.entry(cluster).or_insert(0) += 1;
+            }
+        }
+        // Ties go to the earlier, stronger cluster.
+        let best = shared
+            .iter()
+            .map(|(&cluster, &count)| {
+                let representative = &itemsets[representatives[cluster]];
+                let similarity = jaccard(count, items.len(), representative.len());
+                (cluster, similarity)
+            })
+            .filter(|&(_, similarity)| similarity >= min_similarity)
+            .max_by(|a, b| {
+                a.1.partial_cmp(&b.1)
+                    .unwrap_or(Ordering::Equal)
+                    .then(b.0.cmp(&a.0))
+            });
+        membership[rule] = match best {
+            Some(member) => member,
+            None => {
+                let cluster = representatives.len();
+                representatives.push(rule);
+                for &item in items {
+                    clusters_with_item[item].push(cluster);
+                }
+                (cluster, 1.0)
+            }
+        };
+    }
+    Clustering {
+        representatives,
+        membership,
+    }
+}
+
+// The "antecedent => consequent" field of a rule's line.
+fn rule_name(rule: &RuleRecord) -> &str {
+    rule.line.split(',').next().unwrap_or("")
+}
+
+pub fn run_cluster(args: &ClusterArguments) -> Result<(), ArmError> {
+    let rules = read_rules(&args.rules_path)?;
+    let clustering = cluster_rules(&rules, args.min_similarity);
+    println!(
+        "Clustered {} rules into {} clusters.",
+        rules.len(),
+        clustering.representatives.len()
+    );
+
+    let path = &args.output_path;
+    let write = || -> std::io::Result<()> {
+        let mut output = OutputFile::create(path, None)?;
+        writeln!(output, "{}", RULES_HEADER)?;
+        for &rule in &clustering.representatives {
+            writeln!(output, "{}", rules[rule].line)?;
+        }
+        output.finish()
+    };
+    write().map_err(|e| ArmError::io(path, e))?;
+    println!("Wrote representative rules to {}.", path);
+
+    if let Some(ref path) = args.membership_path {
+        let write = || -> std::io::Result<()> {
+            let mut output = OutputFile::create(path, None)?;
+            writeln!(
+                output,
+                "Cluster,Representative,Antecedent => Consequent,Similarity"
+            )?;
+            for (rule, &(cluster, similarity)) in rules.iter().zip(clustering.membership.iter()) {
+                let representative = &rules[clustering.representatives[cluster]];
+                writeln!(
+                    output,
+                    "{},{},{},{}",
+                    cluster,
+                    rule_name(representative),
+                    rule_name(rule),
+                    similarity
+                )?;
+            }
+            output.finish()
+        };
+        write().map_err(|e| ArmError::io(path, e))?;
+        println!("Wrote cluster membership to {}.", path);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::cluster_rules;
+    use rule_file::RuleRecord;
+
+    fn rule(antecedent: &[&str], consequent: &[&str], confidence: f64) -> RuleRecord {
+        let items = |items: &[&str]| items.iter().map(|&s| String::from(s)).collect();
+        RuleRecord {
+            antecedent: items(antecedent),
+            consequent: items(consequent),
+            confidence,
+            lift: 1.0,
+            support: 0.1,
+            line: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_cluster_rules() {
+        let rules = vec![
+            rule(&["a", "b"], &["c"], 0.5),
+            rule(&["a"], &["b", "c"], 0.9),
+            rule(&["a", "b", "c"], &["d"], 0.7),
+            rule(&["x"], &["y"], 0.6),
+            rule(&["b"], &["c"], 0.8),
+        ];
+        // Rule 1 is the strongest, so represents rules 0 and 2, with
+        // similarities 1 and 3/4. Rule 4 has {b, c}, similarity 2/3 to rule
+        // 1, so is in its own cluster at 0.7.
+        let clustering = cluster_rules(&rules, 0.7);
+        assert_eq!(clustering.representatives, vec![1, 4, 3]);
+        assert_eq!(
+            clustering.membership,
+            vec![(0, 1.0), (0, 1.0), (0, 0.75), (2, 1.0), (1, 1.0)]
+        );
+
+        let clustering = cluster_rules(&rules, 0.5);
+        assert_eq!(clustering.representatives, vec![1, 3]);
+        assert_eq!(clustering.membership[4], (0, 2.0 / 3.0));
+    }
+}
@@ -16,54 +16,2977 @@ use std::env;
 use std::io;
 use std::process;
 
-use argparse::{ArgumentParser, Store, StoreOption};
+use argparse::{ArgumentParser, Collect, List, Store, StoreOption, StoreTrue};
+use convert::TransactionFormat;
+use covering::CoverageTarget;
+use diff::DiffFormat;
+use filter_expression::FilterExpression;
+use generate_rules::ConfidenceByLength;
+use index::IndexBackend;
+use input::{is_postgres, is_stream};
+use itemizer::UnseenItems;
+use mining::{Algorithm, ItemOrder};
+use output::{Compression, Notation};
+use preview::ColorChoice;
+use rule::DEFAULT_LAPLACE_K;
+use rules_from_itemsets::ItemsetsFormat;
+use table::TableOptions;
+use transaction_reader::{Encoding, InputFormat};
+use trends::Window;
 
 pub struct Arguments {
     pub input_file_path: String,
+    pub query: Option<String>,
     pub output_rules_path: String,
+    pub output_itemsets_path: Option<String>,
+    pub output_lattice_path: Option<String>,
+    pub output_generators_path: Option<String>,
+    pub dump_tree_path: Option<String>,
+    pub flame_stacks_path: Option<String>,
+    pub html_report_path: Option<String>,
+    pub preview: Option<usize>,
+    pub preview_color: ColorChoice,
+    pub manifest: bool,
+    pub compress_output: Option<Compression>,
+    pub number_format: Notation,
+    pub precision: Option<usize>,
+    pub item_map_path: Option<String>,
+    pub dictionary_path: Option<String>,
+    pub unseen_items: UnseenItems,
+    pub output_dictionary_path: Option<String>,
+    pub weighted: bool,
+    pub input_format: InputFormat,
+    pub table_options: TableOptions,
+    pub encoding: Encoding,
+    pub in_memory: bool,
+    pub approximate_counts: bool,
+    pub algorithm: Algorithm,
+    pub sample_size: Option<usize>,
+    pub sample_seed: u64,
+    pub item_order: ItemOrder,
     pub min_support: f64,
     pub min_confidence: f64,
+    pub min_confidence_by_length: Option<ConfidenceByLength>,
     pub min_lift: Option<f64>,
+    pub max_lift: Option<f64>,
+    pub min_confidence_lower_bound: Option<f64>,
+    pub laplace_k: f64,
+    pub min_laplace: Option<f64>,
+    pub min_added_value: Option<f64>,
+    pub min_gini_gain: Option<f64>,
+    pub min_info_gain: Option<f64>,
+    pub min_odds_ratio: Option<f64>,
+    pub min_yules_q: Option<f64>,
+    pub min_phi: Option<f64>,
+    pub filter: Option<FilterExpression>,
+    pub min_itemset_size: usize,
+    pub max_itemset_size: Option<usize>,
+    pub parallel_tree_build: bool,
+    pub aggregate_duplicates: bool,
+    pub deterministic_order: bool,
+    pub top_per_consequent: Option<usize>,
+    pub target_consequent: Option<String>,
+    pub target_rules: Option<usize>,
+    pub select_covering: Option<CoverageTarget>,
+    pub no_rules: bool,
+    pub permutations: Option<usize>,
+    pub permutation_seed: u64,
+    pub verify: bool,
+    pub verify_sample: Option<usize>,
+    pub coverage_report_path: Option<String>,
+    pub self_check: bool,
+    pub index_backend: IndexBackend,
+    pub timeout: Option<u64>,
+    pub max_itemsets: Option<usize>,
+    pub max_rules: Option<usize>,
+    pub write_partial: bool,
+    pub pfp_groups: Option<usize>,
+    pub min_all_confidence: Option<f64>,
+    pub max_support_ratio: Option<f64>,
+    pub item_min_support_path: Option<String>,
+    pub baseline_input_path: Option<String>,
+    pub output_contrasts_path: Option<String>,
+    pub min_growth_rate: f64,
+    pub max_p_value: f64,
+    pub suppress_below_count: Option<u32>,
+    pub dp_epsilon: Option<f64>,
+    pub dp_max_length: Option<usize>,
 }
 
-pub fn parse_args_or_exit() -> Arguments {
+pub struct BenchArguments {
+    pub dataset_paths: Vec<String>,
+    pub min_supports: Vec<f64>,
+    pub min_confidence: f64,
+    pub min_lift: Option<f64>,
+    pub parallel_tree_build: bool,
+    pub aggregate_duplicates: bool,
+    pub recount: bool,
+}
+
+pub struct ThresholdsArguments {
+    pub input_file_path: String,
+    pub weighted: bool,
+    pub input_format: InputFormat,
+    pub encoding: Encoding,
+    pub sample_size: usize,
+    pub seed: u64,
+    pub min_supports: Vec<f64>,
+    pub min_confidence: f64,
+    pub min_lift: Option<f64>,
+    pub max_itemset_size: Option<usize>,
+}
+
+pub struct RulesArguments {
+    pub itemsets_path: String,
+    pub itemsets_format: ItemsetsFormat,
+    pub input_file_path: Option<String>,
+    pub weighted: bool,
+    pub num_transactions: Option<usize>,
+    pub output_rules_path: String,
+    pub min_confidence: f64,
+    pub min_confidence_by_length: Option<ConfidenceByLength>,
+    pub min_lift: Option<f64>,
+    pub max_lift: Option<f64>,
+}
+
+pub struct StatsArguments {
+    pub input_file_path: String,
+    pub weighted: bool,
+    pub input_format: InputFormat,
+    pub encoding: Encoding,
+    pub top_items: usize,
+    pub compare_item_orders: bool,
+}
+
+pub struct SupportArguments {
+    pub input_file_path: String,
+    pub weighted: bool,
+    pub input_format: InputFormat,
+    pub encoding: Encoding,
+    pub itemsets_path: String,
+    pub output_path: String,
+    pub index_backend: IndexBackend,
+}
+
+pub struct RevalidateArguments {
+    pub rules_path: String,
+    pub input_file_path: String,
+    pub weighted: bool,
+    pub input_format: InputFormat,
+    pub encoding: Encoding,
+    pub output_path: String,
+    pub tolerance: f64,
+    pub index_backend: IndexBackend,
+}
+
+pub struct DiffArguments {
+    pub old_rules_path: String,
+    pub new_rules_path: String,
+    pub output_path: String,
+    pub format: DiffFormat,
+    pub tolerance: f64,
+}
+
+pub struct MergeArguments {
+    pub itemsets_paths: Vec<String>,
+    pub input_file_path: String,
+    pub weighted: bool,
+    pub input_format: InputFormat,
+    pub encoding: Encoding,
+    pub output_rules_path: String,
+    pub output_itemsets_path: Option<String>,
+    pub min_support: f64,
+    pub min_confidence: f64,
+    pub min_lift: Option<f64>,
+    pub max_lift: Option<f64>,
+    pub index_backend: IndexBackend,
+}
+
+pub struct SegmentsArguments {
+    pub input_file_path: String,
+    pub segment_column: usize,
+    pub output_rules_path: String,
+    pub comparison_path: Option<String>,
+    pub min_support: f64,
+    pub min_confidence: f64,
+    pub min_lift: Option<f64>,
+    pub max_lift: Option<f64>,
+    pub max_itemset_size: Option<usize>,
+}
+
+pub struct TrendsArguments {
+    pub input_file_path: String,
+    pub timestamp_column: usize,
+    pub window: Window,
+    pub output_path: String,
+    pub min_support: f64,
+    pub min_confidence: f64,
+    pub min_lift: Option<f64>,
+    pub max_itemset_size: Option<usize>,
+    pub trend_threshold: f64,
+}
+
+pub struct QueryArguments {
+    pub rules_path: String,
+    pub items: Vec<String>,
+    pub side: String,
+    pub min_confidence: f64,
+    pub min_lift: f64,
+    pub sort_by: String,
+    pub limit: Option<usize>,
+}
+
+pub struct RecommendArguments {
+    pub rules_path: String,
+    pub basket: String,
+    pub top: usize,
+}
+
+pub struct EvaluateArguments {
+    pub rules_path: String,
+    pub input_file_path: String,
+    pub input_format: InputFormat,
+    pub encoding: Encoding,
+    pub top: usize,
+    pub seed: u64,
+}
+
+pub struct ScoreArguments {
+    pub rules_path: String,
+    pub input_file_path: String,
+    pub input_format: InputFormat,
+    pub encoding: Encoding,
+    pub output_path: String,
+    pub min_confidence: f64,
+    pub min_lift: Option<f64>,
+    pub top: usize,
+}
+
+pub struct ClusterArguments {
+    pub rules_path: String,
+    pub output_path: String,
+    pub membership_path: Option<String>,
+    pub min_similarity: f64,
+}
+
+pub struct TuiArguments {
+    pub rules_path: Option<String>,
+    pub itemsets_path: Option<String>,
+    pub itemsets_format: ItemsetsFormat,
+}
+
+pub struct ConvertArguments {
+    pub input_file_path: String,
+    pub input_format: InputFormat,
+    pub weighted: bool,
+    pub encoding: Encoding,
+    pub output_path: String,
+    pub output_format: TransactionFormat,
+}
+
+pub struct GenArguments {
+    pub output_path: String,
+    pub num_transactions: usize,
+    pub num_items: usize,
+    pub avg_length: usize,
+    pub seed: u64,
+}
+
+pub enum Command {
+    Mine(Box<Arguments>),
+    Bench(BenchArguments),
+    Thresholds(ThresholdsArguments),
+    Rules(RulesArguments),
+    Stats(StatsArguments),
+    Support(SupportArguments),
+    Revalidate(RevalidateArguments),
+    Diff(DiffArguments),
+    Merge(MergeArguments),
+    Segments(SegmentsArguments),
+    Trends(TrendsArguments),
+    Query(QueryArguments),
+    Recommend(RecommendArguments),
+    Evaluate(EvaluateArguments),
+    Score(ScoreArguments),
+    Cluster(ClusterArguments),
+    Tui(TuiArguments),
+    Gen(GenArguments),
+    Convert(ConvertArguments),
+}
+
+const COMMANDS_HELP: &str = "Commands:
+    mine       Mine frequent itemsets and association rules from a dataset.
+               This is the default if no command is given.
+    rules      Generate rules from a previously written itemsets file.
+    stats      Print summary statistics of a dataset.
+    support    Count the exact support of given itemsets in a dataset.
+    revalidate Recompute the metrics of a rules file against a dataset.
+    diff       Compare two rules files.
+    merge      Merge itemsets mined from partitions of a dataset.
+    segments   Mine rules separately for each segment of a dataset.
+    trends     Mine rules in time windows of a dataset, and report trends.
+    query      Print rules from a rules file which contain given items.
+    recommend  Recommend items to add to a basket, using a rules file.
+    evaluate   Evaluate a rules file as a recommender on test baskets.
+    score      Score transactions as anomalous by the rules they violate.
+    cluster    Cluster similar rules in a rules file.
+    tui        Explore a rules or itemsets file in an interactive terminal UI.
+    gen        Generate a synthetic dataset.
+    convert    Convert a dataset between transaction formats.
+    bench      Benchmark mining over datasets and support thresholds.
+    thresholds Mine a sample of a dataset at several supports, to choose one.
+
+Run `arm <command> --help` for a command's options.";
+
+const INPUT_FORMAT_HELP: &str = "Format of the input: csv, in which items may be \
+    quoted with double quotes so they can contain commas; plain, which splits \
+    lines on commas without quoting; table, a CSV file with a header row whose \
+    rows are converted into column=value items; onehot, a CSV file with a \
+    header row of items and a 0 or 1 cell per item in each row; spmf, space \
+    separated integer items as read by SPMF; jsonl, a JSON array of items \
+    per line; or counted, csv lines ending in a colon and the number of times \
+    the transaction occurred, e.g. a,b,c:42. Default csv.";
+
+const ENCODING_HELP: &str = "Character encoding of the input: utf8, utf8-lossy \
+    (replace invalid UTF-8 sequences) or latin1. Default utf8.";
+
+// Exits if --weighted is passed with an input format which can't carry
+// weights at the start of each line.
+fn check_weighted_input_or_exit(input_format: InputFormat, weighted: bool) {
+    if input_format.has_header() && weighted {
+        eprintln!("--weighted can't be used with input formats with a header row");
+        process::exit(1);
+    }
+    if input_format == InputFormat::Counted && weighted {
+        eprintln!("--weighted can't be used with counted input, whose lines end with their counts");
+        process::exit(1);
+    }
+}
+
+pub fn parse_args_or_exit() -> Command {
+    let args: Vec<String> = env::args().collect();
+    if args.len() == 1 || args[1] == "help" {
+        eprintln!(
+            "Light weight parallel FPGrowth in Rust.\n\n{}",
+            COMMANDS_HELP
+        );
+        process::exit(1);
+    }
+    let rest = &args[2..];
+    match args[1].as_str() {
+        "mine" => Command::Mine(Box::new(parse_mine_args_or_exit("arm mine", rest))),
+        "bench" => Command::Bench(parse_bench_args_or_exit(rest)),
+        "thresholds" => Command::Thresholds(parse_thresholds_args_or_exit(rest)),
+        "rules" => Command::Rules(parse_rules_args_or_exit(rest)),
+        "stats" => Command::Stats(parse_stats_args_or_exit(rest)),
+        "support" => Command::Support(parse_support_args_or_exit(rest)),
+        "revalidate" => Command::Revalidate(parse_revalidate_args_or_exit(rest)),
+        "diff" => Command::Diff(parse_diff_args_or_exit(rest)),
+        "merge" => Command::Merge(parse_merge_args_or_exit(rest)),
+        "segments" => Command::Segments(parse_segments_args_or_exit(rest)),
+        "trends" => Command::Trends(parse_trends_args_or_exit(rest)),
+        "query" => Command::Query(parse_query_args_or_exit(rest)),
+        "recommend" => Command::Recommend(parse_recommend_args_or_exit(rest)),
+        "evaluate" => Command::Evaluate(parse_evaluate_args_or_exit(rest)),
+        "score" => Command::Score(parse_score_args_or_exit(rest)),
+        "cluster" => Command::Cluster(parse_cluster_args_or_exit(rest)),
+        "tui" => Command::Tui(parse_tui_args_or_exit(rest)),
+        "gen" => Command::Gen(parse_gen_args_or_exit(rest)),
+        "convert" => Command::Convert(parse_convert_args_or_exit(rest)),
+        // For compatibility, flags without a command mean mine.
+        _ => Command::Mine(Box::new(parse_mine_args_or_exit("arm", &args[1..]))),
+    }
+}
+
+// Parses arguments for a command, exiting on error or after printing help.
+fn parse_or_exit(parser: &ArgumentParser, program: &str, arguments: &[String]) {
+    let mut argv = vec![String::from(program)];
+    argv.extend_from_slice(arguments);
+    if let Err(err) = parser.parse(argv, &mut io::stdout(), &mut io::stderr()) {
+        process::exit(err);
+    }
+}
+
+fn check_min_confidence_or_exit(min_confidence: f64) {
+    if !(0.0..=1.0).contains(&min_confidence) {
+        eprintln!("Minimum rule confidence threshold must be in range [0,1]");
+        process::exit(1);
+    }
+}
+
+fn check_min_lift_or_exit(min_lift: Option<f64>) {
+    if let Some(min_lift) = min_lift {
+        if min_lift < 1.0 {
+            println!("Minimum lift must be in range [1,∞]");
+            process::exit(1);
+        }
+    }
+}
+
+fn check_max_lift_or_exit(min_lift: Option<f64>, max_lift: Option<f64>) {
+    if let Some(max_lift) = max_lift {
+        if max_lift < min_lift.unwrap_or(0.0) {
+            eprintln!("Maximum lift must not be less than minimum lift");
+            process::exit(1);
+        }
+    }
+}
+
+fn check_min_support_or_exit(min_support: f64) {
+    if !(0.0..=1.0).contains(&min_support) {
+        eprintln!("Minimum itemset support must be in range [0,1]");
+        process::exit(1);
+    }
+}
+
+fn parse_bench_args_or_exit(arguments: &[String]) -> BenchArguments {
+    let mut args = BenchArguments {
+        dataset_paths: vec![],
+        min_supports: vec![],
+        min_confidence: 0.05,
+        min_lift: None,
+        parallel_tree_build: false,
+        aggregate_duplicates: false,
+        recount: false,
+    };
+
+    {
+        let mut parser = ArgumentParser::new();
+        parser.set_description(
+            "Benchmark mining over datasets with a range of support thresholds. \
+             Prints phase timings, peak memory, and itemset and rule counts.",
+        );
+
+        parser
+            .refer(&mut args.dataset_paths)
+            .add_argument("datasets", List, "Input datasets in CSV format.")
+            .metavar("file_path")
+            .required();
+
+        parser
+            .refer(&mut args.min_supports)
+            .add_option(
+                &["--min-support"],
+                Collect,
+                "Minimum itemset support threshold, in range [0,1]. \
+                 Pass multiple times to benchmark multiple thresholds.",
+            )
+            .metavar("threshold")
+            .required();
+
+        parser
+            .refer(&mut args.min_confidence)
+            .add_option(
+                &["--min-confidence"],
+                Store,
+                "Minimum rule confidence threshold, in range [0,1]. Default 0.05.",
+            )
+            .metavar("threshold");
+
+        parser
+            .refer(&mut args.min_lift)
+            .add_option(
+                &["--min-lift"],
+                StoreOption,
+                "Minimum rule lift confidence threshold, in range [1,∞].",
+            )
+            .metavar("threshold");
+
+        parser.refer(&mut args.parallel_tree_build).add_option(
+            &["--parallel-tree-build"],
+            StoreTrue,
+            "Build the initial FPTree in parallel.",
+        );
+
+        parser.refer(&mut args.aggregate_duplicates).add_option(
+            &["--aggregate-duplicates"],
+            StoreTrue,
+            "Collapse identical transactions before building the initial FPTree.",
+        );
+
+        parser.refer(&mut args.recount).add_option(
+            &["--recount"],
+            StoreTrue,
+            "Also build a transaction index, and time recounting the support \
+             of every itemset with scalar and SIMD tid-list intersection.",
+        );
+
+        parse_or_exit(&parser, "arm bench", arguments);
+    }
+
+    for &min_support in &args.min_supports {
+        check_min_support_or_exit(min_support);
+    }
+    check_min_confidence_or_exit(args.min_confidence);
+    check_min_lift_or_exit(args.min_lift);
+
+    args
+}
+
+fn parse_thresholds_args_or_exit(arguments: &[String]) -> ThresholdsArguments {
+    let mut args = ThresholdsArguments {
+        input_file_path: String::new(),
+        weighted: false,
+        input_format: InputFormat::Csv,
+        encoding: Encoding::Utf8,
+        sample_size: 10_000,
+        seed: 1,
+        min_supports: vec![],
+        min_confidence: 0.05,
+        min_lift: None,
+        max_itemset_size: None,
+    };
+
+    {
+        let mut parser = ArgumentParser::new();
+        parser.set_description(
+            "Sample transactions from a dataset in one pass, mine the sample \
+             at each of several support thresholds, and print how many \
+             itemsets and rules each gives. A cheap way to choose thresholds \
+             before mining the whole dataset.",
+        );
+
+        parser
+            .refer(&mut args.input_file_path)
+            .add_option(&["--input"], Store, "Input dataset in CSV format.")
+            .metavar("file_path")
+            .required();
+
+        parser.refer(&mut args.weighted).add_option(
+            &["--weighted"],
+            StoreTrue,
+            "Input lines are of the form count;item1,item2,...",
+        );
+
+        parser
+            .refer(&mut args.input_format)
+            .add_option(&["--input-format"], Store, INPUT_FORMAT_HELP)
+            .metavar("format");
+
+        parser
+            .refer(&mut args.encoding)
+            .add_option(&["--encoding"], Store, ENCODING_HELP)
+            .metavar("encoding");
+
+        parser
+            .refer(&mut args.sample_size)
+            .add_option(
+                &["--sample-size"],
+                Store,
+                "Number of transactions to sample. Default 10000.",
+            )
+            .metavar("count");
+
+        parser
+            .refer(&mut args.seed)
+            .add_option(
+                &["--seed"],
+                Store,
+                "Random number generator seed for sampling. Default 1.",
+            )
+            .metavar("seed");
+
+        parser
+            .refer(&mut args.min_supports)
+            .add_option(
+                &["--min-support"],
+                Collect,
+                "Minimum itemset support threshold, in range [0,1]. \
+                 Pass multiple times to try multiple thresholds.",
+            )
+            .metavar("threshold")
+            .required();
+
+        parser
+            .refer(&mut args.min_confidence)
+            .add_option(
+                &["--min-confidence"],
+                Store,
+                "Minimum rule confidence threshold, in range [0,1]. Default 0.05.",
+            )
+            .metavar("threshold");
+
+        parser
+            .refer(&mut args.min_lift)
+            .add_option(
+                &["--min-lift"],
+                StoreOption,
+                "Minimum rule lift confidence threshold, in range [1,∞].",
+            )
+            .metavar("threshold");
+
+        parser
+            .refer(&mut args.max_itemset_size)
+            .add_option(
+                &["--max-itemset-size"],
+                StoreOption,
+                "Don't mine itemsets with more than this many items.",
+            )
+            .metavar("size");
+
+        parse_or_exit(&parser, "arm thresholds", arguments);
+    }
+    check_weighted_input_or_exit(args.input_format, args.weighted);
+    if args.sample_size == 0 {
+        eprintln!("--sample-size must be at least 1");
+        process::exit(1);
+    }
+    if args.max_itemset_size == Some(0) {
+        eprintln!("Maximum itemset size must be at least 1");
+        process::exit(1);
+    }
+    for &min_support in &args.min_supports {
+        check_min_support_or_exit(min_support);
+    }
+    check_min_confidence_or_exit(args.min_confidence);
+    check_min_lift_or_exit(args.min_lift);
+
+    args
+}
+
+// The options passed which output or depend on exact counts, and so would
+// leak them under differential privacy.
+fn exact_count_options(args: &Arguments) -> Vec<&'static str> {
+    [
+        (
+            "--suppress-below-count",
+            args.suppress_below_count.is_some(),
+        ),
+        ("--baseline-input", args.baseline_input_path.is_some()),
+        ("--item-min-support", args.item_min_support_path.is_some()),
+        ("--target-consequent", args.target_consequent.is_some()),
+        ("--target-rules", args.target_rules.is_some()),
+        ("--select-covering", args.select_covering.is_some()),
+        ("--permutations", args.permutations.is_some()),
+        ("--verify", args.verify),
+        ("--coverage-report", args.coverage_report_path.is_some()),
+        ("--dump-tree", args.dump_tree_path.is_some()),
+        ("--self-check", args.self_check),
+    ]
+    .iter()
+    .filter(|&&(_, passed)| passed)
+    .map(|&(option, _)| option)
+    .collect()
+}
+
+pub fn parse_mine_args_or_exit(program: &str, arguments: &[String]) -> Arguments {
     let mut args: Arguments = Arguments {
         input_file_path: String::new(),
-        output_rules_path: String::new(),
-        min_support: 0.0,
-        min_confidence: 0.0,
-        min_lift: None,
+        query: None,
+        output_rules_path: String::new(),
+        output_itemsets_path: None,
+        output_lattice_path: None,
+        output_generators_path: None,
+        dump_tree_path: None,
+        flame_stacks_path: None,
+        html_report_path: None,
+        preview: None,
+        preview_color: ColorChoice::Auto,
+        manifest: false,
+        compress_output: None,
+        number_format: Notation::Fixed,
+        precision: None,
+        item_map_path: None,
+        dictionary_path: None,
+        unseen_items: UnseenItems::Error,
+        output_dictionary_path: None,
+        weighted: false,
+        input_format: InputFormat::Csv,
+        table_options: TableOptions::default(),
+        encoding: Encoding::Utf8,
+        in_memory: false,
+        approximate_counts: false,
+        algorithm: Algorithm::FPGrowth,
+        sample_size: None,
+        sample_seed: 1,
+        item_order: ItemOrder::Frequency,
+        min_support: 0.0,
+        min_confidence: 0.0,
+        min_confidence_by_length: None,
+        min_lift: None,
+        max_lift: None,
+        min_confidence_lower_bound: None,
+        laplace_k: DEFAULT_LAPLACE_K,
+        min_laplace: None,
+        min_added_value: None,
+        min_gini_gain: None,
+        min_info_gain: None,
+        min_odds_ratio: None,
+        min_yules_q: None,
+        min_phi: None,
+        filter: None,
+        min_itemset_size: 1,
+        max_itemset_size: None,
+        parallel_tree_build: false,
+        aggregate_duplicates: false,
+        deterministic_order: false,
+        top_per_consequent: None,
+        target_consequent: None,
+        target_rules: None,
+        select_covering: None,
+        no_rules: false,
+        permutations: None,
+        permutation_seed: 0,
+        verify: false,
+        verify_sample: None,
+        coverage_report_path: None,
+        self_check: false,
+        index_backend: IndexBackend::TidList,
+        timeout: None,
+        max_itemsets: None,
+        max_rules: None,
+        write_partial: false,
+        pfp_groups: None,
+        min_all_confidence: None,
+        max_support_ratio: None,
+        item_min_support_path: None,
+        baseline_input_path: None,
+        output_contrasts_path: None,
+        min_growth_rate: 2.0,
+        max_p_value: 0.05,
+        suppress_below_count: None,
+        dp_epsilon: None,
+        dp_max_length: None,
+    };
+    let mut itemset_size: Option<usize> = None;
+    let mut filter: Option<String> = None;
+    let mut min_confidence: Option<f64> = None;
+
+    {
+        let mut parser = ArgumentParser::new();
+        parser.set_description(
+            "Mine frequent itemsets and association rules from a dataset. \
+             Run `arm help` for other commands.",
+        );
+
+        parser
+            .refer(&mut args.input_file_path)
+            .add_option(
+                &["--input"],
+                Store,
+                "Input dataset in CSV format, which may be an http(s):// or \
+                 s3://bucket/key URL, and is decompressed if it ends in .gz \
+                 or .zst. Or - to read from stdin, kafka://brokers/topic to \
+                 read the messages in a Kafka topic (add ?payload=json if \
+                 messages are JSON arrays of items) as a snapshot, up to the \
+                 last message in each partition when the topic is opened, or \
+                 postgres://user@host/database to read the results of \
+                 --query. URL, Kafka and PostgreSQL input require building \
+                 with --features http, kafka or postgres.",
+            )
+            .metavar("file_path")
+            .required();
+
+        parser
+            .refer(&mut args.query)
+            .add_option(
+                &["--query"],
+                StoreOption,
+                "With PostgreSQL input, SQL query returning (transaction id, \
+                 item) rows. Rows are grouped into transactions by id.",
+            )
+            .metavar("sql");
+
+        parser.refer(&mut args.in_memory).add_option(
+            &["--in-memory"],
+            StoreTrue,
+            "Read the dataset once and hold it in memory, rather than \
+             reading it twice. Implied when reading from stdin.",
+        );
+
+        parser.refer(&mut args.approximate_counts).add_option(
+            &["--approximate-counts"],
+            StoreTrue,
+            "Count item frequencies approximately with a Count-Min sketch on \
+             the first pass, and only keep track of items which may be \
+             frequent. Uses less memory on datasets with very many distinct \
+             items, at the cost of an extra pass over the dataset.",
+        );
+
+        parser
+            .refer(&mut args.output_rules_path)
+            .add_option(
+                &["--output"],
+                Store,
+                "File path in which to store output rules. \
+                 Format: antecedent -> consequent, confidence, lift, support, \
+                 antecedent count, consequent count, count, confidence \
+                 lower bound, confidence upper bound, lift standard error, \
+                 Laplace accuracy, added value, Gini gain, information gain, \
+                 odds ratio, Yule's Q, phi coefficient, permutation test \
+                 p-value. Required unless --no-rules is passed.",
+            )
+            .metavar("file_path");
+
+        parser
+            .refer(&mut args.output_itemsets_path)
+            .add_option(
+                &["--output-itemsets"],
+                StoreOption,
+                "File path in which to store frequent itemsets. \
+                 Format: itemset, support, count.",
+            )
+            .metavar("file_path");
+
+        parser
+            .refer(&mut args.output_lattice_path)
+            .add_option(
+                &["--output-lattice"],
+                StoreOption,
+                "File path in which to store the lattice of closed frequent \
+                 itemsets, that is, the Hasse diagram of the concept lattice \
+                 of formal concept analysis. Written in Graphviz DOT format, \
+                 or as JSON if the path ends in .json. Only practical for \
+                 thousands of closed itemsets.",
+            )
+            .metavar("file_path");
+
+        parser
+            .refer(&mut args.output_generators_path)
+            .add_option(
+                &["--output-generators"],
+                StoreOption,
+                "File path in which to store the minimal generators of the \
+                 closed frequent itemsets: the smallest itemsets contained \
+                 in the same transactions as each closed itemset. Format: \
+                 generator, closure, support, count.",
+            )
+            .metavar("file_path");
+
+        parser
+            .refer(&mut args.compress_output)
+            .add_option(
+                &["--compress-output"],
+                StoreOption,
+                "Compress the rules and itemsets files; one of none, gzip or \
+                 zstd. By default, output paths ending in .gz or .zst are \
+                 compressed with gzip or zstd respectively.",
+            )
+            .metavar("compression");
+
+        parser
+            .refer(&mut args.number_format)
+            .add_option(
+                &["--number-format"],
+                Store,
+                "Notation for stats in the rules and itemsets files; fixed \
+                 or scientific. Default fixed.",
+            )
+            .metavar("notation");
+
+        parser
+            .refer(&mut args.precision)
+            .add_option(
+                &["--precision"],
+                StoreOption,
+                "Number of digits after the decimal point for stats in the \
+                 rules and itemsets files. By default stats are written with \
+                 as many digits as needed to read them back exactly.",
+            )
+            .metavar("digits");
+
+        parser
+            .refer(&mut args.html_report_path)
+            .add_option(
+                &["--html-report"],
+                StoreOption,
+                "File path in which to store a self-contained HTML report, \
+                 with summary stats, a sortable table of the top rules, and \
+                 charts.",
+            )
+            .metavar("file_path");
+
+        parser
+            .refer(&mut args.preview)
+            .add_option(
+                &["--preview"],
+                StoreOption,
+                "After writing the rules, print the N rules with the highest \
+                 lift as a table.",
+            )
+            .metavar("N");
+
+        parser
+            .refer(&mut args.preview_color)
+            .add_option(
+                &["--preview-color"],
+                Store,
+                "Whether to color the --preview table: auto, always or never. \
+                 Auto colors it if output is to a terminal and NO_COLOR isn't \
+                 set. Default auto.",
+            )
+            .metavar("when");
+
+        parser.refer(&mut args.manifest).add_option(
+            &["--manifest"],
+            StoreTrue,
+            "Write a manifest beside the output, e.g. rules.meta.json for \
+             rules.csv, recording the command line, a SHA-256 hash of the \
+             input file, the version of arm, result counts and timings. With \
+             --no-rules, it's written beside the itemsets file.",
+        );
+
+        parser
+            .refer(&mut args.dump_tree_path)
+            .add_option(
+                &["--dump-tree"],
+                StoreOption,
+                "File path in which to store the initial FPTree, in Graphviz \
+                 DOT format. Only practical for small datasets.",
+            )
+            .metavar("file_path");
+
+        parser
+            .refer(&mut args.flame_stacks_path)
+            .add_option(
+                &["--flame-stacks"],
+                StoreOption,
+                "File path in which to write the time spent in each phase of \
+                 mining, and in mining each item, as folded stacks for \
+                 inferno-flamegraph or flamegraph.pl. Requires building with \
+                 --features flame.",
+            )
+            .metavar("file_path");
+
+        parser
+            .refer(&mut args.item_map_path)
+            .add_option(
+                &["--item-map"],
+                StoreOption,
+                "File of raw_name,canonical_name pairs, one per line. Items \
+                 are renamed to their canonical name as they're read, so \
+                 variants of an item can be merged into one item.",
+            )
+            .metavar("file_path");
+
+        parser
+            .refer(&mut args.dictionary_path)
+            .add_option(
+                &["--dictionary"],
+                StoreOption,
+                "File of item,id pairs, one per line, assigning items fixed \
+                 ids, so ids don't depend on the order of the dataset. Ids \
+                 start at 1. Itemsets and rules are written in order of \
+                 their items' ids.",
+            )
+            .metavar("file_path");
+
+        parser
+            .refer(&mut args.unseen_items)
+            .add_option(
+                &["--unseen-items"],
+                Store,
+                "What to do with items not in the dictionary: error, or \
+                 append them to it with new ids. Default error.",
+            )
+            .metavar("policy");
+
+        parser
+            .refer(&mut args.output_dictionary_path)
+            .add_option(
+                &["--output-dictionary"],
+                StoreOption,
+                "File path in which to store the item,id pair of every item, \
+                 in the format read by --dictionary.",
+            )
+            .metavar("file_path");
+
+        parser.refer(&mut args.weighted).add_option(
+            &["--weighted"],
+            StoreTrue,
+            "Input lines are of the form count;item1,item2,... meaning \
+             the transaction occurred count times.",
+        );
+
+        parser
+            .refer(&mut args.input_format)
+            .add_option(&["--input-format"], Store, INPUT_FORMAT_HELP)
+            .metavar("format");
+
+        parser
+            .refer(&mut args.table_options.columns)
+            .add_option(
+                &["--column"],
+                Collect,
+                "With --input-format table, only convert this column into \
+                 items. Pass multiple times to convert multiple columns. By \
+                 default every column is converted.",
+            )
+            .metavar("name");
+
+        parser
+            .refer(&mut args.table_options.exclude_columns)
+            .add_option(
+                &["--exclude-column"],
+                Collect,
+                "With --input-format table, don't convert this column into \
+                 items. Pass multiple times to exclude multiple columns.",
+            )
+            .metavar("name");
+
+        parser
+            .refer(&mut args.table_options.bins)
+            .add_option(
+                &["--bin"],
+                Collect,
+                "With --input-format table, group a numeric column's values \
+                 into bins, whose ranges are the column's items. One of \
+                 column=width:n, n bins of equal width; column=quantile:n, n \
+                 bins with about as many values in each; or \
+                 column=edges:e1,e2,..., bins split at the given values. \
+                 Pass multiple times to bin multiple columns.",
+            )
+            .metavar("binning");
+
+        parser
+            .refer(&mut args.table_options.missing)
+            .add_option(
+                &["--missing"],
+                Collect,
+                "With --input-format table, what to do with missing values, \
+                 empty or NA cells: skip, leave the cell without an item; \
+                 encode, give the cell the item column=NA; or drop, leave the \
+                 row out. Pass column=policy to set a column's policy, or \
+                 policy to set the policy of columns without one. Missing \
+                 values are skipped by default.",
+            )
+            .metavar("policy");
+
+        parser
+            .refer(&mut args.encoding)
+            .add_option(&["--encoding"], Store, ENCODING_HELP)
+            .metavar("encoding");
+
+        parser
+            .refer(&mut args.min_support)
+            .add_option(
+                &["--min-support"],
+                Store,
+                "Minimum itemset support threshold, in range [0,1].",
+            )
+            .metavar("threshold")
+            .required();
+
+        parser
+            .refer(&mut min_confidence)
+            .add_option(
+                &["--min-confidence"],
+                StoreOption,
+                "Minimum rule confidence threshold, in range [0,1]. Required \
+                 unless --no-rules is passed.",
+            )
+            .metavar("threshold");
+
+        parser
+            .refer(&mut args.min_confidence_by_length)
+            .add_option(
+                &["--min-confidence-by-length"],
+                StoreOption,
+                "Minimum rule confidence by antecedent length, overriding \
+                 --min-confidence for the lengths given, e.g. \
+                 \"1:0.9,2:0.7,3+:0.5\". A length ending in + applies to \
+                 all longer antecedents without their own threshold.",
+            )
+            .metavar("thresholds");
+
+        parser
+            .refer(&mut args.min_lift)
+            .add_option(
+                &["--min-lift"],
+                StoreOption,
+                "Minimum rule lift confidence threshold, in range [1,∞].",
+            )
+            .metavar("threshold");
+
+        parser
+            .refer(&mut args.max_lift)
+            .add_option(
+                &["--max-lift"],
+                StoreOption,
+                "Maximum rule lift. Excludes rules with implausibly high lift, \
+                 which are usually driven by items with tiny supports.",
+            )
+            .metavar("threshold");
+
+        parser
+            .refer(&mut args.min_confidence_lower_bound)
+            .add_option(
+                &["--min-confidence-lower-bound"],
+                StoreOption,
+                "Minimum lower bound of the 95% Wilson score interval of rule \
+                 confidence, in range [0,1]. Excludes rules whose confidence \
+                 is high only because they're based on few transactions.",
+            )
+            .metavar("threshold");
+
+        parser
+            .refer(&mut args.laplace_k)
+            .add_option(
+                &["--laplace-k"],
+                Store,
+                "Number of classes k used to calculate rules' Laplace \
+                 accuracy, (count + 1) / (antecedent count + k). Default 2.",
+            )
+            .metavar("k");
+
+        parser
+            .refer(&mut args.min_laplace)
+            .add_option(
+                &["--min-laplace"],
+                StoreOption,
+                "Minimum rule Laplace accuracy, in range [0,1].",
+            )
+            .metavar("threshold");
+
+        parser
+            .refer(&mut args.min_added_value)
+            .add_option(
+                &["--min-added-value"],
+                StoreOption,
+                "Minimum rule added value, the rule's confidence minus its \
+                 consequent's support, in range [-1,1]. Positive if the \
+                 antecedent makes the consequent more likely.",
+            )
+            .metavar("threshold");
+
+        parser
+            .refer(&mut args.min_gini_gain)
+            .add_option(
+                &["--min-gini-gain"],
+                StoreOption,
+                "Minimum reduction in the Gini impurity of the consequent \
+                 from splitting transactions on the antecedent, in range \
+                 [0,0.5].",
+            )
+            .metavar("threshold");
+
+        parser
+            .refer(&mut args.min_info_gain)
+            .add_option(
+                &["--min-info-gain"],
+                StoreOption,
+                "Minimum information gain, in bits, of the consequent from \
+                 splitting transactions on the antecedent, in range [0,1].",
+            )
+            .metavar("threshold");
+
+        parser
+            .refer(&mut args.min_odds_ratio)
+            .add_option(
+                &["--min-odds-ratio"],
+                StoreOption,
+                "Minimum odds ratio of the consequent given the antecedent, \
+                 in range [0,∞]. Zero cells in the contingency table are \
+                 corrected by adding 0.5 to every cell.",
+            )
+            .metavar("threshold");
+
+        parser
+            .refer(&mut args.min_yules_q)
+            .add_option(
+                &["--min-yules-q"],
+                StoreOption,
+                "Minimum Yule's Q of the antecedent and consequent, in range \
+                 [-1,1].",
+            )
+            .metavar("threshold");
+
+        parser
+            .refer(&mut args.min_phi)
+            .add_option(
+                &["--min-phi"],
+                StoreOption,
+                "Minimum phi coefficient of the antecedent and consequent, in \
+                 range [-1,1].",
+            )
+            .metavar("threshold");
+
+        parser
+            .refer(&mut filter)
+            .add_option(
+                &["--filter"],
+                StoreOption,
+                "Only output rules matching this expression, e.g. \
+                 \"lift > 2 && support >= 0.01 && antecedent_len <= 3 && \
+                 consequent contains 'beer'\". Compares fields named after \
+                 the output columns in snake case, or antecedent_len, \
+                 consequent_len and len, with <, <=, >, >=, == or !=. \
+                 antecedent, consequent or rule contains 'item' tests for \
+                 an item. Combine with &&, || and !, and group with \
+                 parentheses.",
+            )
+            .metavar("expression");
+
+        parser
+            .refer(&mut itemset_size)
+            .add_option(
+                &["--itemset-size"],
+                StoreOption,
+                "Only output itemsets with exactly this many items, and rules \
+                 generated from them. Shorthand for setting both \
+                 --min-itemset-size and --max-itemset-size.",
+            )
+            .metavar("size");
+
+        parser
+            .refer(&mut args.min_itemset_size)
+            .add_option(
+                &["--min-itemset-size"],
+                Store,
+                "Only output itemsets with at least this many items, and rules \
+                 generated from them. Default 1.",
+            )
+            .metavar("size");
+
+        parser
+            .refer(&mut args.min_all_confidence)
+            .add_option(
+                &["--min-all-confidence"],
+                StoreOption,
+                "Only mine hyperclique patterns: itemsets whose count is at \
+                 least this fraction of the count of their most frequent \
+                 item, in range [0,1]. Prunes itemsets mixing common and rare \
+                 items, which are often spurious.",
+            )
+            .metavar("threshold");
+
+        parser
+            .refer(&mut args.max_support_ratio)
+            .add_option(
+                &["--max-support-ratio"],
+                StoreOption,
+                "Don't mine itemsets whose most frequent item occurs more than \
+                 this many times as often as their least frequent item. Such \
+                 cross-support itemsets are often spurious on skewed data.",
+            )
+            .metavar("ratio");
+
+        parser
+            .refer(&mut args.item_min_support_path)
+            .add_option(
+                &["--item-min-support"],
+                StoreOption,
+                "File of item,min_support pairs, one per line, giving items \
+                 their own minimum supports. An itemset is frequent if its \
+                 support is at least the lowest minimum support of its items. \
+                 Items not in the file use --min-support.",
+            )
+            .metavar("file_path");
+
+        parser
+            .refer(&mut args.max_itemset_size)
+            .add_option(
+                &["--max-itemset-size"],
+                StoreOption,
+                "Don't mine itemsets with more than this many items. With a \
+                 maximum of 2 and the fpgrowth algorithm, items and pairs are \
+                 counted directly, without building an FPTree.",
+            )
+            .metavar("size");
+
+        parser.refer(&mut args.parallel_tree_build).add_option(
+            &["--parallel-tree-build"],
+            StoreTrue,
+            "Build the initial FPTree in parallel, by building partial trees \
+             from chunks of transactions on each thread and merging them.",
+        );
+
+        parser.refer(&mut args.aggregate_duplicates).add_option(
+            &["--aggregate-duplicates"],
+            StoreTrue,
+            "Collapse identical transactions into a single weighted \
+             transaction before building the initial FPTree. Speeds up \
+             tree building on datasets with many duplicate transactions.",
+        );
+
+        parser.refer(&mut args.deterministic_order).add_option(
+            &["--deterministic-order"],
+            StoreTrue,
+            "Write rules in the same order on every run. By default rules \
+             are written in whatever order threads generate them.",
+        );
+
+        parser
+            .refer(&mut args.top_per_consequent)
+            .add_option(
+                &["--top-per-consequent"],
+                StoreOption,
+                "Group rules by consequent, and keep only this many rules \
+                 with the highest confidence for each consequent.",
+            )
+            .metavar("k");
+
+        parser
+            .refer(&mut args.target_consequent)
+            .add_option(
+                &["--target-consequent"],
+                StoreOption,
+                "Only generate rules whose consequent is exactly these \
+                 comma separated items, e.g. \"churn=yes\". Only the \
+                 itemsets needed for those rules are mined.",
+            )
+            .metavar("items");
+
+        parser
+            .refer(&mut args.target_rules)
+            .add_option(
+                &["--target-rules"],
+                StoreOption,
+                "Raise the minimum support until about this many rules are \
+                 generated, searching supports by mining the initial tree \
+                 repeatedly. --min-support is the lowest support tried, so \
+                 it should be low enough to give at least this many rules.",
+            )
+            .metavar("count");
+
+        parser
+            .refer(&mut args.select_covering)
+            .add_option(
+                &["--select-covering"],
+                StoreOption,
+                "Output only a small set of rules which together cover at \
+                 least this percentage of transactions, picked greedily by \
+                 how many uncovered transactions each rule covers.",
+            )
+            .metavar("N%");
+
+        parser
+            .refer(&mut args.permutations)
+            .add_option(
+                &["--permutations"],
+                StoreOption,
+                "Estimate a p-value for each rule's lift with a permutation \
+                 test, shuffling which transactions each item occurs in this \
+                 many times. P-values are written in the PValue column.",
+            )
+            .metavar("count");
+
+        parser
+            .refer(&mut args.permutation_seed)
+            .add_option(
+                &["--permutation-seed"],
+                Store,
+                "Random number generator seed for --permutations. Default 0.",
+            )
+            .metavar("seed");
+
+        parser.refer(&mut args.no_rules).add_option(
+            &["--no-rules"],
+            StoreTrue,
+            "Stop after mining itemsets, without generating rules. Use with \
+             --output-itemsets.",
+        );
+
+        parser.refer(&mut args.verify).add_option(
+            &["--verify"],
+            StoreTrue,
+            "After mining, recount the supports of itemsets and rules using \
+             a transaction index, and report any discrepancies.",
+        );
+
+        parser
+            .refer(&mut args.verify_sample)
+            .add_option(
+                &["--verify-sample"],
+                StoreOption,
+                "With --verify, only recount a random sample of this many \
+                 itemsets and rules, rather than all of them.",
+            )
+            .metavar("count");
+
+        parser
+            .refer(&mut args.coverage_report_path)
+            .add_option(
+                &["--coverage-report"],
+                StoreOption,
+                "After mining, count the transactions matched by at least one \
+                 rule's antecedent and by at least one whole rule, using a \
+                 transaction index, and write each rule's coverage to this \
+                 CSV file.",
+            )
+            .metavar("file_path");
+
+        parser.refer(&mut args.self_check).add_option(
+            &["--self-check"],
+            StoreTrue,
+            "After mining, compare itemsets and rules against those found \
+             by brute force, and report any discrepancies. Only for small \
+             data sets; transactions may have at most 20 frequent items.",
+        );
+
+        parser
+            .refer(&mut args.algorithm)
+            .add_option(
+                &["--algorithm"],
+                Store,
+                "Algorithm used to mine frequent itemsets; one of fpgrowth \
+                 (default), tdfpgrowth, eclat, declat or toivonen. tdfpgrowth \
+                 mines the FPTree from the root down without building \
+                 conditional trees. Eclat uses diffsets rather than tidsets \
+                 on dense datasets; declat always uses diffsets. toivonen \
+                 mines a sample of the dataset at a lowered support, then \
+                 counts the itemsets found and their negative border exactly \
+                 in an index of the dataset, giving exact results.",
+            )
+            .metavar("algorithm");
+
+        parser
+            .refer(&mut args.sample_size)
+            .add_option(
+                &["--sample-size"],
+                StoreOption,
+                "Number of transactions sampled by --algorithm toivonen. \
+                 Default 10000.",
+            )
+            .metavar("count");
+
+        parser
+            .refer(&mut args.sample_seed)
+            .add_option(
+                &["--sample-seed"],
+                Store,
+                "Random number generator seed for --algorithm toivonen's \
+                 sample. Default 1.",
+            )
+            .metavar("seed");
+
+        parser
+            .refer(&mut args.item_order)
+            .add_option(
+                &["--item-order"],
+                Store,
+                "Order of items in the initial FPTree; frequency (default), \
+                 most frequent first, or lexicographic. Frequency order \
+                 usually gives a smaller tree. Output is the same either \
+                 way. Use arm stats --compare-item-orders to compare tree \
+                 sizes.",
+            )
+            .metavar("order");
+
+        parser
+            .refer(&mut args.index_backend)
+            .add_option(
+                &["--index-backend"],
+                Store,
+                "Transaction index used by --verify and --baseline-input; one \
+                 of tidlist (default) or roaring. Roaring bitmaps use less memory on large, sparse \
+                 datasets.",
+            )
+            .metavar("backend");
+
+        parser
+            .refer(&mut args.baseline_input_path)
+            .add_option(
+                &["--baseline-input"],
+                StoreOption,
+                "Baseline dataset to contrast the input with, such as data from \
+                 before a change. The support of each frequent itemset is \
+                 counted in the baseline, and emerging patterns, whose support \
+                 grew significantly, are written to --output-contrasts.",
+            )
+            .metavar("file_path");
+
+        parser
+            .refer(&mut args.output_contrasts_path)
+            .add_option(
+                &["--output-contrasts"],
+                StoreOption,
+                "File path in which to store emerging patterns. Format: \
+                 itemset, support, baseline support, count, baseline count, \
+                 growth rate, chi-squared, p-value.",
+            )
+            .metavar("file_path");
+
+        parser
+            .refer(&mut args.min_growth_rate)
+            .add_option(
+                &["--min-growth-rate"],
+                Store,
+                "Minimum ratio of an emerging pattern's support in the input \
+                 to its support in the baseline. Default 2.",
+            )
+            .metavar("ratio");
+
+        parser
+            .refer(&mut args.max_p_value)
+            .add_option(
+                &["--max-p-value"],
+                Store,
+                "Maximum p-value of the chi-squared test of an emerging \
+                 pattern's change in support. Default 0.05.",
+            )
+            .metavar("p");
+
+        parser
+            .refer(&mut args.suppress_below_count)
+            .add_option(
+                &["--suppress-below-count"],
+                StoreOption,
+                "Withhold every itemset and rule whose itemset occurs in \
+                 fewer than this many transactions, whatever its support, \
+                 so that no output describes a small group of transactions.",
+            )
+            .metavar("count");
+
+        parser
+            .refer(&mut args.dp_epsilon)
+            .add_option(
+                &["--dp-epsilon"],
+                StoreOption,
+                "Mine with differential privacy: select the itemsets to \
+                 release by thresholding their counts with geometric noise \
+                 added, then add fresh noise to the counts released, \
+                 spending half of this privacy budget epsilon on each. \
+                 Smaller is more private and noisier. Requires \
+                 --dp-max-length.",
+            )
+            .metavar("epsilon");
+
+        parser
+            .refer(&mut args.dp_max_length)
+            .add_option(
+                &["--dp-max-length"],
+                StoreOption,
+                "With --dp-epsilon, truncate transactions to their first \
+                 this many items, which bounds how many itemset counts one \
+                 transaction can change, and so how much noise is needed.",
+            )
+            .metavar("length");
+
+        parser
+            .refer(&mut args.timeout)
+            .add_option(
+                &["--timeout"],
+                StoreOption,
+                "Stop FPGrowth after this many seconds, and output the \
+                 itemsets found so far and the rules which can be generated \
+                 from them.",
+            )
+            .metavar("seconds");
+
+        parser
+            .refer(&mut args.max_itemsets)
+            .add_option(
+                &["--max-itemsets"],
+                StoreOption,
+                "Stop mining once more than this many frequent itemsets are \
+                 found, rather than running out of memory, and exit with an \
+                 error saying the minimum support is too low.",
+            )
+            .metavar("count");
+
+        parser
+            .refer(&mut args.max_rules)
+            .add_option(
+                &["--max-rules"],
+                StoreOption,
+                "Stop generating rules once more than this many are \
+                 generated, and exit with an error saying the thresholds \
+                 are too low.",
+            )
+            .metavar("count");
+
+        parser.refer(&mut args.write_partial).add_option(
+            &["--write-partial"],
+            StoreTrue,
+            "When --max-itemsets or --max-rules is exceeded, write the \
+             results found so far instead of exiting with an error, as \
+             with --timeout.",
+        );
+
+        parser
+            .refer(&mut args.pfp_groups)
+            .add_option(
+                &["--pfp-groups"],
+                StoreOption,
+                "Mine in the style of Parallel FP-Growth: split the frequent \
+                 items into this many groups, and mine each group as a \
+                 separate task from its own tree.",
+            )
+            .metavar("groups");
+
+        parse_or_exit(&parser, program, arguments);
+    }
+
+    if min_confidence.is_none() && !args.no_rules {
+        eprintln!("--min-confidence is required unless --no-rules is passed");
+        process::exit(1);
+    }
+    args.min_confidence = min_confidence.unwrap_or(0.0);
+    check_min_support_or_exit(args.min_support);
+    check_min_confidence_or_exit(args.min_confidence);
+    check_min_lift_or_exit(args.min_lift);
+    check_max_lift_or_exit(args.min_lift, args.max_lift);
+    if args.laplace_k <= 0.0 {
+        eprintln!("Laplace k must be greater than 0");
+        process::exit(1);
+    }
+    if let Some(min_laplace) = args.min_laplace {
+        if !(0.0..=1.0).contains(&min_laplace) {
+            eprintln!("Minimum Laplace accuracy must be in range [0,1]");
+            process::exit(1);
+        }
+    }
+    if let Some(min_added_value) = args.min_added_value {
+        if !(-1.0..=1.0).contains(&min_added_value) {
+            eprintln!("Minimum added value must be in range [-1,1]");
+            process::exit(1);
+        }
+    }
+    if let Some(min_gini_gain) = args.min_gini_gain {
+        if !(0.0..=0.5).contains(&min_gini_gain) {
+            eprintln!("Minimum Gini gain must be in range [0,0.5]");
+            process::exit(1);
+        }
+    }
+    if let Some(min_info_gain) = args.min_info_gain {
+        if !(0.0..=1.0).contains(&min_info_gain) {
+            eprintln!("Minimum information gain must be in range [0,1]");
+            process::exit(1);
+        }
+    }
+    if args.min_odds_ratio.is_some_and(|ratio| ratio < 0.0) {
+        eprintln!("Minimum odds ratio must be at least 0");
+        process::exit(1);
+    }
+    if let Some(min_yules_q) = args.min_yules_q {
+        if !(-1.0..=1.0).contains(&min_yules_q) {
+            eprintln!("Minimum Yule's Q must be in range [-1,1]");
+            process::exit(1);
+        }
+    }
+    if let Some(min_phi) = args.min_phi {
+        if !(-1.0..=1.0).contains(&min_phi) {
+            eprintln!("Minimum phi coefficient must be in range [-1,1]");
+            process::exit(1);
+        }
+    }
+    if let Some(lower_bound) = args.min_confidence_lower_bound {
+        check_min_confidence_or_exit(lower_bound);
+    }
+    if let Some(min_all_confidence) = args.min_all_confidence {
+        if !(0.0..=1.0).contains(&min_all_confidence) {
+            eprintln!("Minimum all-confidence must be in range [0,1]");
+            process::exit(1);
+        }
+    }
+    if args.max_support_ratio.is_some_and(|ratio| ratio < 1.0) {
+        eprintln!("Maximum support ratio must be at least 1");
+        process::exit(1);
+    }
+
+    if let Some(filter) = filter {
+        match filter.parse() {
+            Ok(expression) => args.filter = Some(expression),
+            Err(error) => {
+                eprintln!("Invalid filter: {}", error);
+                process::exit(1);
+            }
+        }
+    }
+    if let Some(size) = itemset_size {
+        args.min_itemset_size = size;
+        args.max_itemset_size = Some(size);
+    }
+    if is_postgres(&args.input_file_path) != args.query.is_some() {
+        eprintln!("--query is required with, and only used with, PostgreSQL input");
+        process::exit(1);
+    }
+    if args.approximate_counts && (args.in_memory || is_stream(&args.input_file_path)) {
+        eprintln!(
+            "--approximate-counts can't be used with --in-memory, or stdin, Kafka or PostgreSQL \
+             input"
+        );
+        process::exit(1);
+    }
+    check_weighted_input_or_exit(args.input_format, args.weighted);
+    if args.input_format != InputFormat::Table && args.table_options != TableOptions::default() {
+        eprintln!("--column, --exclude-column, --bin and --missing require --input-format table");
+        process::exit(1);
+    }
+    if args.table_options.needs_fitting() && is_stream(&args.input_file_path) {
+        eprintln!(
+            "--bin with width or quantile bins can't be used with stdin, Kafka or PostgreSQL \
+             input"
+        );
+        process::exit(1);
+    }
+    if (args.output_lattice_path.is_some() || args.output_generators_path.is_some())
+        && (args.max_itemset_size.is_some() || args.item_min_support_path.is_some())
+    {
+        eprintln!(
+            "--output-lattice and --output-generators can't be used with --max-itemset-size \
+             or --item-min-support"
+        );
+        process::exit(1);
+    }
+    if !args.algorithm.builds_fptree() && args.dump_tree_path.is_some() {
+        eprintln!("--dump-tree requires --algorithm fpgrowth or tdfpgrowth");
+        process::exit(1);
+    }
+    if args.algorithm != Algorithm::Toivonen && args.sample_size.is_some() {
+        eprintln!("--sample-size requires --algorithm toivonen");
+        process::exit(1);
+    }
+    if args.sample_size == Some(0) {
+        eprintln!("--sample-size must be at least 1");
+        process::exit(1);
+    }
+    if args.algorithm != Algorithm::FPGrowth && args.pfp_groups.is_some() {
+        eprintln!("--pfp-groups requires --algorithm fpgrowth");
+        process::exit(1);
+    }
+    if args.item_order != ItemOrder::Frequency
+        && (!args.algorithm.builds_fptree() || args.item_min_support_path.is_some())
+    {
+        eprintln!(
+            "--item-order requires --algorithm fpgrowth or tdfpgrowth, and can't be used with \
+             --item-min-support"
+        );
+        process::exit(1);
+    }
+    if !args.algorithm.builds_fptree() && args.item_min_support_path.is_some() {
+        eprintln!("--item-min-support requires --algorithm fpgrowth or tdfpgrowth");
+        process::exit(1);
+    }
+    if args.approximate_counts && args.item_min_support_path.is_some() {
+        eprintln!("--approximate-counts can't be used with --item-min-support");
+        process::exit(1);
+    }
+    if args.dp_epsilon.is_some() != args.dp_max_length.is_some() {
+        eprintln!("--dp-epsilon and --dp-max-length must be used together");
+        process::exit(1);
+    }
+    if args.dp_epsilon.is_some_and(|epsilon| epsilon <= 0.0) {
+        eprintln!("--dp-epsilon must be greater than 0");
+        process::exit(1);
+    }
+    if args.dp_max_length == Some(0) {
+        eprintln!("--dp-max-length must be at least 1");
+        process::exit(1);
+    }
+    let exact_count_options = exact_count_options(&args);
+    if args.dp_epsilon.is_some() && !exact_count_options.is_empty() {
+        eprintln!(
+            "--dp-epsilon can't be used with {}, which use exact counts",
+            exact_count_options.join(", ")
+        );
+        process::exit(1);
+    }
+    if args.dp_epsilon.is_some()
+        && (args.timeout.is_some()
+            || args.max_itemsets.is_some()
+            || args.min_all_confidence.is_some()
+            || args.max_support_ratio.is_some()
+            || args.approximate_counts)
+    {
+        eprintln!(
+            "--dp-epsilon can't be used with --timeout, --max-itemsets, --min-all-confidence, \
+             --max-support-ratio or --approximate-counts, as every candidate itemset must be \
+             mined with its exact count"
+        );
+        process::exit(1);
+    }
+    if args.suppress_below_count.is_some() && args.baseline_input_path.is_some() {
+        eprintln!(
+            "--suppress-below-count can't be used with --baseline-input, as contrasts include \
+             baseline counts"
+        );
+        process::exit(1);
+    }
+    if args.baseline_input_path.is_some() != args.output_contrasts_path.is_some() {
+        eprintln!("--baseline-input and --output-contrasts must be used together");
+        process::exit(1);
+    }
+    if args.min_growth_rate < 1.0 {
+        eprintln!("Minimum growth rate must be at least 1");
+        process::exit(1);
+    }
+    if !(args.max_p_value > 0.0 && args.max_p_value <= 1.0) {
+        eprintln!("Maximum p-value must be in range (0,1]");
+        process::exit(1);
+    }
+    if args.output_rules_path.is_empty() && !args.no_rules {
+        eprintln!("--output is required unless --no-rules is passed");
+        process::exit(1);
+    }
+    if args.no_rules && args.preview.is_some() {
+        eprintln!("--preview can't be used with --no-rules");
+        process::exit(1);
+    }
+    if args.no_rules && !args.output_rules_path.is_empty() {
+        eprintln!("--output can't be used with --no-rules");
+        process::exit(1);
+    }
+    if args.manifest && args.no_rules && args.output_itemsets_path.is_none() {
+        eprintln!("--manifest with --no-rules requires --output-itemsets");
+        process::exit(1);
+    }
+    if args.no_rules
+        && (args.html_report_path.is_some()
+            || args.top_per_consequent.is_some()
+            || args.select_covering.is_some()
+            || args.permutations.is_some()
+            || args.coverage_report_path.is_some())
+    {
+        eprintln!(
+            "--html-report, --top-per-consequent, --select-covering, --permutations and \
+             --coverage-report can't be used with --no-rules"
+        );
+        process::exit(1);
+    }
+    if args.target_consequent.is_some()
+        && (args.algorithm != Algorithm::FPGrowth
+            || args.pfp_groups.is_some()
+            || args.item_min_support_path.is_some()
+            || args.no_rules
+            || args.self_check)
+    {
+        eprintln!(
+            "--target-consequent requires --algorithm fpgrowth, and can't be used with \
+             --pfp-groups, --item-min-support, --no-rules or --self-check"
+        );
+        process::exit(1);
+    }
+    if args.target_rules.is_some()
+        && (!args.algorithm.builds_fptree()
+            || args.pfp_groups.is_some()
+            || args.target_consequent.is_some()
+            || args.item_min_support_path.is_some()
+            || args.no_rules)
+    {
+        eprintln!(
+            "--target-rules requires --algorithm fpgrowth or tdfpgrowth, and can't be used \
+             with --pfp-groups, --target-consequent, --item-min-support or --no-rules"
+        );
+        process::exit(1);
+    }
+    if args.self_check
+        && (args.approximate_counts
+            || args.item_min_support_path.is_some()
+            || args.min_all_confidence.is_some()
+            || args.max_support_ratio.is_some()
+            || args.timeout.is_some()
+            || args.max_itemsets.is_some()
+            || args.max_rules.is_some())
+    {
+        eprintln!(
+            "--self-check can't be used with --approximate-counts, --item-min-support, \
+             --min-all-confidence, --max-support-ratio, --timeout, --max-itemsets or \
+             --max-rules"
+        );
+        process::exit(1);
+    }
+    if args.max_itemsets == Some(0) || args.max_rules == Some(0) {
+        eprintln!("--max-itemsets and --max-rules must be at least 1");
+        process::exit(1);
+    }
+    if args.write_partial && args.max_itemsets.is_none() && args.max_rules.is_none() {
+        eprintln!("--write-partial requires --max-itemsets or --max-rules");
+        process::exit(1);
+    }
+    if args.max_rules.is_some() && args.no_rules {
+        eprintln!("--max-rules can't be used with --no-rules");
+        process::exit(1);
+    }
+    if args.permutations == Some(0) {
+        eprintln!("Number of permutations must be at least 1");
+        process::exit(1);
+    }
+    if args.permutations.is_some() && (args.weighted || args.approximate_counts) {
+        eprintln!("--permutations can't be used with --weighted or --approximate-counts");
+        process::exit(1);
+    }
+    if args.top_per_consequent == Some(0) {
+        eprintln!("Number of rules per consequent must be at least 1");
+        process::exit(1);
+    }
+    if args.pfp_groups == Some(0) {
+        eprintln!("Number of PFP groups must be at least 1");
+        process::exit(1);
+    }
+    if args.max_itemset_size == Some(0) {
+        eprintln!("Maximum itemset size must be at least 1");
+        process::exit(1);
+    }
+    if args
+        .max_itemset_size
+        .is_some_and(|max| max < args.min_itemset_size)
+    {
+        eprintln!("Minimum itemset size must not exceed maximum itemset size");
+        process::exit(1);
+    }
+
+    args
+}
+
+fn parse_rules_args_or_exit(arguments: &[String]) -> RulesArguments {
+    let mut args = RulesArguments {
+        itemsets_path: String::new(),
+        itemsets_format: ItemsetsFormat::Arm,
+        input_file_path: None,
+        weighted: false,
+        num_transactions: None,
+        output_rules_path: String::new(),
+        min_confidence: 0.0,
+        min_confidence_by_length: None,
+        min_lift: None,
+        max_lift: None,
+    };
+
+    {
+        let mut parser = ArgumentParser::new();
+        parser.set_description(
+            "Generate rules from an itemsets file written by `arm mine \
+             --output-itemsets` or another tool, without mining the dataset \
+             again. The file must contain all frequent itemsets, not only \
+             long ones, unless the dataset is given to count missing ones.",
+        );
+
+        parser
+            .refer(&mut args.itemsets_path)
+            .add_option(&["--itemsets"], Store, "Input itemsets file.")
+            .metavar("file_path")
+            .required();
+
+        parser
+            .refer(&mut args.itemsets_format)
+            .add_option(
+                &["--itemsets-format"],
+                Store,
+                "Format of the itemsets file: arm, spmf (item1 item2 #SUP: \
+                 count) or mlxtend (support,frozenset({...}) as written by \
+                 pandas). Default arm.",
+            )
+            .metavar("format");
+
+        parser
+            .refer(&mut args.input_file_path)
+            .add_option(
+                &["--input"],
+                StoreOption,
+                "Dataset the itemsets were mined from, in CSV format. If \
+                 given, subsets missing from the itemsets file are counted \
+                 in it.",
+            )
+            .metavar("file_path");
+
+        parser.refer(&mut args.weighted).add_option(
+            &["--weighted"],
+            StoreTrue,
+            "Dataset lines are of the form count;item1,item2,...",
+        );
+
+        parser
+            .refer(&mut args.num_transactions)
+            .add_option(
+                &["--num-transactions"],
+                StoreOption,
+                "Number of transactions in the dataset the itemsets were \
+                 mined from, if the itemsets file doesn't give both counts \
+                 and supports, and --input isn't given.",
+            )
+            .metavar("count");
+
+        parser
+            .refer(&mut args.output_rules_path)
+            .add_option(
+                &["--output"],
+                Store,
+                "File path in which to store output rules.",
+            )
+            .metavar("file_path")
+            .required();
+
+        parser
+            .refer(&mut args.min_confidence)
+            .add_option(
+                &["--min-confidence"],
+                Store,
+                "Minimum rule confidence threshold, in range [0,1].",
+            )
+            .metavar("threshold")
+            .required();
+
+        parser
+            .refer(&mut args.min_confidence_by_length)
+            .add_option(
+                &["--min-confidence-by-length"],
+                StoreOption,
+                "Minimum rule confidence by antecedent length, overriding \
+                 --min-confidence for the lengths given, e.g. \
+                 \"1:0.9,2:0.7,3+:0.5\". A length ending in + applies to \
+                 all longer antecedents without their own threshold.",
+            )
+            .metavar("thresholds");
+
+        parser
+            .refer(&mut args.min_lift)
+            .add_option(
+                &["--min-lift"],
+                StoreOption,
+                "Minimum rule lift confidence threshold, in range [1,∞].",
+            )
+            .metavar("threshold");
+
+        parser
+            .refer(&mut args.max_lift)
+            .add_option(&["--max-lift"], StoreOption, "Maximum rule lift.")
+            .metavar("threshold");
+
+        parse_or_exit(&parser, "arm rules", arguments);
+    }
+
+    check_min_confidence_or_exit(args.min_confidence);
+    check_min_lift_or_exit(args.min_lift);
+    check_max_lift_or_exit(args.min_lift, args.max_lift);
+    if args.num_transactions == Some(0) {
+        eprintln!("Number of transactions must be at least 1");
+        process::exit(1);
+    }
+
+    args
+}
+
+fn parse_stats_args_or_exit(arguments: &[String]) -> StatsArguments {
+    let mut args = StatsArguments {
+        input_file_path: String::new(),
+        weighted: false,
+        input_format: InputFormat::Csv,
+        encoding: Encoding::Utf8,
+        top_items: 10,
+        compare_item_orders: false,
+    };
+
+    {
+        let mut parser = ArgumentParser::new();
+        parser.set_description(
+            "Print summary statistics of a dataset: transaction counts and \
+             lengths, number of distinct items, and the most frequent items.",
+        );
+
+        parser
+            .refer(&mut args.input_file_path)
+            .add_option(&["--input"], Store, "Input dataset in CSV format.")
+            .metavar("file_path")
+            .required();
+
+        parser.refer(&mut args.weighted).add_option(
+            &["--weighted"],
+            StoreTrue,
+            "Input lines are of the form count;item1,item2,...",
+        );
+
+        parser
+            .refer(&mut args.input_format)
+            .add_option(&["--input-format"], Store, INPUT_FORMAT_HELP)
+            .metavar("format");
+
+        parser
+            .refer(&mut args.encoding)
+            .add_option(&["--encoding"], Store, ENCODING_HELP)
+            .metavar("encoding");
+
+        parser
+            .refer(&mut args.top_items)
+            .add_option(
+                &["--top"],
+                Store,
+                "Number of most frequent items to print. Default 10.",
+            )
+            .metavar("count");
+
+        parser.refer(&mut args.compare_item_orders).add_option(
+            &["--compare-item-orders"],
+            StoreTrue,
+            "Build FPTrees of the dataset with items in frequency and in \
+             lexicographic order, and print how many nodes each has. Reads \
+             the dataset once per order.",
+        );
+
+        parse_or_exit(&parser, "arm stats", arguments);
+    }
+    check_weighted_input_or_exit(args.input_format, args.weighted);
+    if args.compare_item_orders && is_stream(&args.input_file_path) {
+        eprintln!("--compare-item-orders can't be used with stdin, Kafka or PostgreSQL input");
+        process::exit(1);
+    }
+
+    args
+}
+
+fn parse_support_args_or_exit(arguments: &[String]) -> SupportArguments {
+    let mut args = SupportArguments {
+        input_file_path: String::new(),
+        weighted: false,
+        input_format: InputFormat::Csv,
+        encoding: Encoding::Utf8,
+        itemsets_path: String::new(),
+        output_path: String::new(),
+        index_backend: IndexBackend::TidList,
+    };
+
+    {
+        let mut parser = ArgumentParser::new();
+        parser.set_description(
+            "Count the exact support of each of a list of itemsets in a \
+             dataset, without mining it. Itemsets are read one per line, with \
+             items separated by spaces; itemsets files written by `arm mine \
+             --output-itemsets` can also be read. Output is an itemsets file.",
+        );
+
+        parser
+            .refer(&mut args.input_file_path)
+            .add_option(&["--input"], Store, "Input dataset in CSV format.")
+            .metavar("file_path")
+            .required();
+
+        parser.refer(&mut args.weighted).add_option(
+            &["--weighted"],
+            StoreTrue,
+            "Input lines are of the form count;item1,item2,...",
+        );
+
+        parser
+            .refer(&mut args.input_format)
+            .add_option(&["--input-format"], Store, INPUT_FORMAT_HELP)
+            .metavar("format");
+
+        parser
+            .refer(&mut args.encoding)
+            .add_option(&["--encoding"], Store, ENCODING_HELP)
+            .metavar("encoding");
+
+        parser
+            .refer(&mut args.itemsets_path)
+            .add_option(&["--itemsets"], Store, "Itemsets to count.")
+            .metavar("file_path")
+            .required();
+
+        parser
+            .refer(&mut args.output_path)
+            .add_option(
+                &["--output"],
+                Store,
+                "File path in which to store the itemsets with their counts.",
+            )
+            .metavar("file_path")
+            .required();
+
+        parser
+            .refer(&mut args.index_backend)
+            .add_option(
+                &["--index-backend"],
+                Store,
+                "Transaction index used to count; one of tidlist (default) \
+                 or roaring.",
+            )
+            .metavar("backend");
+
+        parse_or_exit(&parser, "arm support", arguments);
+    }
+
+    check_weighted_input_or_exit(args.input_format, args.weighted);
+
+    args
+}
+
+fn parse_revalidate_args_or_exit(arguments: &[String]) -> RevalidateArguments {
+    let mut args = RevalidateArguments {
+        rules_path: String::new(),
+        input_file_path: String::new(),
+        weighted: false,
+        input_format: InputFormat::Csv,
+        encoding: Encoding::Utf8,
+        output_path: String::new(),
+        tolerance: 0.01,
+        index_backend: IndexBackend::TidList,
+    };
+
+    {
+        let mut parser = ArgumentParser::new();
+        parser.set_description(
+            "Recompute the confidence, lift and support of the rules in a \
+             rules file against a dataset, such as a newer version of the \
+             dataset the rules were mined from, and report how much each \
+             rule's metrics have drifted.",
+        );
+
+        parser
+            .refer(&mut args.rules_path)
+            .add_option(&["--rules"], Store, "Rules file to revalidate.")
+            .metavar("file_path")
+            .required();
+
+        parser
+            .refer(&mut args.input_file_path)
+            .add_option(&["--input"], Store, "Input dataset in CSV format.")
+            .metavar("file_path")
+            .required();
+
+        parser.refer(&mut args.weighted).add_option(
+            &["--weighted"],
+            StoreTrue,
+            "Input lines are of the form count;item1,item2,...",
+        );
+
+        parser
+            .refer(&mut args.input_format)
+            .add_option(&["--input-format"], Store, INPUT_FORMAT_HELP)
+            .metavar("format");
+
+        parser
+            .refer(&mut args.encoding)
+            .add_option(&["--encoding"], Store, ENCODING_HELP)
+            .metavar("encoding");
+
+        parser
+            .refer(&mut args.output_path)
+            .add_option(
+                &["--output"],
+                Store,
+                "File path in which to store each rule's old and new metrics.",
+            )
+            .metavar("file_path")
+            .required();
+
+        parser
+            .refer(&mut args.tolerance)
+            .add_option(
+                &["--tolerance"],
+                Store,
+                "Rules whose confidence, lift or support changed by more than \
+                 this are counted as drifted. Default 0.01.",
+            )
+            .metavar("tolerance");
+
+        parser
+            .refer(&mut args.index_backend)
+            .add_option(
+                &["--index-backend"],
+                Store,
+                "Transaction index used to count; one of tidlist (default) \
+                 or roaring.",
+            )
+            .metavar("backend");
+
+        parse_or_exit(&parser, "arm revalidate", arguments);
+    }
+
+    check_weighted_input_or_exit(args.input_format, args.weighted);
+
+    if args.tolerance < 0.0 {
+        eprintln!("Tolerance must not be negative");
+        process::exit(1);
+    }
+
+    args
+}
+
+fn parse_diff_args_or_exit(arguments: &[String]) -> DiffArguments {
+    let mut args = DiffArguments {
+        old_rules_path: String::new(),
+        new_rules_path: String::new(),
+        output_path: String::new(),
+        format: DiffFormat::Csv,
+        tolerance: 0.01,
+    };
+
+    {
+        let mut parser = ArgumentParser::new();
+        parser.set_description(
+            "Compare two rules files, such as from successive runs: list the \
+             rules only in the old file, the rules only in the new file, and \
+             the rules in both whose metrics changed.",
+        );
+
+        parser
+            .refer(&mut args.old_rules_path)
+            .add_argument("old", Store, "Old rules file.")
+            .required();
+
+        parser
+            .refer(&mut args.new_rules_path)
+            .add_argument("new", Store, "New rules file.")
+            .required();
+
+        parser
+            .refer(&mut args.output_path)
+            .add_option(
+                &["--output"],
+                Store,
+                "File path in which to store the differences.",
+            )
+            .metavar("file_path")
+            .required();
+
+        parser
+            .refer(&mut args.format)
+            .add_option(
+                &["--format"],
+                Store,
+                "Output format; one of csv (default) or json.",
+            )
+            .metavar("format");
+
+        parser
+            .refer(&mut args.tolerance)
+            .add_option(
+                &["--tolerance"],
+                Store,
+                "Rules in both files whose confidence, lift or support changed \
+                 by more than this are reported. Default 0.01.",
+            )
+            .metavar("tolerance");
+
+        parse_or_exit(&parser, "arm diff", arguments);
+    }
+
+    if args.tolerance < 0.0 {
+        eprintln!("Tolerance must not be negative");
+        process::exit(1);
+    }
+
+    args
+}
+
+fn parse_merge_args_or_exit(arguments: &[String]) -> MergeArguments {
+    let mut args = MergeArguments {
+        itemsets_paths: vec![],
+        input_file_path: String::new(),
+        weighted: false,
+        input_format: InputFormat::Csv,
+        encoding: Encoding::Utf8,
+        output_rules_path: String::new(),
+        output_itemsets_path: None,
+        min_support: 0.0,
+        min_confidence: 0.0,
+        min_lift: None,
+        max_lift: None,
+        index_backend: IndexBackend::TidList,
+    };
+
+    {
+        let mut parser = ArgumentParser::new();
+        parser.set_description(
+            "Merge itemsets files written by `arm mine --output-itemsets` for \
+             partitions of a dataset, each mined with the same minimum \
+             support. The union of the itemsets is recounted over the whole \
+             dataset to find the globally frequent itemsets and their rules.",
+        );
+
+        parser
+            .refer(&mut args.itemsets_paths)
+            .add_argument("itemsets", List, "Itemsets files of the partitions.")
+            .metavar("file_path")
+            .required();
+
+        parser
+            .refer(&mut args.input_file_path)
+            .add_option(&["--input"], Store, "The whole dataset in CSV format.")
+            .metavar("file_path")
+            .required();
+
+        parser.refer(&mut args.weighted).add_option(
+            &["--weighted"],
+            StoreTrue,
+            "Input lines are of the form count;item1,item2,...",
+        );
+
+        parser
+            .refer(&mut args.input_format)
+            .add_option(&["--input-format"], Store, INPUT_FORMAT_HELP)
+            .metavar("format");
+
+        parser
+            .refer(&mut args.encoding)
+            .add_option(&["--encoding"], Store, ENCODING_HELP)
+            .metavar("encoding");
+
+        parser
+            .refer(&mut args.output_rules_path)
+            .add_option(
+                &["--output"],
+                Store,
+                "File path in which to store output rules.",
+            )
+            .metavar("file_path")
+            .required();
+
+        parser
+            .refer(&mut args.output_itemsets_path)
+            .add_option(
+                &["--output-itemsets"],
+                StoreOption,
+                "File path in which to store the globally frequent itemsets.",
+            )
+            .metavar("file_path");
+
+        parser
+            .refer(&mut args.min_support)
+            .add_option(
+                &["--min-support"],
+                Store,
+                "Minimum itemset support threshold, in range [0,1]. Should \
+                 match the threshold the partitions were mined with.",
+            )
+            .metavar("threshold")
+            .required();
+
+        parser
+            .refer(&mut args.min_confidence)
+            .add_option(
+                &["--min-confidence"],
+                Store,
+                "Minimum rule confidence threshold, in range [0,1].",
+            )
+            .metavar("threshold")
+            .required();
+
+        parser
+            .refer(&mut args.min_lift)
+            .add_option(
+                &["--min-lift"],
+                StoreOption,
+                "Minimum rule lift confidence threshold, in range [1,∞].",
+            )
+            .metavar("threshold");
+
+        parser
+            .refer(&mut args.max_lift)
+            .add_option(&["--max-lift"], StoreOption, "Maximum rule lift.")
+            .metavar("threshold");
+
+        parser
+            .refer(&mut args.index_backend)
+            .add_option(
+                &["--index-backend"],
+                Store,
+                "Transaction index used to count; one of tidlist (default) \
+                 or roaring.",
+            )
+            .metavar("backend");
+
+        parse_or_exit(&parser, "arm merge", arguments);
+    }
+
+    check_weighted_input_or_exit(args.input_format, args.weighted);
+
+    check_min_support_or_exit(args.min_support);
+    check_min_confidence_or_exit(args.min_confidence);
+    check_min_lift_or_exit(args.min_lift);
+    check_max_lift_or_exit(args.min_lift, args.max_lift);
+
+    args
+}
+
+fn parse_segments_args_or_exit(arguments: &[String]) -> SegmentsArguments {
+    let mut args = SegmentsArguments {
+        input_file_path: String::new(),
+        segment_column: 0,
+        output_rules_path: String::new(),
+        comparison_path: None,
+        min_support: 0.0,
+        min_confidence: 0.0,
+        min_lift: None,
+        max_lift: None,
+        max_itemset_size: None,
+    };
+
+    {
+        let mut parser = ArgumentParser::new();
+        parser.set_description(
+            "Mine rules separately for each segment of a dataset, such as each \
+             store region, in one pass over the data. Each line names its \
+             segment in one column; the other columns are its items.",
+        );
+
+        parser
+            .refer(&mut args.input_file_path)
+            .add_option(&["--input"], Store, "Input dataset in CSV format.")
+            .metavar("file_path")
+            .required();
+
+        parser
+            .refer(&mut args.segment_column)
+            .add_option(
+                &["--segment-column"],
+                Store,
+                "Column of each line holding the segment, counting from 0. \
+                 Default 0.",
+            )
+            .metavar("column");
+
+        parser
+            .refer(&mut args.output_rules_path)
+            .add_option(
+                &["--output"],
+                Store,
+                "File path in which to store the rules, tagged by segment.",
+            )
+            .metavar("file_path")
+            .required();
+
+        parser
+            .refer(&mut args.comparison_path)
+            .add_option(
+                &["--comparison"],
+                StoreOption,
+                "File path in which to store a table of each rule's metrics \
+                 in each segment.",
+            )
+            .metavar("file_path");
+
+        parser
+            .refer(&mut args.min_support)
+            .add_option(
+                &["--min-support"],
+                Store,
+                "Minimum itemset support threshold within each segment, in \
+                 range [0,1].",
+            )
+            .metavar("threshold")
+            .required();
+
+        parser
+            .refer(&mut args.min_confidence)
+            .add_option(
+                &["--min-confidence"],
+                Store,
+                "Minimum rule confidence threshold, in range [0,1].",
+            )
+            .metavar("threshold")
+            .required();
+
+        parser
+            .refer(&mut args.min_lift)
+            .add_option(
+                &["--min-lift"],
+                StoreOption,
+                "Minimum rule lift confidence threshold, in range [1,∞].",
+            )
+            .metavar("threshold");
+
+        parser
+            .refer(&mut args.max_lift)
+            .add_option(&["--max-lift"], StoreOption, "Maximum rule lift.")
+            .metavar("threshold");
+
+        parser
+            .refer(&mut args.max_itemset_size)
+            .add_option(
+                &["--max-itemset-size"],
+                StoreOption,
+                "Don't generate itemsets longer than this.",
+            )
+            .metavar("size");
+
+        parse_or_exit(&parser, "arm segments", arguments);
+    }
+
+    check_min_support_or_exit(args.min_support);
+    check_min_confidence_or_exit(args.min_confidence);
+    check_min_lift_or_exit(args.min_lift);
+    check_max_lift_or_exit(args.min_lift, args.max_lift);
+    if args.max_itemset_size == Some(0) {
+        eprintln!("Maximum itemset size must be at least 1");
+        process::exit(1);
+    }
+
+    args
+}
+
+fn parse_trends_args_or_exit(arguments: &[String]) -> TrendsArguments {
+    let mut args = TrendsArguments {
+        input_file_path: String::new(),
+        timestamp_column: 0,
+        window: Window {
+            seconds: 7 * 24 * 60 * 60,
+        },
+        output_path: String::new(),
+        min_support: 0.0,
+        min_confidence: 0.0,
+        min_lift: None,
+        max_itemset_size: None,
+        trend_threshold: 0.1,
+    };
+
+    {
+        let mut parser = ArgumentParser::new();
+        parser.set_description(
+            "Split a dataset into time windows, mine rules in each window, \
+             and write each rule's support and confidence in every window, \
+             with whether it's emerging, fading or stable. Each line has a \
+             timestamp in one column; the other columns are its items.",
+        );
+
+        parser
+            .refer(&mut args.input_file_path)
+            .add_option(&["--input"], Store, "Input dataset in CSV format.")
+            .metavar("file_path")
+            .required();
+
+        parser
+            .refer(&mut args.timestamp_column)
+            .add_option(
+                &["--timestamp-column"],
+                Store,
+                "Column of each line holding the timestamp, counting from 0. \
+                 Timestamps are Unix seconds, or UTC dates and times such as \
+                 2024-03-01 or 2024-03-01T09:30:00. Default 0.",
+            )
+            .metavar("column");
+
+        parser
+            .refer(&mut args.window)
+            .add_option(
+                &["--window"],
+                Store,
+                "Length of each time window, as a count and a unit of s, m, \
+                 h, d or w, e.g. 1d. Windows are aligned to the Unix epoch. \
+                 Default 1w.",
+            )
+            .metavar("length");
+
+        parser
+            .refer(&mut args.output_path)
+            .add_option(
+                &["--output"],
+                Store,
+                "File path in which to store each rule's time series.",
+            )
+            .metavar("file_path")
+            .required();
+
+        parser
+            .refer(&mut args.min_support)
+            .add_option(
+                &["--min-support"],
+                Store,
+                "Minimum itemset support threshold within each window, in \
+                 range [0,1].",
+            )
+            .metavar("threshold")
+            .required();
+
+        parser
+            .refer(&mut args.min_confidence)
+            .add_option(
+                &["--min-confidence"],
+                Store,
+                "Minimum rule confidence threshold, in range [0,1].",
+            )
+            .metavar("threshold")
+            .required();
+
+        parser
+            .refer(&mut args.min_lift)
+            .add_option(
+                &["--min-lift"],
+                StoreOption,
+                "Minimum rule lift confidence threshold, in range [1,∞].",
+            )
+            .metavar("threshold");
+
+        parser
+            .refer(&mut args.max_itemset_size)
+            .add_option(
+                &["--max-itemset-size"],
+                StoreOption,
+                "Don't generate itemsets longer than this.",
+            )
+            .metavar("size");
+
+        parser
+            .refer(&mut args.trend_threshold)
+            .add_option(
+                &["--trend-threshold"],
+                Store,
+                "Rules whose support changes by more than this fraction of \
+                 their mean support per window are emerging or fading; \
+                 others are stable. Default 0.1.",
+            )
+            .metavar("fraction");
+
+        parse_or_exit(&parser, "arm trends", arguments);
+    }
+
+    check_min_support_or_exit(args.min_support);
+    check_min_confidence_or_exit(args.min_confidence);
+    check_min_lift_or_exit(args.min_lift);
+    if args.max_itemset_size == Some(0) {
+        eprintln!("Maximum itemset size must be at least 1");
+        process::exit(1);
+    }
+    if args.trend_threshold < 0.0 {
+        eprintln!("Trend threshold must not be negative");
+        process::exit(1);
+    }
+
+    args
+}
+fn parse_query_args_or_exit(arguments: &[String]) -> QueryArguments {
+    let mut args = QueryArguments {
+        rules_path: String::new(),
+        items: vec![],
+        side: String::from("any"),
+        min_confidence: 0.0,
+        min_lift: 0.0,
+        sort_by: String::from("lift"),
+        limit: None,
+    };
+
+    {
+        let mut parser = ArgumentParser::new();
+        parser.set_description(
+            "Print rules from a rules file which contain all the given items, \
+             in the same format as the rules file.",
+        );
+
+        parser
+            .refer(&mut args.rules_path)
+            .add_option(&["--rules"], Store, "Input rules file.")
+            .metavar("file_path")
+            .required();
+
+        parser
+            .refer(&mut args.items)
+            .add_option(
+                &["--item"],
+                Collect,
+                "Item which matching rules must contain. Pass multiple times \
+                 to match rules containing all of the items.",
+            )
+            .metavar("item");
+
+        parser
+            .refer(&mut args.side)
+            .add_option(
+                &["--in"],
+                Store,
+                "Where the items must be; one of antecedent, consequent or \
+                 any. Default any.",
+            )
+            .metavar("side");
+
+        parser
+            .refer(&mut args.min_confidence)
+            .add_option(&["--min-confidence"], Store, "Minimum rule confidence.")
+            .metavar("threshold");
+
+        parser
+            .refer(&mut args.min_lift)
+            .add_option(&["--min-lift"], Store, "Minimum rule lift.")
+            .metavar("threshold");
+
+        parser
+            .refer(&mut args.sort_by)
+            .add_option(
+                &["--sort"],
+                Store,
+                "Sort matching rules by descending confidence, lift or \
+                 support. Default lift.",
+            )
+            .metavar("metric");
+
+        parser
+            .refer(&mut args.limit)
+            .add_option(
+                &["--limit"],
+                StoreOption,
+                "Maximum number of rules to print.",
+            )
+            .metavar("count");
+
+        parse_or_exit(&parser, "arm query", arguments);
+    }
+
+    if !["antecedent", "consequent", "any"].contains(&args.side.as_str()) {
+        eprintln!("--in must be one of antecedent, consequent or any");
+        process::exit(1);
+    }
+    if !["confidence", "lift", "support"].contains(&args.sort_by.as_str()) {
+        eprintln!("--sort must be one of confidence, lift or support");
+        process::exit(1);
+    }
+
+    args
+}
+
+fn parse_recommend_args_or_exit(arguments: &[String]) -> RecommendArguments {
+    let mut args = RecommendArguments {
+        rules_path: String::new(),
+        basket: String::new(),
+        top: 10,
+    };
+
+    {
+        let mut parser = ArgumentParser::new();
+        parser.set_description(
+            "Recommend items to add to a basket. Items are scored by the \
+             highest confidence of the rules whose antecedent is contained \
+             in the basket and whose consequent contains the item.",
+        );
+
+        parser
+            .refer(&mut args.rules_path)
+            .add_option(&["--rules"], Store, "Input rules file.")
+            .metavar("file_path")
+            .required();
+
+        parser
+            .refer(&mut args.basket)
+            .add_option(
+                &["--basket"],
+                Store,
+                "Items in the basket, separated by commas.",
+            )
+            .metavar("items")
+            .required();
+
+        parser
+            .refer(&mut args.top)
+            .add_option(
+                &["--top"],
+                Store,
+                "Number of items to recommend. Default 10.",
+            )
+            .metavar("count");
+
+        parse_or_exit(&parser, "arm recommend", arguments);
+    }
+
+    args
+}
+
+fn parse_evaluate_args_or_exit(arguments: &[String]) -> EvaluateArguments {
+    let mut args = EvaluateArguments {
+        rules_path: String::new(),
+        input_file_path: String::new(),
+        input_format: InputFormat::Csv,
+        encoding: Encoding::Utf8,
+        top: 10,
+        seed: 1,
     };
 
     {
         let mut parser = ArgumentParser::new();
-        parser.set_description("Light weight parallel FPGrowth in Rust.");
+        parser.set_description(
+            "Evaluate a rules file as a recommender. One item is hidden from \
+             each test basket, items are recommended for the rest of the \
+             basket as by `arm recommend`, and the hit rate, precision and \
+             mean reciprocal rank of the hidden item are reported.",
+        );
+
+        parser
+            .refer(&mut args.rules_path)
+            .add_option(&["--rules"], Store, "Input rules file.")
+            .metavar("file_path")
+            .required();
 
         parser
             .refer(&mut args.input_file_path)
-            .add_option(&["--input"], Store, "Input dataset in CSV format.")
+            .add_option(
+                &["--input"],
+                Store,
+                "Test baskets, ideally not those the rules were mined \
+                 from.",
+            )
             .metavar("file_path")
             .required();
 
         parser
-            .refer(&mut args.output_rules_path)
+            .refer(&mut args.input_format)
+            .add_option(&["--input-format"], Store, INPUT_FORMAT_HELP)
+            .metavar("format");
+
+        parser
+            .refer(&mut args.encoding)
+            .add_option(&["--encoding"], Store, ENCODING_HELP)
+            .metavar("encoding");
+
+        parser
+            .refer(&mut args.top)
             .add_option(
-                &["--output"],
+                &["--top"],
                 Store,
-                "File path in which to store output rules. \
-                 Format: antecedent -> consequent, confidence, lift, support.",
+                "Number of items to recommend for each basket. Default 10.",
+            )
+            .metavar("k");
+
+        parser
+            .refer(&mut args.seed)
+            .add_option(
+                &["--seed"],
+                Store,
+                "Seed for choosing which item to hide. Default 1.",
             )
+            .metavar("seed");
+
+        parse_or_exit(&parser, "arm evaluate", arguments);
+    }
+
+    if args.top == 0 {
+        eprintln!("Number of items to recommend must be at least 1");
+        process::exit(1);
+    }
+
+    args
+}
+
+fn parse_score_args_or_exit(arguments: &[String]) -> ScoreArguments {
+    let mut args = ScoreArguments {
+        rules_path: String::new(),
+        input_file_path: String::new(),
+        input_format: InputFormat::Csv,
+        encoding: Encoding::Utf8,
+        output_path: String::new(),
+        min_confidence: 0.9,
+        min_lift: None,
+        top: 10,
+    };
+
+    {
+        let mut parser = ArgumentParser::new();
+        parser.set_description(
+            "Score transactions as anomalous by the high confidence rules \
+             they violate, that is, whose antecedents they contain but not \
+             their consequents. A transaction's score is the sum of the \
+             confidences of the rules it violates. Writes each \
+             transaction's number, score, number of rules violated \
+             and the violated rule with the highest confidence.",
+        );
+
+        parser
+            .refer(&mut args.rules_path)
+            .add_option(&["--rules"], Store, "Input rules file.")
             .metavar("file_path")
             .required();
 
         parser
-            .refer(&mut args.min_support)
+            .refer(&mut args.input_file_path)
+            .add_option(&["--input"], Store, "Transactions to score.")
+            .metavar("file_path")
+            .required();
+
+        parser
+            .refer(&mut args.input_format)
+            .add_option(&["--input-format"], Store, INPUT_FORMAT_HELP)
+            .metavar("format");
+
+        parser
+            .refer(&mut args.encoding)
+            .add_option(&["--encoding"], Store, ENCODING_HELP)
+            .metavar("encoding");
+
+        parser
+            .refer(&mut args.output_path)
             .add_option(
-                &["--min-support"],
+                &["--output"],
                 Store,
-                "Minimum itemset support threshold, in range [0,1].",
+                "File path in which to store each transaction's score.",
             )
-            .metavar("threshold")
+            .metavar("file_path")
             .required();
 
         parser
@@ -71,49 +2994,342 @@ pub fn parse_args_or_exit() -> Arguments {
             .add_option(
                 &["--min-confidence"],
                 Store,
-                "Minimum rule confidence threshold, in range [0,1].",
+                "Only rules with at least this confidence are checked. \
+                 Default 0.9.",
             )
-            .metavar("threshold")
-            .required();
+            .metavar("threshold");
 
         parser
             .refer(&mut args.min_lift)
             .add_option(
                 &["--min-lift"],
                 StoreOption,
-                "Minimum rule lift confidence threshold, in range [1,∞].",
+                "Only rules with at least this lift are checked.",
             )
             .metavar("threshold");
 
-        if env::args().count() == 1 {
-            parser.print_help("Usage:", &mut io::stderr()).unwrap();
-            process::exit(1);
-        }
+        parser
+            .refer(&mut args.top)
+            .add_option(
+                &["--top"],
+                Store,
+                "Number of most anomalous transactions to print. Default 10.",
+            )
+            .metavar("count");
 
-        match parser.parse_args() {
-            Ok(()) => {}
-            Err(err) => {
-                process::exit(err);
-            }
-        }
+        parse_or_exit(&parser, "arm score", arguments);
     }
+    check_min_confidence_or_exit(args.min_confidence);
+    check_min_lift_or_exit(args.min_lift);
 
-    if args.min_support < 0.0 || args.min_support > 1.0 {
-        eprintln!("Minimum itemset support must be in range [0,1]");
+    args
+}
+
+fn parse_cluster_args_or_exit(arguments: &[String]) -> ClusterArguments {
+    let mut args = ClusterArguments {
+        rules_path: String::new(),
+        output_path: String::new(),
+        membership_path: None,
+        min_similarity: 0.5,
+    };
+
+    {
+        let mut parser = ArgumentParser::new();
+        parser.set_description(
+            "Cluster the rules in a rules file by the Jaccard similarity of \
+             their items, and write the strongest rule of each cluster, to \
+             reduce near-duplicate rules to one representative each.",
+        );
+
+        parser
+            .refer(&mut args.rules_path)
+            .add_option(&["--rules"], Store, "Input rules file.")
+            .metavar("file_path")
+            .required();
+
+        parser
+            .refer(&mut args.output_path)
+            .add_option(
+                &["--output"],
+                Store,
+                "File path in which to store the representative rules.",
+            )
+            .metavar("file_path")
+            .required();
+
+        parser
+            .refer(&mut args.membership_path)
+            .add_option(
+                &["--membership"],
+                StoreOption,
+                "File path in which to store the cluster of each rule, and \
+                 its similarity to the cluster's representative.",
+            )
+            .metavar("file_path");
+
+        parser
+            .refer(&mut args.min_similarity)
+            .add_option(
+                &["--min-similarity"],
+                Store,
+                "Minimum Jaccard similarity of a rule's items to its \
+                 cluster's representative's items. Default 0.5.",
+            )
+            .metavar("threshold");
+
+        parse_or_exit(&parser, "arm cluster", arguments);
+    }
+
+    if !(args.min_similarity > 0.0 && args.min_similarity <= 1.0) {
+        eprintln!("Minimum similarity must be in range (0,1]");
         process::exit(1);
     }
 
-    if args.min_confidence < 0.0 || args.min_confidence > 1.0 {
-        eprintln!("Minimum rule confidence threshold must be in range [0,1]");
+    args
+}
+
+fn parse_tui_args_or_exit(arguments: &[String]) -> TuiArguments {
+    let mut args = TuiArguments {
+        rules_path: None,
+        itemsets_path: None,
+        itemsets_format: ItemsetsFormat::Arm,
+    };
+
+    {
+        let mut parser = ArgumentParser::new();
+        parser.set_description(
+            "Explore a rules or itemsets file in an interactive terminal UI. \
+             Type / to filter by items, 1-9 to sort by a column, left and \
+             right to pick an item of the selected row and Enter to drill \
+             into the rows containing it, Esc to go back, e to export the \
+             rows in view, and q to quit. Requires building with --features \
+             tui.",
+        );
+
+        parser
+            .refer(&mut args.rules_path)
+            .add_option(&["--rules"], StoreOption, "Input rules file.")
+            .metavar("file_path");
+
+        parser
+            .refer(&mut args.itemsets_path)
+            .add_option(&["--itemsets"], StoreOption, "Input itemsets file.")
+            .metavar("file_path");
+
+        parser
+            .refer(&mut args.itemsets_format)
+            .add_option(
+                &["--itemsets-format"],
+                Store,
+                "Format of the itemsets file: arm, spmf (item1 item2 #SUP: \
+                 count) or mlxtend (support,frozenset({...}) as written by \
+                 pandas). Default arm.",
+            )
+            .metavar("format");
+
+        parse_or_exit(&parser, "arm tui", arguments);
+    }
+
+    if args.rules_path.is_some() == args.itemsets_path.is_some() {
+        eprintln!("Exactly one of --rules and --itemsets is required");
         process::exit(1);
     }
 
-    args.min_lift.as_ref().map(|&min_lift| {
-        if min_lift < 1.0 {
-            println!("Minimum lift must be in range [1,∞]");
-            process::exit(1);
-        }
-    });
+    args
+}
+
+fn parse_gen_args_or_exit(arguments: &[String]) -> GenArguments {
+    let mut args = GenArguments {
+        output_path: String::new(),
+        num_transactions: 10_000,
+        num_items: 1_000,
+        avg_length: 10,
+        seed: 0,
+    };
+
+    {
+        let mut parser = ArgumentParser::new();
+        parser.set_description(
+            "Generate a synthetic dataset in CSV format, with item \
+             popularity following a Zipf-like distribution.",
+        );
+
+        parser
+            .refer(&mut args.output_path)
+            .add_option(
+                &["--output"],
+                Store,
+                "File path in which to store the dataset.",
+            )
+            .metavar("file_path")
+            .required();
+
+        parser
+            .refer(&mut args.num_transactions)
+            .add_option(
+                &["--transactions"],
+                Store,
+                "Number of transactions. Default 10000.",
+            )
+            .metavar("count");
+
+        parser
+            .refer(&mut args.num_items)
+            .add_option(
+                &["--items"],
+                Store,
+                "Number of distinct items. Default 1000.",
+            )
+            .metavar("count");
+
+        parser
+            .refer(&mut args.avg_length)
+            .add_option(
+                &["--avg-length"],
+                Store,
+                "Average number of items per transaction. Default 10.",
+            )
+            .metavar("count");
+
+        parser
+            .refer(&mut args.seed)
+            .add_option(
+                &["--seed"],
+                Store,
+                "Random number generator seed. Default 0.",
+            )
+            .metavar("seed");
+
+        parse_or_exit(&parser, "arm gen", arguments);
+    }
+
+    if args.num_items == 0 || args.avg_length == 0 {
+        eprintln!("--items and --avg-length must be at least 1");
+        process::exit(1);
+    }
+
+    args
+}
+
+fn parse_convert_args_or_exit(arguments: &[String]) -> ConvertArguments {
+    let mut args = ConvertArguments {
+        input_file_path: String::new(),
+        input_format: InputFormat::Csv,
+        weighted: false,
+        encoding: Encoding::Utf8,
+        output_path: String::new(),
+        output_format: TransactionFormat::Csv,
+    };
+
+    {
+        let mut parser = ArgumentParser::new();
+        parser.set_description(
+            "Convert a dataset from one transaction format to another. \
+             Items are written in lexicographic order.",
+        );
+
+        parser
+            .refer(&mut args.input_file_path)
+            .add_option(&["--input"], Store, "Input dataset.")
+            .metavar("file_path")
+            .required();
+
+        parser
+            .refer(&mut args.input_format)
+            .add_option(&["--input-format"], Store, INPUT_FORMAT_HELP)
+            .metavar("format");
+
+        parser.refer(&mut args.weighted).add_option(
+            &["--weighted"],
+            StoreTrue,
+            "Input lines are of the form count;items. CSV output keeps the \
+             counts; other output formats repeat each transaction count \
+             times.",
+        );
+
+        parser
+            .refer(&mut args.encoding)
+            .add_option(&["--encoding"], Store, ENCODING_HELP)
+            .metavar("encoding");
+
+        parser
+            .refer(&mut args.output_path)
+            .add_option(
+                &["--output"],
+                Store,
+                "File path in which to store the converted dataset.",
+            )
+            .metavar("file_path")
+            .required();
+
+        parser
+            .refer(&mut args.output_format)
+            .add_option(
+                &["--output-format"],
+                Store,
+                "Format of the output: csv, items separated by commas and \
+                 quoted if need be; spmf, space separated integer items, \
+                 with @ITEM lines naming them unless the items are already \
+                 integers; onehot, a CSV file with a header row of items \
+                 and a 0 or 1 cell per item in each row; or jsonl, a JSON \
+                 array of items per line. Default csv.",
+            )
+            .metavar("format");
+
+        parse_or_exit(&parser, "arm convert", arguments);
+    }
+    check_weighted_input_or_exit(args.input_format, args.weighted);
 
     args
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{exact_count_options, parse_mine_args_or_exit};
+
+    #[test]
+    fn test_no_rules_without_min_confidence() {
+        let arguments: Vec<String> = [
+            "--input",
+            "datasets/UCI-zoo.csv",
+            "--min-support",
+            "0.3",
+            "--no-rules",
+            "--output-itemsets",
+            "itemsets.csv",
+        ]
+        .iter()
+        .map(|&s| String::from(s))
+        .collect();
+        let args = parse_mine_args_or_exit("arm", &arguments);
+        assert!(args.no_rules);
+        assert_eq!(args.output_itemsets_path.as_deref(), Some("itemsets.csv"));
+    }
+
+    #[test]
+    fn test_exact_count_options() {
+        let arguments: Vec<String> = [
+            "--input",
+            "datasets/UCI-zoo.csv",
+            "--min-support",
+            "0.3",
+            "--min-confidence",
+            "0.5",
+            "--output",
+            "rules.csv",
+            "--dump-tree",
+            "tree.dot",
+            "--verify",
+        ]
+        .iter()
+        .map(|&s| String::from(s))
+        .collect();
+        let mut args = parse_mine_args_or_exit("arm", &arguments);
+        // The FPTree dump has every exact count, so can't be written under
+        // differential privacy.
+        assert_eq!(exact_count_options(&args), vec!["--verify", "--dump-tree"]);
+        args.dump_tree_path = None;
+        args.verify = false;
+        assert!(exact_count_options(&args).is_empty());
+    }
+}
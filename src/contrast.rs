@@ -0,0 +1,227 @@
+// Copyright 2018 Chris Pearce
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use command_line_args::Arguments;
+use error::ArmError;
+use fnv::FnvHashSet;
+use fptree::ItemSet;
+use index::TransactionIndex;
+use item_map::ItemMap;
+use itemizer::Itemizer;
+use output::{write_item_slice, OutputFile};
+use rayon::prelude::*;
+use std::cmp::Ordering;
+use std::io::{self, Write};
+use table::TableOptions;
+use transaction_reader::TransactionReader;
+
+// An itemset mined from the target dataset, compared with its support in a
+// baseline dataset, such as sales before and after a promotion.
+pub struct Contrast {
+    pub itemset: ItemSet,
+    pub support: f64,
+    pub baseline_count: u32,
+    pub baseline_support: f64,
+    // Support in the target divided by support in the baseline; infinite
+    // if the itemset doesn't occur in the baseline.
+    pub growth_rate: f64,
+    // Pearson's chi-squared statistic of the 2x2 table of dataset by
+    // whether transactions contain the itemset, and its p-value.
+    pub chi_squared: f64,
+    pub p_value: f64,
+}
+
+// Complementary error function, with fractional error below 1.2e-7.
+// From Numerical Recipes, section 6.2.
+fn erfc(x: f64) -> f64 {
+    let z = x.abs();
+    let t = 1.0 / (1.0 + 0.5 * z);
+    let poly = -1.265_512_23
+        + t * (1.000_023_68
+            + t * (0.374_091_96
+                + t * (0.096_784_18
+                    + t * (-0.186_288_06
+                        + t * (0.278_868_07
+                            + t * (-1.135_203_98
+                                + t * (1.488_515_87 + t * (-0.822_152_23 + t * 0.170_872_77))))))));
+    let result = t * (-z * z + poly).exp();
+    if x >= 0.0 {
+        result
+    } else {
+        2.0 - result
+    }
+}
+
+// Returns Pearson's chi-squared statistic for a 2x2 contingency table of
+// count of n transactions in one dataset and baseline_count of baseline_n
+// in the other, and its p-value with one degree of freedom.
+fn chi_squared_test(count: u32, n: usize, baseline_count: u32, baseline_n: usize) -> (f64, f64) {
+    let (a, b) = (count as f64, (n - count as usize) as f64);
+    let (c, d) = (
+        baseline_count as f64,
+        (baseline_n - baseline_count as usize) as f64,
+    );
+    let total = a + b + c + d;
+    let denominator = (a + b) * (c + d) * (a + c) * (b + d);
+    if denominator == 0.0 {
+        return (0.0, 1.0);
+    }
+    let chi_squared = total * (a * d - b * c).powi(2) / denominator;
+    (chi_squared, erfc((chi_squared / 2.0).sqrt()))
+}
+
+// Builds an index of the baseline dataset at path, containing only the items
+// in itemsets. Items are read with the same itemizer, item map and input
+// options as the target dataset, including any fitted table bins, so they
+// have the same ids.
+pub fn index_baseline(
+    path: &str,
+    args: &Arguments,
+    table_options: &TableOptions,
+    item_map: Option<&ItemMap>,
+    itemsets: &[ItemSet],
+    itemizer: &mut Itemizer,
+) -> Result<Box<dyn TransactionIndex>, ArmError> {
+    let items: FnvHashSet<String> = itemsets
+        .iter()
+        .flat_map(|itemset| itemset.items.iter())
+        .map(|&item| String::from(itemizer.str_of(item)))
+        .collect();
+    let item_filter = |item: &str| items.contains(item);
+    let mut index = args.index_backend.new_index();
+    let reader = TransactionReader::new(path, itemizer)?
+        .with_item_map(item_map)
+        .with_weights(args.weighted)
+        .with_format(args.input_format)
+        .with_table_options(table_options)
+        .with_encoding(args.encoding)
+        .with_item_filter(Some(&item_filter));
+    for transaction in reader {
+        let (transaction, count) = transaction?;
+        index.insert(&transaction, count);
+    }
+    index.finish();
+    Ok(index)
+}
+
+// Returns the itemsets whose support grew by at least min_growth_rate from
+// the baseline, significant at max_p_value, in descending order of growth
+// rate and then support.
+pub fn emerging_patterns(
+    itemsets: &[ItemSet],
+    num_transactions: usize,
+    baseline: &dyn TransactionIndex,
+    min_growth_rate: f64,
+    max_p_value: f64,
+) -> Vec<Contrast> {
+    let baseline_n = baseline.transaction_count();
+    let mut contrasts: Vec<Contrast> = itemsets
+        .par_iter()
+        .map(|itemset| {
+            let baseline_count = baseline.count(&itemset.items) as u32;
+            let support = itemset.count as f64 / num_transactions as f64;
+            let baseline_support = if baseline_n == 0 {
+                0.0
+            } else {
+                baseline_count as f64 / baseline_n as f64
+            };
+            let (chi_squared, p_value) =
+                chi_squared_test(itemset.count, num_transactions, baseline_count, baseline_n);
+            Contrast {
+                itemset: itemset.clone(),
+                support,
+                baseline_count,
+                baseline_support,
+                growth_rate: support / baseline_support,
+                chi_squared,
+                p_value,
+            }
+        })
+        .filter(|contrast| {
+            contrast.growth_rate >= min_growth_rate && contrast.p_value <= max_p_value
+        })
+        .collect();
+    contrasts.sort_by(|a, b| {
+        b.growth_rate
+            .partial_cmp(&a.growth_rate)
+            .unwrap_or(Ordering::Equal)
+            .then(b.itemset.count.cmp(&a.itemset.count))
+    });
+    contrasts
+}
+
+pub fn write_contrasts(contrasts: &[Contrast], path: &str, itemizer: &Itemizer) -> io::Result<()> {
+    let mut output = OutputFile::create(path, None)?;
+    writeln!(
+        output,
+        "Itemset,Support,BaselineSupport,Count,BaselineCount,GrowthRate,ChiSquared,PValue"
+    )?;
+    for contrast in contrasts {
+        write_item_slice(&mut output, &contrast.itemset.items, itemizer)?;
+        writeln!(
+            output,
+            ",{},{},{},{},{},{},{}",
+            contrast.support,
+            contrast.baseline_support,
+            contrast.itemset.count,
+            contrast.baseline_count,
+            contrast.growth_rate,
+            contrast.chi_squared,
+            contrast.p_value
+        )?;
+    }
+    output.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{chi_squared_test, emerging_patterns, erfc};
+    use fptree::ItemSet;
+    use index::IndexBackend;
+    use item::Item;
+
+    #[test]
+    fn test_emerging_patterns() {
+        assert!((erfc(0.0) - 1.0).abs() < 1e-6);
+        assert!((erfc(1.0) - 0.157_299_21).abs() < 1e-6);
+        assert!((erfc(-1.0) - 1.842_700_79).abs() < 1e-6);
+        // 30 of 100 against 10 of 100 gives chi-squared 12.5, p = 0.0004.
+        let (chi_squared, p_value) = chi_squared_test(30, 100, 10, 100);
+        assert!((chi_squared - 12.5).abs() < 1e-9);
+        assert!((p_value - 0.000_406_952).abs() < 1e-6);
+
+        let (a, b) = (Item::with_id(1), Item::with_id(2));
+        let mut baseline = IndexBackend::TidList.new_index();
+        for _ in 0..10 {
+            baseline.insert(&[a, b], 1);
+        }
+        for _ in 0..30 {
+            baseline.insert(&[a], 1);
+        }
+        for _ in 0..60 {
+            baseline.insert(&[b], 1);
+        }
+        baseline.finish();
+        let itemsets = vec![
+            ItemSet::new(vec![a], 40),
+            ItemSet::new(vec![b], 70),
+            ItemSet::new(vec![a, b], 30),
+        ];
+        let contrasts = emerging_patterns(&itemsets, 100, baseline.as_ref(), 2.0, 0.05);
+        assert_eq!(contrasts.len(), 1);
+        assert_eq!(contrasts[0].itemset.items, vec![a, b]);
+        assert!((contrasts[0].growth_rate - 3.0).abs() < 1e-9);
+        assert_eq!(contrasts[0].baseline_count, 10);
+    }
+}
@@ -0,0 +1,269 @@
+// Copyright 2018 Chris Pearce
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use command_line_args::ConvertArguments;
+use counter::Counter;
+use error::ArmError;
+use input::csv_line;
+use item::Item;
+use itemizer::Itemizer;
+use mining::{count_item_frequencies, duration_as_ms, read_transactions, remap_items};
+use serde_json;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::str::FromStr;
+use std::time::Instant;
+use transaction_reader::TransactionReader;
+
+// Formats in which convert writes transactions. Each can also be read as an
+// input format.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TransactionFormat {
+    Csv,
+    Spmf,
+    OneHot,
+    Jsonl,
+}
+
+impl FromStr for TransactionFormat {
+    type Err = String;
+    fn from_str(s: &str) -> Result<TransactionFormat, String> {
+        match s {
+            "csv" => Ok(TransactionFormat::Csv),
+            "spmf" => Ok(TransactionFormat::Spmf),
+            "onehot" => Ok(TransactionFormat::OneHot),
+            "jsonl" => Ok(TransactionFormat::Jsonl),
+            _ => Err(format!("Unknown output format {}", s)),
+        }
+    }
+}
+
+// Writes transactions, whose items are ids in the itemizer in lexicographic
+// order, in format. If weighted, CSV lines are written as count;items, and
+// otherwise each transaction is written count times.
+pub fn write_transactions<W: Write>(
+    output: &mut W,
+    format: TransactionFormat,
+    transactions: &[(Vec<Item>, u32)],
+    itemizer: &Itemizer,
+    weighted: bool,
+) -> io::Result<()> {
+    let mut items: Vec<Item> = transactions
+        .iter()
+        .flat_map(|(transaction, _)| transaction.iter().cloned())
+        .collect();
+    items.sort_unstable();
+    items.dedup();
+    // SPMF items are positive integers, so items which are already numbers
+    // are written as they are, and others are numbered by their ids and
+    // named in @ITEM lines.
+    let numeric = items
+        .iter()
+        .all(|&item| itemizer.str_of(item).parse::<u32>().is_ok_and(|id| id > 0));
+    match format {
+        TransactionFormat::Spmf if !numeric => {
+            writeln!(output, "@CONVERTED_FROM_TEXT")?;
+            for &item in &items {
+                writeln!(
+                    output,
+                    "@ITEM={}={}",
+                    item.as_index(),
+                    itemizer.str_of(item)
+                )?;
+            }
+        }
+        TransactionFormat::OneHot => {
+            let names: Vec<&str> = items.iter().map(|&item| itemizer.str_of(item)).collect();
+            output.write_all(csv_line(&names).as_bytes())?;
+        }
+        _ => {}
+    }
+
+    for (transaction, count) in transactions {
+        let names: Vec<&str> = transaction
+            .iter()
+            .map(|&item| itemizer.str_of(item))
+            .collect();
+        let line = match format {
+            TransactionFormat::Csv => csv_line(&names),
+            TransactionFormat::Spmf if numeric => names.join(" ") + "\n",
+            TransactionFormat::Spmf => {
+                let ids: Vec<String> = transaction
+                    .iter()
+                    .map(|item| item.as_index().to_string())
+                    .collect();
+                ids.join(" ") + "\n"
+            }
+            TransactionFormat::OneHot => {
+                let mut cells = vec!["0"; items.len()];
+                for item in transaction {
+                    if let Ok(index) = items.binary_search(item) {
+                        cells[index] = "1";
+                    }
+                }
+                cells.join(",") + "\n"
+            }
+            TransactionFormat::Jsonl => serde_json::to_string(&names)? + "\n",
+        };
+        if format == TransactionFormat::Csv && weighted {
+            write!(output, "{};{}", count, line)?;
+        } else {
+            for _ in 0..*count {
+                output.write_all(line.as_bytes())?;
+            }
+        }
+    }
+    Ok(())
+}
+
+pub fn run_convert(args: &ConvertArguments) -> Result<(), ArmError> {
+    println!("Reading {}...", args.input_file_path);
+    let timer = Instant::now();
+    let mut itemizer = Itemizer::new();
+    let mut transactions = read_transactions(
+        &mut TransactionReader::new(&args.input_file_path, &mut itemizer)?
+            .with_weights(args.weighted)
+            .with_format(args.input_format)
+            .with_encoding(args.encoding),
+    )?;
+    let (mut item_count, num_transactions) =
+        count_item_frequencies(transactions.iter().map(|&(ref t, count)| Ok((t, count))))?;
+
+    // Items are renumbered in lexicographic order, so that they're written
+    // in that order.
+    let new_ids = itemizer.reorder_sorted(&mut item_count);
+    remap_items(&mut transactions, &new_ids);
+    for (transaction, _) in transactions.iter_mut() {
+        transaction.sort_unstable();
+    }
+
+    let path = &args.output_path;
+    let file = File::create(path).map_err(|e| ArmError::io(path, e))?;
+    let mut output = BufWriter::new(file);
+    write_transactions(
+        &mut output,
+        args.output_format,
+        &transactions,
+        &itemizer,
+        args.weighted,
+    )
+    .and_then(|_| output.flush())
+    .map_err(|e| ArmError::io(path, e))?;
+    println!(
+        "Converted {} transactions over {} items to {} in {} ms.",
+        num_transactions,
+        item_count.items_with_count_at_least(1).len(),
+        path,
+        duration_as_ms(&timer.elapsed())
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{write_transactions, TransactionFormat};
+    use item::Item;
+    use itemizer::Itemizer;
+    use mining::read_transactions;
+    use std::fs;
+    use test_util::TempFile;
+    use transaction_reader::{InputFormat, TransactionReader};
+
+    // Writes transactions in format, and reads them back as items' names.
+    fn round_trip(
+        transactions: &[Vec<&str>],
+        format: TransactionFormat,
+        input_format: InputFormat,
+    ) -> (String, Vec<Vec<String>>) {
+        let mut itemizer = Itemizer::new();
+        let mut names: Vec<&str> = transactions.iter().flatten().cloned().collect();
+        names.sort();
+        for name in names {
+            itemizer.id_of(name);
+        }
+        let transactions: Vec<(Vec<Item>, u32)> = transactions
+            .iter()
+            .map(|transaction| {
+                let mut items: Vec<Item> = transaction
+                    .iter()
+                    .map(|name| itemizer.id_of(name))
+                    .collect();
+                items.sort();
+                (items, 1)
+            })
+            .collect();
+        let mut output = vec![];
+        write_transactions(&mut output, format, &transactions, &itemizer, false).unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        let path = TempFile::new(&format!("convert-{:?}", format));
+        fs::write(&path, &output).unwrap();
+        let mut itemizer = Itemizer::new();
+        let read = read_transactions(
+            &mut TransactionReader::new(path.path(), &mut itemizer)
+                .unwrap()
+                .with_format(input_format),
+        )
+        .unwrap();
+        let read = read
+            .iter()
+            .map(|(transaction, _)| {
+                let mut names: Vec<String> = transaction
+                    .iter()
+                    .map(|&item| String::from(itemizer.str_of(item)))
+                    .collect();
+                names.sort();
+                names
+            })
+            .collect();
+        (output, read)
+    }
+
+    #[test]
+    fn test_convert_formats() {
+        let transactions = vec![vec!["a", "b, c"], vec!["d\"e"], vec!["a", "d\"e"]];
+        let expected: Vec<Vec<String>> = transactions
+            .iter()
+            .map(|transaction| transaction.iter().map(|&name| String::from(name)).collect())
+            .collect();
+        let (csv, read) = round_trip(&transactions, TransactionFormat::Csv, InputFormat::Csv);
+        assert_eq!(csv, "a,\"b, c\"\n\"d\"\"e\"\na,\"d\"\"e\"\n");
+        assert_eq!(read, expected);
+
+        let (spmf, read) = round_trip(&transactions, TransactionFormat::Spmf, InputFormat::Spmf);
+        assert!(spmf.starts_with("@CONVERTED_FROM_TEXT\n@ITEM=1=a\n"));
+        assert!(spmf.ends_with("\n1 2\n3\n1 3\n"));
+        assert_eq!(read, expected);
+
+        let (onehot, read) = round_trip(
+            &transactions,
+            TransactionFormat::OneHot,
+            InputFormat::OneHot,
+        );
+        assert_eq!(onehot, "a,\"b, c\",\"d\"\"e\"\n1,1,0\n0,0,1\n1,0,1\n");
+        assert_eq!(read, expected);
+
+        let (jsonl, read) = round_trip(&transactions, TransactionFormat::Jsonl, InputFormat::Jsonl);
+        assert_eq!(
+            jsonl,
+            "[\"a\",\"b, c\"]\n[\"d\\\"e\"]\n[\"a\",\"d\\\"e\"]\n"
+        );
+        assert_eq!(read, expected);
+
+        // Integer items are written to SPMF as they are.
+        let numeric = vec![vec!["10", "2"], vec!["3"]];
+        let (spmf, _) = round_trip(&numeric, TransactionFormat::Spmf, InputFormat::Spmf);
+        assert_eq!(spmf, "10 2\n3\n");
+    }
+}
@@ -0,0 +1,126 @@
+// Copyright 2018 Chris Pearce
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use fnv::FnvHashMap;
+use std::cmp::Ordering;
+use std::hash::Hash;
+
+pub use item::{Item, ItemId};
+pub use item_counter::ItemCounter;
+
+// Counts of keys, such as items' supports. ItemCounter counts items densely
+// by id, as the miner does; HashCounter counts any hashable key, such as
+// items' names. Both count a key which was never added as 0, and only list
+// keys which were added with a count of at least 1.
+pub trait Counter<K: Ord> {
+    fn add(&mut self, key: &K, count: u32);
+    fn get(&self, key: &K) -> u32;
+    fn set(&mut self, key: &K, count: u32);
+
+    // The keys counted at least min_count times, and at least once, in
+    // increasing order.
+    fn items_with_count_at_least(&self, min_count: u32) -> Vec<K>;
+
+    // Sorts keys by decreasing count, breaking ties by decreasing key, which
+    // is the order items are inserted into an FPTree.
+    fn sort_descending(&self, keys: &mut [K]) {
+        keys.sort_by(|a, b| match self.get(b).cmp(&self.get(a)) {
+            Ordering::Equal => b.cmp(a),
+            ordering => ordering,
+        });
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct HashCounter<K: Hash + Eq> {
+    counter: FnvHashMap<K, u32>,
+}
+
+impl<K: Hash + Eq> Default for HashCounter<K> {
+    fn default() -> HashCounter<K> {
+        HashCounter::new()
+    }
+}
+
+impl<K: Hash + Eq> HashCounter<K> {
+    pub fn new() -> HashCounter<K> {
+        HashCounter {
+            counter: FnvHashMap::default(),
+        }
+    }
+}
+
+impl<K: Hash + Ord + Clone> Counter<K> for HashCounter<K> {
+    fn add(&mut self, key: &K, count: u32) {
+        *self.counter.entry(key.clone()).or_insert(0) += count;
+    }
+    fn get(&self, key: &K) -> u32 {
+        *self.counter.get(key).unwrap_or(&0)
+    }
+    fn set(&mut self, key: &K, count: u32) {
+        self.counter.insert(key.clone(), count);
+    }
+    fn items_with_count_at_least(&self, min_count: u32) -> Vec<K> {
+        let mut keys: Vec<K> = self
+            .counter
+            .iter()
+            .filter(|&(_, &count)| count > 0 && count >= min_count)
+            .map(|(key, _)| key.clone())
+            .collect();
+        keys.sort();
+        keys
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Counter, HashCounter, Item, ItemCounter};
+
+    // Both counters count the same keys the same way.
+    fn check_counter<K: Ord + Clone + ::std::fmt::Debug, C: Counter<K>>(
+        mut counter: C,
+        keys: &[K],
+    ) {
+        counter.add(&keys[2], 3);
+        counter.add(&keys[0], 1);
+        counter.add(&keys[2], 1);
+        counter.set(&keys[1], 4);
+        counter.set(&keys[3], 0);
+        assert_eq!(counter.get(&keys[2]), 4);
+        assert_eq!(counter.get(&keys[4]), 0);
+        assert_eq!(
+            counter.items_with_count_at_least(0),
+            vec![keys[0].clone(), keys[1].clone(), keys[2].clone()]
+        );
+        assert_eq!(
+            counter.items_with_count_at_least(2),
+            vec![keys[1].clone(), keys[2].clone()]
+        );
+        let mut sorted = keys[..3].to_vec();
+        counter.sort_descending(&mut sorted);
+        assert_eq!(
+            sorted,
+            vec![keys[2].clone(), keys[1].clone(), keys[0].clone()]
+        );
+    }
+
+    #[test]
+    fn test_counters() {
+        let items: Vec<Item> = (1..6).map(Item::from).collect();
+        check_counter(ItemCounter::new(), &items);
+        check_counter(HashCounter::new(), &items);
+        let names = ["a", "b", "c", "d", "e"];
+        check_counter(HashCounter::new(), &names);
+    }
+}
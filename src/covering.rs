@@ -0,0 +1,188 @@
+// Copyright 2018 Chris Pearce
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use index::TransactionIndex;
+use rule::Rule;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::str::FromStr;
+
+// Fraction of transactions a selection of rules must cover, parsed from a
+// percentage such as "80%" or "80".
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CoverageTarget {
+    pub fraction: f64,
+}
+
+impl FromStr for CoverageTarget {
+    type Err = String;
+    fn from_str(s: &str) -> Result<CoverageTarget, String> {
+        let error = || format!("Invalid coverage {}; expected a percentage e.g. 80%", s);
+        let percent: f64 = s.trim_end_matches('%').parse().map_err(|_| error())?;
+        if !(percent > 0.0 && percent <= 100.0) {
+            return Err(error());
+        }
+        Ok(CoverageTarget {
+            fraction: percent / 100.0,
+        })
+    }
+}
+
+pub struct CoveringSelection {
+    // Selected rules, in the order they were picked.
+    pub rules: Vec<Rule>,
+    // Number of transactions, including repeats, covered by the rules.
+    pub num_covered: usize,
+}
+
+// Greedily picks rules until they cover at least target of the transactions
+// in index, always picking the rule which covers the most transactions not
+// covered yet. A rule covers a transaction if the transaction contains all
+// of the rule's items. Stops early if no remaining rule covers anything new.
+//
+// The number of new transactions a rule covers only falls as other rules are
+// picked, so each rule's last count is an upper bound on its current count.
+// Rules are kept in a heap ordered by that bound, and only the rule at the
+// top is recounted; if its count is still at least the next bound, it's the
+// best rule, otherwise it's pushed back with its new count.
+pub fn select_covering(
+    rules: Vec<Vec<Rule>>,
+    index: &dyn TransactionIndex,
+    target: CoverageTarget,
+) -> CoveringSelection {
+    let rules: Vec<Rule> = rules.into_iter().flatten().collect();
+    let target_count = (target.fraction * index.transaction_count() as f64).ceil() as usize;
+    // Ties are broken in favour of earlier rules, so the selection is
+    // deterministic.
+    let mut heap: BinaryHeap<(usize, Reverse<usize>)> = rules
+        .iter()
+        .enumerate()
+        .map(|(i, rule)| (rule.count as usize, Reverse(i)))
+        .collect();
+    let mut covered: Vec<bool> = vec![];
+    let mut num_covered = 0;
+    let mut selected: Vec<usize> = vec![];
+    while num_covered < target_count {
+        let (_, Reverse(i)) = match heap.pop() {
+            Some(top) => top,
+            None => break,
+        };
+        let rule = &rules[i];
+        let items: Vec<_> = rule
+            .antecedent
+            .iter()
+            .chain(rule.consequent.iter())
+            .cloned()
+            .collect();
+        let uncovered: Vec<u32> = index
+            .tids(&items)
+            .into_iter()
+            .filter(|&tid| !covered.get(tid as usize).cloned().unwrap_or(false))
+            .collect();
+        let gain: usize = uncovered
+            .iter()
+            .map(|&tid| index.weight(tid) as usize)
+            .sum();
+        if gain == 0 {
+            continue;
+        }
+        if heap.peek().is_some_and(|&(bound, _)| gain < bound) {
+            heap.push((gain, Reverse(i)));
+            continue;
+        }
+        for tid in uncovered {
+            let tid = tid as usize;
+            if covered.len() <= tid {
+                covered.resize(tid + 1, false);
+            }
+            covered[tid] = true;
+        }
+        num_covered += gain;
+        selected.push(i);
+    }
+    let mut rules: Vec<Option<Rule>> = rules.into_iter().map(Some).collect();
+    CoveringSelection {
+        rules: selected
+            .into_iter()
+            .map(|i| rules[i].take().unwrap())
+            .collect(),
+        num_covered,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{select_covering, CoverageTarget};
+    use index::{Index, TransactionIndex};
+    use item::Item;
+    use rule::Rule;
+
+    #[test]
+    fn test_select_covering() {
+        assert_eq!("80%".parse(), Ok(CoverageTarget { fraction: 0.8 }));
+        assert_eq!("50".parse(), Ok(CoverageTarget { fraction: 0.5 }));
+        assert!("0%".parse::<CoverageTarget>().is_err());
+        assert!("101".parse::<CoverageTarget>().is_err());
+
+        let item = Item::from;
+        let mut index = Index::new();
+        let transactions = vec![
+            (vec![1, 2], 3),
+            (vec![1, 2, 3], 1),
+            (vec![3, 4], 2),
+            (vec![4, 5], 1),
+            (vec![5], 1),
+        ];
+        for (transaction, count) in &transactions {
+            let transaction: Vec<Item> = transaction.iter().map(|&i| item(i)).collect();
+            index.insert(&transaction, *count);
+        }
+        index.finish();
+
+        let rule = |antecedent: u32, consequent: u32| {
+            let items = [item(antecedent), item(consequent)];
+            let count = index.count(&items) as u32;
+            Rule::new(
+                vec![item(antecedent)],
+                vec![item(consequent)],
+                count,
+                index.count(&items[..1]) as u32,
+                index.count(&items[1..]) as u32,
+                index.transaction_count() as u32,
+            )
+        };
+        // 1 => 2 covers 4 transactions, 2 => 3 covers one of those, 3 => 4
+        // covers 2 others, and 4 => 5 covers 1 other.
+        let rules = vec![
+            vec![rule(2, 3)],
+            vec![rule(1, 2), rule(4, 5)],
+            vec![rule(3, 4)],
+        ];
+
+        let selection = select_covering(rules.clone(), &index, "50%".parse().unwrap());
+        assert_eq!(selection.num_covered, 4);
+        assert_eq!(selection.rules.len(), 1);
+        assert_eq!(selection.rules[0].antecedent, vec![item(1)]);
+
+        let selection = select_covering(rules.clone(), &index, "70%".parse().unwrap());
+        assert_eq!(selection.num_covered, 6);
+        assert_eq!(selection.rules[1].antecedent, vec![item(3)]);
+
+        // Transaction {5} isn't covered by any rule.
+        let selection = select_covering(rules, &index, "100%".parse().unwrap());
+        assert_eq!(selection.num_covered, 7);
+        assert_eq!(selection.rules.len(), 3);
+        assert_eq!(selection.rules[2].antecedent, vec![item(4)]);
+    }
+}
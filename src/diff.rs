@@ -0,0 +1,287 @@
+// Copyright 2018 Chris Pearce
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use command_line_args::DiffArguments;
+use error::ArmError;
+use fnv::{FnvHashMap, FnvHashSet};
+use output::{join_items, OutputFile};
+use rule_file::{read_rules, RuleRecord};
+use std::io::{self, Write};
+use std::str::FromStr;
+
+pub enum RuleChange<'a> {
+    // Rule only in the first file.
+    Removed(&'a RuleRecord),
+    // Rule only in the second file.
+    Added(&'a RuleRecord),
+    // Rule in both files, with some metric differing by more than the
+    // tolerance; (old, new).
+    Changed(&'a RuleRecord, &'a RuleRecord),
+}
+
+impl<'a> RuleChange<'a> {
+    // Returns the change's name, the rule, and its old and new versions.
+    fn parts(
+        &self,
+    ) -> (
+        &'static str,
+        &'a RuleRecord,
+        Option<&'a RuleRecord>,
+        Option<&'a RuleRecord>,
+    ) {
+        match *self {
+            RuleChange::Removed(rule) => ("removed", rule, Some(rule), None),
+            RuleChange::Added(rule) => ("added", rule, None, Some(rule)),
+            RuleChange::Changed(old, new) => ("changed", old, Some(old), Some(new)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DiffFormat {
+    Csv,
+    Json,
+}
+
+impl FromStr for DiffFormat {
+    type Err = String;
+    fn from_str(s: &str) -> Result<DiffFormat, String> {
+        match s {
+            "csv" => Ok(DiffFormat::Csv),
+            "json" => Ok(DiffFormat::Json),
+            _ => Err(format!("Unknown diff format {}", s)),
+        }
+    }
+}
+
+// Rules are matched by their items, regardless of the order the items are
+// written in.
+fn rule_key(rule: &RuleRecord) -> (Vec<&str>, Vec<&str>) {
+    let mut antecedent: Vec<&str> = rule.antecedent.iter().map(|s| s.as_str()).collect();
+    let mut consequent: Vec<&str> = rule.consequent.iter().map(|s| s.as_str()).collect();
+    antecedent.sort();
+    consequent.sort();
+    (antecedent, consequent)
+}
+
+fn changed(old: &RuleRecord, new: &RuleRecord, tolerance: f64) -> bool {
+    (old.confidence - new.confidence).abs() > tolerance
+        || (old.lift - new.lift).abs() > tolerance
+        || (old.support - new.support).abs() > tolerance
+}
+
+// Returns the rules removed and changed, in the order of old, followed by
+// the rules added, in the order of new. Also returns the number of rules in
+// both.
+pub fn diff_rules<'a>(
+    old: &'a [RuleRecord],
+    new: &'a [RuleRecord],
+    tolerance: f64,
+) -> (Vec<RuleChange<'a>>, usize) {
+    let new_by_key: FnvHashMap<_, &RuleRecord> =
+        new.iter().map(|rule| (rule_key(rule), rule)).collect();
+    let mut changes = vec![];
+    let mut num_shared = 0;
+    for rule in old {
+        match new_by_key.get(&rule_key(rule)) {
+            Some(new_rule) => {
+                num_shared += 1;
+                if changed(rule, new_rule, tolerance) {
+                    changes.push(RuleChange::Changed(rule, new_rule));
+                }
+            }
+            None => changes.push(RuleChange::Removed(rule)),
+        }
+    }
+    let old_keys: FnvHashSet<_> = old.iter().map(rule_key).collect();
+    changes.extend(
+        new.iter()
+            .filter(|rule| !old_keys.contains(&rule_key(rule)))
+            .map(RuleChange::Added),
+    );
+    (changes, num_shared)
+}
+
+fn write_csv<W: Write>(output: &mut W, changes: &[RuleChange]) -> io::Result<()> {
+    writeln!(
+        output,
+        "Change,Antecedent => Consequent,OldConfidence,NewConfidence,ConfidenceDelta,\
+         OldLift,NewLift,LiftDelta,OldSupport,NewSupport,SupportDelta"
+    )?;
+    for change in changes {
+        let (name, rule, old, new) = change.parts();
+        write!(
+            output,
+            "{},{} => {}",
+            name,
+            join_items(&rule.antecedent),
+            join_items(&rule.consequent)
+        )?;
+        let metrics: [fn(&RuleRecord) -> f64; 3] = [|r| r.confidence, |r| r.lift, |r| r.support];
+        for metric in metrics.iter() {
+            let cell = |rule: Option<&RuleRecord>| rule.map(|r| metric(r).to_string());
+            let delta = match (old, new) {
+                (Some(old), Some(new)) => Some((metric(new) - metric(old)).to_string()),
+                _ => None,
+            };
+            write!(
+                output,
+                ",{},{},{}",
+                cell(old).unwrap_or_default(),
+                cell(new).unwrap_or_default(),
+                delta.unwrap_or_default()
+            )?;
+        }
+        writeln!(output)?;
+    }
+    Ok(())
+}
+
+fn json_string(s: &str) -> String {
+    let mut json = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            c if (c as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+fn json_items(items: &[String]) -> String {
+    let items: Vec<String> = items.iter().map(|item| json_string(item)).collect();
+    format!("[{}]", items.join(","))
+}
+
+fn json_metrics(rule: &RuleRecord) -> String {
+    format!(
+        "{{\"confidence\":{},\"lift\":{},\"support\":{}}}",
+        rule.confidence, rule.lift, rule.support
+    )
+}
+
+// Writes an array of changes, one object per line.
+fn write_json<W: Write>(output: &mut W, changes: &[RuleChange]) -> io::Result<()> {
+    writeln!(output, "[")?;
+    for (index, change) in changes.iter().enumerate() {
+        let (name, rule, old, new) = change.parts();
+        write!(
+            output,
+            "{{\"change\":\"{}\",\"antecedent\":{},\"consequent\":{}",
+            name,
+            json_items(&rule.antecedent),
+            json_items(&rule.consequent)
+        )?;
+        if let Some(old) = old {
+            write!(output, ",\"old\":{}", json_metrics(old))?;
+        }
+        if let Some(new) = new {
+            write!(output, ",\"new\":{}", json_metrics(new))?;
+        }
+        let separator = if index + 1 < changes.len() { "," } else { "" };
+        writeln!(output, "}}{}", separator)?;
+    }
+    writeln!(output, "]")
+}
+
+pub fn run_diff(args: &DiffArguments) -> Result<(), ArmError> {
+    let old = read_rules(&args.old_rules_path)?;
+    let new = read_rules(&args.new_rules_path)?;
+    let (changes, num_shared) = diff_rules(&old, &new, args.tolerance);
+
+    let write = || -> io::Result<()> {
+        let mut output = OutputFile::create(&args.output_path, None)?;
+        match args.format {
+            DiffFormat::Csv => write_csv(&mut output, &changes)?,
+            DiffFormat::Json => write_json(&mut output, &changes)?,
+        }
+        output.finish()
+    };
+    write().map_err(|e| ArmError::io(&args.output_path, e))?;
+
+    let count = |f: fn(&RuleChange) -> bool| changes.iter().filter(|c| f(c)).count();
+    println!(
+        "{} rules only in {}, {} rules only in {}.",
+        count(|c| matches!(c, RuleChange::Removed(_))),
+        args.old_rules_path,
+        count(|c| matches!(c, RuleChange::Added(_))),
+        args.new_rules_path
+    );
+    println!(
+        "{} of {} shared rules changed by more than {}.",
+        count(|c| matches!(c, RuleChange::Changed(..))),
+        num_shared,
+        args.tolerance
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{diff_rules, json_string, write_json, RuleChange};
+    use rule_file::RuleRecord;
+
+    fn rule(antecedent: &[&str], consequent: &[&str], confidence: f64) -> RuleRecord {
+        RuleRecord {
+            antecedent: antecedent.iter().map(|s| String::from(*s)).collect(),
+            consequent: consequent.iter().map(|s| String::from(*s)).collect(),
+            confidence,
+            lift: 1.5,
+            support: 0.25,
+            line: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_diff_rules() {
+        let old = vec![
+            rule(&["a", "b"], &["c"], 0.5),
+            rule(&["a"], &["b"], 0.5),
+            rule(&["b"], &["a"], 0.5),
+        ];
+        let new = vec![
+            rule(&["b", "a"], &["c"], 0.505),
+            rule(&["a"], &["b"], 0.7),
+            rule(&["c"], &["a"], 0.5),
+        ];
+        let (changes, num_shared) = diff_rules(&old, &new, 0.01);
+        assert_eq!(num_shared, 2);
+        assert_eq!(changes.len(), 3);
+        match changes[0] {
+            RuleChange::Changed(old, new) => {
+                assert_eq!(old.consequent, vec!["b"]);
+                assert_eq!(new.confidence, 0.7);
+            }
+            _ => panic!("expected a changed rule"),
+        }
+        assert!(matches!(changes[1], RuleChange::Removed(r) if r.antecedent == vec!["b"]));
+        assert!(matches!(changes[2], RuleChange::Added(r) if r.antecedent == vec!["c"]));
+
+        let mut json = vec![];
+        write_json(&mut json, &changes[1..]).unwrap();
+        assert_eq!(
+            String::from_utf8(json).unwrap(),
+            "[\n\
+             {\"change\":\"removed\",\"antecedent\":[\"b\"],\"consequent\":[\"a\"],\
+             \"old\":{\"confidence\":0.5,\"lift\":1.5,\"support\":0.25}},\n\
+             {\"change\":\"added\",\"antecedent\":[\"c\"],\"consequent\":[\"a\"],\
+             \"new\":{\"confidence\":0.5,\"lift\":1.5,\"support\":0.25}}\n\
+             ]\n"
+        );
+        assert_eq!(json_string("a\"b\\\n"), "\"a\\\"b\\\\\\u000a\"");
+    }
+}
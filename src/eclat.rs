@@ -0,0 +1,351 @@
+// Copyright 2018 Chris Pearce
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use counter::Counter;
+use error::ArmError;
+use fptree::{FPGrowthParameters, FPGrowthProgress, ItemSet};
+use index::TransactionIndex;
+use item::{Item, ItemId};
+use item_counter::ItemCounter;
+use rayon::prelude::*;
+use tracing::{info_span, Span};
+
+// Use diffsets rather than tidsets when the dataset is at least this dense.
+// On dense datasets tidsets barely shrink as itemsets grow, whereas diffsets
+// shrink quickly.
+const DIFFSET_DENSITY_THRESHOLD: f64 = 0.1;
+
+// Vertical layout of a dataset, for mining with Eclat: the tid-list of each
+// frequent item.
+pub struct VerticalDatabase {
+    // Frequent items and their tid-lists, in increasing order of count.
+    items: Vec<(Item, Vec<u32>, u32)>,
+    // Number of times each transaction occurred, indexed by tid.
+    weights: Vec<u32>,
+}
+
+impl VerticalDatabase {
+    // Reads transactions, keeping only the items which would be kept in the
+    // initial FPTree. If an index is passed, the filtered transactions are
+    // also added to it.
+    pub fn build<I>(
+        transactions: I,
+        item_count: &ItemCounter,
+        min_count: u32,
+        mut index: Option<&mut dyn TransactionIndex>,
+    ) -> Result<VerticalDatabase, ArmError>
+    where
+        I: IntoIterator<Item = Result<(Vec<Item>, u32), ArmError>>,
+    {
+        let mut tid_lists: Vec<Vec<u32>> = vec![];
+        let mut weights: Vec<u32> = vec![];
+        for transaction in transactions {
+            let (transaction, count) = transaction?;
+            let filtered_transaction = transaction
+                .into_iter()
+                .filter(|&item| item_count.get(&item) >= min_count)
+                .collect::<Vec<Item>>();
+            if let Some(ref mut index) = index {
+                index.insert(&filtered_transaction, count);
+            }
+            let tid = weights.len() as u32;
+            weights.push(count);
+            for item in filtered_transaction {
+                if tid_lists.len() <= item.as_index() {
+                    tid_lists.resize(item.as_index() + 1, vec![]);
+                }
+                tid_lists[item.as_index()].push(tid);
+            }
+        }
+        if let Some(index) = index {
+            index.finish();
+        }
+        let mut items: Vec<(Item, Vec<u32>, u32)> = tid_lists
+            .into_iter()
+            .enumerate()
+            .filter(|(_, tids)| !tids.is_empty())
+            .map(|(index, tids)| {
+                let count = weight_of(&tids, &weights);
+                (Item::with_id(index as ItemId), tids, count)
+            })
+            .collect();
+        items.sort_by_key(|&(item, _, count)| (count, item));
+        Ok(VerticalDatabase { items, weights })
+    }
+
+    // Fraction of the cells in the transaction by item matrix which are set.
+    pub fn density(&self) -> f64 {
+        let num_transactions: u64 = self.weights.iter().map(|&w| u64::from(w)).sum();
+        if num_transactions == 0 || self.items.is_empty() {
+            return 0.0;
+        }
+        let num_set: u64 = self
+            .items
+            .iter()
+            .map(|&(_, _, count)| u64::from(count))
+            .sum();
+        num_set as f64 / (num_transactions as f64 * self.items.len() as f64)
+    }
+
+    pub fn prefers_diffsets(&self) -> bool {
+        self.density() >= DIFFSET_DENSITY_THRESHOLD
+    }
+}
+
+fn weight_of(tids: &[u32], weights: &[u32]) -> u32 {
+    tids.iter().map(|&tid| weights[tid as usize]).sum()
+}
+
+// Tids in both a and b.
+fn intersection(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut result = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] < b[j] {
+            i += 1;
+        } else if a[i] > b[j] {
+            j += 1;
+        } else {
+            result.push(a[i]);
+            i += 1;
+            j += 1;
+        }
+    }
+    result
+}
+
+// Tids in a but not in b.
+fn difference(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut result = vec![];
+    let mut j = 0;
+    for &tid in a {
+        while j < b.len() && b[j] < tid {
+            j += 1;
+        }
+        if j == b.len() || b[j] != tid {
+            result.push(tid);
+        }
+    }
+    result
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum TidSets {
+    // Tids of the transactions containing the prefix and the item.
+    Tidsets,
+    // Tids of the transactions containing the prefix but not the item.
+    Diffsets,
+}
+
+// A member of an equivalence class of itemsets sharing a prefix; the itemset
+// is the prefix plus item.
+struct ClassMember {
+    item: Item,
+    tids: Vec<u32>,
+    count: u32,
+}
+
+struct Eclat<'a> {
+    params: &'a FPGrowthParameters,
+    weights: &'a [u32],
+    diffsets: bool,
+}
+
+impl<'a> Eclat<'a> {
+    // Mines the itemsets starting with prefix plus class[index]'s item, and
+    // continuing with items of later class members.
+    fn mine_member(
+        &self,
+        prefix: &[Item],
+        class: &[ClassMember],
+        kind: TidSets,
+        index: usize,
+        output: &mut Vec<ItemSet>,
+    ) {
+        if self.params.cancellation.is_cancelled() {
+            return;
+        }
+        let x = &class[index];
+        let mut itemset = Vec::from(prefix);
+        itemset.push(x.item);
+        output.push(ItemSet::new(itemset.clone(), x.count));
+        self.params.cancellation.add_to_count(1);
+        if self
+            .params
+            .max_itemset_size
+            .is_some_and(|max| itemset.len() >= max)
+        {
+            return;
+        }
+
+        let next_kind = if self.diffsets {
+            TidSets::Diffsets
+        } else {
+            TidSets::Tidsets
+        };
+        let mut next_class = vec![];
+        for y in &class[index + 1..] {
+            let tids = match (kind, next_kind) {
+                (TidSets::Tidsets, TidSets::Tidsets) => intersection(&x.tids, &y.tids),
+                // d(XY) = t(X) - t(Y).
+                (TidSets::Tidsets, TidSets::Diffsets) => difference(&x.tids, &y.tids),
+                // d(PXY) = d(PY) - d(PX).
+                _ => difference(&y.tids, &x.tids),
+            };
+            let count = match next_kind {
+                TidSets::Tidsets => weight_of(&tids, self.weights),
+                TidSets::Diffsets => x.count - weight_of(&tids, self.weights),
+            };
+            let admitted = |count| match self.params.cross_support {
+                Some(ref cross_support) => {
+                    let mut items = itemset.clone();
+                    items.push(y.item);
+                    cross_support.admits(&items, count)
+                }
+                None => true,
+            };
+            if count >= self.params.min_count && admitted(count) {
+                next_class.push(ClassMember {
+                    item: y.item,
+                    tids,
+                    count,
+                });
+            }
+        }
+        for index in 0..next_class.len() {
+            self.mine_member(&itemset, &next_class, next_kind, index, output);
+        }
+    }
+}
+
+// Mines frequent itemsets with Eclat, which intersects the tid-lists of
+// itemsets depth first. With diffsets (dEclat), differences between tid-lists
+// are stored instead, which use much less memory on dense datasets. Reports
+// progress as fp_growth_with_progress does.
+pub fn eclat(
+    database: &VerticalDatabase,
+    params: &FPGrowthParameters,
+    diffsets: bool,
+) -> FPGrowthProgress {
+    let class: Vec<ClassMember> = database
+        .items
+        .iter()
+        .filter(|&&(_, _, count)| count >= params.min_count)
+        .map(|&(item, ref tids, count)| ClassMember {
+            item,
+            tids: tids.clone(),
+            count,
+        })
+        .collect();
+    let eclat = Eclat {
+        params,
+        weights: &database.weights,
+        diffsets,
+    };
+    let parent = Span::current();
+    let results: Vec<(Item, Vec<ItemSet>, bool)> = (0..class.len())
+        .into_par_iter()
+        .map(|index| {
+            let _span = info_span!(
+                parent: &parent,
+                "mine_item",
+                item = class[index].item.as_index()
+            )
+            .entered();
+            let mut itemsets = vec![];
+            eclat.mine_member(&[], &class, TidSets::Tidsets, index, &mut itemsets);
+            (
+                class[index].item,
+                itemsets,
+                !params.cancellation.is_cancelled(),
+            )
+        })
+        .collect();
+
+    let mut progress = FPGrowthProgress {
+        itemsets: vec![],
+        num_items: class.len(),
+        incomplete_items: vec![],
+    };
+    for (item, itemsets, complete) in results {
+        progress.itemsets.extend(itemsets);
+        if !complete {
+            progress.incomplete_items.push(item);
+        }
+    }
+    progress
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{eclat, VerticalDatabase};
+    use cancellation::CancellationToken;
+    use counter::Counter;
+    use fptree::{fp_growth, FPGrowthParameters, FPTree, ItemSet};
+    use item::Item;
+    use item_counter::ItemCounter;
+
+    #[test]
+    fn test_eclat_matches_fp_growth() {
+        let transactions: Vec<(Vec<Item>, u32)> = (0..60u32)
+            .map(|i| {
+                let items = (1..9)
+                    .filter(|&item| (i * 7 + item * 3) % (item + 1) != 0)
+                    .map(Item::from)
+                    .collect();
+                (items, 1 + i % 2)
+            })
+            .collect();
+        let mut item_count = ItemCounter::new();
+        for (transaction, count) in &transactions {
+            for item in transaction {
+                item_count.add(item, *count);
+            }
+        }
+        let min_count = 20;
+        let mut fptree = FPTree::new();
+        for (transaction, count) in &transactions {
+            let mut transaction: Vec<Item> = transaction
+                .iter()
+                .cloned()
+                .filter(|item| item_count.get(item) >= min_count)
+                .collect();
+            item_count.sort_descending(&mut transaction);
+            fptree.insert(&transaction, *count);
+        }
+        let params = FPGrowthParameters {
+            min_count,
+            max_itemset_size: None,
+            cancellation: CancellationToken::new(),
+            cross_support: None,
+            item_min_counts: None,
+        };
+        let mut expected = fp_growth(&fptree, &params, &[], 90);
+        expected.sort();
+
+        let database = VerticalDatabase::build(
+            transactions.iter().cloned().map(Ok),
+            &item_count,
+            min_count,
+            None,
+        )
+        .unwrap();
+        for &diffsets in &[false, true] {
+            let mut itemsets: Vec<ItemSet> = eclat(&database, &params, diffsets).itemsets;
+            itemsets.sort();
+            assert_eq!(itemsets, expected);
+        }
+        assert!(database.density() > 0.0 && database.density() <= 1.0);
+    }
+}
@@ -0,0 +1,86 @@
+// Copyright 2018 Chris Pearce
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+#[derive(Debug)]
+pub enum ArmError {
+    // Failed to open or read from a file.
+    Io {
+        path: String,
+        source: io::Error,
+    },
+    // Input file contents couldn't be interpreted. Line numbers start at 1.
+    Parse {
+        path: String,
+        line: usize,
+        message: String,
+    },
+    // A limit on the size of the results, such as --max-rules, was
+    // exceeded.
+    LimitExceeded {
+        message: String,
+    },
+}
+
+impl ArmError {
+    pub fn io(path: &str, source: io::Error) -> ArmError {
+        ArmError::Io {
+            path: String::from(path),
+            source,
+        }
+    }
+
+    pub fn limit_exceeded(message: &str) -> ArmError {
+        ArmError::LimitExceeded {
+            message: String::from(message),
+        }
+    }
+
+    pub fn parse(path: &str, line: usize, message: &str) -> ArmError {
+        ArmError::Parse {
+            path: String::from(path),
+            line,
+            message: String::from(message),
+        }
+    }
+}
+
+impl fmt::Display for ArmError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ArmError::Io {
+                ref path,
+                ref source,
+            } => write!(f, "{}: {}", path, source),
+            ArmError::Parse {
+                ref path,
+                line,
+                ref message,
+            } => write!(f, "{}:{}: {}", path, line, message),
+            ArmError::LimitExceeded { ref message } => write!(f, "{}", message),
+        }
+    }
+}
+
+impl Error for ArmError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            ArmError::Io { ref source, .. } => Some(source),
+            ArmError::Parse { .. } | ArmError::LimitExceeded { .. } => None,
+        }
+    }
+}
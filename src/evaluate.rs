@@ -0,0 +1,216 @@
+// Copyright 2018 Chris Pearce
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use command_line_args::EvaluateArguments;
+use error::ArmError;
+use fnv::FnvHashMap;
+use query::recommend;
+use rand::prelude::*;
+use rayon::prelude::*;
+use rule_file::{read_rules, RuleRecord};
+use transaction_reader::{Encoding, InputFormat, LineReader};
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Evaluation {
+    // Number of baskets with at least two items, which were evaluated.
+    pub num_baskets: usize,
+    // Number of baskets for which any item was recommended.
+    pub num_covered: usize,
+    // Number of baskets whose hidden item was in the top k recommendations.
+    pub num_hits: usize,
+    // Sum over baskets of the reciprocal of the hidden item's rank in the
+    // recommendations, or 0 if it wasn't recommended.
+    pub reciprocal_rank_sum: f64,
+}
+
+impl Evaluation {
+    fn add(mut self, other: Evaluation) -> Evaluation {
+        self.num_baskets += other.num_baskets;
+        self.num_covered += other.num_covered;
+        self.num_hits += other.num_hits;
+        self.reciprocal_rank_sum += other.reciprocal_rank_sum;
+        self
+    }
+
+    pub fn hit_rate(&self) -> f64 {
+        self.num_hits as f64 / self.num_baskets as f64
+    }
+
+    // There's one hidden item per basket, so precision@k is the hit rate
+    // divided by k.
+    pub fn precision_at(&self, k: usize) -> f64 {
+        self.hit_rate() / k as f64
+    }
+
+    pub fn mean_reciprocal_rank(&self) -> f64 {
+        self.reciprocal_rank_sum / self.num_baskets as f64
+    }
+
+    pub fn coverage(&self) -> f64 {
+        self.num_covered as f64 / self.num_baskets as f64
+    }
+}
+
+// Reads baskets of items, one per line, in the given format and encoding.
+// Each basket is evaluated once, whatever its count in counted input.
+fn read_baskets(
+    path: &str,
+    format: InputFormat,
+    encoding: Encoding,
+) -> Result<Vec<Vec<String>>, ArmError> {
+    let mut lines = LineReader::open(path, None)?;
+    lines.set_format(format);
+    lines.set_encoding(encoding);
+    let mut baskets = vec![];
+    let mut line = String::new();
+    while let Some(items) = lines.read_items(&mut line) {
+        let (items, _) = items?;
+        let mut basket: Vec<String> = items.into_iter().filter(|item| !item.is_empty()).collect();
+        basket.sort();
+        basket.dedup();
+        baskets.push(basket);
+    }
+    Ok(baskets)
+}
+
+// Evaluates the rules as a recommender: from each basket with at least two
+// items, one item chosen at random is hidden, the top k items recommended
+// for the rest of the basket are compared with it.
+pub fn evaluate(rules: &[RuleRecord], baskets: &[Vec<String>], k: usize, seed: u64) -> Evaluation {
+    // Only rules whose antecedent is in a basket can recommend anything for
+    // it, so index rules by the item of their antecedent in fewest baskets,
+    // to avoid scanning every rule for every basket.
+    let mut item_count: FnvHashMap<&str, usize> = FnvHashMap::default();
+    for item in baskets.iter().flatten() {
+        *item_count.entry(item).or_insert(0) += 1;
+    }
+    let mut rules_by_item: FnvHashMap<&str, Vec<&RuleRecord>> = FnvHashMap::default();
+    for rule in rules {
+        let rarest = rule
+            .antecedent
+            .iter()
+            .map(|item| {
+                (
+                    item.as_str(),
+                    item_count.get(item.as_str()).cloned().unwrap_or(0),
+                )
+            })
+            .min_by_key(|&(_, count)| count);
+        if let Some((item, count)) = rarest {
+            if count > 0 {
+                rules_by_item.entry(item).or_default().push(rule);
+            }
+        }
+    }
+    baskets
+        .par_iter()
+        .enumerate()
+        .filter(|(_, basket)| basket.len() >= 2)
+        .map(|(index, basket)| {
+            // Seed each basket separately, so results don't depend on the
+            // order baskets are evaluated in.
+            let mut rng = StdRng::seed_from_u64(seed ^ index as u64);
+            let mut basket = basket.clone();
+            let hidden = basket.swap_remove(rng.gen_range(0..basket.len()));
+            let candidates = basket
+                .iter()
+                .filter_map(|item| rules_by_item.get(item.as_str()))
+                .flatten()
+                .cloned();
+            let recommendations = recommend(candidates, &basket, k);
+            let rank = recommendations.iter().position(|r| r.item == hidden);
+            Evaluation {
+                num_baskets: 1,
+                num_covered: if recommendations.is_empty() { 0 } else { 1 },
+                num_hits: if rank.is_some() { 1 } else { 0 },
+                reciprocal_rank_sum: rank.map_or(0.0, |rank| 1.0 / (rank + 1) as f64),
+            }
+        })
+        .reduce(Evaluation::default, Evaluation::add)
+}
+
+pub fn run_evaluate(args: &EvaluateArguments) -> Result<(), ArmError> {
+    let rules = read_rules(&args.rules_path)?;
+    let baskets = read_baskets(&args.input_file_path, args.input_format, args.encoding)?;
+    println!(
+        "Evaluating {} rules on {} baskets...",
+        rules.len(),
+        baskets.len()
+    );
+    let evaluation = evaluate(&rules, &baskets, args.top, args.seed);
+    if evaluation.num_baskets == 0 {
+        println!("No baskets had at least two items to evaluate.");
+        return Ok(());
+    }
+    println!("Baskets evaluated: {}", evaluation.num_baskets);
+    println!("Coverage: {:.4}", evaluation.coverage());
+    println!("Hit rate@{}: {:.4}", args.top, evaluation.hit_rate());
+    println!(
+        "Precision@{}: {:.4}",
+        args.top,
+        evaluation.precision_at(args.top)
+    );
+    println!("MRR: {:.4}", evaluation.mean_reciprocal_rank());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::evaluate;
+    use rule_file::RuleRecord;
+
+    fn rule(antecedent: &str, consequent: &str, confidence: f64) -> RuleRecord {
+        RuleRecord {
+            antecedent: vec![String::from(antecedent)],
+            consequent: vec![String::from(consequent)],
+            confidence,
+            lift: 1.0,
+            support: 0.1,
+            line: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let rules = vec![
+            rule("a", "b", 0.9),
+            rule("b", "a", 0.9),
+            rule("a", "c", 0.5),
+            rule("c", "a", 0.8),
+        ];
+        let basket =
+            |items: &[&str]| -> Vec<String> { items.iter().map(|&s| String::from(s)).collect() };
+        let baskets = vec![basket(&["a", "b"]), basket(&["d"]), basket(&["d", "e"])];
+        // Hiding either item of a,b, the other predicts it first.
+        let evaluation = evaluate(&rules, &baskets, 2, 1);
+        assert_eq!(evaluation.num_baskets, 2);
+        assert_eq!(evaluation.num_covered, 1);
+        assert_eq!(evaluation.num_hits, 1);
+        assert_eq!(evaluation.mean_reciprocal_rank(), 0.5);
+        assert_eq!(evaluation.precision_at(2), 0.25);
+
+        // Hiding c from a,c ranks it second, after b; hiding a ranks it first.
+        let baskets = vec![basket(&["a", "c"]); 20];
+        let evaluation = evaluate(&rules, &baskets, 2, 1);
+        assert_eq!(evaluation.num_hits, 20);
+        // The same items are hidden with the same seed, so the hits with
+        // k = 1 are the baskets in which a was hidden.
+        let hidden_a = evaluate(&rules, &baskets, 1, 1).num_hits as f64;
+        assert!(hidden_a > 0.0 && hidden_a < 20.0);
+        assert_eq!(
+            evaluation.reciprocal_rank_sum,
+            hidden_a + (20.0 - hidden_a) / 2.0
+        );
+    }
+}
@@ -0,0 +1,326 @@
+// Copyright 2018 Chris Pearce
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use error::ArmError;
+use output::{join_items, RULES_HEADER};
+use rule_file::RuleRecord;
+use rules_from_itemsets::ItemsetRecord;
+use std::cmp::Ordering;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+// A rule or itemset shown by the explorer.
+pub struct Row {
+    pub cells: Vec<String>,
+    // The numeric value of each cell, if it has one, to sort by.
+    values: Vec<Option<f64>>,
+    // The rule's or itemset's items, which filters and drilling match.
+    pub items: Vec<String>,
+    // The row as a line of the file it came from, for exporting.
+    line: String,
+}
+
+// The state of `arm tui`'s view of a rules or itemsets file: which rows
+// match the filter and the items drilled into, and their order. Kept apart
+// from the terminal, so it can be tested.
+pub struct Explorer {
+    columns: Vec<&'static str>,
+    header: &'static str,
+    rows: Vec<Row>,
+    // Whitespace separated terms, each of which must be part of one of a
+    // row's items, ignoring case.
+    filter: String,
+    // Items which rows must contain, in the order they were drilled into.
+    drilled: Vec<String>,
+    // The column sorted by, and whether descending.
+    sort: Option<(usize, bool)>,
+    // Indices in rows of the rows in view, in order.
+    view: Vec<usize>,
+}
+
+impl Explorer {
+    fn new(columns: Vec<&'static str>, header: &'static str, rows: Vec<Row>) -> Explorer {
+        let mut explorer = Explorer {
+            columns,
+            header,
+            rows,
+            filter: String::new(),
+            drilled: vec![],
+            sort: None,
+            view: vec![],
+        };
+        explorer.refresh();
+        explorer
+    }
+
+    pub fn from_rules(rules: Vec<RuleRecord>) -> Explorer {
+        let rows = rules
+            .into_iter()
+            .map(|rule| {
+                let values = vec![
+                    None,
+                    None,
+                    Some(rule.confidence),
+                    Some(rule.lift),
+                    Some(rule.support),
+                ];
+                Row {
+                    cells: vec![
+                        rule.antecedent.join(" "),
+                        rule.consequent.join(" "),
+                        format!("{:.4}", rule.confidence),
+                        format!("{:.4}", rule.lift),
+                        format!("{:.4}", rule.support),
+                    ],
+                    values,
+                    items: rule
+                        .antecedent
+                        .iter()
+                        .chain(&rule.consequent)
+                        .cloned()
+                        .collect(),
+                    line: rule.line,
+                }
+            })
+            .collect();
+        Explorer::new(
+            vec!["Antecedent", "Consequent", "Confidence", "Lift", "Support"],
+            RULES_HEADER,
+            rows,
+        )
+    }
+
+    // Itemsets are exported in the format written by --output-itemsets,
+    // whatever format they were read in.
+    pub fn from_itemsets(itemsets: Vec<ItemsetRecord>) -> Explorer {
+        let rows = itemsets
+            .into_iter()
+            .map(|itemset| {
+                let show = |value: Option<String>| value.unwrap_or_default();
+                let support = show(itemset.support.map(|support| support.to_string()));
+                let count = show(itemset.count.map(|count| count.to_string()));
+                Row {
+                    line: format!("{},{},{}", join_items(&itemset.items), support, count),
+                    cells: vec![
+                        itemset.items.join(" "),
+                        show(itemset.support.map(|support| format!("{:.4}", support))),
+                        count,
+                    ],
+                    values: vec![None, itemset.support, itemset.count.map(f64::from)],
+                    items: itemset.items,
+                }
+            })
+            .collect();
+        Explorer::new(
+            vec!["Itemset", "Support", "Count"],
+            "Itemset,Support,Count",
+            rows,
+        )
+    }
+
+    pub fn columns(&self) -> &[&'static str] {
+        &self.columns
+    }
+
+    // Whether a column holds numbers rather than items.
+    pub fn is_numeric(&self, column: usize) -> bool {
+        self.rows
+            .first()
+            .is_some_and(|row| row.values[column].is_some())
+    }
+
+    pub fn num_rows(&self) -> usize {
+        self.rows.len()
+    }
+
+    // The number of rows in view.
+    pub fn len(&self) -> usize {
+        self.view.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.view.is_empty()
+    }
+
+    // The index'th row in view.
+    pub fn row(&self, index: usize) -> &Row {
+        &self.rows[self.view[index]]
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &Row> {
+        self.view.iter().map(move |&index| &self.rows[index])
+    }
+
+    pub fn filter(&self) -> &str {
+        &self.filter
+    }
+
+    pub fn set_filter(&mut self, filter: &str) {
+        self.filter = String::from(filter);
+        self.refresh();
+    }
+
+    pub fn drilled(&self) -> &[String] {
+        &self.drilled
+    }
+
+    // Narrows the view to rows containing item, as well as the items
+    // already drilled into.
+    pub fn drill(&mut self, item: &str) {
+        if !self.drilled.iter().any(|drilled| drilled == item) {
+            self.drilled.push(String::from(item));
+            self.refresh();
+        }
+    }
+
+    // Undoes the last drill, returning false if there was none.
+    pub fn undrill(&mut self) -> bool {
+        let undrilled = self.drilled.pop().is_some();
+        self.refresh();
+        undrilled
+    }
+
+    pub fn sort(&self) -> Option<(usize, bool)> {
+        self.sort
+    }
+
+    // Sorts by column, reversing the order if already sorted by it.
+    // Numeric columns are first sorted descending, and items ascending.
+    pub fn sort_by(&mut self, column: usize) {
+        let descending = match self.sort {
+            Some((sorted, descending)) if sorted == column => !descending,
+            _ => self.is_numeric(column),
+        };
+        self.sort = Some((column, descending));
+        self.refresh();
+    }
+
+    // Whether row contains the drilled items, and matches the filter's
+    // terms, which must be lowercase.
+    fn matches(&self, row: &Row, terms: &[String]) -> bool {
+        let has_item = |item: &String| row.items.contains(item);
+        if !self.drilled.iter().all(has_item) {
+            return false;
+        }
+        if terms.is_empty() {
+            return true;
+        }
+        let lowercase_items: Vec<String> =
+            row.items.iter().map(|item| item.to_lowercase()).collect();
+        terms
+            .iter()
+            .all(|term| lowercase_items.iter().any(|item| item.contains(term)))
+    }
+
+    fn refresh(&mut self) {
+        let terms: Vec<String> = self
+            .filter
+            .split_whitespace()
+            .map(|term| term.to_lowercase())
+            .collect();
+        let mut view: Vec<usize> = (0..self.rows.len())
+            .filter(|&index| self.matches(&self.rows[index], &terms))
+            .collect();
+        if let Some((column, descending)) = self.sort {
+            let rows = &self.rows;
+            view.sort_by(|&a, &b| {
+                let (a, b) = (&rows[a], &rows[b]);
+                let order = match (a.values[column], b.values[column]) {
+                    (Some(x), Some(y)) => x.partial_cmp(&y).unwrap_or(Ordering::Equal),
+                    _ => a.cells[column].cmp(&b.cells[column]),
+                };
+                if descending {
+                    order.reverse()
+                } else {
+                    order
+                }
+            });
+        }
+        self.view = view;
+    }
+
+    // Writes the rows in view, in order, in the format of the file they
+    // came from.
+    pub fn write_view<W: Write>(&self, output: &mut W) -> io::Result<()> {
+        writeln!(output, "{}", self.header)?;
+        for row in self.rows() {
+            writeln!(output, "{}", row.line)?;
+        }
+        Ok(())
+    }
+
+    pub fn export(&self, path: &str) -> Result<(), ArmError> {
+        let file = File::create(path).map_err(|e| ArmError::io(path, e))?;
+        let mut output = BufWriter::new(file);
+        self.write_view(&mut output)
+            .and_then(|_| output.flush())
+            .map_err(|e| ArmError::io(path, e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Explorer;
+    use rule_file::RuleRecord;
+
+    #[test]
+    fn test_explorer() {
+        let rules: Vec<RuleRecord> = [
+            "milk => bread,0.6,1.2,0.3",
+            "bread => eggs,0.5,0.9,0.2",
+            "Milk eggs => bread,0.9,1.5,0.1",
+        ]
+        .iter()
+        .map(|line| line.parse().unwrap())
+        .collect();
+        let mut explorer = Explorer::from_rules(rules);
+        assert_eq!(explorer.len(), 3);
+        assert!(explorer.is_numeric(3) && !explorer.is_numeric(0));
+
+        let antecedents = |explorer: &Explorer| -> Vec<String> {
+            explorer.rows().map(|row| row.cells[0].clone()).collect()
+        };
+        explorer.sort_by(3);
+        assert_eq!(explorer.sort(), Some((3, true)));
+        assert_eq!(antecedents(&explorer), vec!["Milk eggs", "milk", "bread"]);
+        explorer.sort_by(3);
+        assert_eq!(antecedents(&explorer), vec!["bread", "milk", "Milk eggs"]);
+
+        // Filter terms match parts of items, ignoring case.
+        explorer.set_filter("MIL");
+        assert_eq!(antecedents(&explorer), vec!["milk", "Milk eggs"]);
+        explorer.set_filter("");
+        explorer.drill("eggs");
+        assert_eq!(antecedents(&explorer), vec!["bread", "Milk eggs"]);
+        explorer.drill("bread");
+        explorer.drill("bread");
+        assert_eq!(explorer.drilled(), &["eggs", "bread"]);
+        assert!(explorer.undrill() && explorer.undrill() && !explorer.undrill());
+        assert_eq!(explorer.len(), 3);
+
+        explorer.set_filter("eggs");
+        let mut output = vec![];
+        explorer.write_view(&mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert!(lines[0].starts_with("Antecedent => Consequent"));
+        assert_eq!(
+            &lines[1..],
+            &[
+                "bread => eggs,0.5,0.9,0.2",
+                "Milk eggs => bread,0.9,1.5,0.1"
+            ]
+        );
+    }
+}
@@ -0,0 +1,416 @@
+// Copyright 2018 Chris Pearce
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use item::Item;
+use itemizer::Itemizer;
+use rule::Rule;
+use std::str::FromStr;
+
+// An expression over a rule's stats and items, such as
+// "lift > 2 && support >= 0.01 && antecedent_len <= 3 && consequent contains 'beer'".
+//
+// Comparisons are of a field against a number, with <, <=, >, >=, == or !=.
+// Fields are the rule's columns in snake case, e.g. confidence,
+// confidence_lower_bound or p_value, and antecedent_len, consequent_len and
+// len, the number of items in the antecedent, consequent and whole rule.
+// "antecedent contains", "consequent contains" and "rule contains" test for
+// an item, quoted with ' or ". Comparisons can be combined with &&, || and
+// !, and grouped with parentheses; && binds more tightly than ||.
+//
+// Item names are resolved to ids with resolve() before evaluating, so that
+// evaluating doesn't need the Itemizer.
+#[derive(Clone, Debug)]
+pub struct FilterExpression {
+    root: Node,
+}
+
+#[derive(Clone, Debug)]
+enum Node {
+    And(Box<Node>, Box<Node>),
+    Or(Box<Node>, Box<Node>),
+    Not(Box<Node>),
+    Compare(Field, Op, f64),
+    // The item's id is None until resolved, or if it's not in the dataset.
+    Contains(Side, String, Option<Item>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Field {
+    Confidence,
+    Lift,
+    Support,
+    AntecedentCount,
+    ConsequentCount,
+    Count,
+    ConfidenceLowerBound,
+    ConfidenceUpperBound,
+    LiftStdError,
+    Laplace,
+    AddedValue,
+    GiniGain,
+    InfoGain,
+    OddsRatio,
+    YulesQ,
+    Phi,
+    PValue,
+    AntecedentLen,
+    ConsequentLen,
+    Len,
+}
+
+impl Field {
+    fn from_name(name: &str) -> Option<Field> {
+        Some(match name {
+            "confidence" => Field::Confidence,
+            "lift" => Field::Lift,
+            "support" => Field::Support,
+            "antecedent_count" => Field::AntecedentCount,
+            "consequent_count" => Field::ConsequentCount,
+            "count" => Field::Count,
+            "confidence_lower_bound" => Field::ConfidenceLowerBound,
+            "confidence_upper_bound" => Field::ConfidenceUpperBound,
+            "lift_std_error" => Field::LiftStdError,
+            "laplace" => Field::Laplace,
+            "added_value" => Field::AddedValue,
+            "gini_gain" => Field::GiniGain,
+            "info_gain" => Field::InfoGain,
+            "odds_ratio" => Field::OddsRatio,
+            "yules_q" => Field::YulesQ,
+            "phi" => Field::Phi,
+            "p_value" => Field::PValue,
+            "antecedent_len" => Field::AntecedentLen,
+            "consequent_len" => Field::ConsequentLen,
+            "len" => Field::Len,
+            _ => return None,
+        })
+    }
+
+    // Rules without a p-value have NaN, which fails every comparison.
+    fn value(self, rule: &Rule) -> f64 {
+        match self {
+            Field::Confidence => rule.confidence,
+            Field::Lift => rule.lift,
+            Field::Support => rule.support,
+            Field::AntecedentCount => rule.antecedent_count as f64,
+            Field::ConsequentCount => rule.consequent_count as f64,
+            Field::Count => rule.count as f64,
+            Field::ConfidenceLowerBound => rule.confidence_lower_bound,
+            Field::ConfidenceUpperBound => rule.confidence_upper_bound,
+            Field::LiftStdError => rule.lift_std_error,
+            Field::Laplace => rule.laplace,
+            Field::AddedValue => rule.added_value,
+            Field::GiniGain => rule.gini_gain,
+            Field::InfoGain => rule.info_gain,
+            Field::OddsRatio => rule.odds_ratio,
+            Field::YulesQ => rule.yules_q,
+            Field::Phi => rule.phi,
+            Field::PValue => rule.p_value.unwrap_or(f64::NAN),
+            Field::AntecedentLen => rule.antecedent.len() as f64,
+            Field::ConsequentLen => rule.consequent.len() as f64,
+            Field::Len => (rule.antecedent.len() + rule.consequent.len()) as f64,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Op {
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Equal,
+    NotEqual,
+}
+
+impl Op {
+    fn apply(self, a: f64, b: f64) -> bool {
+        match self {
+            Op::Less => a < b,
+            Op::LessOrEqual => a <= b,
+            Op::Greater => a > b,
+            Op::GreaterOrEqual => a >= b,
+            Op::Equal => a == b,
+            Op::NotEqual => a != b,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Side {
+    Antecedent,
+    Consequent,
+    Rule,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Word(String),
+    Number(f64),
+    Str(String),
+    Op(Op),
+    And,
+    Or,
+    Not,
+    Open,
+    Close,
+}
+
+fn tokenize(s: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = vec![];
+    let mut chars = s.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let mut word = String::new();
+            while let Some(&c) = chars
+                .peek()
+                .filter(|c| c.is_ascii_alphanumeric() || **c == '_')
+            {
+                word.push(c);
+                chars.next();
+            }
+            tokens.push(Token::Word(word));
+        } else if c.is_ascii_digit() || c == '-' || c == '.' {
+            let mut number = String::new();
+            while let Some(&c) = chars
+                .peek()
+                .filter(|c| c.is_ascii_alphanumeric() || ['.', '-', '+'].contains(c))
+            {
+                number.push(c);
+                chars.next();
+            }
+            let value = number
+                .parse()
+                .map_err(|_| format!("Invalid number {}", number))?;
+            tokens.push(Token::Number(value));
+        } else if c == '\'' || c == '"' {
+            chars.next();
+            let mut string = String::new();
+            loop {
+                match chars.next() {
+                    Some(end) if end == c => break,
+                    Some(c) => string.push(c),
+                    None => return Err(String::from("Unterminated string")),
+                }
+            }
+            tokens.push(Token::Str(string));
+        } else {
+            chars.next();
+            tokens.push(match c {
+                '(' => Token::Open,
+                ')' => Token::Close,
+                '&' if chars.next_if_eq(&'&').is_some() => Token::And,
+                '|' if chars.next_if_eq(&'|').is_some() => Token::Or,
+                '<' if chars.next_if_eq(&'=').is_some() => Token::Op(Op::LessOrEqual),
+                '<' => Token::Op(Op::Less),
+                '>' if chars.next_if_eq(&'=').is_some() => Token::Op(Op::GreaterOrEqual),
+                '>' => Token::Op(Op::Greater),
+                '=' if chars.next_if_eq(&'=').is_some() => Token::Op(Op::Equal),
+                '!' if chars.next_if_eq(&'=').is_some() => Token::Op(Op::NotEqual),
+                '!' => Token::Not,
+                _ => return Err(format!("Unexpected character {}", c)),
+            });
+        }
+    }
+    Ok(tokens)
+}
+
+// Recursive descent parser over the tokens.
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn accept(&mut self, token: &Token) -> bool {
+        if self.tokens.get(self.position) == Some(token) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn or(&mut self) -> Result<Node, String> {
+        let mut node = self.and()?;
+        while self.accept(&Token::Or) {
+            node = Node::Or(Box::new(node), Box::new(self.and()?));
+        }
+        Ok(node)
+    }
+
+    fn and(&mut self) -> Result<Node, String> {
+        let mut node = self.unary()?;
+        while self.accept(&Token::And) {
+            node = Node::And(Box::new(node), Box::new(self.unary()?));
+        }
+        Ok(node)
+    }
+
+    fn unary(&mut self) -> Result<Node, String> {
+        match self.next() {
+            Some(Token::Not) => Ok(Node::Not(Box::new(self.unary()?))),
+            Some(Token::Open) => {
+                let node = self.or()?;
+                if !self.accept(&Token::Close) {
+                    return Err(String::from("Expected )"));
+                }
+                Ok(node)
+            }
+            Some(Token::Word(word)) => self.condition(&word),
+            Some(token) => Err(format!("Unexpected {:?}", token)),
+            None => Err(String::from("Unexpected end of expression")),
+        }
+    }
+
+    fn condition(&mut self, word: &str) -> Result<Node, String> {
+        let side = match word {
+            "antecedent" => Some(Side::Antecedent),
+            "consequent" => Some(Side::Consequent),
+            "rule" => Some(Side::Rule),
+            _ => None,
+        };
+        if let Some(side) = side {
+            if self.next() != Some(Token::Word(String::from("contains"))) {
+                return Err(format!("Expected contains after {}", word));
+            }
+            return match self.next() {
+                Some(Token::Str(item)) => Ok(Node::Contains(side, item, None)),
+                _ => Err(String::from("Expected a quoted item after contains")),
+            };
+        }
+        let field = Field::from_name(word).ok_or_else(|| format!("Unknown field {}", word))?;
+        let op = match self.next() {
+            Some(Token::Op(op)) => op,
+            _ => return Err(format!("Expected a comparison after {}", word)),
+        };
+        match self.next() {
+            Some(Token::Number(value)) => Ok(Node::Compare(field, op, value)),
+            _ => Err(format!("Expected a number to compare {} with", word)),
+        }
+    }
+}
+
+impl FromStr for FilterExpression {
+    type Err = String;
+    fn from_str(s: &str) -> Result<FilterExpression, String> {
+        let mut parser = Parser {
+            tokens: tokenize(s)?,
+            position: 0,
+        };
+        let root = parser.or()?;
+        if let Some(token) = parser.next() {
+            return Err(format!("Unexpected {:?}", token));
+        }
+        Ok(FilterExpression { root })
+    }
+}
+
+fn resolve_node(node: &mut Node, itemizer: &Itemizer) {
+    match *node {
+        Node::And(ref mut a, ref mut b) | Node::Or(ref mut a, ref mut b) => {
+            resolve_node(a, itemizer);
+            resolve_node(b, itemizer);
+        }
+        Node::Not(ref mut a) => resolve_node(a, itemizer),
+        Node::Compare(..) => {}
+        Node::Contains(_, ref name, ref mut item) => *item = itemizer.get_id(name),
+    }
+}
+
+fn evaluate(node: &Node, rule: &Rule) -> bool {
+    match *node {
+        Node::And(ref a, ref b) => evaluate(a, rule) && evaluate(b, rule),
+        Node::Or(ref a, ref b) => evaluate(a, rule) || evaluate(b, rule),
+        Node::Not(ref a) => !evaluate(a, rule),
+        Node::Compare(field, op, value) => op.apply(field.value(rule), value),
+        Node::Contains(_, _, None) => false,
+        Node::Contains(side, _, Some(item)) => match side {
+            Side::Antecedent => rule.antecedent.contains(&item),
+            Side::Consequent => rule.consequent.contains(&item),
+            Side::Rule => rule.antecedent.contains(&item) || rule.consequent.contains(&item),
+        },
+    }
+}
+
+impl FilterExpression {
+    // Looks up the ids of the items the expression tests for.
+    pub fn resolve(&mut self, itemizer: &Itemizer) {
+        resolve_node(&mut self.root, itemizer);
+    }
+
+    pub fn matches(&self, rule: &Rule) -> bool {
+        evaluate(&self.root, rule)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FilterExpression;
+    use itemizer::Itemizer;
+    use rule::Rule;
+
+    #[test]
+    fn test_filter_expression() {
+        let mut itemizer = Itemizer::new();
+        let beer = itemizer.id_of("beer");
+        let chips = itemizer.id_of("chips");
+        let salsa = itemizer.id_of("salsa");
+        // Lift 2, support 0.2.
+        let rule = Rule::new(vec![chips, salsa], vec![beer], 2, 2, 5, 10);
+        let matches = |expression: &str| {
+            let mut expression: FilterExpression = expression.parse().unwrap();
+            expression.resolve(&itemizer);
+            expression.matches(&rule)
+        };
+        assert!(matches(
+            "lift >= 2 && support >= 0.1 && antecedent_len <= 3 && consequent contains 'beer'"
+        ));
+        assert!(!matches("lift > 2"));
+        assert!(matches("lift > 2 || rule contains \"salsa\""));
+        assert!(!matches(
+            "antecedent contains 'beer' || consequent contains 'nuts'"
+        ));
+        assert!(matches("!(count < 2) && len == 3 && consequent_len != 2"));
+        assert!(!matches(
+            "lift > 1 && (confidence < 0.5 || antecedent_count > 2)"
+        ));
+        assert!(matches("added_value > -0.5"));
+        // Rules without a p-value fail every p-value comparison.
+        assert!(!matches("p_value < 1") && !matches("p_value >= 0"));
+
+        for invalid in &[
+            "",
+            "lift >",
+            "lift > x",
+            "lift 2",
+            "height > 2",
+            "(lift > 2",
+            "lift > 2)",
+            "lift > 2 &",
+            "consequent contains beer",
+            "consequent has 'beer'",
+            "consequent contains 'beer",
+        ] {
+            assert!(invalid.parse::<FilterExpression>().is_err(), "{}", invalid);
+        }
+    }
+}
@@ -0,0 +1,53 @@
+// Copyright 2018 Chris Pearce
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use error::ArmError;
+#[cfg(feature = "flame")]
+use std::fs::File;
+use std::io;
+#[cfg(feature = "flame")]
+use std::io::BufWriter;
+#[cfg(feature = "flame")]
+use tracing;
+#[cfg(feature = "flame")]
+use tracing_flame::{FlameLayer, FlushGuard};
+#[cfg(feature = "flame")]
+use tracing_subscriber::layer::SubscriberExt;
+#[cfg(feature = "flame")]
+use tracing_subscriber::registry::Registry;
+
+// Mining's phases, and the mining of each frequent item, are recorded as
+// tracing spans. Library users can collect them with any tracing subscriber;
+// the command line can write them as folded stacks, from which inferno or
+// flamegraph.pl draws a flame graph. The stacks are flushed when the
+// returned guard is dropped.
+#[cfg(feature = "flame")]
+pub fn write_flame_stacks(path: &str) -> Result<FlushGuard<BufWriter<File>>, ArmError> {
+    let to_io = |e: tracing_flame::Error| io::Error::other(e.to_string());
+    let (layer, guard) = FlameLayer::with_file(path).map_err(|e| ArmError::io(path, to_io(e)))?;
+    tracing::subscriber::set_global_default(Registry::default().with(layer))
+        .map_err(|e| ArmError::io(path, io::Error::other(e.to_string())))?;
+    Ok(guard)
+}
+
+#[cfg(not(feature = "flame"))]
+pub fn write_flame_stacks(path: &str) -> Result<(), ArmError> {
+    Err(ArmError::io(
+        path,
+        io::Error::new(
+            io::ErrorKind::Unsupported,
+            "Writing flame graph stacks requires building with --features flame",
+        ),
+    ))
+}
@@ -12,146 +12,176 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use cancellation::CancellationToken;
+use counter::Counter;
 use item::Item;
 use item_counter::ItemCounter;
+use item_table::ItemTable;
 use itemizer::Itemizer;
 use itertools::Itertools;
+use memory::vec_bytes;
 use rayon::prelude::*;
+use rayon::{scope, Scope};
 use std::cmp;
-use std::hash::{Hash, Hasher};
+use std::fmt::Write;
+use std::sync::Mutex;
+use tracing::{info_span, Span};
 
-#[derive(Eq, Debug)]
-struct FPNode {
-    id: usize,
-    item: Item,
-    count: u32,
-    children: Vec<usize>,
-    parent: usize,
-}
-
-impl PartialEq for FPNode {
-    fn eq(&self, other: &FPNode) -> bool {
-        self.id == other.id
-    }
-}
-
-impl Hash for FPNode {
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        self.id.hash(state);
-    }
-}
+// Sentinel for "no node", used to terminate child and sibling lists.
+const NO_NODE: u32 = u32::MAX;
 
+// Nodes are stored in a structure-of-arrays layout, indexed by node id, to
+// keep per-node overhead small; trees for large datasets can have hundreds
+// of millions of nodes. Rather than each node owning a Vec of children, a
+// node's children form a singly linked list threaded through next_sibling,
+// starting at first_child. Similarly the header table stores the first and
+// last node for each item, and the nodes for an item are linked in insertion
+// order through next_same_item. The arrays are, in effect, the tree's node
+// arena: adding a node, child list entry included, pushes onto them, so
+// building a tree makes no per-node allocations.
 pub struct FPTree {
-    nodes: Vec<Vec<FPNode>>,
+    items: Vec<Item>,
+    counts: Vec<u32>,
+    parents: Vec<u32>,
+    first_child: Vec<u32>,
+    next_sibling: Vec<u32>,
+    next_same_item: Vec<u32>,
     item_count: ItemCounter,
-    next_node_id: usize,
-    item_lists: Vec<Vec<usize>>,
+    // Indexed by item.
+    header_first: ItemTable<u32>,
+    header_last: ItemTable<u32>,
+    // The root's child with each item, indexed by item, and its last child.
+    // The root can have a child for every item in a large vocabulary, too
+    // many to search linearly on every insert; other nodes have few
+    // children, so their lists are searched.
+    root_children: ItemTable<u32>,
+    root_last_child: u32,
 }
 
-impl FPNode {
-    fn new(id: usize, item: Item, parent: usize) -> FPNode {
-        FPNode {
-            id,
-            item,
-            count: 0,
-            children: Vec::with_capacity(1),
-            parent,
-        }
-    }
+const ROOT: u32 = 0;
 
-    fn is_root(&self) -> bool {
-        self.item.is_null()
+impl Default for FPTree {
+    fn default() -> FPTree {
+        FPTree::new()
     }
 }
 
-static FPTREE_SPLAY: usize = 1 << FPTREE_COHORT_SHIFT;
-static FPTREE_COHORT_SHIFT: usize = 8;
-
 impl FPTree {
+    // Estimated bytes used by the tree's nodes and indexes.
+    pub fn heap_size(&self) -> usize {
+        vec_bytes(&self.items)
+            + vec_bytes(&self.counts)
+            + vec_bytes(&self.parents)
+            + vec_bytes(&self.first_child)
+            + vec_bytes(&self.next_sibling)
+            + vec_bytes(&self.next_same_item)
+            + self.header_first.heap_size()
+            + self.header_last.heap_size()
+            + self.root_children.heap_size()
+            + self.item_count.heap_size()
+    }
+
     pub fn new() -> FPTree {
         let mut tree = FPTree {
-            nodes: Vec::with_capacity(FPTREE_SPLAY),
+            items: vec![],
+            counts: vec![],
+            parents: vec![],
+            first_child: vec![],
+            next_sibling: vec![],
+            next_same_item: vec![],
             item_count: ItemCounter::new(),
-            next_node_id: 0,
-            item_lists: Vec::new(),
+            header_first: ItemTable::new(NO_NODE),
+            header_last: ItemTable::new(NO_NODE),
+            root_children: ItemTable::new(NO_NODE),
+            root_last_child: NO_NODE,
         };
-        // Add root.
-        tree.add_node(0, Item::null());
-        return tree;
-    }
-
-    pub fn add_node(&mut self, parent: usize, item: Item) -> usize {
-        let id = self.next_node_id;
-        self.next_node_id += 1;
-        let (cohort, element) = self.sub_indicies_of(id);
-        // Should only be at most 1 element too small.
-        assert!(cohort <= self.nodes.len());
-        if self.nodes.len() <= cohort {
-            self.nodes.push(Vec::with_capacity(FPTREE_SPLAY));
-        }
-        assert!(element == self.nodes[cohort].len());
-        self.nodes[cohort].push(FPNode::new(id, item, parent));
-        assert!(self.get_node(id).item == item);
-        self.get_node_mut(parent).children.push(id);
-        self.add_to_item_list(item, id);
+        // Add root. The root is its own parent, but not its own child.
+        tree.add_node(ROOT, Item::null(), NO_NODE);
+        tree
+    }
+
+    // Adds a node as the next sibling of prev_sibling, or as the first child
+    // of parent if prev_sibling is NO_NODE. Appending rather than prepending
+    // keeps children in insertion order, so the most frequent items, which
+    // are generally inserted first, are found first when searching children.
+    fn add_node(&mut self, parent: u32, item: Item, prev_sibling: u32) -> u32 {
+        let id = self.items.len() as u32;
+        assert!(id != NO_NODE, "FPTree has too many nodes");
+        self.items.push(item);
+        self.counts.push(0);
+        self.parents.push(parent);
+        self.first_child.push(NO_NODE);
+        self.next_sibling.push(NO_NODE);
+        self.next_same_item.push(NO_NODE);
+        if id != parent {
+            if prev_sibling == NO_NODE {
+                self.first_child[parent as usize] = id;
+            } else {
+                self.next_sibling[prev_sibling as usize] = id;
+            }
+        }
+        self.add_to_header(item, id);
         id
     }
 
-    fn add_to_item_list(&mut self, item: Item, id: usize) {
+    fn add_to_header(&mut self, item: Item, id: u32) {
         if item.is_null() {
             return;
         }
-        let index = item.as_index();
-        if index >= self.item_lists.len() {
-            self.item_lists.resize(index + 1, vec![]);
+        let last = self.header_last.get(item);
+        if last == NO_NODE {
+            self.header_first.set(item, id);
+        } else {
+            self.next_same_item[last as usize] = id;
         }
-        self.item_lists[index].push(id);
-    }
-
-    fn sub_indicies_of(&self, id: usize) -> (usize, usize) {
-        (
-            id >> FPTREE_COHORT_SHIFT,
-            id & ((1 << FPTREE_COHORT_SHIFT) - 1),
-        )
+        self.header_last.set(item, id);
     }
 
-    fn get_node_mut(&mut self, id: usize) -> &mut FPNode {
-        let (cohort, index) = self.sub_indicies_of(id);
-        if cohort >= self.nodes.len() || index >= self.nodes[cohort].len() {
-            panic!("Invalid node id")
+    fn children(&self, id: u32) -> LinkedNodes<'_> {
+        LinkedNodes {
+            links: &self.next_sibling,
+            next: self.first_child[id as usize],
         }
-        &mut self.nodes[cohort][index]
     }
 
-    fn get_node(&self, id: usize) -> &FPNode {
-        let (cohort, index) = self.sub_indicies_of(id);
-        if cohort >= self.nodes.len() || index >= self.nodes[cohort].len() {
-            panic!("Invalid node id")
+    fn nodes_with_item(&self, item: Item) -> LinkedNodes<'_> {
+        LinkedNodes {
+            links: &self.next_same_item,
+            next: self.header_first.get(item),
         }
-        &self.nodes[cohort][index]
     }
 
-    pub fn child_of(&self, id: usize, item: Item) -> Option<usize> {
-        for &node_id in &self.get_node(id).children {
-            if self.get_node(node_id).item == item {
-                return Some(node_id);
-            }
+    fn insert_child(&mut self, id: u32, item: Item, count: u32) -> u32 {
+        if id == ROOT {
+            return self.insert_root_child(item, count);
+        }
+        let mut last_child = NO_NODE;
+        let mut child_id = self.first_child[id as usize];
+        while child_id != NO_NODE && self.items[child_id as usize] != item {
+            last_child = child_id;
+            child_id = self.next_sibling[child_id as usize];
         }
-        None
+        if child_id == NO_NODE {
+            child_id = self.add_node(id, item, last_child);
+        }
+        self.counts[child_id as usize] += count;
+        child_id
     }
 
-    fn insert_child(&mut self, id: usize, item: Item, count: u32) -> usize {
-        let child_id = match self.child_of(id, item) {
-            Some(child_id) => child_id,
-            None => self.add_node(id, item),
-        };
-        self.get_node_mut(child_id).count += count;
+    fn insert_root_child(&mut self, item: Item, count: u32) -> u32 {
+        let mut child_id = self.root_children.get(item);
+        if child_id == NO_NODE {
+            child_id = self.add_node(ROOT, item, self.root_last_child);
+            self.root_children.set(item, child_id);
+            self.root_last_child = child_id;
+        }
+        self.counts[child_id as usize] += count;
         child_id
     }
 
     pub fn insert(&mut self, transaction: &[Item], count: u32) {
         // Start iterating at the root node.
-        let mut id = 0;
+        let mut id = ROOT;
         for &item in transaction {
             // Keep a count of item frequencies of what's in the
             // tree to make sorting later easier.
@@ -161,102 +191,1135 @@ impl FPTree {
         }
     }
 
+    // Adds all paths in other into this tree, as if every transaction
+    // inserted into other had been inserted into this tree instead.
+    pub fn merge(&mut self, other: &FPTree) {
+        // Pairs of (node in self, corresponding node in other), starting
+        // at the roots. Use an explicit stack rather than recursion, as
+        // trees can be very deep.
+        let mut stack: Vec<(u32, u32)> = vec![(ROOT, ROOT)];
+        while let Some((id, other_id)) = stack.pop() {
+            for other_child_id in other.children(other_id) {
+                let item = other.items[other_child_id as usize];
+                let count = other.counts[other_child_id as usize];
+                self.item_count.add(&item, count);
+                let child_id = self.insert_child(id, item, count);
+                stack.push((child_id, other_child_id));
+            }
+        }
+    }
+
+    pub fn num_nodes(&self) -> usize {
+        self.items.len()
+    }
+
     fn item_count(&self) -> &ItemCounter {
         &self.item_count
     }
 
-    pub fn construct_conditional_tree(&self, item: Item) -> FPTree {
-        let item_list = &self.item_lists[item.as_index()];
+    // Builds the tree of prefix paths of nodes with item. Items which would
+    // have a count below min_count in the conditional tree can't be in any
+    // frequent itemset mined from it, so a first pass counts the items in
+    // the prefix paths, and infrequent items are stripped from the paths
+    // before they're inserted.
+    pub fn construct_conditional_tree(&self, item: Item, min_count: u32) -> FPTree {
+        let mut path_item_count = ItemCounter::new();
+        for node_id in self.nodes_with_item(item) {
+            let count = self.counts[node_id as usize];
+            for item in self.ancestor_items(node_id) {
+                path_item_count.add(&item, count);
+            }
+        }
+
         let mut conditional_tree = FPTree::new();
-        for &node_id in item_list {
-            conditional_tree.insert(
-                &self.path_from_root_to_excluding(node_id),
-                self.get_node(node_id).count,
+        // Reused for every path, as allocating a path per node is costly
+        // in deep recursions.
+        let mut path = vec![];
+        for node_id in self.nodes_with_item(item) {
+            path.clear();
+            path.extend(
+                self.ancestor_items(node_id)
+                    .filter(|item| path_item_count.get(item) >= min_count),
             );
+            if !path.is_empty() {
+                path.reverse();
+                conditional_tree.insert(&path, self.counts[node_id as usize]);
+            }
         }
         conditional_tree
     }
 
-    fn path_from_root_to_excluding(&self, node_id: usize) -> Vec<Item> {
-        let mut path = vec![];
-        let mut id = self.get_node(node_id).parent;
-        loop {
-            let node = self.get_node(id);
-            if node.is_root() {
-                break;
+    pub fn count_of(&self, item: Item) -> u32 {
+        self.item_count.get(&item)
+    }
+
+    // Builds the tree of all transactions containing item, with item removed.
+    // Unlike the conditional tree, which holds only the prefix paths of
+    // item's nodes, this also includes the items below item's nodes, so
+    // mining it finds every frequent itemset containing item, not only those
+    // whose other items precede item in the tree. A transaction ends at a
+    // node when the node's count exceeds the sum of its children's counts.
+    pub fn construct_projected_tree(&self, item: Item, min_count: u32) -> FPTree {
+        let mut transactions: Vec<(Vec<Item>, u32)> = vec![];
+        let mut stack: Vec<(u32, Vec<Item>)> = self
+            .nodes_with_item(item)
+            .map(|node_id| (node_id, self.path_from_root_to_excluding(node_id)))
+            .collect();
+        while let Some((id, path)) = stack.pop() {
+            let children_count: u32 = self
+                .children(id)
+                .map(|child| self.counts[child as usize])
+                .sum();
+            let end_count = self.counts[id as usize] - children_count;
+            for child in self.children(id) {
+                let mut child_path = path.clone();
+                child_path.push(self.items[child as usize]);
+                stack.push((child, child_path));
+            }
+            if end_count > 0 {
+                transactions.push((path, end_count));
+            }
+        }
+
+        let mut item_count = ItemCounter::new();
+        for (transaction, count) in &transactions {
+            for item in transaction {
+                item_count.add(item, *count);
+            }
+        }
+        let mut projected_tree = FPTree::new();
+        for (mut transaction, count) in transactions {
+            transaction.retain(|item| item_count.get(item) >= min_count);
+            if !transaction.is_empty() {
+                projected_tree.insert(&transaction, count);
+            }
+        }
+        projected_tree
+    }
+
+    // Builds the tree used to mine a group of items in PFP style, which
+    // holds each path of this tree up to its last node with an item in the
+    // group. The nodes of the group's items,
+    // and their prefix paths, are as in this tree, so their conditional
+    // trees are too, but nodes only on paths to other groups' items are
+    // dropped.
+    pub fn construct_group_tree(&self, in_group: &ItemTable<bool>) -> FPTree {
+        let is_in_group = |item: Item| in_group.get(item);
+        // Number of transactions through each node which contain an item in
+        // the group at or below the node. Children have higher ids than their
+        // parents, so visiting nodes in reverse order visits children first.
+        let mut group_counts = vec![0u32; self.num_nodes()];
+        for id in (1..self.num_nodes()).rev() {
+            if is_in_group(self.items[id]) {
+                group_counts[id] = self.counts[id];
+            }
+            if group_counts[id] > 0 {
+                group_counts[self.parents[id] as usize] += group_counts[id];
+            }
+        }
+
+        let mut group_tree = FPTree::new();
+        let mut stack: Vec<(u32, u32)> = vec![(ROOT, ROOT)];
+        while let Some((id, group_id)) = stack.pop() {
+            for child_id in self.children(id) {
+                let count = group_counts[child_id as usize];
+                if count == 0 {
+                    continue;
+                }
+                let item = self.items[child_id as usize];
+                group_tree.item_count.add(&item, count);
+                let group_child_id = group_tree.insert_child(group_id, item, count);
+                stack.push((child_id, group_child_id));
+            }
+        }
+        group_tree
+    }
+
+    // Returns the number of transactions in the tree containing items.
+    // last_item must be the item of items furthest from the root, so that
+    // every such transaction passes through one of its nodes.
+    pub fn count_itemset(&self, items: &[Item], last_item: Item) -> u32 {
+        let mut count = 0;
+        for node_id in self.nodes_with_item(last_item) {
+            let mut remaining = items.len() - 1;
+            let mut id = self.parents[node_id as usize];
+            while id != ROOT && remaining > 0 {
+                if items.contains(&self.items[id as usize]) {
+                    remaining -= 1;
+                }
+                id = self.parents[id as usize];
+            }
+            if remaining == 0 {
+                count += self.counts[node_id as usize];
+            }
+        }
+        count
+    }
+
+    // Renders the tree in Graphviz DOT format. Nodes are labelled with their
+    // item and count, solid edges link parents to children, and dashed edges
+    // link nodes with the same item, as in the header table's node lists.
+    pub fn to_dot(&self, itemizer: &Itemizer) -> String {
+        let mut dot = String::from("digraph fptree {\n");
+        for id in 0..self.num_nodes() as u32 {
+            let label = if id == ROOT {
+                String::from("root")
+            } else {
+                let index = id as usize;
+                format!(
+                    "{}:{}",
+                    itemizer.str_of(self.items[index]),
+                    self.counts[index]
+                )
+            };
+            writeln!(
+                dot,
+                "    n{} [label=\"{}\"];",
+                id,
+                label.replace('\\', "\\\\").replace('"', "\\\"")
+            )
+            .unwrap();
+            for child in self.children(id) {
+                writeln!(dot, "    n{} -> n{};", id, child).unwrap();
+            }
+        }
+        for (id, &next) in self.next_same_item.iter().enumerate() {
+            if next != NO_NODE {
+                writeln!(
+                    dot,
+                    "    n{} -> n{} [style=dashed, constraint=false];",
+                    id, next
+                )
+                .unwrap();
             }
-            path.push(node.item);
-            id = node.parent;
         }
+        dot.push_str("}\n");
+        dot
+    }
+
+    // Each node's item, count and the position one past the end of its
+    // subtree, in depth-first pre-order starting with the root. A node's
+    // descendants are the nodes between its position and its subtree's end.
+    pub fn pre_order(&self) -> Vec<(Item, u32, u32)> {
+        // Children have higher ids than their parents, so visiting nodes in
+        // reverse order visits children first.
+        let mut subtree_sizes = vec![1u32; self.num_nodes()];
+        for id in (1..self.num_nodes()).rev() {
+            subtree_sizes[self.parents[id] as usize] += subtree_sizes[id];
+        }
+        let mut nodes = Vec::with_capacity(self.num_nodes());
+        let mut stack: Vec<u32> = vec![ROOT];
+        while let Some(id) = stack.pop() {
+            let index = id as usize;
+            let end = nodes.len() as u32 + subtree_sizes[index];
+            nodes.push((self.items[index], self.counts[index], end));
+            stack.extend(self.children(id));
+        }
+        nodes
+    }
+
+    // Items on the path from node_id's parent up to, but excluding, the
+    // root; that is, the path from the root in reverse.
+    fn ancestor_items(&self, node_id: u32) -> AncestorItems<'_> {
+        AncestorItems {
+            tree: self,
+            id: self.parents[node_id as usize],
+        }
+    }
+
+    fn path_from_root_to_excluding(&self, node_id: u32) -> Vec<Item> {
+        let mut path: Vec<Item> = self.ancestor_items(node_id).collect();
         path.reverse();
         path
     }
 }
 
-#[derive(Clone, Hash, PartialEq, Eq, Debug, Ord)]
+struct AncestorItems<'a> {
+    tree: &'a FPTree,
+    id: u32,
+}
+
+impl<'a> Iterator for AncestorItems<'a> {
+    type Item = Item;
+    fn next(&mut self) -> Option<Item> {
+        if self.id == ROOT {
+            return None;
+        }
+        let item = self.tree.items[self.id as usize];
+        self.id = self.tree.parents[self.id as usize];
+        Some(item)
+    }
+}
+
+// Iterates over a linked list of nodes threaded through links, such as a
+// node's children or the nodes for an item.
+struct LinkedNodes<'a> {
+    links: &'a [u32],
+    next: u32,
+}
+
+impl<'a> Iterator for LinkedNodes<'a> {
+    type Item = u32;
+    fn next(&mut self) -> Option<u32> {
+        if self.next == NO_NODE {
+            return None;
+        }
+        let id = self.next;
+        self.next = self.links[id as usize];
+        Some(id)
+    }
+}
+
+#[derive(Clone, Hash, PartialEq, Eq, Debug)]
 pub struct ItemSet {
     pub items: Vec<Item>,
     pub count: u32,
 }
 
-impl PartialOrd for ItemSet {
-    fn partial_cmp(&self, other: &ItemSet) -> Option<cmp::Ordering> {
+impl Ord for ItemSet {
+    fn cmp(&self, other: &ItemSet) -> cmp::Ordering {
         if other.len() != self.len() {
-            return Some(self.len().cmp(&other.len()));
+            return self.len().cmp(&other.len());
         }
-        Some(self.items.cmp(&other.items))
+        self.items.cmp(&other.items)
+    }
+}
+
+impl PartialOrd for ItemSet {
+    fn partial_cmp(&self, other: &ItemSet) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
     }
 }
 
 impl ItemSet {
     pub fn new(items: Vec<Item>, count: u32) -> ItemSet {
         ItemSet {
-            items: items.iter().map(|&x| x).sorted(),
-            count: count,
+            items: items.iter().cloned().sorted(),
+            count,
         }
     }
 
     pub fn len(&self) -> usize {
         self.items.len()
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
 }
 
-pub fn fp_growth(
+pub struct FPGrowthParameters {
+    pub min_count: u32,
+    // If set, don't generate itemsets longer than this, and don't recurse
+    // into conditional trees which could only produce longer itemsets.
+    pub max_itemset_size: Option<usize>,
+    // Checked before mining each item's conditional tree; once cancelled,
+    // only the itemsets found so far are returned.
+    pub cancellation: CancellationToken,
+    // If set, itemsets mixing common and rare items are pruned.
+    pub cross_support: Option<CrossSupportPruning>,
+    // Per-item minimum counts, for mining with multiple minimum supports. An
+    // itemset is frequent if its count is at least the lowest minimum count
+    // of its items. The tree must be ordered so that items with lower
+    // minimum counts are further from the root; then the first item of the
+    // path is the item with the lowest minimum count of every itemset mined
+    // from its conditional tree. min_count must be the lowest minimum count.
+    pub item_min_counts: Option<ItemCounter>,
+}
+
+impl FPGrowthParameters {
+    // Minimum count of itemsets starting with first_item.
+    pub fn min_count_for(&self, first_item: Option<&Item>) -> u32 {
+        match (first_item, &self.item_min_counts) {
+            (Some(item), Some(item_min_counts)) => item_min_counts.get(item),
+            _ => self.min_count,
+        }
+    }
+}
+
+// Prunes cross-support patterns: itemsets containing items with very
+// different supports, such as a very common item with a rare one, whose
+// items mostly occur without each other. Both constraints are
+// anti-monotone, so itemsets which fail them are pruned along with all
+// their supersets.
+#[derive(Clone)]
+pub struct CrossSupportPruning {
+    // Counts of each item in the whole dataset.
+    pub item_count: ItemCounter,
+    // The all-confidence (or h-confidence) of an itemset is its count
+    // divided by the count of its most frequent item. Itemsets meeting this
+    // are hyperclique patterns.
+    pub min_all_confidence: Option<f64>,
+    // The largest ratio allowed between the counts of an itemset's most and
+    // least frequent items.
+    pub max_support_ratio: Option<f64>,
+}
+
+impl CrossSupportPruning {
+    pub fn admits(&self, items: &[Item], count: u32) -> bool {
+        let counts = items.iter().map(|item| self.item_count.get(item));
+        let max_count = counts.clone().max().unwrap_or(0) as f64;
+        let min_count = counts.min().unwrap_or(0) as f64;
+        self.min_all_confidence
+            .is_none_or(|min_all_confidence| count as f64 >= min_all_confidence * max_count)
+            && self
+                .max_support_ratio
+                .is_none_or(|max_support_ratio| max_count <= max_support_ratio * min_count)
+    }
+}
+
+// Conditional trees with at least this many nodes are mined as separate
+// rayon tasks, which idle threads can steal; smaller trees are mined on the
+// current task's stack, as they're mined faster than they can be scheduled.
+const SPAWN_MIN_NODES: usize = 1024;
+
+// A conditional tree still to be mined, and the itemset it was built for.
+struct MiningTask {
+    tree: FPTree,
+    path: Vec<Item>,
+    path_count: u32,
+}
+
+// Adds the itemset of item appended to path to itemsets, if it's frequent in
+// fptree, and returns the task to mine its conditional tree, if any.
+fn mine_item_step(
     fptree: &FPTree,
-    min_count: u32,
+    params: &FPGrowthParameters,
     path: &[Item],
     path_count: u32,
-    itemizer: &Itemizer,
+    item: Item,
+    itemsets: &mut Vec<ItemSet>,
+) -> Option<MiningTask> {
+    if params.cancellation.is_cancelled() {
+        return None;
+    }
+    // The path to here plus this item must be above the minimum
+    // support threshold.
+    let mut itemset: Vec<Item> = Vec::from(path);
+    let new_path_count = cmp::min(path_count, fptree.item_count().get(&item));
+    itemset.push(item);
+    let min_count = params.min_count_for(itemset.first());
+    if new_path_count < min_count {
+        return None;
+    }
+    if let Some(ref cross_support) = params.cross_support {
+        if !cross_support.admits(&itemset, new_path_count) {
+            return None;
+        }
+    }
+
+    itemsets.push(ItemSet::new(itemset.clone(), new_path_count));
+    params.cancellation.add_to_count(1);
+    let at_max_size = params
+        .max_itemset_size
+        .is_some_and(|max| itemset.len() >= max);
+    if at_max_size {
+        return None;
+    }
+    Some(MiningTask {
+        tree: fptree.construct_conditional_tree(item, min_count),
+        path: itemset,
+        path_count: new_path_count,
+    })
+}
+
+// Mines task's tree, and the conditional trees built from it, using an
+// explicit stack rather than recursion, as conditional trees can nest as
+// deeply as the longest frequent itemset, which can overflow the stacks of
+// rayon's threads. Large conditional trees are spawned on scope instead.
+fn run_mining_task<'s>(
+    scope: &Scope<'s>,
+    params: &'s FPGrowthParameters,
+    task: MiningTask,
+    results: &'s Mutex<Vec<ItemSet>>,
+) {
+    let mut itemsets = vec![];
+    let mut stack = vec![task];
+    while let Some(task) = stack.pop() {
+        let items = task
+            .tree
+            .item_count()
+            .items_with_count_at_least(params.min_count_for(task.path.first()));
+        for item in items {
+            let child = mine_item_step(
+                &task.tree,
+                params,
+                &task.path,
+                task.path_count,
+                item,
+                &mut itemsets,
+            );
+            match child {
+                Some(child) if child.tree.num_nodes() >= SPAWN_MIN_NODES => {
+                    scope.spawn(move |scope| run_mining_task(scope, params, child, results));
+                }
+                Some(child) => stack.push(child),
+                None => {}
+            }
+        }
+    }
+    results.lock().unwrap().extend(itemsets);
+}
+
+// Mines the itemsets in fptree which end in item, appended to path.
+fn mine_item(
+    fptree: &FPTree,
+    params: &FPGrowthParameters,
+    path: &[Item],
+    path_count: u32,
+    item: Item,
 ) -> Vec<ItemSet> {
-    let mut itemsets: Vec<ItemSet> = vec![];
+    let mut itemsets = vec![];
+    let task = match mine_item_step(fptree, params, path, path_count, item, &mut itemsets) {
+        Some(task) => task,
+        None => return itemsets,
+    };
+    let results = Mutex::new(itemsets);
+    scope(|s| run_mining_task(s, params, task, &results));
+    let mut itemsets = results.into_inner().unwrap();
+    // Spawned tasks finish in any order; sort so that output is the same
+    // from run to run.
+    itemsets.sort_unstable();
+    itemsets
+}
 
+pub fn fp_growth(
+    fptree: &FPTree,
+    params: &FPGrowthParameters,
+    path: &[Item],
+    path_count: u32,
+) -> Vec<ItemSet> {
     // Get list of items in the tree which are above the minimum support
     // threshold.
-    let items: Vec<Item> = fptree.item_count().items_with_count_at_least(min_count);
+    let items: Vec<Item> = fptree
+        .item_count()
+        .items_with_count_at_least(params.min_count_for(path.first()));
 
-    let x: Vec<ItemSet> = items
+    items
         .par_iter()
-        .flat_map(|item| -> Vec<ItemSet> {
-            // The path to here plus this item must be above the minimum
-            // support threshold.
-            let mut itemset: Vec<Item> = Vec::from(path);
-            let new_path_count = cmp::min(path_count, fptree.item_count().get(&item));
-            itemset.push(*item);
-
-            let conditional_tree = fptree.construct_conditional_tree(*item);
-            let mut result = fp_growth(
-                &conditional_tree,
-                min_count,
-                &itemset,
-                new_path_count,
-                itemizer,
-            );
+        .flat_map(|&item| mine_item(fptree, params, path, path_count, item))
+        .collect::<Vec<ItemSet>>()
+}
+
+pub struct FPGrowthProgress {
+    pub itemsets: Vec<ItemSet>,
+    // Number of frequent items in the tree mined.
+    pub num_items: usize,
+    // Items whose conditional trees weren't completely mined before
+    // cancellation. Every frequent itemset made up only of other items was
+    // found, so their subsets are all present too.
+    pub incomplete_items: Vec<Item>,
+}
+
+// Mines the whole of fptree like fp_growth, also reporting which parts of the
+// item space were left unmined if params.cancellation is cancelled.
+pub fn fp_growth_with_progress(
+    fptree: &FPTree,
+    params: &FPGrowthParameters,
+    num_transactions: u32,
+) -> FPGrowthProgress {
+    let items: Vec<Item> = fptree
+        .item_count()
+        .items_with_count_at_least(params.min_count);
+
+    let parent = Span::current();
+    let results: Vec<(Item, Vec<ItemSet>, bool)> = items
+        .par_iter()
+        .map(|&item| mine_item_with_progress(fptree, params, num_transactions, item, &parent))
+        .collect();
+    collect_progress(items.len(), results)
+}
+
+// Mines item's itemsets, also returning whether mining was completed. The
+// mining is recorded as a span within parent, as tasks run on other threads
+// don't inherit the current span.
+fn mine_item_with_progress(
+    fptree: &FPTree,
+    params: &FPGrowthParameters,
+    num_transactions: u32,
+    item: Item,
+    parent: &Span,
+) -> (Item, Vec<ItemSet>, bool) {
+    let _span = info_span!(parent: parent, "mine_item", item = item.as_index()).entered();
+    let itemsets = mine_item(fptree, params, &[], num_transactions, item);
+    // Cancellation is never undone, so if the token isn't cancelled after
+    // mining, it wasn't cancelled during mining either.
+    (item, itemsets, !params.cancellation.is_cancelled())
+}
+
+pub fn collect_progress(
+    num_items: usize,
+    results: Vec<(Item, Vec<ItemSet>, bool)>,
+) -> FPGrowthProgress {
+    let mut progress = FPGrowthProgress {
+        itemsets: vec![],
+        num_items,
+        incomplete_items: vec![],
+    };
+    for (item, itemsets, complete) in results {
+        progress.itemsets.extend(itemsets);
+        if !complete {
+            progress.incomplete_items.push(item);
+        }
+    }
+    progress
+}
 
-            result.push(ItemSet::new(itemset, new_path_count));
-            result
+// Mines the itemsets needed to generate rules with target as their
+// consequent: the frequent itemsets containing every item of target, the
+// same itemsets without target, which are the rules' antecedents, and
+// target itself. Rather than mining the whole tree, only the tree of
+// transactions containing target is mined, and the antecedents are counted
+// in fptree. item_order must be the order fptree's items were inserted in,
+// as passed to ItemCounter::sort_descending.
+pub fn fp_growth_targeted(
+    fptree: &FPTree,
+    params: &FPGrowthParameters,
+    target: &[Item],
+    item_order: &ItemCounter,
+) -> FPGrowthProgress {
+    let furthest_from_root = |items: &[Item]| {
+        let mut items = items.to_vec();
+        item_order.sort_descending(&mut items);
+        *items.last().unwrap()
+    };
+    let target_count = fptree.count_itemset(target, furthest_from_root(target));
+    if target_count < params.min_count {
+        return FPGrowthProgress {
+            itemsets: vec![],
+            num_items: 0,
+            incomplete_items: vec![],
+        };
+    }
+    let mut projected_tree = fptree.construct_projected_tree(target[0], params.min_count);
+    for &item in &target[1..] {
+        projected_tree = projected_tree.construct_projected_tree(item, params.min_count);
+    }
+    // Itemsets mined from the projected tree are extended with target, so
+    // must be shorter to stay within the maximum size.
+    let projected_params = FPGrowthParameters {
+        min_count: params.min_count,
+        max_itemset_size: params
+            .max_itemset_size
+            .map(|max| max.saturating_sub(target.len())),
+        cancellation: params.cancellation.clone(),
+        cross_support: params.cross_support.clone(),
+        item_min_counts: None,
+    };
+    let mut progress = if projected_params.max_itemset_size == Some(0) {
+        FPGrowthProgress {
+            itemsets: vec![],
+            num_items: 0,
+            incomplete_items: vec![],
+        }
+    } else {
+        fp_growth_with_progress(&projected_tree, &projected_params, target_count)
+    };
+
+    let mut itemsets: Vec<ItemSet> = progress
+        .itemsets
+        .par_iter()
+        .flat_map(|antecedent| {
+            let mut items = antecedent.items.clone();
+            items.extend_from_slice(target);
+            if let Some(ref cross_support) = params.cross_support {
+                if !cross_support.admits(&items, antecedent.count) {
+                    return vec![];
+                }
+            }
+            let antecedent_count =
+                fptree.count_itemset(&antecedent.items, furthest_from_root(&antecedent.items));
+            vec![
+                ItemSet::new(items, antecedent.count),
+                ItemSet::new(antecedent.items.clone(), antecedent_count),
+            ]
         })
-        .collect::<Vec<ItemSet>>();
+        .collect();
+    itemsets.push(ItemSet::new(target.to_vec(), target_count));
+    progress.itemsets = itemsets;
+    progress
+}
 
-    itemsets.extend(x);
-    itemsets
+// Mines fptree like fp_growth_with_progress, but in the style of Parallel
+// FP-Growth (PFP): the frequent items are dealt into num_groups groups in
+// descending order of frequency, so each group gets a mix of frequent and
+// infrequent items, and each group is mined as a separate task from its own
+// group tree. This bounds the part of the tree each task works on, and
+// balances the load better than mining each item as a separate task when a
+// few items dominate.
+pub fn fp_growth_grouped_with_progress(
+    fptree: &FPTree,
+    params: &FPGrowthParameters,
+    num_transactions: u32,
+    num_groups: usize,
+) -> FPGrowthProgress {
+    let mut items: Vec<Item> = fptree
+        .item_count()
+        .items_with_count_at_least(params.min_count);
+    fptree.item_count().sort_descending(&mut items);
+
+    let parent = Span::current();
+    let results: Vec<(Item, Vec<ItemSet>, bool)> = (0..num_groups)
+        .into_par_iter()
+        .flat_map(|group| {
+            let span = info_span!(parent: &parent, "mine_group", group);
+            let _entered = span.enter();
+            let group_items: Vec<Item> = items
+                .iter()
+                .skip(group)
+                .step_by(num_groups)
+                .cloned()
+                .collect();
+            let mut in_group = ItemTable::new(false);
+            for &item in &group_items {
+                in_group.set(item, true);
+            }
+            let group_tree = fptree.construct_group_tree(&in_group);
+            group_items
+                .into_iter()
+                .map(|item| {
+                    mine_item_with_progress(&group_tree, params, num_transactions, item, &span)
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    collect_progress(items.len(), results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        fp_growth, fp_growth_grouped_with_progress, fp_growth_targeted, fp_growth_with_progress,
+        CrossSupportPruning, FPGrowthParameters, FPTree, ItemSet, SPAWN_MIN_NODES,
+    };
+    use cancellation::CancellationToken;
+    use counter::Counter;
+    use item::Item;
+    use item_counter::ItemCounter;
+    use itemizer::Itemizer;
+    use self_check::naive_itemsets;
+
+    fn to_item_vec(nums: &[u32]) -> Vec<Item> {
+        nums.iter().map(|&i| Item::from(i)).collect()
+    }
+
+    fn mine_sorted(tree: &FPTree, min_count: u32, num_transactions: u32) -> Vec<ItemSet> {
+        let params = FPGrowthParameters {
+            min_count,
+            max_itemset_size: None,
+            cancellation: CancellationToken::new(),
+            cross_support: None,
+            item_min_counts: None,
+        };
+        let mut itemsets = fp_growth(tree, &params, &[], num_transactions);
+        itemsets.sort();
+        itemsets
+    }
+
+    #[test]
+    fn test_merge() {
+        let transactions: Vec<Vec<Item>> = [
+            vec![1, 2, 3],
+            vec![1, 2],
+            vec![1, 3, 4],
+            vec![2, 3],
+            vec![1, 2, 3, 4],
+            vec![4],
+            vec![1, 4],
+            vec![2, 4],
+        ]
+        .iter()
+        .map(|t| to_item_vec(t))
+        .collect();
+
+        let mut whole = FPTree::new();
+        for transaction in &transactions {
+            whole.insert(transaction, 1);
+        }
+
+        let (left, right) = transactions.split_at(3);
+        let mut merged = FPTree::new();
+        for transaction in left {
+            merged.insert(transaction, 1);
+        }
+        let mut other = FPTree::new();
+        for transaction in right {
+            other.insert(transaction, 1);
+        }
+        merged.merge(&other);
+
+        assert_eq!(merged.num_nodes(), whole.num_nodes());
+        let n = transactions.len() as u32;
+        for min_count in 1..4 {
+            assert_eq!(
+                mine_sorted(&merged, min_count, n),
+                mine_sorted(&whole, min_count, n)
+            );
+        }
+    }
+
+    #[test]
+    fn test_fp_growth_spawns_large_conditional_trees() {
+        // Pseudo-random transactions over 16 items, enough that some
+        // conditional trees are large enough to be mined as separate tasks.
+        let mut state: u32 = 12345;
+        let transactions: Vec<(Vec<Item>, u32)> = (0..4000)
+            .map(|_| {
+                state = state.wrapping_mul(1103515245).wrapping_add(12345);
+                let bits = state >> 8;
+                let items = (1..17)
+                    .filter(|&item| bits & (1 << item) != 0)
+                    .collect::<Vec<u32>>();
+                (to_item_vec(&items), 1)
+            })
+            .collect();
+        let mut item_count = ItemCounter::new();
+        for (transaction, count) in &transactions {
+            for item in transaction {
+                item_count.add(item, *count);
+            }
+        }
+        let min_count = 300;
+        let mut tree = FPTree::new();
+        for (transaction, count) in &transactions {
+            let mut transaction: Vec<Item> = transaction
+                .iter()
+                .cloned()
+                .filter(|item| item_count.get(item) >= min_count)
+                .collect();
+            item_count.sort_descending(&mut transaction);
+            tree.insert(&transaction, *count);
+        }
+        let conditional_tree = tree.construct_conditional_tree(Item::with_id(16), min_count);
+        assert!(conditional_tree.num_nodes() >= SPAWN_MIN_NODES);
+
+        let mut expected = naive_itemsets(&transactions, min_count, None).unwrap();
+        expected.sort();
+        assert_eq!(mine_sorted(&tree, min_count, 4000), expected);
+    }
+
+    #[test]
+    fn test_fp_growth_targeted() {
+        let transactions: Vec<Vec<u32>> = vec![
+            vec![1, 2, 3],
+            vec![1, 2],
+            vec![1, 3, 4],
+            vec![2, 3],
+            vec![1, 2, 3, 4],
+            vec![4],
+            vec![1, 4],
+            vec![2, 4],
+            vec![1, 2, 4],
+        ];
+        let mut item_count = ItemCounter::new();
+        for transaction in &transactions {
+            for item in to_item_vec(transaction) {
+                item_count.add(&item, 1);
+            }
+        }
+        let mut tree = FPTree::new();
+        for transaction in &transactions {
+            let mut transaction = to_item_vec(transaction);
+            item_count.sort_descending(&mut transaction);
+            tree.insert(&transaction, 1);
+        }
+        let all = mine_sorted(&tree, 2, 9);
+        let params = FPGrowthParameters {
+            min_count: 2,
+            max_itemset_size: None,
+            cancellation: CancellationToken::new(),
+            cross_support: None,
+            item_min_counts: None,
+        };
+        for target in &[vec![2], vec![1, 4]] {
+            let target = to_item_vec(target);
+            let mut itemsets = fp_growth_targeted(&tree, &params, &target, &item_count).itemsets;
+            itemsets.sort();
+            // The itemsets containing target, and the same itemsets
+            // without target.
+            let with_target: Vec<&ItemSet> = all
+                .iter()
+                .filter(|itemset| target.iter().all(|item| itemset.items.contains(item)))
+                .collect();
+            let expected: Vec<ItemSet> = all
+                .iter()
+                .filter(|itemset| {
+                    let num_in_target = target
+                        .iter()
+                        .filter(|item| itemset.items.contains(item))
+                        .count();
+                    (num_in_target == 0 || num_in_target == target.len())
+                        && with_target.iter().any(|other| {
+                            let mut items: Vec<Item> =
+                                itemset.items.iter().chain(&target).cloned().collect();
+                            items.sort();
+                            items.dedup();
+                            items == other.items
+                        })
+                })
+                .cloned()
+                .collect();
+            assert!(expected.len() > with_target.len());
+            assert_eq!(itemsets, expected);
+        }
+        let missing = fp_growth_targeted(&tree, &params, &[Item::null()], &item_count);
+        assert!(missing.itemsets.is_empty());
+    }
+
+    #[test]
+    fn test_max_itemset_size() {
+        let mut tree = FPTree::new();
+        for transaction in &[vec![1, 2, 3], vec![1, 2], vec![1, 2, 3, 4], vec![2, 3, 4]] {
+            tree.insert(&to_item_vec(transaction), 1);
+        }
+        let expected: Vec<ItemSet> = mine_sorted(&tree, 1, 4)
+            .into_iter()
+            .filter(|itemset| itemset.len() <= 2)
+            .collect();
+        let params = FPGrowthParameters {
+            min_count: 1,
+            max_itemset_size: Some(2),
+            cancellation: CancellationToken::new(),
+            cross_support: None,
+            item_min_counts: None,
+        };
+        let mut itemsets = fp_growth(&tree, &params, &[], 4);
+        itemsets.sort();
+        assert_eq!(itemsets, expected);
+    }
+
+    #[test]
+    fn test_fp_growth_with_progress() {
+        let mut tree = FPTree::new();
+        for transaction in &[vec![1, 2, 3], vec![1, 2], vec![2, 3]] {
+            tree.insert(&to_item_vec(transaction), 1);
+        }
+        let params = FPGrowthParameters {
+            min_count: 1,
+            max_itemset_size: None,
+            cancellation: CancellationToken::new(),
+            cross_support: None,
+            item_min_counts: None,
+        };
+        let mut progress = fp_growth_with_progress(&tree, &params, 3);
+        progress.itemsets.sort();
+        assert_eq!(progress.itemsets, mine_sorted(&tree, 1, 3));
+        assert_eq!(progress.num_items, 3);
+        assert!(progress.incomplete_items.is_empty());
+
+        params.cancellation.cancel();
+        let progress = fp_growth_with_progress(&tree, &params, 3);
+        assert!(progress.itemsets.is_empty());
+        assert_eq!(progress.incomplete_items.len(), 3);
+    }
+
+    #[test]
+    fn test_fp_growth_max_count() {
+        let mut tree = FPTree::new();
+        for transaction in &[vec![1, 2, 3], vec![1, 2], vec![2, 3]] {
+            tree.insert(&to_item_vec(transaction), 1);
+        }
+        let num_itemsets = mine_sorted(&tree, 1, 3).len();
+        let params = FPGrowthParameters {
+            min_count: 1,
+            max_itemset_size: None,
+            cancellation: CancellationToken::new(),
+            cross_support: None,
+            item_min_counts: None,
+        };
+        params.cancellation.cancel_above_count(num_itemsets);
+        let progress = fp_growth_with_progress(&tree, &params, 3);
+        assert_eq!(progress.itemsets.len(), num_itemsets);
+        assert!(!params.cancellation.is_count_exceeded());
+
+        let params = FPGrowthParameters {
+            cancellation: CancellationToken::new(),
+            ..params
+        };
+        params.cancellation.cancel_above_count(num_itemsets - 1);
+        let progress = fp_growth_with_progress(&tree, &params, 3);
+        assert!(params.cancellation.is_count_exceeded());
+        assert!(!progress.incomplete_items.is_empty());
+    }
+
+    #[test]
+    fn test_cross_support_pruning() {
+        let mut tree = FPTree::new();
+        let transactions = [
+            vec![1, 2, 3],
+            vec![1, 2],
+            vec![1, 3],
+            vec![1, 4],
+            vec![1, 2, 4],
+            vec![2, 3],
+            vec![1],
+            vec![1, 3],
+        ];
+        let mut item_count = ItemCounter::new();
+        for transaction in &transactions {
+            let transaction = to_item_vec(transaction);
+            for item in &transaction {
+                item_count.add(item, 1);
+            }
+            tree.insert(&transaction, 1);
+        }
+        let cross_support = CrossSupportPruning {
+            item_count,
+            min_all_confidence: Some(0.4),
+            max_support_ratio: None,
+        };
+        let mut expected = mine_sorted(&tree, 1, 8);
+        expected.retain(|itemset| cross_support.admits(&itemset.items, itemset.count));
+        // {1, 4} has count 2 but item 1 has count 7, so it's pruned.
+        assert!(!expected.contains(&ItemSet::new(to_item_vec(&[1, 4]), 2)));
+        assert!(expected.contains(&ItemSet::new(to_item_vec(&[2, 3]), 2)));
+
+        let params = FPGrowthParameters {
+            min_count: 1,
+            max_itemset_size: None,
+            cancellation: CancellationToken::new(),
+            cross_support: Some(cross_support),
+            item_min_counts: None,
+        };
+        let mut itemsets = fp_growth(&tree, &params, &[], 8);
+        itemsets.sort();
+        assert_eq!(itemsets, expected);
+
+        // Items 1, 2, 3 and 4 have counts 7, 4, 4 and 2, so with a maximum
+        // support ratio of 2, itemsets containing 1 and 4 are pruned.
+        let params = FPGrowthParameters {
+            cross_support: params
+                .cross_support
+                .map(|cross_support| CrossSupportPruning {
+                    min_all_confidence: None,
+                    max_support_ratio: Some(2.0),
+                    ..cross_support
+                }),
+            ..params
+        };
+        let mut itemsets = fp_growth(&tree, &params, &[], 8);
+        itemsets.sort();
+        let mut expected = mine_sorted(&tree, 1, 8);
+        expected.retain(|itemset| {
+            let items = &itemset.items;
+            !(items.contains(&Item::with_id(1)) && items.contains(&Item::with_id(4)))
+        });
+        assert_eq!(itemsets, expected);
+    }
+
+    #[test]
+    fn test_item_min_counts() {
+        let transactions = [
+            vec![1, 2, 3],
+            vec![1, 2],
+            vec![1, 3],
+            vec![1, 4],
+            vec![1, 2, 4],
+            vec![2, 3],
+            vec![1],
+            vec![1, 3],
+        ];
+        // Item 4 is rare, with count 2, so has a lower minimum count.
+        let mut item_min_counts = ItemCounter::new();
+        for (item, min_count) in [(1, 3), (2, 3), (3, 3), (4, 1)].iter() {
+            item_min_counts.set(&Item::with_id(*item), *min_count);
+        }
+        // Items with higher minimum counts must be nearer the root.
+        let mut item_order = ItemCounter::new();
+        for (item, rank) in [(1, 4), (2, 3), (3, 2), (4, 1)].iter() {
+            item_order.set(&Item::with_id(*item), *rank);
+        }
+        let mut tree = FPTree::new();
+        for transaction in &transactions {
+            let mut transaction = to_item_vec(transaction);
+            item_order.sort_descending(&mut transaction);
+            tree.insert(&transaction, 1);
+        }
+        assert_eq!(
+            tree.count_itemset(&to_item_vec(&[1, 2, 4]), Item::with_id(4)),
+            1
+        );
+        assert_eq!(
+            tree.count_itemset(&to_item_vec(&[1, 3]), Item::with_id(3)),
+            3
+        );
+
+        let mut expected = mine_sorted(&tree, 1, 8);
+        expected.retain(|itemset| {
+            let min_count = itemset
+                .items
+                .iter()
+                .map(|item| item_min_counts.get(item))
+                .min()
+                .unwrap();
+            itemset.count >= min_count
+        });
+        // {1, 2, 4} is frequent with count 1, as item 4's minimum count is 1,
+        // but {2, 3} isn't with count 2.
+        assert!(expected.contains(&ItemSet::new(to_item_vec(&[1, 2, 4]), 1)));
+        assert!(!expected.contains(&ItemSet::new(to_item_vec(&[2, 3]), 2)));
+
+        let params = FPGrowthParameters {
+            min_count: 1,
+            max_itemset_size: None,
+            cancellation: CancellationToken::new(),
+            cross_support: None,
+            item_min_counts: Some(item_min_counts),
+        };
+        let mut itemsets = fp_growth(&tree, &params, &[], 8);
+        itemsets.sort();
+        assert_eq!(itemsets, expected);
+    }
+
+    #[test]
+    fn test_fp_growth_grouped() {
+        let mut tree = FPTree::new();
+        let transactions = [
+            vec![1, 2, 3, 5],
+            vec![1, 2, 4],
+            vec![1, 3, 4, 5],
+            vec![2, 3],
+            vec![1, 2, 3, 4, 5],
+            vec![4, 5],
+            vec![1, 5],
+            vec![2, 4, 5],
+        ];
+        for transaction in &transactions {
+            tree.insert(&to_item_vec(transaction), 1);
+        }
+        for min_count in 1..4 {
+            let params = FPGrowthParameters {
+                min_count,
+                max_itemset_size: None,
+                cancellation: CancellationToken::new(),
+                cross_support: None,
+                item_min_counts: None,
+            };
+            let expected = mine_sorted(&tree, min_count, 8);
+            for num_groups in 1..7 {
+                let mut progress = fp_growth_grouped_with_progress(&tree, &params, 8, num_groups);
+                progress.itemsets.sort();
+                assert_eq!(progress.itemsets, expected);
+                assert!(progress.incomplete_items.is_empty());
+            }
+        }
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let mut itemizer = Itemizer::new();
+        let a = itemizer.id_of("a");
+        let b = itemizer.id_of("b");
+        let mut tree = FPTree::new();
+        tree.insert(&[a, b], 2);
+        tree.insert(&[b], 1);
+        let dot = tree.to_dot(&itemizer);
+        assert!(dot.starts_with("digraph fptree {"));
+        assert!(dot.contains("n0 [label=\"root\"];"));
+        assert!(dot.contains("n1 [label=\"a:2\"];"));
+        assert!(dot.contains("n2 [label=\"b:2\"];"));
+        assert!(dot.contains("n3 [label=\"b:1\"];"));
+        assert!(dot.contains("n0 -> n1;"));
+        assert!(dot.contains("n2 -> n3 [style=dashed, constraint=false];"));
+    }
 }
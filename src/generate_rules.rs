@@ -12,38 +12,48 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use cancellation::CancellationToken;
+use counter::Counter;
+use filter_expression::FilterExpression;
 use fnv::FnvHashMap;
 use fptree::ItemSet;
 use item::Item;
+use item_counter::ItemCounter;
 use rayon::prelude::*;
 use rule::Rule;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::str::FromStr;
 use vec_sets::{split_out, split_out_item, union};
 
-pub type ItemsetSupport = FnvHashMap<Vec<Item>, f64>;
+pub type ItemsetCounts = FnvHashMap<Vec<Item>, u32>;
 
-fn create_support_lookup(itemsets: &Vec<ItemSet>, dataset_size: u32) -> ItemsetSupport {
+fn create_count_lookup(itemsets: &[ItemSet]) -> ItemsetCounts {
     itemsets
         .iter()
-        .map(|itemset| {
-            (
-                itemset.items.clone(),
-                itemset.count as f64 / dataset_size as f64,
-            )
-        })
+        .map(|itemset| (itemset.items.clone(), itemset.count))
         .collect()
 }
 
-fn stats(
-    support: f64,
-    antecedent: &[Item],
-    consequent: &[Item],
-    itemset_support: &ItemsetSupport,
-) -> (f64, f64) {
-    let a_sup = itemset_support[antecedent];
-    let confidence = support / a_sup;
-    let c_sup = itemset_support[consequent];
-    let lift = support / (a_sup * c_sup);
-    (confidence, lift)
+// Creates a rule, looking up the counts of the antecedent and consequent
+// so that the rule's stats can be calculated.
+fn make_rule(
+    antecedent: Vec<Item>,
+    consequent: Vec<Item>,
+    count: u32,
+    itemset_counts: &ItemsetCounts,
+    dataset_size: u32,
+) -> Rule {
+    let antecedent_count = itemset_counts[&antecedent];
+    let consequent_count = itemset_counts[&consequent];
+    Rule::new(
+        antecedent,
+        consequent,
+        count,
+        antecedent_count,
+        consequent_count,
+        dataset_size,
+    )
 }
 
 // Returns the number of items that match in a and b, starting from offset 0.
@@ -61,10 +71,11 @@ fn prefx_match_len(a: &[Item], b: &[Item]) -> usize {
 
 fn generate_rules_for_itemset(
     itemset: &[Item],
-    support: f64,
-    itemset_support: &ItemsetSupport,
-    min_confidence: f64,
-    min_lift: f64,
+    count: u32,
+    itemset_counts: &ItemsetCounts,
+    dataset_size: u32,
+    thresholds: &ConfidenceThresholds,
+    filter: &RuleFilter,
 ) -> Vec<Rule> {
     // Generate rules via appgenrules algorithm. Combine consequents until
     // all combinations have been tested.
@@ -73,20 +84,15 @@ fn generate_rules_for_itemset(
     let mut candidates: Vec<Vec<Item>> = vec![];
     for item in itemset.iter() {
         let (antecedent, consequent) = split_out_item(itemset, *item);
-        let (confidence, lift) = stats(support, &antecedent, &consequent, &itemset_support);
-        if confidence < min_confidence {
+        let rule = make_rule(antecedent, consequent, count, itemset_counts, dataset_size)
+            .with_laplace_k(filter.laplace_k);
+        if !thresholds.may_extend(&rule) {
             continue;
         }
-        if lift >= min_lift {
-            output.push(Rule {
-                antecedent,
-                consequent: consequent.clone(),
-                confidence,
-                lift,
-                support,
-            });
+        candidates.push(rule.consequent.clone());
+        if thresholds.accepts(&rule) && filter.accepts(&rule) {
+            output.push(rule);
         }
-        candidates.push(consequent)
     }
 
     // Create subsequent generations by merging consequents which have size-1 items
@@ -112,21 +118,16 @@ fn generate_rules_for_itemset(
                     break;
                 }
                 let consequent = union(c1, c2);
-                let antecedent = split_out(&itemset, &consequent);
-                let (confidence, lift) = stats(support, &antecedent, &consequent, &itemset_support);
-                if confidence < min_confidence {
+                let antecedent = split_out(itemset, &consequent);
+                let rule = make_rule(antecedent, consequent, count, itemset_counts, dataset_size)
+                    .with_laplace_k(filter.laplace_k);
+                if !thresholds.may_extend(&rule) {
                     continue;
                 }
-                if lift >= min_lift {
-                    output.push(Rule {
-                        antecedent,
-                        consequent: consequent.clone(),
-                        confidence,
-                        lift,
-                        support,
-                    });
+                next_gen.push(rule.consequent.clone());
+                if thresholds.accepts(&rule) && filter.accepts(&rule) {
+                    output.push(rule);
                 }
-                next_gen.push(consequent)
             }
         }
         candidates = next_gen;
@@ -136,139 +137,413 @@ fn generate_rules_for_itemset(
     output
 }
 
+// Minimum confidences for rules by the length of their antecedent, parsed
+// from e.g. "1:0.9,2:0.7,3+:0.5". Lengths without a threshold use the global
+// minimum confidence.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConfidenceByLength {
+    // Indexed by antecedent length.
+    exact: Vec<Option<f64>>,
+    // Threshold for antecedents at least this long, which aren't given an
+    // exact threshold.
+    at_least: Option<(usize, f64)>,
+}
+
+impl ConfidenceByLength {
+    pub fn get(&self, length: usize) -> Option<f64> {
+        self.exact
+            .get(length)
+            .cloned()
+            .flatten()
+            .or(match self.at_least {
+                Some((min_length, confidence)) if length >= min_length => Some(confidence),
+                _ => None,
+            })
+    }
+
+    // Length beyond which every antecedent length has the same threshold.
+    fn max_length(&self) -> usize {
+        self.exact
+            .len()
+            .max(self.at_least.map_or(0, |(min_length, _)| min_length + 1))
+    }
+}
+
+impl FromStr for ConfidenceByLength {
+    type Err = String;
+    fn from_str(s: &str) -> Result<ConfidenceByLength, String> {
+        let mut by_length = ConfidenceByLength {
+            exact: vec![],
+            at_least: None,
+        };
+        for entry in s.split(',').map(str::trim) {
+            let (length, confidence) = entry
+                .split_once(':')
+                .ok_or_else(|| format!("Expected length:confidence, got \"{}\"", entry))?;
+            let confidence: f64 = confidence
+                .trim()
+                .parse()
+                .map_err(|_| format!("Invalid confidence in \"{}\"", entry))?;
+            if !(0.0..=1.0).contains(&confidence) {
+                return Err(format!(
+                    "Confidence in \"{}\" must be in range [0,1]",
+                    entry
+                ));
+            }
+            let length = length.trim();
+            let (length, open) = match length.strip_suffix('+') {
+                Some(length) => (length, true),
+                None => (length, false),
+            };
+            let length: usize = match length.parse() {
+                Ok(length) if length > 0 => length,
+                _ => return Err(format!("Invalid antecedent length in \"{}\"", entry)),
+            };
+            if open {
+                if by_length.at_least.is_some() {
+                    return Err(String::from("Only one length may end with +"));
+                }
+                by_length.at_least = Some((length, confidence));
+            } else {
+                if by_length.exact.len() <= length {
+                    by_length.exact.resize(length + 1, None);
+                }
+                if by_length.exact[length].is_some() {
+                    return Err(format!("Antecedent length {} is given twice", length));
+                }
+                by_length.exact[length] = Some(confidence);
+            }
+        }
+        Ok(by_length)
+    }
+}
+
+// Minimum confidences indexed by antecedent length; the last applies to all
+// longer antecedents.
+struct ConfidenceThresholds {
+    // Rules with a lower confidence aren't output.
+    output: Vec<f64>,
+    // Rules with a lower confidence don't have their consequents extended.
+    // Extending a rule's consequent shortens its antecedent and can't raise
+    // its confidence, so this is the lowest threshold of any antecedent no
+    // longer than the rule's.
+    prune: Vec<f64>,
+}
+
+impl ConfidenceThresholds {
+    fn new(min_confidence: f64, by_length: Option<&ConfidenceByLength>) -> ConfidenceThresholds {
+        let output: Vec<f64> = match by_length {
+            Some(by_length) => (0..=by_length.max_length())
+                .map(|length| by_length.get(length).unwrap_or(min_confidence))
+                .collect(),
+            None => vec![min_confidence],
+        };
+        let mut prune = output.clone();
+        for length in 2..prune.len() {
+            prune[length] = prune[length].min(prune[length - 1]);
+        }
+        ConfidenceThresholds { output, prune }
+    }
+
+    fn accepts(&self, rule: &Rule) -> bool {
+        let length = rule.antecedent.len().min(self.output.len() - 1);
+        rule.confidence >= self.output[length]
+    }
+
+    fn may_extend(&self, rule: &Rule) -> bool {
+        let length = rule.antecedent.len().min(self.prune.len() - 1);
+        rule.confidence >= self.prune[length]
+    }
+}
+
+pub struct RuleParameters {
+    pub min_confidence: f64,
+    // If set, overrides min_confidence for the antecedent lengths it covers.
+    pub min_confidence_by_length: Option<ConfidenceByLength>,
+    pub min_lift: Option<f64>,
+    pub max_lift: Option<f64>,
+    pub min_confidence_lower_bound: Option<f64>,
+    // Number of classes used to calculate rules' Laplace accuracy.
+    pub laplace_k: f64,
+    pub min_laplace: Option<f64>,
+    pub min_added_value: Option<f64>,
+    pub min_gini_gain: Option<f64>,
+    pub min_info_gain: Option<f64>,
+    pub min_odds_ratio: Option<f64>,
+    pub min_yules_q: Option<f64>,
+    pub min_phi: Option<f64>,
+    // Only rules matching this expression are output.
+    pub filter: Option<FilterExpression>,
+    // Only generate rules from itemsets with at least this many items.
+    pub min_itemset_size: usize,
+    // Once cancelled, no more itemsets have rules generated for them.
+    pub cancellation: CancellationToken,
+    // Per-item minimum counts. If set, only itemsets with at least the
+    // lowest minimum count of their items have rules generated for them;
+    // the other itemsets are only used to look up counts.
+    pub item_min_counts: Option<ItemCounter>,
+    // If set, only rules whose consequent is exactly these items, which
+    // must be sorted, are generated.
+    pub target_consequent: Option<Vec<Item>>,
+}
+
+// Thresholds which rules must pass to be output, but which can't be used to
+// prune the search for rules, as rules failing them may still have
+// consequents which lead to rules which pass.
+struct RuleFilter {
+    min_lift: f64,
+    max_lift: f64,
+    min_confidence_lower_bound: f64,
+    // Number of classes for rules' Laplace accuracy, which is recalculated
+    // before they're filtered.
+    laplace_k: f64,
+    min_laplace: f64,
+    min_added_value: f64,
+    min_gini_gain: f64,
+    min_info_gain: f64,
+    min_odds_ratio: f64,
+    min_yules_q: f64,
+    min_phi: f64,
+    expression: Option<FilterExpression>,
+}
+
+impl RuleFilter {
+    fn accepts(&self, rule: &Rule) -> bool {
+        rule.lift >= self.min_lift
+            && rule.lift <= self.max_lift
+            && rule.confidence_lower_bound >= self.min_confidence_lower_bound
+            && rule.laplace >= self.min_laplace
+            && rule.added_value >= self.min_added_value
+            && rule.gini_gain >= self.min_gini_gain
+            && rule.info_gain >= self.min_info_gain
+            && rule.odds_ratio >= self.min_odds_ratio
+            && rule.yules_q >= self.min_yules_q
+            && rule.phi >= self.min_phi
+            && self
+                .expression
+                .as_ref()
+                .is_none_or(|expression| expression.matches(rule))
+    }
+}
+
+// Generates the rules for each itemset, sharing a lookup of itemset counts
+// between calls. Callers can use this to drive rule generation themselves,
+// e.g. to serialize rules as they're generated rather than collecting them.
+pub struct RuleGenerator {
+    itemset_counts: ItemsetCounts,
+    dataset_size: u32,
+    thresholds: ConfidenceThresholds,
+    filter: RuleFilter,
+    min_itemset_size: usize,
+    cancellation: CancellationToken,
+    item_min_counts: Option<ItemCounter>,
+    target_consequent: Option<Vec<Item>>,
+}
+
+impl RuleGenerator {
+    pub fn new(itemsets: &[ItemSet], dataset_size: u32, params: &RuleParameters) -> RuleGenerator {
+        RuleGenerator {
+            // Create a lookup of itemset to count, so we can quickly
+            // determine an itemset's support during rule generation.
+            itemset_counts: create_count_lookup(itemsets),
+            dataset_size,
+            thresholds: ConfidenceThresholds::new(
+                params.min_confidence,
+                params.min_confidence_by_length.as_ref(),
+            ),
+            filter: RuleFilter {
+                min_lift: params.min_lift.unwrap_or(0.0),
+                max_lift: params.max_lift.unwrap_or(f64::INFINITY),
+                min_confidence_lower_bound: params.min_confidence_lower_bound.unwrap_or(0.0),
+                laplace_k: params.laplace_k,
+                min_laplace: params.min_laplace.unwrap_or(0.0),
+                min_added_value: params.min_added_value.unwrap_or(f64::NEG_INFINITY),
+                min_gini_gain: params.min_gini_gain.unwrap_or(0.0),
+                min_info_gain: params.min_info_gain.unwrap_or(0.0),
+                min_odds_ratio: params.min_odds_ratio.unwrap_or(0.0),
+                min_yules_q: params.min_yules_q.unwrap_or(-1.0),
+                min_phi: params.min_phi.unwrap_or(-1.0),
+                expression: params.filter.clone(),
+            },
+            min_itemset_size: params.min_itemset_size,
+            cancellation: params.cancellation.clone(),
+            item_min_counts: params.item_min_counts.clone(),
+            target_consequent: params.target_consequent.clone(),
+        }
+    }
+
+    // Whether rules are generated from itemset at all; it must have at least
+    // two items, contain the target consequent if there is one, and be
+    // frequent under its items' minimum supports.
+    pub fn generates_rules_for(&self, itemset: &ItemSet) -> bool {
+        if itemset.len() < 2 || itemset.len() < self.min_itemset_size {
+            return false;
+        }
+        if let Some(ref target) = self.target_consequent {
+            if itemset.len() <= target.len()
+                || !target
+                    .iter()
+                    .all(|item| itemset.items.binary_search(item).is_ok())
+            {
+                return false;
+            }
+        }
+        match self.item_min_counts {
+            Some(ref item_min_counts) => itemset
+                .items
+                .iter()
+                .map(|item| item_min_counts.get(item))
+                .min()
+                .is_none_or(|min_count| itemset.count >= min_count),
+            None => true,
+        }
+    }
+
+    // Creates the rule antecedent => consequent, whose items occur together
+    // in count transactions.
+    pub fn make_rule(&self, antecedent: Vec<Item>, consequent: Vec<Item>, count: u32) -> Rule {
+        make_rule(
+            antecedent,
+            consequent,
+            count,
+            &self.itemset_counts,
+            self.dataset_size,
+        )
+        .with_laplace_k(self.filter.laplace_k)
+    }
+
+    // Whether rule passes every threshold, regardless of how it was found.
+    pub fn accepts(&self, rule: &Rule) -> bool {
+        self.thresholds.accepts(rule) && self.filter.accepts(rule)
+    }
+
+    // Returns the rules generated from itemset, which is empty if the
+    // itemset is too small to generate rules from.
+    pub fn rules_for(&self, itemset: &ItemSet) -> Vec<Rule> {
+        if !self.generates_rules_for(itemset) || self.cancellation.is_cancelled() {
+            return vec![];
+        }
+        if let Some(ref target) = self.target_consequent {
+            let antecedent = split_out(&itemset.items, target);
+            let rule = self.make_rule(antecedent, target.clone(), itemset.count);
+            return if self.accepts(&rule) {
+                self.cancellation.add_to_count(1);
+                vec![rule]
+            } else {
+                vec![]
+            };
+        }
+        let rules = generate_rules_for_itemset(
+            &itemset.items,
+            itemset.count,
+            &self.itemset_counts,
+            self.dataset_size,
+            &self.thresholds,
+            &self.filter,
+        );
+        self.cancellation.add_to_count(rules.len());
+        rules
+    }
+}
+
 pub fn generate_rules(
-    itemsets: &Vec<ItemSet>,
+    itemsets: &[ItemSet],
     dataset_size: u32,
-    min_confidence: f64,
-    min_lift: Option<f64>,
+    params: &RuleParameters,
 ) -> Vec<Vec<Rule>> {
-    // Create a lookup of itemset to support, so we can quickly determine
-    // an itemset's support during rule generation.
-    let itemset_support = create_support_lookup(itemsets, dataset_size);
-
-    let min_lift = min_lift.unwrap_or(0.0);
+    let generator = RuleGenerator::new(itemsets, dataset_size, params);
+    itemsets
+        .par_iter()
+        .filter(|&i| i.items.len() > 1 && i.items.len() >= params.min_itemset_size)
+        .map(|i| generator.rules_for(i))
+        .collect()
+}
 
+// Counts the rules generate_rules would generate, without holding them.
+pub fn count_rules(itemsets: &[ItemSet], dataset_size: u32, params: &RuleParameters) -> usize {
+    let generator = RuleGenerator::new(itemsets, dataset_size, params);
     itemsets
         .par_iter()
-        .filter(|&i| i.items.len() > 1)
-        .map(|ref i| -> Vec<Rule> {
-            let support = i.count as f64 / dataset_size as f64;
-            generate_rules_for_itemset(
-                &i.items,
-                support,
-                &itemset_support,
-                min_confidence,
-                min_lift,
-            )
+        .filter(|&i| i.items.len() > 1 && i.items.len() >= params.min_itemset_size)
+        .map(|i| generator.rules_for(i).len())
+        .sum()
+}
+
+// Groups rules by consequent, keeping the k rules with the highest
+// confidence for each consequent, ties broken by lift and then count.
+// Returns one group per consequent, in order of consequent.
+pub fn top_rules_per_consequent(rules: Vec<Vec<Rule>>, k: usize) -> Vec<Vec<Rule>> {
+    let mut groups: BTreeMap<Vec<Item>, Vec<Rule>> = BTreeMap::new();
+    for rule in rules.into_iter().flatten() {
+        groups
+            .entry(rule.consequent.clone())
+            .or_default()
+            .push(rule);
+    }
+    groups
+        .into_values()
+        .map(|mut group| {
+            group.sort_by(|a, b| {
+                b.confidence
+                    .partial_cmp(&a.confidence)
+                    .unwrap_or(Ordering::Equal)
+                    .then(b.lift.partial_cmp(&a.lift).unwrap_or(Ordering::Equal))
+                    .then(b.count.cmp(&a.count))
+            });
+            group.truncate(k);
+            group
         })
         .collect()
 }
 
+// Generates rules lazily, one itemset at a time, so callers can filter or
+// stop early without holding every rule in memory at once.
+pub fn generate_rules_iter(
+    itemsets: Vec<ItemSet>,
+    dataset_size: u32,
+    params: &RuleParameters,
+) -> impl Iterator<Item = Rule> {
+    let generator = RuleGenerator::new(&itemsets, dataset_size, params);
+    itemsets
+        .into_iter()
+        .flat_map(move |itemset| generator.rules_for(&itemset))
+}
+
+// Parallel version of generate_rules_iter, for consumers which can process
+// rules out of order.
+pub fn par_generate_rules_iter(
+    itemsets: Vec<ItemSet>,
+    dataset_size: u32,
+    params: &RuleParameters,
+) -> impl ParallelIterator<Item = Rule> {
+    let generator = RuleGenerator::new(&itemsets, dataset_size, params);
+    itemsets
+        .into_par_iter()
+        .flat_map(move |itemset| generator.rules_for(&itemset))
+}
+
 #[cfg(test)]
+#[allow(
+    clippy::bool_assert_comparison,
+    clippy::needless_borrow,
+    clippy::needless_borrowed_reference,
+    clippy::type_complexity
+)]
 mod tests {
 
-    use super::create_support_lookup;
-    use super::stats;
-    use super::ItemsetSupport;
+    use cancellation::CancellationToken;
     use fnv::FnvHashSet;
     use fptree::ItemSet;
     use item::Item;
-    use rule::Rule;
+    use rayon::prelude::*;
+    use rule::{Rule, DEFAULT_LAPLACE_K};
+    use self_check::naive_rules;
     use std::collections::HashMap;
-    use vec_sets::union;
 
     type RuleSet = FnvHashSet<Rule>;
 
-    fn naive_add_rules_for(
-        rules: &mut RuleSet,
-        items: &[Item],
-        antecedent: &mut Vec<Item>,
-        consequent: &mut Vec<Item>,
-        itemset_support: &ItemsetSupport,
-        min_confidence: f64,
-        min_lift: Option<f64>,
-    ) {
-        if items.is_empty() {
-            if antecedent.is_empty() || consequent.is_empty() {
-                return;
-            }
-            let both = union(antecedent, consequent);
-            let support = itemset_support[&both];
-            let (confidence, lift) = stats(support, &antecedent, &consequent, &itemset_support);
-            let min_lift = min_lift.unwrap_or(0.0);
-            if confidence >= min_confidence && lift >= min_lift {
-                rules.insert(Rule {
-                    antecedent: antecedent.to_vec(),
-                    consequent: consequent.to_vec(),
-                    confidence,
-                    lift,
-                    support,
-                });
-            }
-            return;
-        }
-        let item = items[0];
-
-        antecedent.push(item);
-        naive_add_rules_for(
-            rules,
-            &items[1..],
-            antecedent,
-            consequent,
-            itemset_support,
-            min_confidence,
-            min_lift,
-        );
-        antecedent.pop();
-
-        consequent.push(item);
-        naive_add_rules_for(
-            rules,
-            &items[1..],
-            antecedent,
-            consequent,
-            itemset_support,
-            min_confidence,
-            min_lift,
-        );
-        consequent.pop();
-    }
-
-    // Naive implementation of rule generation which simply tries all
-    // combinations of rules. Compare cleverer approach with this to ensure
-    // the cleverer approach isn't over-pruning.
-    fn naive_generate_rules(
-        itemsets: &Vec<ItemSet>,
-        dataset_size: u32,
-        min_confidence: f64,
-        min_lift: Option<f64>,
-    ) -> RuleSet {
-        // Create a lookup of itemset to support, so we can quickly determine
-        // an itemset's support during rule generation.
-        let itemset_support = create_support_lookup(itemsets, dataset_size);
-        itemsets
-            .iter()
-            .map(|ref itemset| &itemset.items)
-            .filter(|ref items| items.len() > 1)
-            .fold(
-                RuleSet::default(),
-                |mut rules: RuleSet, ref items| -> RuleSet {
-                    naive_add_rules_for(
-                        &mut rules,
-                        &items,
-                        &mut vec![],
-                        &mut vec![],
-                        &itemset_support,
-                        min_confidence,
-                        min_lift,
-                    );
-                    rules
-                },
-            )
-    }
-
     fn to_item_vec(nums: &[u32]) -> Vec<Item> {
-        nums.iter().map(|&i| Item::with_id(i)).collect()
+        nums.iter().map(|&i| Item::from(i)).collect()
     }
 
     fn fuzzy_float_eq(a: f64, b: f64) -> bool {
@@ -385,11 +660,32 @@ mod tests {
         })
         .collect();
 
-        let generated_rules = super::generate_rules(&kosarak, 990002, 0.05, Some(1.5));
+        let params = super::RuleParameters {
+            min_confidence: 0.05,
+            min_confidence_by_length: None,
+            min_lift: Some(1.5),
+            max_lift: None,
+            min_confidence_lower_bound: None,
+            laplace_k: DEFAULT_LAPLACE_K,
+            min_laplace: None,
+            min_added_value: None,
+            min_gini_gain: None,
+            min_info_gain: None,
+            min_odds_ratio: None,
+            min_yules_q: None,
+            min_phi: None,
+            filter: None,
+            min_itemset_size: 1,
+            cancellation: CancellationToken::new(),
+            item_min_counts: None,
+            target_consequent: None,
+        };
+        let generated_rules = super::generate_rules(&kosarak, 990002, &params);
         let num_rules: usize = generated_rules.iter().map(|ref x| x.len()).sum();
         assert_eq!(num_rules, expected_rules.len());
 
-        let naive_rules = naive_generate_rules(&kosarak, 990002, 0.05, Some(1.5));
+        let generator = super::RuleGenerator::new(&kosarak, 990002, &params);
+        let naive_rules = naive_rules(&kosarak, &generator);
         assert_eq!(naive_rules.len(), num_rules);
 
         for rule in &naive_rules {
@@ -411,5 +707,136 @@ mod tests {
                 assert!(fuzzy_float_eq(rule.support, support));
             }
         }
+
+        let generated_rules: RuleSet = kosarak
+            .iter()
+            .flat_map(|i| generator.rules_for(i))
+            .collect();
+        assert_eq!(generated_rules, naive_rules);
+
+        let generated_rules: RuleSet =
+            super::generate_rules_iter(kosarak.clone(), 990002, &params).collect();
+        assert_eq!(generated_rules, naive_rules);
+        let generated_rules: RuleSet =
+            super::par_generate_rules_iter(kosarak.clone(), 990002, &params).collect();
+        assert_eq!(generated_rules, naive_rules);
+
+        let params = super::RuleParameters {
+            max_lift: Some(2.0),
+            ..params
+        };
+        let generated_rules = super::generate_rules(&kosarak, 990002, &params);
+        let num_rules: usize = generated_rules.iter().map(|x| x.len()).sum();
+        assert_eq!(
+            num_rules,
+            naive_rules.iter().filter(|rule| rule.lift <= 2.0).count()
+        );
+
+        let consequents: FnvHashSet<Vec<Item>> = naive_rules
+            .iter()
+            .filter(|rule| rule.lift <= 2.0)
+            .map(|rule| rule.consequent.clone())
+            .collect();
+        let groups = super::top_rules_per_consequent(generated_rules, 2);
+        assert_eq!(groups.len(), consequents.len());
+        assert!(groups
+            .windows(2)
+            .all(|pair| pair[0][0].consequent < pair[1][0].consequent));
+        for group in &groups {
+            assert!(group.len() <= 2);
+            assert!(group
+                .iter()
+                .all(|rule| rule.consequent == group[0].consequent));
+            assert!(group
+                .windows(2)
+                .all(|pair| pair[0].confidence >= pair[1].confidence));
+            // Every rule left out has no higher confidence than those kept.
+            let min_kept = group.last().unwrap().confidence;
+            assert!(naive_rules
+                .iter()
+                .filter(|rule| rule.consequent == group[0].consequent && rule.lift <= 2.0)
+                .all(|rule| group.contains(rule) || rule.confidence <= min_kept));
+        }
+
+        // Single item antecedents have a higher threshold than two item
+        // antecedents, so rules failing it must still be extended.
+        let by_length: super::ConfidenceByLength = "1:0.9, 2:0.1, 3+:0.85".parse().unwrap();
+        let params = super::RuleParameters {
+            min_confidence_by_length: Some(by_length.clone()),
+            max_lift: None,
+            ..params
+        };
+        let generated_rules: RuleSet = super::generate_rules(&kosarak, 990002, &params)
+            .into_iter()
+            .flatten()
+            .collect();
+        let expected: RuleSet = naive_rules
+            .iter()
+            .filter(|rule| rule.confidence >= by_length.get(rule.antecedent.len()).unwrap())
+            .cloned()
+            .collect();
+        assert!(!expected.is_empty());
+        assert_eq!(generated_rules, expected);
+
+        let params = super::RuleParameters {
+            min_confidence_by_length: None,
+            laplace_k: 3.0,
+            min_laplace: Some(0.8),
+            ..params
+        };
+        let generated_rules: Vec<Rule> = super::generate_rules(&kosarak, 990002, &params)
+            .into_iter()
+            .flatten()
+            .collect();
+        let expected = naive_rules
+            .iter()
+            .filter(|rule| (rule.count + 1) as f64 / (rule.antecedent_count + 3) as f64 >= 0.8)
+            .count();
+        assert_eq!(generated_rules.len(), expected);
+        assert!(generated_rules
+            .iter()
+            .all(|rule| rule.laplace >= 0.8 && rule.laplace < rule.confidence));
+
+        // With a target consequent, only the rules with exactly that
+        // consequent are generated.
+        let target = to_item_vec(&[11]);
+        let params = super::RuleParameters {
+            laplace_k: DEFAULT_LAPLACE_K,
+            min_laplace: None,
+            target_consequent: Some(target.clone()),
+            ..params
+        };
+        let generated_rules: RuleSet = super::generate_rules(&kosarak, 990002, &params)
+            .into_iter()
+            .flatten()
+            .collect();
+        let expected: RuleSet = naive_rules
+            .iter()
+            .filter(|rule| rule.consequent == target)
+            .cloned()
+            .collect();
+        assert!(!expected.is_empty());
+        assert_eq!(generated_rules, expected);
+    }
+
+    #[test]
+    fn test_confidence_by_length() {
+        let by_length: super::ConfidenceByLength = "2:0.7,1:0.9,4+:0.5".parse().unwrap();
+        assert_eq!(by_length.get(1), Some(0.9));
+        assert_eq!(by_length.get(2), Some(0.7));
+        assert_eq!(by_length.get(3), None);
+        assert_eq!(by_length.get(4), Some(0.5));
+        assert_eq!(by_length.get(10), Some(0.5));
+        for invalid in &[
+            "",
+            "1",
+            "0:0.5",
+            "1:1.5",
+            "x:0.5",
+            "1:0.5,1:0.6",
+            "1+:0.5,2+:0.4",
+        ] {
+            assert!(invalid.parse::<super::ConfidenceByLength>().is_err());
+        }
     }
 }
@@ -12,94 +12,285 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-#[cfg(test)]
+use intersect::{advance_to, advance_to_scalar};
 use item::Item;
 #[cfg(test)]
 use itemizer::Itemizer;
+use roaring_index::RoaringIndex;
+use std::str::FromStr;
 
-#[cfg(test)]
+// An index of which transactions contain each item, used to count the exact
+// support of itemsets after mining. Transactions are inserted and then
+// finish() is called once before counting.
+pub trait TransactionIndex: Sync {
+    fn insert(&mut self, transaction: &[Item], count: u32);
+    fn finish(&mut self);
+    // Number of transactions containing all of items, including repeats.
+    fn count(&self, items: &[Item]) -> usize;
+    // Ids of the transactions containing all of items, in increasing order.
+    fn tids(&self, items: &[Item]) -> Vec<u32>;
+    // Number of times the transaction with id tid occurred.
+    fn weight(&self, tid: u32) -> u32;
+    fn transaction_count(&self) -> usize;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IndexBackend {
+    // Index, using tid-lists and bitmaps.
+    TidList,
+    // RoaringIndex, using compressed bitmaps.
+    Roaring,
+}
+
+impl FromStr for IndexBackend {
+    type Err = String;
+    fn from_str(s: &str) -> Result<IndexBackend, String> {
+        match s {
+            "tidlist" => Ok(IndexBackend::TidList),
+            "roaring" => Ok(IndexBackend::Roaring),
+            _ => Err(format!("Unknown index backend {}", s)),
+        }
+    }
+}
+
+impl IndexBackend {
+    pub fn new_index(self) -> Box<dyn TransactionIndex> {
+        match self {
+            IndexBackend::TidList => Box::new(Index::new()),
+            IndexBackend::Roaring => Box::new(RoaringIndex::new()),
+        }
+    }
+}
+
+const WORD_BITS: usize = 64;
+
+// The tid-lists and bitmaps of the items in an itemset.
+type ItemLists<'a> = (Vec<&'a [u32]>, Vec<&'a [u64]>);
+
+fn bit_is_set(bitmap: &[u64], tid: u32) -> bool {
+    let tid = tid as usize;
+    let word = tid / WORD_BITS;
+    word < bitmap.len() && (bitmap[word] >> (tid % WORD_BITS)) & 1 == 1
+}
+
+// Maps each item to the list of ids of the transactions which contain it,
+// so that the exact support of any itemset can be counted by intersecting
+// the tid-lists of its items. Once all transactions are inserted, finish()
+// replaces the tid-lists of items in many transactions with bitmaps, which
+// are smaller and can be intersected a word at a time.
 pub struct Index {
-    index: Vec<Vec<usize>>,
+    index: Vec<Vec<u32>>,
+    // Bitmap of tids for dense items, indexed by item; empty until finish().
+    bitmaps: Vec<Option<Vec<u64>>>,
+    // Number of times each transaction occurred, indexed by tid.
+    weights: Vec<u32>,
+    // Whether every transaction occurred once, so that counts of bitmaps
+    // can be calculated with popcount.
+    unit_weights: bool,
     transaction_count: usize,
+    // Whether to use the vectorized tid-list intersection.
+    simd: bool,
+}
+
+impl Default for Index {
+    fn default() -> Index {
+        Index::new()
+    }
 }
 
-#[cfg(test)]
 impl Index {
     pub fn new() -> Index {
         Index {
             index: Vec::new(),
+            bitmaps: Vec::new(),
+            weights: Vec::new(),
+            unit_weights: true,
             transaction_count: 0,
+            simd: true,
         }
     }
-    pub fn insert(&mut self, transaction: &[Item]) {
-        let tid = self.transaction_count;
-        self.transaction_count += 1;
-        for &item in transaction {
-            while self.index.len() <= item.as_index() {
-                self.index.push(vec![]);
+
+    // Used to benchmark the vectorized intersection against the scalar one.
+    pub fn set_simd(&mut self, simd: bool) {
+        self.simd = simd;
+    }
+
+    fn weight_of_bits(&self, word_index: usize, mut word: u64) -> usize {
+        if self.unit_weights {
+            return word.count_ones() as usize;
+        }
+        let mut count = 0;
+        while word != 0 {
+            let tid = word_index * WORD_BITS + word.trailing_zeros() as usize;
+            count += self.weights[tid] as usize;
+            word &= word - 1;
+        }
+        count
+    }
+
+    fn count_bitmaps(&self, bitmaps: &[&[u64]]) -> usize {
+        let num_words = bitmaps.iter().map(|bitmap| bitmap.len()).min().unwrap_or(0);
+        let mut count = 0;
+        for i in 0..num_words {
+            let word = bitmaps[1..]
+                .iter()
+                .fold(bitmaps[0][i], |word, bitmap| word & bitmap[i]);
+            if word != 0 {
+                count += self.weight_of_bits(i, word);
             }
-            self.index[item.as_index()].push(tid);
         }
+        count
     }
 
-    pub fn count(&self, transaction: &[Item]) -> usize {
-        if transaction.is_empty() {
-            return 0;
+    // The tid-lists and bitmaps of items, or None if an item is in no
+    // transactions, or items is empty.
+    fn lists_of(&self, items: &[Item]) -> Option<ItemLists<'_>> {
+        if items.is_empty() {
+            return None;
         }
+        let mut tid_lists: Vec<&[u32]> = vec![];
+        let mut bitmaps: Vec<&[u64]> = vec![];
+        for &item in items.iter() {
+            let index = item.as_index();
+            if index >= self.index.len() {
+                return None;
+            }
+            match self.bitmaps.get(index) {
+                Some(Some(bitmap)) => bitmaps.push(bitmap),
+                _ => tid_lists.push(&self.index[index]),
+            }
+        }
+        Some((tid_lists, bitmaps))
+    }
 
-        if transaction.len() == 1 {
-            let item_index = transaction[0].as_index();
-            if item_index >= self.index.len() {
-                return 0;
+    // Iterates over the shortest tid-list, checking whether each tid is
+    // also in the other tid-lists and bitmaps, and calls f with each tid
+    // that's in all of them. tid_lists must not be empty.
+    fn for_each_common_tid<F>(&self, mut tid_lists: Vec<&[u32]>, bitmaps: &[&[u64]], mut f: F)
+    where
+        F: FnMut(u32),
+    {
+        tid_lists.sort_by_key(|tids| tids.len());
+        let (shortest, others) = tid_lists.split_first().unwrap();
+        let advance: fn(&[u32], usize, u32) -> usize = if self.simd {
+            advance_to
+        } else {
+            advance_to_scalar
+        };
+        let mut p: Vec<usize> = vec![0; others.len()];
+        for &tid in shortest.iter() {
+            let in_others = others.iter().zip(p.iter_mut()).all(|(tids, p)| {
+                *p = advance(tids, *p, tid);
+                *p < tids.len() && tids[*p] == tid
+            });
+            if in_others && bitmaps.iter().all(|bitmap| bit_is_set(bitmap, tid)) {
+                f(tid);
             }
-            return self.index[item_index].len();
         }
+    }
 
-        let mut tid_lists: Vec<&Vec<usize>> = vec![];
-        for &item in transaction.iter() {
-            tid_lists.push(&self.index[item.as_index()]);
+    #[allow(dead_code)]
+    pub fn support(&self, transaction: &[Item]) -> f64 {
+        let count = self.count(transaction);
+        (count as f64) / (self.transaction_count as f64)
+    }
+}
+
+impl TransactionIndex for Index {
+    fn insert(&mut self, transaction: &[Item], count: u32) {
+        assert!(self.bitmaps.is_empty(), "insert called after finish");
+        let tid = self.weights.len() as u32;
+        self.weights.push(count);
+        self.unit_weights &= count == 1;
+        self.transaction_count += count as usize;
+        for &item in transaction {
+            while self.index.len() <= item.as_index() {
+                self.index.push(vec![]);
+            }
+            self.index[item.as_index()].push(tid);
         }
+    }
 
-        let mut p: Vec<usize> = vec![0; tid_lists.len()];
+    // Converts the tid-lists of items which are in at least 1 in 64
+    // transactions to bitmaps, as their bitmaps are no larger than their
+    // tid-lists. No more transactions can be inserted afterwards.
+    fn finish(&mut self) {
+        let num_tids = self.weights.len();
+        let num_words = num_tids.div_ceil(WORD_BITS);
+        self.bitmaps = self
+            .index
+            .iter_mut()
+            .map(|tids| {
+                if tids.is_empty() || tids.len() * WORD_BITS < num_tids {
+                    return None;
+                }
+                let mut bitmap = vec![0u64; num_words];
+                for &tid in tids.iter() {
+                    let tid = tid as usize;
+                    bitmap[tid / WORD_BITS] |= 1 << (tid % WORD_BITS);
+                }
+                *tids = vec![];
+                Some(bitmap)
+            })
+            .collect();
+    }
 
-        // For each tid in the transaction's first item's list of tids.
+    fn count(&self, transaction: &[Item]) -> usize {
+        let (tid_lists, bitmaps) = match self.lists_of(transaction) {
+            Some(lists) => lists,
+            None => return 0,
+        };
+        if tid_lists.is_empty() {
+            return self.count_bitmaps(&bitmaps);
+        }
         let mut count = 0;
-        for &tid in tid_lists[0].iter() {
-            // Check whether all the other tid lists contain that tid.
-            let mut tid_in_all_item_tid_lists = true;
-            for i in 1..tid_lists.len() {
-                while p[i] < tid_lists[i].len() && tid_lists[i][p[i]] < tid {
-                    p[i] += 1;
-                }
-                if p[i] == tid_lists[i].len() || tid_lists[i][p[i]] != tid {
-                    // This tidlist doesn't include that tid. So this tid cannot
-                    // have all items in it.
-                    tid_in_all_item_tid_lists = false;
-                    break;
+        self.for_each_common_tid(tid_lists, &bitmaps, |tid| {
+            count += self.weights[tid as usize] as usize;
+        });
+        count
+    }
+
+    fn tids(&self, transaction: &[Item]) -> Vec<u32> {
+        let (tid_lists, bitmaps) = match self.lists_of(transaction) {
+            Some(lists) => lists,
+            None => return vec![],
+        };
+        let mut tids = vec![];
+        if tid_lists.is_empty() {
+            let num_words = bitmaps.iter().map(|bitmap| bitmap.len()).min().unwrap_or(0);
+            for i in 0..num_words {
+                let mut word = bitmaps[1..]
+                    .iter()
+                    .fold(bitmaps[0][i], |word, bitmap| word & bitmap[i]);
+                while word != 0 {
+                    tids.push((i * WORD_BITS) as u32 + word.trailing_zeros());
+                    word &= word - 1;
                 }
             }
-            if tid_in_all_item_tid_lists {
-                count += 1
-            }
+        } else {
+            self.for_each_common_tid(tid_lists, &bitmaps, |tid| tids.push(tid));
         }
+        tids
+    }
 
-        count
+    fn weight(&self, tid: u32) -> u32 {
+        self.weights[tid as usize]
     }
 
-    #[allow(dead_code)]
-    pub fn support(&self, transaction: &[Item]) -> f64 {
-        let count = self.count(transaction);
-        (count as f64) / (self.transaction_count as f64)
+    fn transaction_count(&self) -> usize {
+        self.transaction_count
     }
 }
 
 #[cfg(test)]
+#[allow(clippy::useless_vec)]
 mod tests {
     #[test]
     fn test_index() {
         use super::Index;
         use super::Item;
         use super::Itemizer;
+        use super::TransactionIndex;
 
         let mut index = Index::new();
         let transactions = vec![
@@ -116,7 +307,7 @@ mod tests {
                 .iter()
                 .map(|s| itemizer.id_of(s.trim()))
                 .collect::<Vec<Item>>();
-            index.insert(&transaction);
+            index.insert(&transaction, 1);
         }
 
         assert_eq!(index.support(&vec![itemizer.id_of("a")]), 1.0 / 6.0);
@@ -145,4 +336,60 @@ mod tests {
             ]) == 2.0 / 6.0
         );
     }
+
+    #[test]
+    fn test_bitmaps() {
+        use super::Index;
+        use super::Item;
+        use super::TransactionIndex;
+
+        // Items 1 and 2 are in most transactions, so get bitmaps; items 3
+        // and 4 are rare, so keep tid-lists.
+        let mut transactions: Vec<(Vec<u32>, u32)> = vec![];
+        for i in 0..200u32 {
+            let mut transaction = vec![];
+            if i % 2 == 0 {
+                transaction.push(1);
+            }
+            if i % 3 == 0 {
+                transaction.push(2);
+            }
+            if i % 70 == 0 {
+                transaction.push(3);
+            }
+            if i % 90 == 0 {
+                transaction.push(4);
+            }
+            transactions.push((transaction, 1 + i % 4));
+        }
+        for &unit_weights in &[true, false] {
+            let mut index = Index::new();
+            for (transaction, count) in &transactions {
+                let items: Vec<Item> = transaction.iter().map(|&i| Item::from(i)).collect();
+                index.insert(&items, if unit_weights { 1 } else { *count });
+            }
+            let itemsets: Vec<Vec<Item>> = [
+                vec![1],
+                vec![2],
+                vec![3],
+                vec![1, 2],
+                vec![1, 3],
+                vec![2, 3, 4],
+                vec![1, 2, 3, 4],
+            ]
+            .iter()
+            .map(|items| items.iter().map(|&i| Item::from(i)).collect())
+            .collect();
+            let expected: Vec<usize> = itemsets.iter().map(|items| index.count(items)).collect();
+            let expected_tids: Vec<Vec<u32>> =
+                itemsets.iter().map(|items| index.tids(items)).collect();
+            index.finish();
+            assert!(index.bitmaps[1].is_some() && index.bitmaps[2].is_some());
+            assert!(index.bitmaps[3].is_none() && index.bitmaps[4].is_none());
+            let counts: Vec<usize> = itemsets.iter().map(|items| index.count(items)).collect();
+            assert_eq!(counts, expected);
+            let tids: Vec<Vec<u32>> = itemsets.iter().map(|items| index.tids(items)).collect();
+            assert_eq!(tids, expected_tids);
+        }
+    }
 }
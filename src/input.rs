@@ -0,0 +1,148 @@
+// Copyright 2018 Chris Pearce
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use error::ArmError;
+use flate2::read::MultiGzDecoder;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use zstd;
+
+#[cfg(feature = "kafka")]
+use kafka::KafkaReader;
+#[cfg(feature = "postgres")]
+use postgres_input::PostgresReader;
+#[cfg(feature = "http")]
+use remote::open_url;
+
+pub const KAFKA_SCHEME: &str = "kafka://";
+const POSTGRES_SCHEMES: [&str; 2] = ["postgres://", "postgresql://"];
+const URL_SCHEMES: [&str; 3] = ["http://", "https://", "s3://"];
+
+pub fn is_postgres(path: &str) -> bool {
+    POSTGRES_SCHEMES
+        .iter()
+        .any(|scheme| path.starts_with(scheme))
+}
+
+// Opens transaction input for reading. Path is a file, "-" for stdin, a
+// kafka://brokers/topic URL, a postgres:// connection URL, for which query
+// selects (transaction id, item) rows, or an HTTP(S) or S3 URL. Files and
+// URLs ending in .gz or .zst are decompressed as they're read.
+pub fn open_input(path: &str, query: Option<&str>) -> Result<Box<dyn BufRead>, ArmError> {
+    if path == "-" {
+        return Ok(Box::new(BufReader::new(io::stdin())));
+    }
+    if path.starts_with(KAFKA_SCHEME) {
+        return open_kafka(path);
+    }
+    if is_postgres(path) {
+        return match query {
+            Some(query) => open_postgres(path, query),
+            None => Err(ArmError::io(
+                path,
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "PostgreSQL input requires a query",
+                ),
+            )),
+        };
+    }
+    let reader: Box<dyn Read> = if URL_SCHEMES.iter().any(|scheme| path.starts_with(scheme)) {
+        open_remote(path)?
+    } else {
+        Box::new(File::open(path).map_err(|e| ArmError::io(path, e))?)
+    };
+    // Any query string or fragment isn't part of the name.
+    let name = path.split(['?', '#']).next().unwrap_or(path);
+    if name.ends_with(".gz") {
+        Ok(Box::new(BufReader::new(MultiGzDecoder::new(reader))))
+    } else if name.ends_with(".zst") {
+        let decoder = zstd::Decoder::new(reader).map_err(|e| ArmError::io(path, e))?;
+        Ok(Box::new(BufReader::new(decoder)))
+    } else {
+        Ok(Box::new(BufReader::new(reader)))
+    }
+}
+
+#[cfg(feature = "http")]
+fn open_remote(path: &str) -> Result<Box<dyn Read>, ArmError> {
+    open_url(path)
+}
+
+#[cfg(not(feature = "http"))]
+fn open_remote(path: &str) -> Result<Box<dyn Read>, ArmError> {
+    Err(ArmError::io(
+        path,
+        io::Error::new(
+            io::ErrorKind::Unsupported,
+            "HTTP(S) and S3 input requires building with --features http",
+        ),
+    ))
+}
+
+#[cfg(feature = "kafka")]
+fn open_kafka(path: &str) -> Result<Box<dyn BufRead>, ArmError> {
+    Ok(Box::new(BufReader::new(KafkaReader::open(path)?)))
+}
+
+#[cfg(not(feature = "kafka"))]
+fn open_kafka(path: &str) -> Result<Box<dyn BufRead>, ArmError> {
+    Err(ArmError::io(
+        path,
+        io::Error::new(
+            io::ErrorKind::Unsupported,
+            "Kafka input requires building with --features kafka",
+        ),
+    ))
+}
+
+#[cfg(feature = "postgres")]
+fn open_postgres(path: &str, query: &str) -> Result<Box<dyn BufRead>, ArmError> {
+    Ok(Box::new(BufReader::new(PostgresReader::open(path, query)?)))
+}
+
+#[cfg(not(feature = "postgres"))]
+fn open_postgres(path: &str, _query: &str) -> Result<Box<dyn BufRead>, ArmError> {
+    Err(ArmError::io(
+        path,
+        io::Error::new(
+            io::ErrorKind::Unsupported,
+            "PostgreSQL input requires building with --features postgres",
+        ),
+    ))
+}
+
+// Formats items as a line of CSV, quoting those which need it.
+pub fn csv_line<S: AsRef<str>>(items: &[S]) -> String {
+    let fields: Vec<String> = items
+        .iter()
+        .map(|item| {
+            let item = item.as_ref();
+            if item.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", item.replace('"', "\"\""))
+            } else {
+                String::from(item)
+            }
+        })
+        .collect();
+    fields.join(",") + "\n"
+}
+
+// Whether the input may not yield the same transactions if it's read again,
+// so must be held in memory to be read more than once. Stdin can't be read
+// twice, a topic may have had messages added between reads, and a query's
+// results may change and are expensive to recompute.
+pub fn is_stream(path: &str) -> bool {
+    path == "-" || path.starts_with(KAFKA_SCHEME) || is_postgres(path)
+}
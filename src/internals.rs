@@ -0,0 +1,58 @@
+// Copyright 2018 Chris Pearce
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// The internals exercised by the benchmarks in benches/, which can only use
+// the library's public API. Not a stable API; it changes with them.
+
+pub use cancellation::CancellationToken;
+pub use fptree::{fp_growth, FPGrowthParameters, FPTree, ItemSet};
+pub use generate_rules::{generate_rules, RuleParameters};
+pub use index::{Index, TransactionIndex};
+pub use mining::{build_initial_fptree, TreeBuildOptions};
+pub use vec_sets::{split_out, union};
+
+use rule::DEFAULT_LAPLACE_K;
+
+pub fn fp_growth_parameters(min_count: u32) -> FPGrowthParameters {
+    FPGrowthParameters {
+        min_count,
+        max_itemset_size: None,
+        cancellation: CancellationToken::new(),
+        cross_support: None,
+        item_min_counts: None,
+    }
+}
+
+pub fn rule_parameters(min_confidence: f64) -> RuleParameters {
+    RuleParameters {
+        min_confidence,
+        min_confidence_by_length: None,
+        min_lift: None,
+        max_lift: None,
+        min_confidence_lower_bound: None,
+        laplace_k: DEFAULT_LAPLACE_K,
+        min_laplace: None,
+        min_added_value: None,
+        min_gini_gain: None,
+        min_info_gain: None,
+        min_odds_ratio: None,
+        min_yules_q: None,
+        min_phi: None,
+        filter: None,
+        min_itemset_size: 1,
+        cancellation: CancellationToken::new(),
+        item_min_counts: None,
+        target_consequent: None,
+    }
+}
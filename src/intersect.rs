@@ -0,0 +1,87 @@
+// Copyright 2018 Chris Pearce
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Helpers for intersecting sorted lists of transaction ids.
+
+// Returns the index of the first tid in tids[start..] which is at least tid,
+// or tids.len() if there is none.
+pub fn advance_to_scalar(tids: &[u32], start: usize, tid: u32) -> usize {
+    let mut p = start;
+    while p < tids.len() && tids[p] < tid {
+        p += 1;
+    }
+    p
+}
+
+// As advance_to_scalar, but compares tid against blocks of 8 tids at a time
+// using SSE2, which all x86_64 processors support.
+#[cfg(target_arch = "x86_64")]
+pub fn advance_to(tids: &[u32], start: usize, tid: u32) -> usize {
+    use std::arch::x86_64::*;
+    let mut p = start;
+    // Safety: SSE2 is always available on x86_64, and loads only read
+    // tids[p..p + 8], which is in bounds.
+    unsafe {
+        // SSE2 only has signed comparisons, so flip the sign bits to
+        // make signed order match unsigned order.
+        let bias = _mm_set1_epi32(i32::MIN);
+        let target = _mm_xor_si128(_mm_set1_epi32(tid as i32), bias);
+        while p + 8 <= tids.len() {
+            let block = tids.as_ptr().add(p) as *const __m128i;
+            let low = _mm_xor_si128(_mm_loadu_si128(block), bias);
+            let high = _mm_xor_si128(_mm_loadu_si128(block.add(1)), bias);
+            // Each lane less than tid sets two bits of the mask.
+            let less = _mm_packs_epi32(_mm_cmplt_epi32(low, target), _mm_cmplt_epi32(high, target));
+            let mask = _mm_movemask_epi8(less) as u32;
+            if mask != 0xffff {
+                // Tids are sorted, so the lanes less than tid are a prefix
+                // of the block.
+                return p + (mask.count_ones() / 2) as usize;
+            }
+            p += 8;
+        }
+    }
+    advance_to_scalar(tids, p, tid)
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+pub fn advance_to(tids: &[u32], start: usize, tid: u32) -> usize {
+    advance_to_scalar(tids, start, tid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{advance_to, advance_to_scalar};
+
+    #[test]
+    fn test_advance_to() {
+        let mut tids: Vec<u32> = (0..100).map(|i| i * 3).collect();
+        // Values above i32::MAX, to check comparisons are unsigned.
+        tids.extend((0..20).map(|i| u32::MAX - 100 + i * 5));
+        let targets = (0..310)
+            .chain(u32::MAX - 110..u32::MAX)
+            .chain(Some(u32::MAX));
+        for tid in targets {
+            for &start in &[0, 1, 7, 50, 99, 115, tids.len()] {
+                assert_eq!(
+                    advance_to(&tids, start, tid),
+                    advance_to_scalar(&tids, start, tid),
+                    "tid={} start={}",
+                    tid,
+                    start
+                );
+            }
+        }
+    }
+}
@@ -12,17 +12,26 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+// Items' ids are u32s, so at most about four billion distinct items can be
+// mined. Building with --features wide-items makes them u64s, for
+// vocabularies beyond that, such as URLs or user agents, at the cost of
+// twice the memory for every item held in itemsets and trees.
+#[cfg(not(feature = "wide-items"))]
+pub type ItemId = u32;
+#[cfg(feature = "wide-items")]
+pub type ItemId = u64;
+
 #[derive(Copy, Clone, Hash, PartialOrd, PartialEq, Eq, Ord, Debug)]
 pub struct Item {
-    id: u32,
+    id: ItemId,
 }
 
 impl Item {
     pub fn null() -> Item {
         Item { id: 0 }
     }
-    pub fn with_id(id: u32) -> Item {
-        Item { id: id }
+    pub fn with_id(id: ItemId) -> Item {
+        Item { id }
     }
     pub fn as_index(&self) -> usize {
         self.id as usize
@@ -31,3 +40,10 @@ impl Item {
         self.id == 0
     }
 }
+
+// Any u32 is a valid id, whatever the width of ids.
+impl From<u32> for Item {
+    fn from(id: u32) -> Item {
+        Item { id: id as ItemId }
+    }
+}
@@ -12,58 +12,52 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use counter::Counter;
 use item::Item;
+use item_table::ItemTable;
 
+#[derive(Clone)]
 pub struct ItemCounter {
-    counter: Vec<u32>,
+    counter: ItemTable<u32>,
+}
+
+impl Default for ItemCounter {
+    fn default() -> ItemCounter {
+        ItemCounter::new()
+    }
 }
 
 impl ItemCounter {
-    pub fn new() -> ItemCounter {
-        ItemCounter { counter: vec![] }
+    // Estimated bytes used by the counts.
+    pub fn heap_size(&self) -> usize {
+        self.counter.heap_size()
     }
-    pub fn add(&mut self, item: &Item, count: u32) {
-        let index = item.as_index();
-        if self.counter.len() <= index {
-            self.counter.resize(index + 1, 0);
+
+    pub fn new() -> ItemCounter {
+        ItemCounter {
+            counter: ItemTable::new(0),
         }
-        self.counter[index] += count;
     }
-    pub fn get(&self, item: &Item) -> u32 {
-        let index = item.as_index();
-        if index >= self.counter.len() {
-            0
-        } else {
-            self.counter[index]
-        }
+    pub fn take(&mut self, other: ItemCounter) {
+        self.counter = other.counter;
     }
-    pub fn set(&mut self, item: &Item, count: u32) {
-        let index = item.as_index();
-        if self.counter.len() <= index {
-            self.counter.resize(index + 1, 0);
-        }
-        self.counter[index] = count;
+}
+
+impl Counter<Item> for ItemCounter {
+    fn add(&mut self, item: &Item, count: u32) {
+        *self.counter.get_mut(*item) += count;
     }
-    pub fn items_with_count_at_least(&self, min_count: u32) -> Vec<Item> {
-        let mut v: Vec<Item> = vec![];
-        for i in 1..self.counter.len() {
-            if self.counter[i] >= min_count {
-                v.push(Item::with_id(i as u32));
-            }
-        }
-        v
+    fn get(&self, item: &Item) -> u32 {
+        self.counter.get(*item)
     }
-    pub fn take(&mut self, other: ItemCounter) {
-        self.counter = other.counter;
+    fn set(&mut self, item: &Item, count: u32) {
+        self.counter.set(*item, count);
     }
-    pub fn sort_descending(&self, v: &mut Vec<Item>) {
-        v.sort_by(|a, b| {
-            let count_a = self.get(a);
-            let count_b = self.get(b);
-            if count_a == count_b {
-                return b.cmp(a);
-            }
-            count_b.cmp(&count_a)
-        });
+    fn items_with_count_at_least(&self, min_count: u32) -> Vec<Item> {
+        self.counter
+            .iter()
+            .filter(|&(_, count)| count > 0 && count >= min_count)
+            .map(|(item, _)| item)
+            .collect()
     }
 }
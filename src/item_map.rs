@@ -0,0 +1,112 @@
+// Copyright 2018 Chris Pearce
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use error::ArmError;
+use fnv::FnvHashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+
+// Maps raw item names to canonical item names, so that variants of the
+// same logical item (e.g. different package sizes of one product) can be
+// merged into a single item as transactions are read.
+pub struct ItemMap {
+    map: FnvHashMap<String, String>,
+}
+
+impl ItemMap {
+    pub fn new() -> ItemMap {
+        ItemMap {
+            map: FnvHashMap::default(),
+        }
+    }
+
+    // Loads a mapping file, with one "raw_name,canonical_name" pair per line.
+    pub fn load(path: &str) -> Result<ItemMap, ArmError> {
+        let file = File::open(path).map_err(|e| ArmError::io(path, e))?;
+        let mut item_map = ItemMap::new();
+        for (index, line) in BufReader::new(file).lines().enumerate() {
+            let line = line.map_err(|e| ArmError::io(path, e))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let mut splits = line.splitn(2, ',');
+            let raw = splits.next().unwrap_or("").trim();
+            let canonical = match splits.next() {
+                Some(canonical) => canonical.trim(),
+                None => {
+                    return Err(ArmError::parse(
+                        path,
+                        index + 1,
+                        "expected raw_name,canonical_name",
+                    ))
+                }
+            };
+            item_map.insert(raw, canonical);
+        }
+        Ok(item_map)
+    }
+
+    pub fn insert(&mut self, raw: &str, canonical: &str) {
+        self.map.insert(String::from(raw), String::from(canonical));
+    }
+
+    // Returns the canonical name for an item, or the item itself if it
+    // has no mapping.
+    pub fn canonical<'a>(&'a self, raw: &'a str) -> &'a str {
+        match self.map.get(raw) {
+            Some(canonical) => canonical,
+            None => raw,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ItemMap;
+    use itemizer::Itemizer;
+    use std::fs;
+    use test_util::TempFile;
+    use transaction_reader::TransactionReader;
+
+    #[test]
+    fn test_item_map() {
+        let map_path = TempFile::new("item-map.csv");
+        fs::write(&map_path, "cola 330ml,cola\ncola 1.5l, cola\n\n").unwrap();
+        let data_path = TempFile::new("item-map-data.csv");
+        fs::write(&data_path, "cola 330ml,chips,cola 1.5l\ncola 1.5l,salsa\n").unwrap();
+
+        let item_map = ItemMap::load(map_path.path()).unwrap();
+        assert_eq!(item_map.canonical("cola 330ml"), "cola");
+        assert_eq!(item_map.canonical("chips"), "chips");
+
+        let mut itemizer = Itemizer::new();
+        let transactions: Vec<Vec<String>> =
+            TransactionReader::new(data_path.path(), &mut itemizer)
+                .unwrap()
+                .with_item_map(Some(&item_map))
+                .map(|t| t.unwrap())
+                .collect::<Vec<_>>()
+                .into_iter()
+                .map(|(t, _)| {
+                    t.iter()
+                        .map(|&i| String::from(itemizer.str_of(i)))
+                        .collect()
+                })
+                .collect();
+        assert_eq!(
+            transactions,
+            vec![vec!["cola", "chips"], vec!["cola", "salsa"]]
+        );
+    }
+}
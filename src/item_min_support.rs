@@ -0,0 +1,223 @@
+// Copyright 2018 Chris Pearce
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use counter::Counter;
+use error::ArmError;
+use fnv::{FnvHashMap, FnvHashSet};
+use fptree::{FPTree, ItemSet};
+use item::Item;
+use item_counter::ItemCounter;
+use itemizer::Itemizer;
+use mining::min_count;
+use rayon::prelude::*;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+
+// Minimum support thresholds for individual items, as in MSApriori. An
+// itemset is frequent if its support is at least the lowest threshold of its
+// items, so rare but important items can be given low thresholds without
+// lowering the threshold for common items. Items without a threshold of
+// their own use the global minimum support.
+pub struct ItemMinSupports {
+    supports: FnvHashMap<String, f64>,
+}
+
+impl ItemMinSupports {
+    // Loads a file with one "item,min_support" pair per line.
+    pub fn load(path: &str) -> Result<ItemMinSupports, ArmError> {
+        let file = File::open(path).map_err(|e| ArmError::io(path, e))?;
+        let mut supports = FnvHashMap::default();
+        for (index, line) in BufReader::new(file).lines().enumerate() {
+            let line = line.map_err(|e| ArmError::io(path, e))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let mut splits = line.rsplitn(2, ',');
+            let support = splits
+                .next()
+                .and_then(|support| support.trim().parse::<f64>().ok())
+                .filter(|support| (0.0..=1.0).contains(support));
+            match (splits.next(), support) {
+                (Some(item), Some(support)) => {
+                    supports.insert(String::from(item.trim()), support);
+                }
+                _ => {
+                    return Err(ArmError::parse(
+                        path,
+                        index + 1,
+                        "expected item,min_support with min_support in range [0,1]",
+                    ))
+                }
+            }
+        }
+        Ok(ItemMinSupports { supports })
+    }
+
+    // Returns the minimum count of each item counted in item_count.
+    pub fn min_counts(
+        &self,
+        item_count: &ItemCounter,
+        itemizer: &Itemizer,
+        default_min_support: f64,
+        num_transactions: usize,
+    ) -> ItemCounter {
+        let mut min_counts = ItemCounter::new();
+        for item in item_count.items_with_count_at_least(1) {
+            let support = self
+                .supports
+                .get(itemizer.str_of(item))
+                .cloned()
+                .unwrap_or(default_min_support);
+            min_counts.set(&item, min_count(support, num_transactions));
+        }
+        min_counts
+    }
+}
+
+// Returns the order in which items are inserted into the FPTree, as values
+// which are highest for the first item. Items with the highest minimum
+// counts come first, then the most frequent. Each itemset mined from an
+// item's conditional tree then has that item as its item with the lowest
+// minimum count, so the conditional tree can be mined with that count.
+pub fn item_order(min_counts: &ItemCounter, item_count: &ItemCounter) -> ItemCounter {
+    let mut items = item_count.items_with_count_at_least(1);
+    items.sort_by_key(|item| (min_counts.get(item), item_count.get(item)));
+    let mut order = ItemCounter::new();
+    for (rank, item) in items.iter().enumerate() {
+        order.set(item, rank as u32 + 1);
+    }
+    order
+}
+
+// Missing subsets are counted in memory, and a missing subset of n items can
+// have 2^n - 2 missing subsets of its own, so longer ones aren't enumerated.
+const MAX_MISSING_SUBSET_LENGTH: usize = 24;
+
+// Counts the subsets of itemsets which aren't themselves in itemsets. Under
+// per-item minimum supports, subsets of frequent itemsets may not be
+// frequent, but their counts are still needed to calculate rule metrics.
+// fptree must be the tree itemsets were mined from, ordered by item_order.
+pub fn count_missing_subsets(
+    fptree: &FPTree,
+    itemsets: &[ItemSet],
+    item_order: &ItemCounter,
+) -> Result<Vec<ItemSet>, ArmError> {
+    Ok(missing_subsets(itemsets)?
+        .into_par_iter()
+        .map(|items| {
+            let last_item = *items
+                .iter()
+                .min_by_key(|item| item_order.get(item))
+                .unwrap();
+            let count = fptree.count_itemset(&items, last_item);
+            ItemSet::new(items, count)
+        })
+        .collect())
+}
+
+// Every subset of an itemset is reached through immediate subsets, so
+// checking the immediate subsets of the itemsets, and of each missing subset
+// as it's found, finds them all. Subsets already seen aren't checked again.
+fn missing_subsets(itemsets: &[ItemSet]) -> Result<Vec<Vec<Item>>, ArmError> {
+    let mut seen: FnvHashSet<Vec<Item>> = itemsets.iter().map(|i| i.items.clone()).collect();
+    let mut missing: Vec<Vec<Item>> = vec![];
+    let mut pending: Vec<Vec<Item>> = itemsets.iter().map(|i| i.items.clone()).collect();
+    while let Some(items) = pending.pop() {
+        for skip in 0..items.len() {
+            let mut subset = items.clone();
+            subset.remove(skip);
+            if subset.is_empty() || !seen.insert(subset.clone()) {
+                continue;
+            }
+            if subset.len() > MAX_MISSING_SUBSET_LENGTH {
+                return Err(ArmError::limit_exceeded(&format!(
+                    "An itemset of {} items has infrequent subsets of more than {} items, \
+                     too many to count. Lower --max-itemset-size or the per-item minimum \
+                     supports.",
+                    items.len(),
+                    MAX_MISSING_SUBSET_LENGTH
+                )));
+            }
+            missing.push(subset.clone());
+            pending.push(subset);
+        }
+    }
+    Ok(missing)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{item_order, missing_subsets, ItemMinSupports};
+    use counter::Counter;
+    use fptree::ItemSet;
+    use item::Item;
+    use item_counter::ItemCounter;
+    use itemizer::Itemizer;
+    use std::fs;
+    use test_util::TempFile;
+
+    #[test]
+    fn test_item_min_supports() {
+        let path = TempFile::new("item-min-supports.csv");
+        fs::write(&path, "rare,0.01\n\ncommon, 0.5\n").unwrap();
+        let supports = ItemMinSupports::load(path.path()).unwrap();
+        let mut itemizer = Itemizer::new();
+        let mut item_count = ItemCounter::new();
+        let (rare, common, other) = (
+            itemizer.id_of("rare"),
+            itemizer.id_of("common"),
+            itemizer.id_of("other"),
+        );
+        item_count.add(&rare, 2);
+        item_count.add(&common, 80);
+        item_count.add(&other, 30);
+        let min_counts = supports.min_counts(&item_count, &itemizer, 0.1, 200);
+        assert_eq!(min_counts.get(&rare), 2);
+        assert_eq!(min_counts.get(&common), 100);
+        assert_eq!(min_counts.get(&other), 20);
+
+        let order = item_order(&min_counts, &item_count);
+        let mut items = vec![rare, other, common];
+        order.sort_descending(&mut items);
+        assert_eq!(items, vec![common, other, rare]);
+
+        fs::write(&path, "rare,2\n").unwrap();
+        assert!(ItemMinSupports::load(path.path()).is_err());
+    }
+
+    #[test]
+    fn test_missing_subsets() {
+        let items = |ids: &[u32]| -> Vec<Item> { ids.iter().map(|&i| Item::from(i)).collect() };
+        let itemsets = vec![
+            ItemSet::new(items(&[1]), 5),
+            ItemSet::new(items(&[1, 2]), 3),
+            ItemSet::new(items(&[1, 2, 3]), 2),
+        ];
+        let mut missing = missing_subsets(&itemsets).unwrap();
+        missing.sort();
+        assert_eq!(
+            missing,
+            vec![items(&[1, 3]), items(&[2]), items(&[2, 3]), items(&[3])]
+        );
+    }
+
+    #[test]
+    fn test_missing_subsets_of_long_itemset() {
+        // Too many subsets to enumerate, which is an error rather than a
+        // panic or a hang.
+        let items: Vec<Item> = (1..=64u32).map(Item::from).collect();
+        let itemsets = vec![ItemSet::new(items, 2)];
+        assert!(missing_subsets(&itemsets).is_err());
+    }
+}
@@ -0,0 +1,153 @@
+// Copyright 2018 Chris Pearce
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use fnv::FnvHashMap;
+use item::{Item, ItemId};
+use std::cmp;
+use std::mem::size_of;
+
+// Ids beyond the end of the dense values by more than this, or than the
+// number of dense values if that's more, are held in the hash map.
+const MAX_DENSE_GAP: usize = 1 << 16;
+
+// Values indexed by item, such as counts, for ids which may be sparse.
+// While ids are dense, as the itemizer assigns them, values are held in a
+// Vec indexed by id. An id far beyond the end of the Vec, as in data sets
+// pre-encoded with integer ids with large gaps between them, is held in a
+// hash map instead, so that the Vec isn't grown to the largest id. The
+// Vec's growth is bounded to double its length, so it's at least half full
+// of ids which were set.
+#[derive(Clone, Debug)]
+pub struct ItemTable<T> {
+    dense: Vec<T>,
+    // Ids at least dense.len().
+    sparse: FnvHashMap<usize, T>,
+    default: T,
+}
+
+impl<T: Copy> ItemTable<T> {
+    // A table in which every item has the value default.
+    pub fn new(default: T) -> ItemTable<T> {
+        ItemTable {
+            dense: vec![],
+            sparse: FnvHashMap::default(),
+            default,
+        }
+    }
+
+    pub fn get(&self, item: Item) -> T {
+        let index = item.as_index();
+        match self.dense.get(index) {
+            Some(&value) => value,
+            None => *self.sparse.get(&index).unwrap_or(&self.default),
+        }
+    }
+
+    pub fn get_mut(&mut self, item: Item) -> &mut T {
+        let index = item.as_index();
+        let len = self.dense.len();
+        if index >= len && index - len < cmp::max(MAX_DENSE_GAP, len) {
+            self.grow(index + 1);
+        }
+        if index < self.dense.len() {
+            &mut self.dense[index]
+        } else {
+            self.sparse.entry(index).or_insert(self.default)
+        }
+    }
+
+    pub fn set(&mut self, item: Item, value: T) {
+        *self.get_mut(item) = value;
+    }
+
+    // Extends the dense values to len, moving in any sparse values now
+    // within them.
+    fn grow(&mut self, len: usize) {
+        self.dense.resize(len, self.default);
+        if self.sparse.is_empty() {
+            return;
+        }
+        let dense = &mut self.dense;
+        self.sparse.retain(|&index, value| {
+            if index < len {
+                dense[index] = *value;
+                false
+            } else {
+                true
+            }
+        });
+    }
+
+    // Every item in the dense values, and every item set in the sparse
+    // values, with its value, in increasing order of id. Ids start at 1, so
+    // the null item isn't included.
+    pub fn iter(&self) -> impl Iterator<Item = (Item, T)> + '_ {
+        let mut sparse: Vec<(usize, T)> = self.sparse.iter().map(|(&i, &v)| (i, v)).collect();
+        sparse.sort_unstable_by_key(|&(index, _)| index);
+        self.dense
+            .iter()
+            .cloned()
+            .enumerate()
+            .skip(1)
+            .chain(sparse)
+            .map(|(index, value)| (Item::with_id(index as ItemId), value))
+    }
+
+    // Estimated bytes used by the values.
+    pub fn heap_size(&self) -> usize {
+        self.dense.capacity() * size_of::<T>()
+            + self.sparse.capacity() * (size_of::<usize>() + size_of::<T>())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ItemTable, MAX_DENSE_GAP};
+    use item::{Item, ItemId};
+
+    #[test]
+    fn test_item_table() {
+        let mut table = ItemTable::new(0u32);
+        table.set(Item::from(3), 5);
+        *table.get_mut(Item::from(1)) += 2;
+        assert_eq!(table.dense.len(), 4);
+        assert!(table.sparse.is_empty());
+
+        // A large id is held sparsely, and the dense values aren't grown.
+        let far = Item::with_id(10 * MAX_DENSE_GAP as ItemId);
+        table.set(far, 7);
+        assert_eq!(table.dense.len(), 4);
+        assert_eq!(table.get(far), 7);
+        assert_eq!(table.get(Item::from(2)), 0);
+        assert_eq!(table.get(Item::with_id(far.as_index() as ItemId + 1)), 0);
+        assert_eq!(
+            table.iter().collect::<Vec<_>>(),
+            vec![
+                (Item::from(1), 2),
+                (Item::from(2), 0),
+                (Item::from(3), 5),
+                (far, 7)
+            ]
+        );
+
+        // As dense ids are added, the dense values grow, and take in any
+        // sparse values they reach.
+        for id in 4..11 * MAX_DENSE_GAP as u32 {
+            *table.get_mut(Item::from(id)) += 1;
+        }
+        assert!(table.sparse.is_empty());
+        assert_eq!(table.get(far), 8);
+        assert_eq!(table.get(Item::from(3)), 5);
+    }
+}
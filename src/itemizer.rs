@@ -12,50 +12,305 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use fnv::FnvHashMap;
-use item::Item;
+use counter::Counter;
+use error::ArmError;
+use fnv::{FnvHashSet, FnvHasher};
+use hashbrown::HashTable;
+use item::{Item, ItemId};
 use item_counter::ItemCounter;
+use output::OutputFile;
+use std::convert::TryFrom;
+use std::fs::File;
+use std::hash::{Hash, Hasher};
+use std::io::{self, BufRead, BufReader, Write};
+use std::mem::size_of;
+use std::str::FromStr;
 
+// What to do on reading an item that isn't in a dictionary.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UnseenItems {
+    Error,
+    // Assign the item the next id after the dictionary's highest.
+    Append,
+}
+
+impl FromStr for UnseenItems {
+    type Err = String;
+    fn from_str(s: &str) -> Result<UnseenItems, String> {
+        match s {
+            "error" => Ok(UnseenItems::Error),
+            "append" => Ok(UnseenItems::Append),
+            _ => Err(format!("Unknown unseen items policy {}", s)),
+        }
+    }
+}
+
+// Maps item strings to integer ids and back. All item strings are stored
+// once, back to back, in a single arena string; the hash table stores only
+// item ids, and compares against the arena when probing. This roughly halves
+// the memory needed compared to storing each string in both a map and a
+// vector, which matters on datasets with millions of distinct items.
 pub struct Itemizer {
-    next_item_id: u32,
-    item_str_to_id: FnvHashMap<String, Item>,
-    item_id_to_str: Vec<String>,
+    arena: String,
+    // Offset in the arena of the end of each item's string. Item with id i's
+    // string is at arena[ends[i - 2]..ends[i - 1]] (starting at 0 for id 1).
+    ends: Vec<usize>,
+    table: HashTable<Item>,
+    unseen_items: UnseenItems,
+}
+
+fn hash_str(s: &str) -> u64 {
+    let mut hasher = FnvHasher::default();
+    s.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn slice_of<'a>(arena: &'a str, ends: &[usize], id: Item) -> &'a str {
+    let index = id.as_index() - 1;
+    let start = if index == 0 { 0 } else { ends[index - 1] };
+    &arena[start..ends[index]]
+}
+
+impl Default for Itemizer {
+    fn default() -> Itemizer {
+        Itemizer::new()
+    }
 }
 
 impl Itemizer {
     pub fn new() -> Itemizer {
         Itemizer {
-            next_item_id: 1,
-            item_str_to_id: FnvHashMap::default(),
-            item_id_to_str: vec![],
+            arena: String::new(),
+            ends: vec![],
+            table: HashTable::new(),
+            unseen_items: UnseenItems::Append,
+        }
+    }
+
+    // Creates an itemizer with pre-assigned ids, so that items have the same
+    // ids however the dataset is ordered. Ids must be at least 1. Ids
+    // between those assigned are left unused; per-item counts of sparse ids
+    // are held in hash maps, see ItemTable.
+    pub fn from_dictionary<I>(dictionary: I) -> Result<Itemizer, String>
+    where
+        I: IntoIterator<Item = (String, ItemId)>,
+    {
+        let mut entries: Vec<(String, ItemId)> = dictionary.into_iter().collect();
+        entries.sort_by_key(|&(_, id)| id);
+        let mut itemizer = Itemizer::new();
+        for (item, id) in entries {
+            if id == 0 {
+                return Err(format!("Item {} has id 0; ids start at 1", item));
+            }
+            if (id as usize) <= itemizer.ends.len() {
+                return Err(format!("Id {} is assigned to more than one item", id));
+            }
+            if itemizer.get_id(&item).is_some() {
+                return Err(format!("Item {} is assigned more than one id", item));
+            }
+            // Unused ids have empty strings, and aren't in the table.
+            while itemizer.ends.len() + 1 < id as usize {
+                itemizer.ends.push(itemizer.arena.len());
+            }
+            assert_eq!(itemizer.id_of(&item), Item::with_id(id));
+        }
+        Ok(itemizer)
+    }
+
+    // Loads a dictionary file, with one "item,id" pair per line.
+    pub fn load_dictionary(path: &str) -> Result<Itemizer, ArmError> {
+        let file = File::open(path).map_err(|e| ArmError::io(path, e))?;
+        let mut entries = vec![];
+        let mut items: FnvHashSet<String> = FnvHashSet::default();
+        let mut ids: FnvHashSet<ItemId> = FnvHashSet::default();
+        for (index, line) in BufReader::new(file).lines().enumerate() {
+            let line = line.map_err(|e| ArmError::io(path, e))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let mut splits = line.rsplitn(2, ',');
+            let id = splits
+                .next()
+                .and_then(|id| id.trim().parse::<ItemId>().ok())
+                .filter(|&id| id > 0);
+            let (item, id) = match (splits.next(), id) {
+                (Some(item), Some(id)) => (String::from(item.trim()), id),
+                _ => {
+                    return Err(ArmError::parse(
+                        path,
+                        index + 1,
+                        "expected item,id with id at least 1",
+                    ))
+                }
+            };
+            if !ids.insert(id) || !items.insert(item.clone()) {
+                return Err(ArmError::parse(
+                    path,
+                    index + 1,
+                    "item or id is already in the dictionary",
+                ));
+            }
+            entries.push((item, id));
+        }
+        Ok(Itemizer::from_dictionary(entries).expect("dictionary entries were checked as read"))
+    }
+
+    // Writes the "item,id" pair of every item, in the format read by
+    // load_dictionary.
+    pub fn write_dictionary(&self, path: &str) -> io::Result<()> {
+        let mut output = OutputFile::create(path, None)?;
+        let mut ids: Vec<Item> = self.table.iter().cloned().collect();
+        ids.sort();
+        for id in ids {
+            writeln!(output, "{},{}", self.str_of(id), id.as_index())?;
+        }
+        output.finish()
+    }
+
+    pub fn set_unseen_items(&mut self, unseen_items: UnseenItems) {
+        self.unseen_items = unseen_items;
+    }
+
+    // Like id_of, but returns None for an unseen item if unseen items are
+    // an error.
+    pub fn try_id_of(&mut self, item: &str) -> Option<Item> {
+        match self.unseen_items {
+            UnseenItems::Append => Some(self.id_of(item)),
+            UnseenItems::Error => self.get_id(item),
         }
     }
     pub fn id_of(&mut self, item: &str) -> Item {
-        if let Some(id) = self.item_str_to_id.get(item) {
-            return *id;
-        }
-        let id = self.next_item_id;
-        self.next_item_id += 1;
-        self.item_str_to_id
-            .insert(String::from(item), Item::with_id(id));
-        self.item_id_to_str.push(String::from(item));
-        assert_eq!(self.item_id_to_str.len(), id as usize);
-        assert_eq!(self.str_of(Item::with_id(id)), item);
-        Item::with_id(id)
+        let hash = hash_str(item);
+        let Itemizer {
+            ref mut arena,
+            ref mut ends,
+            ref mut table,
+            ..
+        } = *self;
+        if let Some(&id) = table.find(hash, |&id| slice_of(arena, ends, id) == item) {
+            return id;
+        }
+        arena.push_str(item);
+        ends.push(arena.len());
+        let id = ItemId::try_from(ends.len())
+            .map(Item::with_id)
+            .expect("Too many distinct items; build with --features wide-items");
+        table.insert_unique(hash, id, |&id| hash_str(slice_of(arena, ends, id)));
+        assert_eq!(self.str_of(id), item);
+        id
+    }
+    // Like id_of, but returns None rather than assigning an id to an item
+    // that hasn't been seen.
+    pub fn get_id(&self, item: &str) -> Option<Item> {
+        self.table
+            .find(hash_str(item), |&id| self.str_of(id) == item)
+            .cloned()
     }
+    // Estimated bytes used by the dictionary.
+    pub fn heap_size(&self) -> usize {
+        self.arena.capacity()
+            + self.ends.capacity() * size_of::<usize>()
+            + self.table.capacity() * size_of::<Item>()
+    }
+
     pub fn str_of(&self, id: Item) -> &str {
-        &self.item_id_to_str[id.as_index() - 1]
+        slice_of(&self.arena, &self.ends, id)
     }
-    pub fn reorder_sorted(&mut self, item_count: &mut ItemCounter) {
-        self.item_id_to_str.sort();
+    // Reassigns ids so that they're in lexicographic order of the items'
+    // strings, and remaps item_count to match. Returns the new id of each
+    // item, indexed by the item's old id.
+    pub fn reorder_sorted(&mut self, item_count: &mut ItemCounter) -> Vec<Item> {
+        let mut old_ids: Vec<Item> = (1..=self.ends.len())
+            .map(|id| Item::with_id(id as ItemId))
+            .collect();
+        old_ids.sort_by(|&a, &b| self.str_of(a).cmp(self.str_of(b)));
+
+        let mut arena = String::with_capacity(self.arena.len());
+        let mut ends = Vec::with_capacity(self.ends.len());
         let mut sorted_counter = ItemCounter::new();
-        for (index, item_str) in self.item_id_to_str.iter().enumerate() {
-            let new_id = Item::with_id((index + 1) as u32);
-            let old_id = self.item_str_to_id[item_str];
-            let count = item_count.get(&old_id);
-            sorted_counter.set(&new_id, count);
-            self.item_str_to_id.insert(item_str.clone(), new_id);
+        let mut new_ids = vec![Item::null(); old_ids.len() + 1];
+        for (index, &old_id) in old_ids.iter().enumerate() {
+            let new_id = Item::with_id((index + 1) as ItemId);
+            new_ids[old_id.as_index()] = new_id;
+            arena.push_str(self.str_of(old_id));
+            ends.push(arena.len());
+            sorted_counter.set(&new_id, item_count.get(&old_id));
         }
         item_count.take(sorted_counter);
+
+        let mut table = HashTable::with_capacity(ends.len());
+        for index in 0..ends.len() {
+            let id = Item::with_id((index + 1) as ItemId);
+            let hash = hash_str(slice_of(&arena, &ends, id));
+            table.insert_unique(hash, id, |&id| hash_str(slice_of(&arena, &ends, id)));
+        }
+        self.arena = arena;
+        self.ends = ends;
+        self.table = table;
+        new_ids
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Itemizer, UnseenItems};
+    use counter::Counter;
+    use item::Item;
+    use item_counter::ItemCounter;
+    use std::fs;
+    use test_util::TempFile;
+
+    #[test]
+    fn test_reorder_sorted() {
+        let mut itemizer = Itemizer::new();
+        let mut item_count = ItemCounter::new();
+        for (s, count) in [("pear", 3), ("apple", 1), ("zucchini", 2), ("banana", 5)].iter() {
+            let id = itemizer.id_of(s);
+            assert_eq!(itemizer.id_of(s), id);
+            assert_eq!(itemizer.str_of(id), *s);
+            item_count.add(&id, *count);
+        }
+
+        let pear = itemizer.id_of("pear");
+        let new_ids = itemizer.reorder_sorted(&mut item_count);
+
+        let ids: Vec<_> = ["apple", "banana", "pear", "zucchini"]
+            .iter()
+            .map(|s| itemizer.id_of(s))
+            .collect();
+        assert!(ids.windows(2).all(|w| w[0] < w[1]));
+        let counts: Vec<u32> = ids.iter().map(|id| item_count.get(id)).collect();
+        assert_eq!(counts, vec![1, 5, 3, 2]);
+        assert_eq!(itemizer.str_of(ids[2]), "pear");
+        assert_eq!(new_ids[pear.as_index()], ids[2]);
+        // New items are appended after the existing ones.
+        let id = itemizer.id_of("cherry");
+        assert_eq!(itemizer.str_of(id), "cherry");
+        assert!(id > ids[3]);
+    }
+
+    #[test]
+    fn test_dictionary() {
+        let dictionary = vec![(String::from("b"), 1), (String::from("a"), 4)];
+        let mut itemizer = Itemizer::from_dictionary(dictionary).unwrap();
+        assert_eq!(itemizer.get_id("a"), Some(Item::with_id(4)));
+        assert_eq!(itemizer.get_id("b"), Some(Item::with_id(1)));
+        assert_eq!(itemizer.get_id(""), None);
+        itemizer.set_unseen_items(UnseenItems::Error);
+        assert_eq!(itemizer.try_id_of("c"), None);
+        itemizer.set_unseen_items(UnseenItems::Append);
+        assert_eq!(itemizer.try_id_of("c"), Some(Item::with_id(5)));
+
+        let path = TempFile::new("dictionary.csv");
+        itemizer.write_dictionary(path.path()).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "b,1\na,4\nc,5\n");
+        let loaded = Itemizer::load_dictionary(path.path()).unwrap();
+        assert_eq!(loaded.get_id("c"), Some(Item::with_id(5)));
+
+        assert!(Itemizer::from_dictionary(vec![(String::from("a"), 0)]).is_err());
+        let duplicates = vec![(String::from("a"), 1), (String::from("b"), 1)];
+        assert!(Itemizer::from_dictionary(duplicates).is_err());
+        let duplicates = vec![(String::from("a"), 1), (String::from("a"), 2)];
+        assert!(Itemizer::from_dictionary(duplicates).is_err());
     }
 }
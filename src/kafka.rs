@@ -0,0 +1,264 @@
+// Copyright 2018 Chris Pearce
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use error::ArmError;
+use input::{csv_line, KAFKA_SCHEME};
+use rdkafka::config::ClientConfig;
+use rdkafka::consumer::{BaseConsumer, Consumer};
+use rdkafka::message::Message;
+use rdkafka::{Offset, TopicPartitionList};
+use serde_json;
+use std::collections::HashMap;
+use std::io::{self, Read};
+use std::time::{Duration, Instant};
+
+// How long to wait for the brokers to respond, or for each poll for the
+// next message.
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+// Default limit, in seconds, on how long reading a topic up to its high
+// watermarks may take, however many polls time out along the way.
+const DEFAULT_READ_TIMEOUT_SECS: u64 = 600;
+
+// Format of message payloads.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Payload {
+    // One or more lines of items, in the --input-format.
+    Delimited,
+    // A JSON array of item names, e.g. ["milk","bread"].
+    Json,
+}
+
+// A kafka://broker1,broker2/topic?payload=json&group=arm&timeout=600 URL.
+// Payload defaults to delimited, group, the consumer group id, to arm, and
+// timeout, the limit in seconds on reading the topic, to 600.
+#[derive(Debug, PartialEq)]
+struct KafkaUrl {
+    brokers: String,
+    topic: String,
+    payload: Payload,
+    group: String,
+    read_timeout: Duration,
+}
+
+impl KafkaUrl {
+    fn parse(url: &str) -> Result<KafkaUrl, String> {
+        let rest = url
+            .strip_prefix(KAFKA_SCHEME)
+            .ok_or_else(|| format!("expected {}brokers/topic", KAFKA_SCHEME))?;
+        let (location, query) = rest.split_once('?').unwrap_or((rest, ""));
+        let (brokers, topic) = match location.split_once('/') {
+            Some((brokers, topic)) if !brokers.is_empty() && !topic.is_empty() => (brokers, topic),
+            _ => return Err(format!("expected {}brokers/topic", KAFKA_SCHEME)),
+        };
+        let mut parsed = KafkaUrl {
+            brokers: String::from(brokers),
+            topic: String::from(topic),
+            payload: Payload::Delimited,
+            group: String::from("arm"),
+            read_timeout: Duration::from_secs(DEFAULT_READ_TIMEOUT_SECS),
+        };
+        for parameter in query.split('&').filter(|p| !p.is_empty()) {
+            match parameter.split_once('=') {
+                Some(("payload", "delimited")) => parsed.payload = Payload::Delimited,
+                Some(("payload", "json")) => parsed.payload = Payload::Json,
+                Some(("group", group)) if !group.is_empty() => parsed.group = String::from(group),
+                Some(("timeout", seconds)) => match seconds.parse::<u64>() {
+                    Ok(seconds) if seconds > 0 => {
+                        parsed.read_timeout = Duration::from_secs(seconds)
+                    }
+                    _ => return Err(String::from("timeout must be a positive number of seconds")),
+                },
+                _ => return Err(format!("unknown parameter {}", parameter)),
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+// Converts a JSON array of item names to a CSV line.
+fn json_to_csv(payload: &[u8]) -> Result<String, String> {
+    let items: Vec<String> = serde_json::from_slice(payload)
+        .map_err(|e| format!("expected a JSON array of item names: {}", e))?;
+    Ok(csv_line(&items))
+}
+
+// Reads the messages in a Kafka topic as lines of transactions, from the
+// start of each partition up to the last message present when opened, so
+// a live topic still has an end. Each message holds one or more
+// transactions; JSON payloads are converted to CSV.
+pub struct KafkaReader {
+    url: KafkaUrl,
+    consumer: BaseConsumer,
+    // Offset of the last message to read in each partition not yet read
+    // to its end.
+    last_offsets: HashMap<i32, i64>,
+    // When to give up waiting for the rest of the messages.
+    deadline: Instant,
+    buffer: Vec<u8>,
+    position: usize,
+}
+
+impl KafkaReader {
+    pub fn open(path: &str) -> Result<KafkaReader, ArmError> {
+        let error = |message: String| ArmError::io(path, io::Error::other(message));
+        let url = KafkaUrl::parse(path).map_err(&error)?;
+        let consumer: BaseConsumer = ClientConfig::new()
+            .set("bootstrap.servers", &url.brokers)
+            .set("group.id", &url.group)
+            .set("enable.auto.commit", "false")
+            .set("enable.partition.eof", "false")
+            .create()
+            .map_err(|e| error(e.to_string()))?;
+        let metadata = consumer
+            .fetch_metadata(Some(&url.topic), TIMEOUT)
+            .map_err(|e| error(e.to_string()))?;
+        let topic = match metadata.topics().first() {
+            Some(topic) if topic.error().is_none() => topic,
+            _ => return Err(error(format!("topic {} not found", url.topic))),
+        };
+        let mut assignment = TopicPartitionList::new();
+        let mut last_offsets = HashMap::new();
+        for partition in topic.partitions() {
+            let (low, high) = consumer
+                .fetch_watermarks(&url.topic, partition.id(), TIMEOUT)
+                .map_err(|e| error(e.to_string()))?;
+            if low < high {
+                assignment
+                    .add_partition_offset(&url.topic, partition.id(), Offset::Offset(low))
+                    .map_err(|e| error(e.to_string()))?;
+                last_offsets.insert(partition.id(), high - 1);
+            }
+        }
+        consumer
+            .assign(&assignment)
+            .map_err(|e| error(e.to_string()))?;
+        Ok(KafkaReader {
+            deadline: Instant::now() + url.read_timeout,
+            url,
+            consumer,
+            last_offsets,
+            buffer: vec![],
+            position: 0,
+        })
+    }
+
+    // Replaces the buffer with the next message's transactions. Returns
+    // false once every partition has been read to its end. Polls which
+    // time out are retried, as brokers can be slow to return messages
+    // partway through a topic, until the reader's deadline passes.
+    fn next_message(&mut self) -> io::Result<bool> {
+        while !self.last_offsets.is_empty() {
+            let now = Instant::now();
+            if now >= self.deadline {
+                let mut partitions: Vec<i32> = self.last_offsets.keys().cloned().collect();
+                partitions.sort_unstable();
+                return Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    format!(
+                        "timed out after {} s with partitions {:?} not read up to their high \
+                         watermarks",
+                        self.url.read_timeout.as_secs(),
+                        partitions
+                    ),
+                ));
+            }
+            let message = match self.consumer.poll(TIMEOUT.min(self.deadline - now)) {
+                Some(message) => message.map_err(io::Error::other)?,
+                None => continue,
+            };
+            let partition = message.partition();
+            match self.last_offsets.get(&partition) {
+                Some(&last) if message.offset() >= last => {
+                    self.last_offsets.remove(&partition);
+                }
+                Some(_) => {}
+                // Messages past the end of a partition already read.
+                None => continue,
+            }
+            let payload = message.payload().unwrap_or(&[]);
+            self.buffer.clear();
+            self.position = 0;
+            match self.url.payload {
+                Payload::Delimited => {
+                    self.buffer.extend_from_slice(payload);
+                    if !payload.ends_with(b"\n") {
+                        self.buffer.push(b'\n');
+                    }
+                }
+                Payload::Json => {
+                    let line = json_to_csv(payload).map_err(|e| {
+                        io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            format!("partition {} offset {}: {}", partition, message.offset(), e),
+                        )
+                    })?;
+                    self.buffer.extend_from_slice(line.as_bytes());
+                }
+            }
+            return Ok(true);
+        }
+        Ok(false)
+    }
+}
+
+impl Read for KafkaReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.position == self.buffer.len() && !self.next_message()? {
+            return Ok(0);
+        }
+        let n = buf.len().min(self.buffer.len() - self.position);
+        buf[..n].copy_from_slice(&self.buffer[self.position..self.position + n]);
+        self.position += n;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{json_to_csv, KafkaUrl, Payload, DEFAULT_READ_TIMEOUT_SECS};
+    use std::time::Duration;
+
+    #[test]
+    fn test_kafka_url() {
+        assert_eq!(
+            KafkaUrl::parse("kafka://a:9092,b:9092/baskets?payload=json&group=g"),
+            Ok(KafkaUrl {
+                brokers: String::from("a:9092,b:9092"),
+                topic: String::from("baskets"),
+                payload: Payload::Json,
+                group: String::from("g"),
+                read_timeout: Duration::from_secs(DEFAULT_READ_TIMEOUT_SECS),
+            })
+        );
+        let url = KafkaUrl::parse("kafka://localhost/baskets").unwrap();
+        assert_eq!(url.payload, Payload::Delimited);
+        assert_eq!(url.group, "arm");
+        let url = KafkaUrl::parse("kafka://localhost/baskets?timeout=5").unwrap();
+        assert_eq!(url.read_timeout, Duration::from_secs(5));
+        assert!(KafkaUrl::parse("kafka://localhost/baskets?timeout=0").is_err());
+        assert!(KafkaUrl::parse("kafka://localhost").is_err());
+        assert!(KafkaUrl::parse("kafka:///baskets").is_err());
+        assert!(KafkaUrl::parse("kafka://localhost/baskets?payload=xml").is_err());
+    }
+
+    #[test]
+    fn test_json_to_csv() {
+        assert_eq!(
+            json_to_csv(br#"["milk", "bread, white", "6\" sub"]"#),
+            Ok(String::from("milk,\"bread, white\",\"6\"\" sub\"\n"))
+        );
+        assert!(json_to_csv(br#"{"items": ["milk"]}"#).is_err());
+    }
+}
@@ -0,0 +1,376 @@
+// Copyright 2018 Chris Pearce
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use fnv::FnvHashMap;
+use fptree::ItemSet;
+use item::Item;
+use itemizer::Itemizer;
+use serde_json::{self, json, Value};
+use std::fmt::Write;
+
+// Whether every item of sorted a is in sorted b.
+fn is_subset(a: &[Item], b: &[Item]) -> bool {
+    let mut b = b.iter();
+    a.iter().all(|item| b.any(|other| other == item))
+}
+
+// For each itemset among itemsets, which must be every frequent itemset as
+// mined, the items which can be added to it without changing its count, and
+// so are in every transaction which contains it. As counts only fall as
+// items are added, only supersets with one more item need checking. The
+// empty itemset is contained in all num_transactions.
+fn closure_extensions(
+    itemsets: &[ItemSet],
+    num_transactions: u32,
+) -> FnvHashMap<Vec<Item>, Vec<Item>> {
+    let counts: FnvHashMap<&[Item], u32> = itemsets
+        .iter()
+        .map(|itemset| (&itemset.items[..], itemset.count))
+        .collect();
+    let mut extensions: FnvHashMap<Vec<Item>, Vec<Item>> = FnvHashMap::default();
+    for itemset in itemsets {
+        for i in 0..itemset.len() {
+            let mut subset = itemset.items.clone();
+            let item = subset.remove(i);
+            let count = if subset.is_empty() {
+                Some(num_transactions)
+            } else {
+                counts.get(&subset[..]).cloned()
+            };
+            if count == Some(itemset.count) {
+                extensions.entry(subset).or_default().push(item);
+            }
+        }
+    }
+    extensions
+}
+
+// The closed itemsets among itemsets, which must be every frequent itemset,
+// as mined. An itemset is closed if none of its supersets has the same
+// count. The empty itemset, contained in all num_transactions, is included
+// if it's closed, that is, if no item is in every transaction, so that the
+// lattice has a top.
+pub fn closed_itemsets(itemsets: &[ItemSet], num_transactions: u32) -> Vec<ItemSet> {
+    let extensions = closure_extensions(itemsets, num_transactions);
+    let mut closed: Vec<ItemSet> = itemsets
+        .iter()
+        .filter(|itemset| !extensions.contains_key(&itemset.items))
+        .cloned()
+        .collect();
+    if !extensions.contains_key(&vec![]) {
+        closed.push(ItemSet::new(vec![], num_transactions));
+    }
+    closed.sort();
+    closed
+}
+
+// The minimal generators among itemsets, which must be every frequent
+// itemset as mined, each paired with its closure. A generator is an itemset
+// none of whose subsets has the same count, so it's a minimal itemset whose
+// transactions are those of its closure. The empty itemset is the generator
+// of the items in every transaction. Generators with their closures are
+// the basis of the minimal non-redundant rules, generator => closure minus
+// generator.
+pub fn minimal_generators(itemsets: &[ItemSet], num_transactions: u32) -> Vec<(ItemSet, ItemSet)> {
+    let extensions = closure_extensions(itemsets, num_transactions);
+    let closure = |itemset: &ItemSet| -> ItemSet {
+        let mut items = itemset.items.clone();
+        if let Some(extension) = extensions.get(&itemset.items) {
+            items.extend(extension);
+        }
+        ItemSet::new(items, itemset.count)
+    };
+    let counts: FnvHashMap<&[Item], u32> = itemsets
+        .iter()
+        .map(|itemset| (&itemset.items[..], itemset.count))
+        .collect();
+    let empty = ItemSet::new(vec![], num_transactions);
+    let mut generators = vec![(empty.clone(), closure(&empty))];
+    for itemset in itemsets {
+        let is_generator = (0..itemset.len()).all(|i| {
+            let mut subset = itemset.items.clone();
+            subset.remove(i);
+            let count = if subset.is_empty() {
+                Some(num_transactions)
+            } else {
+                counts.get(&subset[..]).cloned()
+            };
+            count != Some(itemset.count)
+        });
+        if is_generator {
+            generators.push((itemset.clone(), closure(itemset)));
+        }
+    }
+    generators.sort();
+    generators
+}
+
+// The Hasse diagram of closed itemsets ordered by inclusion: the concept
+// lattice of formal concept analysis, with each itemset the intent of a
+// concept and its count the size of the concept's extent.
+pub struct Lattice {
+    pub itemsets: Vec<ItemSet>,
+    // Pairs of indices in itemsets of a parent and a child, where the
+    // parent is a subset of the child with no other closed itemset between
+    // them.
+    pub edges: Vec<(usize, usize)>,
+}
+
+impl Lattice {
+    // Builds the lattice of closed, as returned by closed_itemsets. Each
+    // itemset is compared with every shorter one, so this is only practical
+    // for thousands of closed itemsets.
+    pub fn new(closed: Vec<ItemSet>) -> Lattice {
+        let mut itemsets = closed;
+        itemsets.sort();
+        let mut edges = vec![];
+        for (child, itemset) in itemsets.iter().enumerate() {
+            // A closed proper subset has a higher count, as otherwise the
+            // subset wouldn't be closed.
+            let ancestors: Vec<usize> = (0..child)
+                .filter(|&i| {
+                    let other = &itemsets[i];
+                    other.len() < itemset.len()
+                        && other.count > itemset.count
+                        && is_subset(&other.items, &itemset.items)
+                })
+                .collect();
+            for &parent in &ancestors {
+                let covered = ancestors.iter().any(|&other| {
+                    itemsets[other].len() > itemsets[parent].len()
+                        && is_subset(&itemsets[parent].items, &itemsets[other].items)
+                });
+                if !covered {
+                    edges.push((parent, child));
+                }
+            }
+        }
+        Lattice { itemsets, edges }
+    }
+
+    fn label(&self, index: usize, itemizer: &Itemizer) -> String {
+        let itemset = &self.itemsets[index];
+        let names: Vec<&str> = itemset
+            .items
+            .iter()
+            .map(|&item| itemizer.str_of(item))
+            .collect();
+        format!("{{{}}}:{}", names.join(", "), itemset.count)
+    }
+
+    // Renders the lattice in Graphviz DOT format, with edges from each
+    // itemset down to its children. Itemsets are labelled with their items
+    // and count.
+    pub fn to_dot(&self, itemizer: &Itemizer) -> String {
+        let mut dot = String::from("digraph lattice {\n");
+        for index in 0..self.itemsets.len() {
+            writeln!(
+                dot,
+                "    n{} [label=\"{}\"];",
+                index,
+                self.label(index, itemizer)
+                    .replace('\\', "\\\\")
+                    .replace('"', "\\\"")
+            )
+            .unwrap();
+        }
+        for &(parent, child) in &self.edges {
+            writeln!(dot, "    n{} -> n{};", parent, child).unwrap();
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    // Renders the lattice as JSON: a list of nodes with their ids, items,
+    // counts and supports, and a list of edges from parent to child ids.
+    pub fn to_json(&self, itemizer: &Itemizer, num_transactions: u32) -> Value {
+        let nodes: Vec<Value> = self
+            .itemsets
+            .iter()
+            .enumerate()
+            .map(|(id, itemset)| {
+                let items: Vec<&str> = itemset
+                    .items
+                    .iter()
+                    .map(|&item| itemizer.str_of(item))
+                    .collect();
+                json!({
+                    "id": id,
+                    "items": items,
+                    "count": itemset.count,
+                    "support": itemset.count as f64 / num_transactions as f64,
+                })
+            })
+            .collect();
+        let edges: Vec<Value> = self
+            .edges
+            .iter()
+            .map(|&(parent, child)| json!({"parent": parent, "child": child}))
+            .collect();
+        json!({
+            "num_transactions": num_transactions,
+            "nodes": nodes,
+            "edges": edges,
+        })
+    }
+
+    // Renders the lattice as JSON if path ends in .json, and otherwise in
+    // DOT format.
+    pub fn render(&self, path: &str, itemizer: &Itemizer, num_transactions: u32) -> String {
+        if path.ends_with(".json") {
+            let json = self.to_json(itemizer, num_transactions);
+            serde_json::to_string_pretty(&json).unwrap() + "\n"
+        } else {
+            self.to_dot(itemizer)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{closed_itemsets, minimal_generators, Lattice};
+    use fptree::ItemSet;
+    use item::Item;
+    use itemizer::Itemizer;
+    use serde_json::json;
+
+    #[test]
+    fn test_lattice() {
+        // Transactions: abc, ab, ac, b. Every itemset, with its count.
+        let mut itemizer = Itemizer::new();
+        let a = itemizer.id_of("a");
+        let b = itemizer.id_of("b");
+        let c = itemizer.id_of("c");
+        let itemsets = vec![
+            ItemSet::new(vec![a], 3),
+            ItemSet::new(vec![b], 3),
+            ItemSet::new(vec![c], 2),
+            ItemSet::new(vec![a, b], 2),
+            ItemSet::new(vec![a, c], 2),
+            ItemSet::new(vec![b, c], 1),
+            ItemSet::new(vec![a, b, c], 1),
+        ];
+        let closed = closed_itemsets(&itemsets, 4);
+        let items: Vec<Vec<Item>> = closed.iter().map(|i| i.items.clone()).collect();
+        // c is always with a, and bc always with a.
+        assert_eq!(
+            items,
+            vec![
+                vec![],
+                vec![a],
+                vec![b],
+                vec![a, b],
+                vec![a, c],
+                vec![a, b, c]
+            ]
+        );
+
+        let lattice = Lattice::new(closed);
+        let mut edges = lattice.edges.clone();
+        edges.sort();
+        assert_eq!(
+            edges,
+            vec![(0, 1), (0, 2), (1, 3), (1, 4), (2, 3), (3, 5), (4, 5)]
+        );
+
+        let dot = lattice.to_dot(&itemizer);
+        assert!(dot.starts_with("digraph lattice {"));
+        assert!(dot.contains("n0 [label=\"{}:4\"];"));
+        assert!(dot.contains("n4 [label=\"{a, c}:2\"];"));
+        assert!(dot.contains("n4 -> n5;"));
+
+        let json = lattice.to_json(&itemizer, 4);
+        assert_eq!(json["nodes"][3]["items"], json!(["a", "b"]));
+        assert_eq!(json["nodes"][3]["support"], 0.5);
+        assert_eq!(json["edges"].as_array().unwrap().len(), 7);
+
+        // Generators: ab is the closure of itself, ac of c, and abc of bc.
+        let generators: Vec<(Vec<Item>, Vec<Item>)> = minimal_generators(&itemsets, 4)
+            .into_iter()
+            .map(|(generator, closure)| (generator.items, closure.items))
+            .collect();
+        assert_eq!(
+            generators,
+            vec![
+                (vec![], vec![]),
+                (vec![a], vec![a]),
+                (vec![b], vec![b]),
+                (vec![c], vec![a, c]),
+                (vec![a, b], vec![a, b]),
+                (vec![b, c], vec![a, b, c]),
+            ]
+        );
+
+        // An item in every transaction is the top of the lattice.
+        let closed = closed_itemsets(&[ItemSet::new(vec![a], 4)], 4);
+        assert_eq!(closed.len(), 1);
+        assert_eq!(closed[0].items, vec![a]);
+        let generators = minimal_generators(&[ItemSet::new(vec![a], 4)], 4);
+        assert_eq!(generators.len(), 1);
+        assert_eq!(generators[0].1.items, vec![a]);
+    }
+
+    #[test]
+    fn test_minimal_generators() {
+        let generators = |itemsets: &[ItemSet], num_transactions| -> Vec<(Vec<Item>, Vec<Item>)> {
+            minimal_generators(itemsets, num_transactions)
+                .into_iter()
+                .map(|(generator, closure)| (generator.items, closure.items))
+                .collect()
+        };
+        let a = Item::from(1);
+        let b = Item::from(2);
+        let c = Item::from(3);
+        let d = Item::from(4);
+
+        // Transactions: abc, abc, ab, d. a and b always occur together, and
+        // c only with both, so ab, ac, bc and abc each have a proper subset
+        // with the same count, and aren't generators.
+        let itemsets = vec![
+            ItemSet::new(vec![a], 3),
+            ItemSet::new(vec![b], 3),
+            ItemSet::new(vec![c], 2),
+            ItemSet::new(vec![d], 1),
+            ItemSet::new(vec![a, b], 3),
+            ItemSet::new(vec![a, c], 2),
+            ItemSet::new(vec![b, c], 2),
+            ItemSet::new(vec![a, b, c], 2),
+        ];
+        assert_eq!(
+            generators(&itemsets, 4),
+            vec![
+                (vec![], vec![]),
+                (vec![a], vec![a, b]),
+                (vec![b], vec![a, b]),
+                (vec![c], vec![a, b, c]),
+                (vec![d], vec![d]),
+            ]
+        );
+
+        // Transactions: ab, ab, a, a. a is in every transaction, so it's the
+        // closure of the empty itemset, and isn't a generator itself.
+        let itemsets = vec![
+            ItemSet::new(vec![a], 4),
+            ItemSet::new(vec![b], 2),
+            ItemSet::new(vec![a, b], 2),
+        ];
+        assert_eq!(
+            generators(&itemsets, 4),
+            vec![(vec![], vec![a]), (vec![b], vec![a, b])]
+        );
+
+        // With no frequent itemsets, the empty itemset is the only generator.
+        assert_eq!(generators(&[], 5), vec![(vec![], vec![])]);
+    }
+}
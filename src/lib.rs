@@ -0,0 +1,173 @@
+// Copyright 2018 Chris Pearce
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Association rule mining via the FPGrowth algorithm. The command line tool
+// is built on this library; library users mine data they already hold in
+// memory with miner::Miner.
+
+extern crate argparse;
+extern crate flate2;
+extern crate fnv;
+extern crate hashbrown;
+#[cfg(feature = "http")]
+extern crate hmac;
+extern crate itertools;
+#[cfg(feature = "postgres")]
+extern crate postgres;
+extern crate rand;
+#[cfg(feature = "tui")]
+extern crate ratatui;
+extern crate rayon;
+#[cfg(feature = "kafka")]
+extern crate rdkafka;
+extern crate roaring;
+extern crate serde_json;
+extern crate sha2;
+extern crate tracing;
+#[cfg(feature = "flame")]
+extern crate tracing_flame;
+#[cfg(feature = "flame")]
+extern crate tracing_subscriber;
+#[cfg(feature = "http")]
+extern crate ureq;
+extern crate zstd;
+
+mod bench;
+pub mod cancellation;
+mod cluster;
+mod command_line_args;
+mod contrast;
+mod convert;
+pub mod counter;
+mod covering;
+mod diff;
+mod eclat;
+pub mod error;
+mod evaluate;
+#[cfg(feature = "tui")]
+mod explorer;
+mod filter_expression;
+mod flame;
+mod fptree;
+mod generate_rules;
+mod index;
+mod input;
+// Exposes internals to the benchmarks in benches/.
+#[doc(hidden)]
+pub mod internals;
+mod intersect;
+mod item;
+mod item_counter;
+mod item_map;
+mod item_min_support;
+mod item_table;
+pub mod itemizer;
+#[cfg(feature = "kafka")]
+mod kafka;
+mod lattice;
+mod manifest;
+mod memory;
+mod merge;
+mod mine;
+pub mod miner;
+mod mining;
+mod output;
+mod pairs;
+mod permutation;
+#[cfg(feature = "postgres")]
+mod postgres_input;
+mod preview;
+mod privacy;
+mod query;
+#[cfg(feature = "http")]
+mod remote;
+mod report;
+mod revalidate;
+mod roaring_index;
+pub mod rule;
+mod rule_coverage;
+pub mod rule_file;
+pub mod rule_model;
+mod rules_from_itemsets;
+mod score;
+mod segments;
+mod self_check;
+mod sketch;
+mod stats;
+mod support;
+mod synthetic;
+mod table;
+#[cfg(test)]
+mod test_util;
+mod thresholds;
+mod toivonen;
+mod top_down;
+mod transaction_reader;
+pub mod transaction_source;
+mod trends;
+mod tui;
+mod tune;
+mod vec_sets;
+mod verify;
+
+use bench::run_benchmarks;
+use cluster::run_cluster;
+use command_line_args::{parse_args_or_exit, Command};
+use convert::run_convert;
+use diff::run_diff;
+use evaluate::run_evaluate;
+use merge::run_merge;
+use mine::mine_fp_growth;
+use query::{run_query, run_recommend};
+use revalidate::run_revalidate;
+use rules_from_itemsets::run_rules;
+use score::run_score;
+use segments::run_segments;
+use stats::run_stats;
+use std::process;
+use support::run_support;
+use synthetic::run_gen;
+use thresholds::run_thresholds;
+use trends::run_trends;
+use tui::run_tui;
+
+// Runs the command given on the command line, exiting on error.
+pub fn run_command_line() {
+    let result = match parse_args_or_exit() {
+        Command::Mine(arguments) => mine_fp_growth(&arguments),
+        Command::Bench(arguments) => run_benchmarks(&arguments),
+        Command::Thresholds(arguments) => run_thresholds(&arguments),
+        Command::Rules(arguments) => run_rules(&arguments),
+        Command::Stats(arguments) => run_stats(&arguments),
+        Command::Support(arguments) => run_support(&arguments),
+        Command::Revalidate(arguments) => run_revalidate(&arguments),
+        Command::Diff(arguments) => run_diff(&arguments),
+        Command::Merge(arguments) => run_merge(&arguments),
+        Command::Segments(arguments) => run_segments(&arguments),
+        Command::Trends(arguments) => run_trends(&arguments),
+        Command::Query(arguments) => run_query(&arguments),
+        Command::Recommend(arguments) => run_recommend(&arguments),
+        Command::Evaluate(arguments) => run_evaluate(&arguments),
+        Command::Score(arguments) => run_score(&arguments),
+        Command::Cluster(arguments) => run_cluster(&arguments),
+        Command::Tui(arguments) => run_tui(&arguments),
+        Command::Gen(arguments) => run_gen(&arguments),
+        Command::Convert(arguments) => run_convert(&arguments),
+    };
+
+    if let Err(err) = result {
+        eprintln!("Error: {}", err);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,164 @@
+// Copyright 2018 Chris Pearce
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use serde_json::{self, Map, Value};
+use sha2::{Digest, Sha256};
+use std::env;
+use std::fs::{self, File};
+use std::io;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Records how a mining run's output was produced: the command line, the
+// input and its hash, the crate version, result counts and timings. Written
+// beside the output as JSON, so that an output file found later can be
+// traced back to the run which produced it.
+pub struct Manifest {
+    input_path: String,
+    command_line: Vec<String>,
+    // Seconds since the Unix epoch at which the run started.
+    started_at: u64,
+    counts: Map<String, Value>,
+    timings_ms: Map<String, Value>,
+    // Parameters of differentially private mining, if the counts are noisy.
+    privacy: Option<Value>,
+}
+
+impl Manifest {
+    pub fn new(input_path: &str) -> Manifest {
+        Manifest {
+            input_path: String::from(input_path),
+            command_line: env::args().collect(),
+            started_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |time| time.as_secs()),
+            counts: Map::new(),
+            timings_ms: Map::new(),
+            privacy: None,
+        }
+    }
+
+    pub fn add_count(&mut self, name: &str, count: usize) {
+        self.counts.insert(String::from(name), Value::from(count));
+    }
+
+    pub fn add_timing(&mut self, stage: &str, ms: u64) {
+        self.timings_ms.insert(String::from(stage), Value::from(ms));
+    }
+
+    pub fn set_privacy(&mut self, privacy: Value) {
+        self.privacy = Some(privacy);
+    }
+
+    fn to_json(&self, input_sha256: Option<String>) -> Value {
+        let mut manifest = Map::new();
+        manifest.insert(
+            String::from("version"),
+            Value::from(env!("CARGO_PKG_VERSION")),
+        );
+        manifest.insert(
+            String::from("command_line"),
+            Value::from(self.command_line.clone()),
+        );
+        manifest.insert(String::from("started_at"), Value::from(self.started_at));
+        manifest.insert(String::from("input"), Value::from(self.input_path.clone()));
+        manifest.insert(String::from("input_sha256"), Value::from(input_sha256));
+        manifest.insert(String::from("counts"), Value::Object(self.counts.clone()));
+        manifest.insert(
+            String::from("timings_ms"),
+            Value::Object(self.timings_ms.clone()),
+        );
+        if let Some(ref privacy) = self.privacy {
+            manifest.insert(String::from("privacy"), privacy.clone());
+        }
+        Value::Object(manifest)
+    }
+
+    // Writes the manifest beside output_path; see manifest_path. The input
+    // is hashed if it's a file; other inputs, such as stdin, have no hash.
+    // Returns the manifest's path.
+    pub fn write_beside(&self, output_path: &str) -> io::Result<String> {
+        let input_sha256 = if Path::new(&self.input_path).is_file() {
+            Some(sha256_of_file(&self.input_path)?)
+        } else {
+            None
+        };
+        let path = manifest_path(output_path);
+        let json = serde_json::to_string_pretty(&self.to_json(input_sha256))?;
+        fs::write(&path, json + "\n")?;
+        Ok(path)
+    }
+}
+
+// The path of the manifest of an output file: the output's path without its
+// extension and any compression extension, plus .meta.json. So the manifest
+// of rules.csv or rules.csv.gz is rules.meta.json.
+pub fn manifest_path(output_path: &str) -> String {
+    let mut path = Path::new(output_path).to_path_buf();
+    if path
+        .extension()
+        .is_some_and(|extension| extension == "gz" || extension == "zst")
+    {
+        path.set_extension("");
+    }
+    path.set_extension("meta.json");
+    path.to_string_lossy().into_owned()
+}
+
+fn sha256_of_file(path: &str) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{manifest_path, Manifest};
+    use serde_json::{json, Value};
+    use std::fs;
+    use test_util::TempFile;
+
+    #[test]
+    fn test_manifest() {
+        assert_eq!(manifest_path("out/rules.csv"), "out/rules.meta.json");
+        assert_eq!(manifest_path("rules.csv.gz"), "rules.meta.json");
+        assert_eq!(manifest_path("rules"), "rules.meta.json");
+
+        let input = TempFile::new("manifest-input.csv");
+        fs::write(&input, "abc").unwrap();
+        let mut manifest = Manifest::new(input.path());
+        manifest.add_count("num_rules", 3);
+        manifest.add_timing("mining", 12);
+        manifest.set_privacy(json!({"epsilon": 0.5}));
+        let output = TempFile::new("manifest-rules.csv");
+        let expected = TempFile::new("manifest-rules.meta.json");
+        let path = manifest.write_beside(output.path()).unwrap();
+        assert_eq!(path, expected.path());
+
+        let json: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(
+            json["input_sha256"],
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(json["version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(json["counts"]["num_rules"], 3);
+        assert_eq!(json["timings_ms"]["mining"], 12);
+        assert!(json["command_line"].is_array());
+        assert_eq!(json["privacy"]["epsilon"], 0.5);
+    }
+}
@@ -0,0 +1,135 @@
+// Copyright 2018 Chris Pearce
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use fptree::ItemSet;
+use rule::Rule;
+use std::fmt;
+use std::fs;
+use std::mem::size_of;
+
+// Returns the peak resident set size of this process in bytes, if the
+// platform exposes it. Only Linux's /proc/self/status is supported.
+pub fn peak_rss_bytes() -> Option<u64> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmHWM:"))?;
+    // Format is "VmHWM:     1234 kB".
+    let kb: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kb * 1024)
+}
+
+// Resets the peak resident set size, so that subsequent calls to
+// peak_rss_bytes() report the peak since this call. Silently does
+// nothing on platforms which don't support it.
+pub fn reset_peak_rss() {
+    let _ = fs::write("/proc/self/clear_refs", "5");
+}
+
+// Bytes allocated by a vector, whether or not they're in use.
+pub fn vec_bytes<T>(v: &Vec<T>) -> usize {
+    v.capacity() * size_of::<T>()
+}
+
+// Estimated bytes used by itemsets, including their item vectors.
+pub fn itemsets_bytes(itemsets: &Vec<ItemSet>) -> usize {
+    vec_bytes(itemsets)
+        + itemsets
+            .iter()
+            .map(|itemset| vec_bytes(&itemset.items))
+            .sum::<usize>()
+}
+
+// Estimated bytes used by groups of rules, including their item vectors.
+pub fn rules_bytes(rules: &Vec<Vec<Rule>>) -> usize {
+    vec_bytes(rules)
+        + rules
+            .iter()
+            .map(|rules| {
+                vec_bytes(rules)
+                    + rules
+                        .iter()
+                        .map(|rule| vec_bytes(&rule.antecedent) + vec_bytes(&rule.consequent))
+                        .sum::<usize>()
+            })
+            .sum::<usize>()
+}
+
+// The peak resident set size of a mining run, and the estimated sizes of
+// its largest data structures. Structures which weren't built, or weren't
+// held in memory, are None.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MemoryUsage {
+    pub peak_rss: Option<u64>,
+    pub itemizer: usize,
+    pub fptree: Option<usize>,
+    pub itemsets: usize,
+    pub rules: Option<usize>,
+}
+
+pub fn megabytes(bytes: u64) -> String {
+    format!("{:.1} MB", bytes as f64 / 1_000_000.0)
+}
+
+impl MemoryUsage {
+    // The sizes as (name, bytes) pairs, for reporting.
+    pub fn sizes(&self) -> Vec<(&'static str, Option<u64>)> {
+        vec![
+            ("Peak resident set size", self.peak_rss),
+            ("Item dictionary", Some(self.itemizer as u64)),
+            ("FP-tree", self.fptree.map(|bytes| bytes as u64)),
+            ("Itemsets", Some(self.itemsets as u64)),
+            ("Rules", self.rules.map(|bytes| bytes as u64)),
+        ]
+    }
+}
+
+impl fmt::Display for MemoryUsage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let sizes: Vec<String> = self
+            .sizes()
+            .into_iter()
+            .filter_map(|(name, bytes)| bytes.map(|bytes| format!("{} {}", name, megabytes(bytes))))
+            .collect();
+        write!(f, "{}", sizes.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{itemsets_bytes, MemoryUsage};
+    use fptree::ItemSet;
+    use item::Item;
+    use std::mem::size_of;
+
+    #[test]
+    fn test_memory_usage() {
+        let mut itemsets = Vec::with_capacity(2);
+        itemsets.push(ItemSet::new(vec![Item::with_id(1), Item::with_id(2)], 1));
+        assert_eq!(
+            itemsets_bytes(&itemsets),
+            2 * size_of::<ItemSet>() + itemsets[0].items.capacity() * size_of::<Item>()
+        );
+
+        let usage = MemoryUsage {
+            peak_rss: None,
+            itemizer: 2_500_000,
+            fptree: Some(1_000),
+            itemsets: 0,
+            rules: None,
+        };
+        assert_eq!(
+            usage.to_string(),
+            "Item dictionary 2.5 MB, FP-tree 0.0 MB, Itemsets 0.0 MB"
+        );
+    }
+}
@@ -0,0 +1,199 @@
+// Copyright 2018 Chris Pearce
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use cancellation::CancellationToken;
+use command_line_args::MergeArguments;
+use error::ArmError;
+use fnv::FnvHashSet;
+use fptree::ItemSet;
+use generate_rules::RuleParameters;
+use item::Item;
+use itemizer::Itemizer;
+use mining::{duration_as_ms, min_count};
+use output::{stream_rules, write_itemsets, OutputOptions};
+use rayon::prelude::*;
+use rule::DEFAULT_LAPLACE_K;
+use std::time::Instant;
+use support::{index_dataset, read_candidates};
+
+// Keeps the itemsets which would have been found by mining the whole
+// dataset. As when mining, itemsets must occur at least min_count times, and
+// contain only frequent items.
+fn frequent_itemsets(itemsets: Vec<ItemSet>, min_count: u32) -> Vec<ItemSet> {
+    let frequent_items: FnvHashSet<Item> = itemsets
+        .iter()
+        .filter(|itemset| itemset.len() == 1 && itemset.count >= min_count)
+        .map(|itemset| itemset.items[0])
+        .collect();
+    itemsets
+        .into_iter()
+        .filter(|itemset| {
+            itemset.count >= min_count
+                && itemset
+                    .items
+                    .iter()
+                    .all(|item| frequent_items.contains(item))
+        })
+        .collect()
+}
+
+// Merges the itemsets mined from partitions of a dataset. Any itemset
+// frequent in the whole dataset is frequent in at least one partition, so
+// the union of the partitions' itemsets contains every frequent itemset;
+// recounting them over the whole dataset finds which are globally frequent.
+pub fn run_merge(args: &MergeArguments) -> Result<(), ArmError> {
+    let start = Instant::now();
+    let mut candidates: Vec<Vec<String>> = vec![];
+    for path in &args.itemsets_paths {
+        candidates.extend(read_candidates(path)?);
+    }
+
+    // Assign ids in lexicographic order, so itemsets are written sorted.
+    let mut itemizer = Itemizer::new();
+    let mut names: Vec<&String> = candidates.iter().flat_map(|items| items.iter()).collect();
+    names.sort();
+    names.dedup();
+    for &name in &names {
+        itemizer.id_of(name);
+    }
+    let candidate_items: FnvHashSet<String> = names.into_iter().cloned().collect();
+    let mut candidates: Vec<Vec<Item>> = candidates
+        .iter()
+        .map(|items| {
+            let mut items: Vec<Item> = items.iter().map(|name| itemizer.id_of(name)).collect();
+            items.sort();
+            items.dedup();
+            items
+        })
+        .collect();
+    candidates.sort();
+    candidates.dedup();
+    println!(
+        "Read {} distinct candidate itemsets from {} files.",
+        candidates.len(),
+        args.itemsets_paths.len()
+    );
+
+    let timer = Instant::now();
+    let index = index_dataset(
+        &args.input_file_path,
+        args.weighted,
+        args.input_format,
+        args.encoding,
+        args.index_backend,
+        &candidate_items,
+        &mut itemizer,
+    )?;
+    let num_transactions = index.transaction_count();
+    let itemsets: Vec<ItemSet> = candidates
+        .into_par_iter()
+        .map(|items| {
+            let count = index.count(&items) as u32;
+            ItemSet::new(items, count)
+        })
+        .collect();
+    let mut itemsets = frequent_itemsets(itemsets, min_count(args.min_support, num_transactions));
+    itemsets.sort();
+    println!(
+        "Counted candidates over {} transactions in {} ms; {} are frequent.",
+        num_transactions,
+        duration_as_ms(&timer.elapsed()),
+        itemsets.len()
+    );
+
+    if let Some(ref path) = args.output_itemsets_path {
+        write_itemsets(
+            &itemsets,
+            num_transactions,
+            path,
+            &OutputOptions::default(),
+            &itemizer,
+        )
+        .map_err(|e| ArmError::io(path, e))?;
+    }
+
+    let timer = Instant::now();
+    let params = RuleParameters {
+        min_confidence: args.min_confidence,
+        min_confidence_by_length: None,
+        min_lift: args.min_lift,
+        max_lift: args.max_lift,
+        min_confidence_lower_bound: None,
+        laplace_k: DEFAULT_LAPLACE_K,
+        min_laplace: None,
+        min_added_value: None,
+        min_gini_gain: None,
+        min_info_gain: None,
+        min_odds_ratio: None,
+        min_yules_q: None,
+        min_phi: None,
+        filter: None,
+        min_itemset_size: 1,
+        cancellation: CancellationToken::new(),
+        item_min_counts: None,
+        target_consequent: None,
+    };
+    let num_rules: usize = stream_rules(
+        &itemsets,
+        num_transactions as u32,
+        &params,
+        true,
+        &args.output_rules_path,
+        &OutputOptions::default(),
+        &itemizer,
+    )
+    .map_err(|e| ArmError::io(&args.output_rules_path, e))?
+    .iter()
+    .sum();
+    println!(
+        "Generated and wrote {} rules in {} ms.",
+        num_rules,
+        duration_as_ms(&timer.elapsed())
+    );
+    println!("Total runtime: {} ms", duration_as_ms(&start.elapsed()));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::frequent_itemsets;
+    use fptree::ItemSet;
+    use item::Item;
+
+    #[test]
+    fn test_frequent_itemsets() {
+        let itemset = |items: &[u32], count: u32| {
+            ItemSet::new(items.iter().map(|&i| Item::from(i)).collect(), count)
+        };
+        let itemsets = vec![
+            itemset(&[1], 5),
+            itemset(&[2], 3),
+            itemset(&[3], 4),
+            itemset(&[1, 2], 3),
+            itemset(&[1, 3], 3),
+            itemset(&[1, 3, 2], 3),
+            itemset(&[4], 2),
+            itemset(&[1, 4], 2),
+        ];
+        // Item 2's count is exactly the minimum count, so it's frequent.
+        assert_eq!(
+            frequent_itemsets(itemsets.clone(), 3),
+            itemsets[..6].to_vec()
+        );
+        assert_eq!(
+            frequent_itemsets(itemsets, 4),
+            vec![itemset(&[1], 5), itemset(&[3], 4)]
+        );
+    }
+}
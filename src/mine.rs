@@ -0,0 +1,1436 @@
+// Copyright 2018 Chris Pearce
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use cancellation::CancellationToken;
+use command_line_args::Arguments;
+use contrast::{emerging_patterns, index_baseline, write_contrasts};
+use counter::Counter;
+use covering::select_covering;
+use eclat::{eclat, VerticalDatabase};
+use error::ArmError;
+use filter_expression::FilterExpression;
+use flame::write_flame_stacks;
+use fnv::FnvHashSet;
+use fptree::{
+    fp_growth_grouped_with_progress, fp_growth_targeted, fp_growth_with_progress,
+    CrossSupportPruning, FPGrowthParameters, FPGrowthProgress, FPTree, ItemSet,
+};
+use generate_rules::{generate_rules, top_rules_per_consequent, RuleGenerator, RuleParameters};
+use index::TransactionIndex;
+use input::is_stream;
+use item::Item;
+use item_counter::ItemCounter;
+use item_map::ItemMap;
+use item_min_support::{count_missing_subsets, item_order, ItemMinSupports};
+use itemizer::{Itemizer, UnseenItems};
+use lattice::{closed_itemsets, minimal_generators, Lattice};
+use manifest::Manifest;
+use memory::{itemsets_bytes, peak_rss_bytes, rules_bytes, MemoryUsage};
+use mining::{
+    build_initial_fptree, count_by_length, count_item_frequencies, duration_as_ms,
+    format_length_counts, lexicographic_order, min_count, read_transactions, remap_items,
+    Algorithm, ItemOrder, TreeBuildOptions,
+};
+use output::{
+    stream_rules, write_generators, write_itemsets, write_rules, NumberFormat, OutputOptions,
+};
+use pairs::count_pairs;
+use permutation::permutation_test;
+use preview::{format_preview, top_rules_by_lift};
+use privacy::{add_noise, PrivacyParameters};
+use rand::prelude::*;
+use rayon::prelude::*;
+use report::{write_html_report, ReportSummary};
+use rule_coverage::{rule_coverage, write_rule_coverage};
+use self_check::{check_itemsets, check_rules, naive_itemsets, naive_rules};
+use sketch::{CountMinSketch, DEFAULT_SKETCH_DEPTH, DEFAULT_SKETCH_WIDTH};
+use std::fs;
+use std::mem;
+use std::time::{Duration, Instant};
+use table::{fit_bins, MissingCounts, TableOptions};
+use toivonen::{toivonen, DEFAULT_SAMPLE_SIZE};
+use top_down::top_down_fp_growth;
+use tracing::info_span;
+use transaction_reader::TransactionReader;
+use tune::tune_min_count;
+use verify::{verify_itemsets, verify_rules};
+
+type Transactions<'a> = Box<dyn Iterator<Item = Result<(Vec<Item>, u32), ArmError>> + 'a>;
+
+// The parameters of rule generation given on the command line. If there's a
+// permutation test, the filter is applied after it rather than here.
+fn rule_parameters(
+    args: &Arguments,
+    filter: Option<FilterExpression>,
+    item_min_counts: Option<ItemCounter>,
+    target_consequent: Option<Vec<Item>>,
+) -> RuleParameters {
+    RuleParameters {
+        min_confidence: args.min_confidence,
+        min_confidence_by_length: args.min_confidence_by_length.clone(),
+        min_lift: args.min_lift,
+        max_lift: args.max_lift,
+        min_confidence_lower_bound: args.min_confidence_lower_bound,
+        laplace_k: args.laplace_k,
+        min_laplace: args.min_laplace,
+        min_added_value: args.min_added_value,
+        min_gini_gain: args.min_gini_gain,
+        min_info_gain: args.min_info_gain,
+        min_odds_ratio: args.min_odds_ratio,
+        min_yules_q: args.min_yules_q,
+        min_phi: args.min_phi,
+        filter: filter.filter(|_| args.permutations.is_none()),
+        min_itemset_size: args.min_itemset_size,
+        cancellation: CancellationToken::new(),
+        item_min_counts,
+        target_consequent,
+    }
+}
+
+// Fails if more than --max-rules rules were generated, unless the rules
+// generated so far are wanted.
+fn check_max_rules(args: &Arguments, rule_params: &RuleParameters) -> Result<(), ArmError> {
+    let max_rules = match args.max_rules {
+        Some(max_rules) if rule_params.cancellation.is_count_exceeded() => max_rules,
+        _ => return Ok(()),
+    };
+    let message = format!(
+        "More than {} rules were generated (--max-rules), so the minimum support or \
+         confidence is too low.",
+        max_rules
+    );
+    if !args.write_partial {
+        return Err(ArmError::limit_exceeded(&format!(
+            "{} Raise them, or pass --write-partial to keep the rules generated so far.",
+            message
+        )));
+    }
+    println!("{} Keeping the rules generated so far.", message);
+    Ok(())
+}
+
+// The data set after the first pass over it: its items itemized and
+// counted, and its transactions, if they're held in memory.
+struct Dataset {
+    itemizer: Itemizer,
+    item_map: Option<ItemMap>,
+    table_options: TableOptions,
+    // With approximate counts, the sketch of item frequencies, and the
+    // count an item's estimate must reach for the item to be counted.
+    sketch: Option<(CountMinSketch, u32)>,
+    item_count: ItemCounter,
+    num_transactions: usize,
+    transactions: Option<Vec<(Vec<Item>, u32)>>,
+    missing_counts: MissingCounts,
+}
+
+// The minimum counts and targets of mining, from the command line and the
+// item frequencies.
+struct Thresholds {
+    // The minimum count of the itemsets mined; with differential privacy,
+    // this is the candidates' count, below the count they're released at.
+    min_count: u32,
+    release_min_count: u32,
+    item_min_counts: Option<ItemCounter>,
+    // The order in which per-item minimum supports require items to be
+    // inserted into the tree.
+    item_order: Option<ItemCounter>,
+    privacy: Option<PrivacyParameters>,
+    target_consequent: Option<Vec<Item>>,
+}
+
+// The itemsets found by mining, and the index of the transactions, if one
+// was built while reading them.
+struct MinedItemsets {
+    // The frequent itemsets, followed, once prepared for rule generation,
+    // by the infrequent subsets whose counts rules need.
+    itemsets: Vec<ItemSet>,
+    num_frequent: usize,
+    // Counts of subsets of frequent itemsets which aren't frequent
+    // themselves, which are needed to generate rules under per-item minimum
+    // supports.
+    infrequent_subsets: Vec<ItemSet>,
+    incomplete_items: Vec<Item>,
+    min_count: u32,
+    index: Option<Box<dyn TransactionIndex>>,
+}
+
+impl MinedItemsets {
+    fn frequent(&self) -> &[ItemSet] {
+        &self.itemsets[..self.num_frequent]
+    }
+
+    // Rule generation looks up the counts of each itemset's subsets, which
+    // may be missing if they contain items which weren't fully mined, so
+    // those itemsets are dropped. Infrequent subsets are appended after the
+    // frequent itemsets, so that their counts can be looked up during rule
+    // generation. Rule generation skips them, as they're below their items'
+    // minimum supports.
+    fn prepare_for_rules(&mut self) {
+        let mut infrequent_subsets = mem::take(&mut self.infrequent_subsets);
+        if !self.incomplete_items.is_empty() {
+            let incomplete: FnvHashSet<Item> = self.incomplete_items.iter().cloned().collect();
+            let is_complete =
+                |itemset: &ItemSet| !itemset.items.iter().any(|item| incomplete.contains(item));
+            self.itemsets.retain(is_complete);
+            infrequent_subsets.retain(is_complete);
+        }
+        self.num_frequent = self.itemsets.len();
+        self.itemsets.extend(infrequent_subsets);
+    }
+}
+
+// What's recorded about a run of the miner, for the manifest and the memory
+// summary.
+struct Run {
+    start: Instant,
+    manifest: Manifest,
+    memory: MemoryUsage,
+}
+
+impl Run {
+    // Prints the memory used and the total runtime, and writes the manifest
+    // beside the output at path, if any.
+    fn finish(mut self, manifest_beside: Option<&str>) -> Result<(), ArmError> {
+        self.memory.peak_rss = peak_rss_bytes();
+        println!("Memory: {}.", self.memory);
+        if let Some(path) = manifest_beside {
+            if let Some(peak_rss) = self.memory.peak_rss {
+                self.manifest.add_count("peak_rss_bytes", peak_rss as usize);
+            }
+            self.manifest
+                .add_timing("total", duration_as_ms(&self.start.elapsed()));
+            let manifest_path = self
+                .manifest
+                .write_beside(path)
+                .map_err(|e| ArmError::io(path, e))?;
+            println!("Wrote manifest to {}.", manifest_path);
+        }
+        println!(
+            "Total runtime: {} ms",
+            duration_as_ms(&self.start.elapsed())
+        );
+        Ok(())
+    }
+}
+
+fn output_options(args: &Arguments) -> OutputOptions {
+    OutputOptions {
+        compression: args.compress_output,
+        number_format: NumberFormat {
+            notation: args.number_format,
+            precision: args.precision,
+        },
+    }
+}
+
+// Opens the data set for a pass over it, read as the command line says.
+fn open_dataset<'a>(
+    args: &'a Arguments,
+    itemizer: &'a mut Itemizer,
+    item_map: Option<&'a ItemMap>,
+    table_options: &TableOptions,
+) -> Result<TransactionReader<'a>, ArmError> {
+    Ok(
+        TransactionReader::open(&args.input_file_path, args.query.as_deref(), itemizer)?
+            .with_item_map(item_map)
+            .with_weights(args.weighted)
+            .with_format(args.input_format)
+            .with_table_options(table_options)
+            .with_encoding(args.encoding)
+            .with_max_length(args.dp_max_length),
+    )
+}
+
+// With approximate counts, only items whose estimated counts reach the
+// minimum count are itemized and counted.
+fn may_be_frequent(sketch: &Option<(CountMinSketch, u32)>, item: &str) -> bool {
+    match *sketch {
+        Some((ref sketch, min_count)) => sketch.estimate(item) >= min_count,
+        None => true,
+    }
+}
+
+// Makes one pass of the data set to calculate the item frequencies for the
+// initial tree, holding the transactions in memory if they're to be read
+// again from there.
+fn first_pass(args: &Arguments, run: &mut Run) -> Result<Dataset, ArmError> {
+    let item_map = match args.item_map_path {
+        Some(ref path) => Some(ItemMap::load(path)?),
+        None => None,
+    };
+    // Bins which depend on the input's values are fitted to it, and the
+    // same bins are used for the baseline data set.
+    let table_options = if args.table_options.needs_fitting() {
+        println!("Fitting bins of table columns...");
+        fit_bins(&args.input_file_path, args.encoding, &args.table_options)?
+    } else {
+        args.table_options.clone()
+    };
+    println!("Making first pass of dataset to count item frequencies...");
+    let timer = Instant::now();
+    let first_pass_span = info_span!("first_pass").entered();
+    let mut itemizer: Itemizer = match args.dictionary_path {
+        Some(ref path) => Itemizer::load_dictionary(path)?,
+        None => Itemizer::new(),
+    };
+    itemizer.set_unseen_items(match args.dictionary_path {
+        Some(_) => args.unseen_items,
+        None => UnseenItems::Append,
+    });
+    // Streamed input, such as stdin, must be held in memory to be read
+    // twice. The self-check needs the transactions again after mining.
+    let in_memory = args.in_memory || args.self_check || is_stream(&args.input_file_path);
+    // Counted on the pass which itemizes the transactions.
+    let mut missing_counts = MissingCounts::default();
+    let mut transactions = if in_memory {
+        let mut reader = open_dataset(args, &mut itemizer, item_map.as_ref(), &table_options)?;
+        let transactions = read_transactions(&mut reader)?;
+        missing_counts = reader.missing_counts();
+        Some(transactions)
+    } else {
+        None
+    };
+    // With approximate counts, item frequencies are first estimated with a
+    // sketch, and only items which may be frequent are itemized and counted.
+    let sketch = if args.approximate_counts {
+        let mut sketch = CountMinSketch::new(DEFAULT_SKETCH_WIDTH, DEFAULT_SKETCH_DEPTH);
+        let num_transactions =
+            open_dataset(args, &mut itemizer, item_map.as_ref(), &table_options)?
+                .sketch_items(&mut sketch)?;
+        println!(
+            "Sketched item frequencies in {} ms.",
+            duration_as_ms(&timer.elapsed())
+        );
+        Some((sketch, min_count(args.min_support, num_transactions)))
+    } else {
+        None
+    };
+    let (mut item_count, num_transactions) = match transactions {
+        Some(ref transactions) => {
+            count_item_frequencies(transactions.iter().map(|&(ref t, count)| Ok((t, count))))?
+        }
+        None => {
+            let is_candidate = |item: &str| may_be_frequent(&sketch, item);
+            let item_filter: Option<&dyn Fn(&str) -> bool> = match sketch {
+                Some(_) => Some(&is_candidate),
+                None => None,
+            };
+            let mut reader = open_dataset(args, &mut itemizer, item_map.as_ref(), &table_options)?
+                .with_item_filter(item_filter);
+            let counts = count_item_frequencies(reader.by_ref())?;
+            missing_counts = reader.missing_counts();
+            counts
+        }
+    };
+    println!(
+        "First pass took {} ms, num_transactions={}.",
+        duration_as_ms(&timer.elapsed()),
+        num_transactions
+    );
+    drop(first_pass_span);
+    run.manifest
+        .add_timing("first_pass", duration_as_ms(&timer.elapsed()));
+    run.manifest.add_count("num_transactions", num_transactions);
+    if missing_counts.rows_with_missing > 0 {
+        println!(
+            "{} rows had missing values; {} of them were dropped.",
+            missing_counts.rows_with_missing, missing_counts.dropped_rows
+        );
+    }
+
+    // We work with items as integers; we convert from strings to int
+    // in the itemizer. We store itemsets as a sorted list of items.
+    // When we output the rules, we want the items to be
+    // lexicographically sorted for human readability. So re-order the
+    // itemizer's string-to-int mapping, so when the itemset is sorted
+    // numerically, it's also sorted lexicographically. This saves
+    // a lot of time when outputting rules at the end, as we don't need
+    // to sort them before writing them; since all itemsets are sorted
+    // numerically, they're automatically sorted lexicographically!
+    // Items in a dictionary keep their ids.
+    if args.dictionary_path.is_none() {
+        println!("Reordering itemizer lexicographically...");
+        let timer = Instant::now();
+        let new_ids = itemizer.reorder_sorted(&mut item_count);
+        if let Some(ref mut transactions) = transactions {
+            remap_items(transactions, &new_ids);
+        }
+        println!(
+            "Reordered itemizer in {} ms.",
+            duration_as_ms(&timer.elapsed())
+        );
+    }
+    if let Some(ref path) = args.output_dictionary_path {
+        itemizer
+            .write_dictionary(path)
+            .map_err(|e| ArmError::io(path, e))?;
+        println!("Wrote item dictionary to {}.", path);
+    }
+    Ok(Dataset {
+        itemizer,
+        item_map,
+        table_options,
+        sketch,
+        item_count,
+        num_transactions,
+        transactions,
+        missing_counts,
+    })
+}
+
+fn thresholds(args: &Arguments, dataset: &Dataset, run: &mut Run) -> Result<Thresholds, ArmError> {
+    let num_transactions = dataset.num_transactions;
+    // Itemsets counted fewer than --suppress-below-count times are never
+    // frequent, whatever the minimum support. Rules are generated from
+    // frequent itemsets, so they're withheld too.
+    let suppress_below_count = args.suppress_below_count.unwrap_or(0);
+    let item_min_supports = match args.item_min_support_path {
+        Some(ref path) => Some(ItemMinSupports::load(path)?),
+        None => None,
+    };
+    let item_min_counts = item_min_supports.map(|supports| {
+        let mut min_counts = supports.min_counts(
+            &dataset.item_count,
+            &dataset.itemizer,
+            args.min_support,
+            num_transactions,
+        );
+        for item in min_counts.items_with_count_at_least(1) {
+            let count = min_counts.get(&item).max(suppress_below_count);
+            min_counts.set(&item, count);
+        }
+        min_counts
+    });
+    // With per-item minimum supports, the tree must hold every item which
+    // meets the lowest of them.
+    let min_count = match item_min_counts {
+        Some(ref item_min_counts) => item_min_counts
+            .items_with_count_at_least(1)
+            .iter()
+            .map(|item| item_min_counts.get(item))
+            .min()
+            .unwrap_or_else(|| min_count(args.min_support, num_transactions)),
+        None => min_count(args.min_support, num_transactions),
+    }
+    .max(suppress_below_count);
+    if args.suppress_below_count.is_some() {
+        println!(
+            "Suppressing itemsets and rules with counts below {}; minimum count is {}.",
+            suppress_below_count, min_count
+        );
+    }
+    // With differential privacy, candidates are mined at a lower count, and
+    // itemsets are released if their noisy counts reach the minimum count.
+    let privacy = args.dp_epsilon.map(|epsilon| {
+        PrivacyParameters::new(
+            epsilon,
+            args.dp_max_length.unwrap_or(usize::MAX),
+            args.max_itemset_size,
+        )
+    });
+    let release_min_count = min_count;
+    let min_count = match privacy {
+        Some(ref privacy) => {
+            let candidate_min_count = privacy.candidate_min_count(min_count);
+            println!(
+                "Mining candidates with min count {} for differential privacy, epsilon {} \
+                 ({} selecting itemsets, {} for counts), sensitivity {}.",
+                candidate_min_count,
+                privacy.epsilon,
+                privacy.selection_epsilon(),
+                privacy.count_epsilon(),
+                privacy.sensitivity
+            );
+            run.manifest.set_privacy(privacy.to_json());
+            candidate_min_count
+        }
+        None => min_count,
+    };
+    let item_order = item_min_counts
+        .as_ref()
+        .map(|item_min_counts| item_order(item_min_counts, &dataset.item_count));
+    // Items not in the data set are the null item, which is in no
+    // transactions, so no rules are generated for the target.
+    let target_consequent: Option<Vec<Item>> = args.target_consequent.as_ref().map(|names| {
+        let mut items: Vec<Item> = names
+            .split(',')
+            .map(|name| {
+                dataset
+                    .itemizer
+                    .get_id(name.trim())
+                    .unwrap_or_else(Item::null)
+            })
+            .collect();
+        items.sort();
+        items.dedup();
+        items
+    });
+    Ok(Thresholds {
+        min_count,
+        release_min_count,
+        item_min_counts,
+        item_order,
+        privacy,
+        target_consequent,
+    })
+}
+
+// Mines the initial FPTree, after tuning the minimum support if asked to.
+// Returns the itemsets found, with the infrequent subsets needed to generate
+// rules under per-item minimum supports, the algorithm's name, and how long
+// mining took.
+fn mine_fptree(
+    args: &Arguments,
+    fptree: &FPTree,
+    tree_order: &ItemCounter,
+    params: &mut FPGrowthParameters,
+    dataset: &Dataset,
+    thresholds: &Thresholds,
+    run: &mut Run,
+) -> Result<(FPGrowthProgress, Vec<ItemSet>, &'static str, u64), ArmError> {
+    let num_transactions = dataset.num_transactions;
+    // The tree holds every item with the lowest minimum support, so
+    // higher minimum supports are searched by mining it again.
+    if let Some(target_rules) = args.target_rules {
+        println!(
+            "Tuning minimum support to generate about {} rules...",
+            target_rules
+        );
+        let timer = Instant::now();
+        let filter = args.filter.clone().map(|mut filter| {
+            filter.resolve(&dataset.itemizer);
+            filter
+        });
+        let rule_params = rule_parameters(args, filter, None, None);
+        let (tuned_min_count, num_rules) = info_span!("tuning").in_scope(|| {
+            tune_min_count(
+                fptree,
+                params,
+                &rule_params,
+                num_transactions as u32,
+                target_rules,
+            )
+        });
+        params.min_count = tuned_min_count;
+        println!(
+            "Tuned minimum support to {} (count {}), giving {} rules, in {} ms.",
+            tuned_min_count as f64 / num_transactions as f64,
+            tuned_min_count,
+            num_rules,
+            duration_as_ms(&timer.elapsed())
+        );
+        run.manifest
+            .add_timing("tuning", duration_as_ms(&timer.elapsed()));
+    }
+
+    let top_down = args.algorithm == Algorithm::TopDownFPGrowth;
+    if top_down {
+        println!("Starting top-down FPGrowth...");
+    } else {
+        println!("Starting recursive FPGrowth...");
+    }
+    let timer = Instant::now();
+    if let Some(timeout) = args.timeout {
+        params
+            .cancellation
+            .cancel_after(Duration::from_secs(timeout));
+    }
+    let mining_span = info_span!("mining").entered();
+    let progress = match (&thresholds.target_consequent, args.pfp_groups) {
+        (Some(target), _) => fp_growth_targeted(fptree, params, target, tree_order),
+        (None, Some(num_groups)) => {
+            fp_growth_grouped_with_progress(fptree, params, num_transactions as u32, num_groups)
+        }
+        (None, None) if top_down => top_down_fp_growth(fptree, params),
+        (None, None) => fp_growth_with_progress(fptree, params, num_transactions as u32),
+    };
+    drop(mining_span);
+    let mining_ms = duration_as_ms(&timer.elapsed());
+    // Infrequent subsets are only needed to generate rules.
+    let mut infrequent_subsets = vec![];
+    if let Some(item_order) = thresholds.item_order.as_ref().filter(|_| !args.no_rules) {
+        let timer = Instant::now();
+        infrequent_subsets = info_span!("count_missing_subsets")
+            .in_scope(|| count_missing_subsets(fptree, &progress.itemsets, item_order))?;
+        println!(
+            "Counted {} infrequent subsets of frequent itemsets in {} ms.",
+            infrequent_subsets.len(),
+            duration_as_ms(&timer.elapsed())
+        );
+    }
+    let algorithm_name = if top_down {
+        "Top-down FPGrowth"
+    } else {
+        "FPGrowth"
+    };
+    Ok((progress, infrequent_subsets, algorithm_name, mining_ms))
+}
+
+// Re-reads the data set (unless it's held in memory), to load it into the
+// initial tree or the vertical database, and mines the frequent itemsets.
+fn mine_itemsets(
+    args: &Arguments,
+    dataset: &mut Dataset,
+    thresholds: &Thresholds,
+    run: &mut Run,
+) -> Result<MinedItemsets, ArmError> {
+    let num_transactions = dataset.num_transactions;
+    // Toivonen's algorithm counts its candidates in the index.
+    let mut index = if args.verify
+        || args.select_covering.is_some()
+        || args.coverage_report_path.is_some()
+        || args.algorithm == Algorithm::Toivonen
+    {
+        Some(args.index_backend.new_index())
+    } else {
+        None
+    };
+    let index_ref = index
+        .as_mut()
+        .map(|index| index.as_mut() as &mut dyn TransactionIndex);
+    // Computed before the reader below borrows the itemizer.
+    let lexicographic = (args.item_order == ItemOrder::Lexicographic).then(|| {
+        lexicographic_order(
+            &dataset.itemizer,
+            dataset.item_count.items_with_count_at_least(1),
+        )
+    });
+    let sketch = &dataset.sketch;
+    let is_candidate = |item: &str| may_be_frequent(sketch, item);
+    let item_filter: Option<&dyn Fn(&str) -> bool> = match *sketch {
+        Some(_) => Some(&is_candidate),
+        None => None,
+    };
+    let source: Transactions = match dataset.transactions.take() {
+        Some(transactions) => Box::new(transactions.into_iter().map(Ok)),
+        None => Box::new(
+            open_dataset(
+                args,
+                &mut dataset.itemizer,
+                dataset.item_map.as_ref(),
+                &dataset.table_options,
+            )?
+            .with_item_filter(item_filter),
+        ),
+    };
+    let item_count = &dataset.item_count;
+    let min_count = thresholds.min_count;
+
+    let mut params = FPGrowthParameters {
+        min_count,
+        max_itemset_size: args.max_itemset_size,
+        cancellation: CancellationToken::new(),
+        cross_support: if args.min_all_confidence.is_some() || args.max_support_ratio.is_some() {
+            Some(CrossSupportPruning {
+                item_count: item_count.clone(),
+                min_all_confidence: args.min_all_confidence,
+                max_support_ratio: args.max_support_ratio,
+            })
+        } else {
+            None
+        },
+        item_min_counts: thresholds.item_min_counts.clone(),
+    };
+    if let Some(max_itemsets) = args.max_itemsets {
+        params.cancellation.cancel_above_count(max_itemsets);
+    }
+    let mut infrequent_subsets = vec![];
+    let count_pairs_only = args.algorithm == Algorithm::FPGrowth
+        && thresholds.target_consequent.is_none()
+        && args.target_rules.is_none()
+        && args.max_itemset_size == Some(2)
+        && args.dump_tree_path.is_none()
+        && args.pfp_groups.is_none()
+        && thresholds.item_min_counts.is_none();
+    let (progress, algorithm_name, mining_ms) = if count_pairs_only {
+        // No tree is needed to find itemsets of at most two items.
+        println!("Counting frequent items and pairs...");
+        let timer = Instant::now();
+        let itemsets = info_span!("count_pairs").in_scope(|| {
+            count_pairs(
+                source,
+                item_count,
+                min_count,
+                params.cross_support.as_ref(),
+                index_ref,
+            )
+        })?;
+        // Pairs are counted all at once, so can't be stopped early, but the
+        // limit on itemsets is still checked.
+        params.cancellation.add_to_count(itemsets.len());
+        let progress = FPGrowthProgress {
+            num_items: itemsets.iter().filter(|itemset| itemset.len() == 1).count(),
+            itemsets,
+            incomplete_items: vec![],
+        };
+        (progress, "Pair counting", duration_as_ms(&timer.elapsed()))
+    } else if args.algorithm.builds_fptree() {
+        // Insert each transaction into the tree sorted by item frequency,
+        // unless another order is requested. Item ids don't depend on the
+        // tree's order, so output is in the same order either way.
+        let tree_item_order = match args.item_order {
+            ItemOrder::Frequency => {
+                println!("Building initial FPTree based on item frequencies...");
+                thresholds.item_order.clone()
+            }
+            ItemOrder::Lexicographic => {
+                println!("Building initial FPTree with items in lexicographic order...");
+                lexicographic
+            }
+        };
+        let timer = Instant::now();
+        let options = TreeBuildOptions {
+            parallel: args.parallel_tree_build,
+            aggregate_duplicates: args.aggregate_duplicates,
+            item_order: tree_item_order,
+        };
+        let fptree = info_span!("build_tree").in_scope(|| {
+            build_initial_fptree(source, item_count, min_count, &options, index_ref)
+        })?;
+        let build_ms = duration_as_ms(&timer.elapsed());
+        if thresholds.privacy.is_some() {
+            // The tree's size depends on exact counts.
+            println!("Building initial FPTree took {} ms.", build_ms);
+        } else {
+            println!(
+                "Building initial FPTree took {} ms, tree has {} nodes.",
+                build_ms,
+                fptree.num_nodes()
+            );
+            run.memory.fptree = Some(fptree.heap_size());
+        }
+
+        if let Some(ref path) = args.dump_tree_path {
+            fs::write(path, fptree.to_dot(&dataset.itemizer)).map_err(|e| ArmError::io(path, e))?;
+            println!("Wrote initial FPTree to {}.", path);
+        }
+
+        let (progress, subsets, algorithm_name, mining_ms) = mine_fptree(
+            args,
+            &fptree,
+            options.item_order.as_ref().unwrap_or(item_count),
+            &mut params,
+            dataset,
+            thresholds,
+            run,
+        )?;
+        infrequent_subsets = subsets;
+        (progress, algorithm_name, mining_ms)
+    } else if args.algorithm == Algorithm::Toivonen {
+        let sample_size = args.sample_size.unwrap_or(DEFAULT_SAMPLE_SIZE);
+        println!(
+            "Sampling {} transactions and building transaction index...",
+            sample_size
+        );
+        let timer = Instant::now();
+        let index = index_ref.expect("Toivonen's algorithm requires an index");
+        let (progress, summary) = info_span!("mining").in_scope(|| {
+            toivonen(
+                source,
+                item_count,
+                &params,
+                num_transactions,
+                sample_size,
+                args.sample_seed,
+                index,
+            )
+        })?;
+        if thresholds.privacy.is_some() {
+            // The number of candidates and rounds depend on exact counts.
+            println!("Mined a sample of transactions and counted candidate itemsets.");
+        } else {
+            println!(
+                "Mined a sample of {} transactions at min count {}, and counted {} candidate \
+                 itemsets in {} rounds.",
+                summary.sample_size,
+                summary.sample_min_count,
+                summary.num_candidates,
+                summary.num_rounds
+            );
+        }
+        (progress, "Toivonen", duration_as_ms(&timer.elapsed()))
+    } else {
+        println!("Building vertical database of item tid-lists...");
+        let timer = Instant::now();
+        let database = info_span!("build_vertical_database")
+            .in_scope(|| VerticalDatabase::build(source, item_count, min_count, index_ref))?;
+        let diffsets = args.algorithm == Algorithm::DEclat || database.prefers_diffsets();
+        let build_ms = duration_as_ms(&timer.elapsed());
+        if thresholds.privacy.is_some() {
+            // The density depends on exact counts.
+            println!("Building vertical database took {} ms.", build_ms);
+        } else {
+            println!(
+                "Building vertical database took {} ms, density is {:.4}.",
+                build_ms,
+                database.density()
+            );
+        }
+
+        println!(
+            "Starting Eclat using {}...",
+            if diffsets { "diffsets" } else { "tidsets" }
+        );
+        let timer = Instant::now();
+        if let Some(timeout) = args.timeout {
+            params
+                .cancellation
+                .cancel_after(Duration::from_secs(timeout));
+        }
+        let progress = info_span!("mining").in_scope(|| eclat(&database, &params, diffsets));
+        (progress, "Eclat", duration_as_ms(&timer.elapsed()))
+    };
+    let FPGrowthProgress {
+        itemsets: patterns,
+        num_items,
+        incomplete_items,
+    } = progress;
+    // Tuning may have changed the minimum count.
+    let mut min_count = params.min_count;
+    let patterns = match thresholds.privacy {
+        Some(ref privacy) => {
+            let candidate_min_count = min_count;
+            min_count = thresholds.release_min_count;
+            let noisy = add_noise(
+                patterns,
+                candidate_min_count,
+                min_count,
+                &item_count.items_with_count_at_least(1),
+                num_transactions as u32,
+                privacy,
+                &mut StdRng::from_entropy(),
+            )?;
+            // The number of candidates isn't given, as it depends on exact
+            // counts.
+            println!(
+                "Selected and added noise to the counts of itemsets, releasing {}.",
+                noisy.len()
+            );
+            noisy
+        }
+        None => patterns,
+    };
+
+    println!(
+        "{} generated {} frequent itemsets in {} ms.",
+        algorithm_name,
+        patterns.len(),
+        mining_ms
+    );
+    run.manifest.add_timing("mining", mining_ms);
+    run.manifest.add_count("min_count", min_count as usize);
+    run.manifest.add_count("num_itemsets", patterns.len());
+    run.memory.itemizer = dataset.itemizer.heap_size();
+    run.memory.itemsets = itemsets_bytes(&patterns);
+    println!(
+        "Itemsets by length: {}.",
+        format_length_counts(&count_by_length(
+            patterns.par_iter().map(|itemset| itemset.len())
+        ))
+    );
+    if let Some(max_itemsets) = args
+        .max_itemsets
+        .filter(|_| params.cancellation.is_count_exceeded())
+    {
+        let message = format!(
+            "Mining found more than {} frequent itemsets (--max-itemsets), so the minimum \
+             support is too low.",
+            max_itemsets
+        );
+        if !args.write_partial {
+            return Err(ArmError::limit_exceeded(&format!(
+                "{} Raise --min-support, or pass --write-partial to write the results found \
+                 so far.",
+                message
+            )));
+        }
+        println!("{}", message);
+    }
+    if !incomplete_items.is_empty() {
+        let names: Vec<&str> = incomplete_items
+            .iter()
+            .take(10)
+            .map(|&item| dataset.itemizer.str_of(item))
+            .collect();
+        println!(
+            "Partial results: mining stopped before mining {} of {} frequent items \
+             ({}{}). Rules are only generated from itemsets of fully mined items.",
+            incomplete_items.len(),
+            num_items,
+            names.join(", "),
+            if incomplete_items.len() > names.len() {
+                ", ..."
+            } else {
+                ""
+            }
+        );
+    }
+    Ok(MinedItemsets {
+        num_frequent: patterns.len(),
+        itemsets: patterns,
+        infrequent_subsets,
+        incomplete_items,
+        min_count,
+        index,
+    })
+}
+
+// Writes the itemsets, and the lattice and minimal generators of the closed
+// itemsets, if they're wanted.
+fn write_itemset_outputs(
+    args: &Arguments,
+    dataset: &Dataset,
+    patterns: &[ItemSet],
+) -> Result<(), ArmError> {
+    let num_transactions = dataset.num_transactions;
+    let itemizer = &dataset.itemizer;
+    if let Some(ref path) = args.output_itemsets_path {
+        let timer = Instant::now();
+        // Shorter itemsets are still needed for calculating rule stats,
+        // so are only filtered out when writing.
+        let itemsets: Vec<ItemSet> = patterns
+            .iter()
+            .filter(|itemset| itemset.len() >= args.min_itemset_size)
+            .cloned()
+            .collect();
+        write_itemsets(
+            &itemsets,
+            num_transactions,
+            path,
+            &output_options(args),
+            itemizer,
+        )
+        .map_err(|e| ArmError::io(path, e))?;
+        println!(
+            "Wrote itemsets to disk in {} ms.",
+            duration_as_ms(&timer.elapsed())
+        );
+    }
+
+    if let Some(ref path) = args.output_lattice_path {
+        let timer = Instant::now();
+        let lattice = Lattice::new(closed_itemsets(patterns, num_transactions as u32));
+        fs::write(
+            path,
+            lattice.render(path, itemizer, num_transactions as u32),
+        )
+        .map_err(|e| ArmError::io(path, e))?;
+        println!(
+            "Wrote lattice of {} closed itemsets to {} in {} ms.",
+            lattice.itemsets.len(),
+            path,
+            duration_as_ms(&timer.elapsed())
+        );
+    }
+
+    if let Some(ref path) = args.output_generators_path {
+        let timer = Instant::now();
+        let generators = minimal_generators(patterns, num_transactions as u32);
+        write_generators(
+            &generators,
+            num_transactions,
+            path,
+            &output_options(args),
+            itemizer,
+        )
+        .map_err(|e| ArmError::io(path, e))?;
+        println!(
+            "Wrote {} minimal generators to {} in {} ms.",
+            generators.len(),
+            path,
+            duration_as_ms(&timer.elapsed())
+        );
+    }
+    Ok(())
+}
+
+// Counts the frequent itemsets in the baseline data set, and writes those
+// which are emerging patterns, more frequent in the data set than in the
+// baseline.
+fn write_emerging_patterns(
+    args: &Arguments,
+    baseline_path: &str,
+    contrasts_path: &str,
+    dataset: &mut Dataset,
+    frequent: &[ItemSet],
+) -> Result<(), ArmError> {
+    println!("Counting itemsets in baseline data set: {}", baseline_path);
+    let timer = Instant::now();
+    let baseline = index_baseline(
+        baseline_path,
+        args,
+        &dataset.table_options,
+        dataset.item_map.as_ref(),
+        frequent,
+        &mut dataset.itemizer,
+    )?;
+    let contrasts = emerging_patterns(
+        frequent,
+        dataset.num_transactions,
+        baseline.as_ref(),
+        args.min_growth_rate,
+        args.max_p_value,
+    );
+    write_contrasts(&contrasts, contrasts_path, &dataset.itemizer)
+        .map_err(|e| ArmError::io(contrasts_path, e))?;
+    println!(
+        "Found {} emerging patterns in {} ms.",
+        contrasts.len(),
+        duration_as_ms(&timer.elapsed())
+    );
+    Ok(())
+}
+
+// Generates rules and holds them in memory, for the steps which need all
+// of them, before writing them.
+fn collect_and_write_rules(
+    args: &Arguments,
+    dataset: &Dataset,
+    thresholds: &Thresholds,
+    rule_params: &RuleParameters,
+    mined: &MinedItemsets,
+    self_checked: bool,
+    run: &mut Run,
+) -> Result<(), ArmError> {
+    let num_transactions = dataset.num_transactions;
+    let itemizer = &dataset.itemizer;
+    let patterns = &mined.itemsets;
+    let index = mined.index.as_deref();
+    let timer = Instant::now();
+    println!("Generating rules...");
+    let mut rules = info_span!("rule_generation")
+        .in_scope(|| generate_rules(patterns, num_transactions as u32, rule_params));
+    let generation_ms = duration_as_ms(&timer.elapsed());
+    check_max_rules(args, rule_params)?;
+    if self_checked {
+        println!("Self-checking rules against brute force...");
+        let timer = Instant::now();
+        let generator = RuleGenerator::new(patterns, num_transactions as u32, rule_params);
+        let expected = naive_rules(patterns, &generator);
+        let num_discrepancies = check_rules(&rules, &expected, itemizer);
+        println!(
+            "Self-check found {} rule discrepancies in {} ms.",
+            num_discrepancies,
+            duration_as_ms(&timer.elapsed())
+        );
+    }
+    if let Some(num_permutations) = args.permutations {
+        println!(
+            "Estimating p-values with {} permutations...",
+            num_permutations
+        );
+        let timer = Instant::now();
+        permutation_test(
+            &mut rules,
+            &dataset.item_count,
+            num_transactions as u32,
+            num_permutations,
+            args.permutation_seed,
+        );
+        println!(
+            "Estimated p-values in {} ms.",
+            duration_as_ms(&timer.elapsed())
+        );
+        // The filter may test p-values, so it's applied once they're
+        // known rather than during generation.
+        if let Some(mut filter) = args.filter.clone() {
+            filter.resolve(itemizer);
+            for rules in rules.iter_mut() {
+                rules.retain(|rule| filter.matches(rule));
+            }
+        }
+    }
+    if let Some(k) = args.top_per_consequent {
+        rules = top_rules_per_consequent(rules, k);
+    }
+    let mut num_rules: usize = rules.iter().map(|x| x.len()).sum();
+    println!("Generated {} rules in {} ms.", num_rules, generation_ms);
+    println!(
+        "Rules by antecedent length: {}.",
+        format_length_counts(&count_by_length(
+            rules
+                .par_iter()
+                .flat_map(|rules| rules.par_iter())
+                .map(|rule| rule.antecedent.len())
+        ))
+    );
+
+    if let (Some(target), Some(index)) = (args.select_covering, index) {
+        println!(
+            "Selecting rules covering {}% of transactions...",
+            target.fraction * 100.0
+        );
+        let timer = Instant::now();
+        let selection = select_covering(rules, index, target);
+        println!(
+            "Selected {} rules covering {} of {} transactions ({:.2}%) in {} ms.",
+            selection.rules.len(),
+            selection.num_covered,
+            num_transactions,
+            100.0 * selection.num_covered as f64 / num_transactions as f64,
+            duration_as_ms(&timer.elapsed())
+        );
+        num_rules = selection.rules.len();
+        rules = vec![selection.rules];
+    }
+
+    if let Some(index) = index.filter(|_| args.verify) {
+        println!("Verifying supports against the transaction index...");
+        let timer = Instant::now();
+        let num_discrepancies =
+            verify_itemsets(mined.frequent(), index, args.verify_sample, itemizer)
+                + verify_rules(&rules, index, args.verify_sample, itemizer);
+        println!(
+            "Verification found {} discrepancies in {} ms.",
+            num_discrepancies,
+            duration_as_ms(&timer.elapsed())
+        );
+    }
+
+    if let (Some(path), Some(index)) = (&args.coverage_report_path, index) {
+        println!("Counting transactions covered by rules...");
+        let timer = Instant::now();
+        let coverage = rule_coverage(&rules, index);
+        let percent = |count: usize| 100.0 * count as f64 / num_transactions as f64;
+        println!(
+            "Rule antecedents match {} of {} transactions ({:.2}%); whole rules match {} \
+             ({:.2}%). Counted in {} ms.",
+            coverage.num_antecedent_covered,
+            num_transactions,
+            percent(coverage.num_antecedent_covered),
+            coverage.num_rule_covered,
+            percent(coverage.num_rule_covered),
+            duration_as_ms(&timer.elapsed())
+        );
+        run.manifest
+            .add_count("num_antecedent_covered", coverage.num_antecedent_covered);
+        run.manifest
+            .add_count("num_rule_covered", coverage.num_rule_covered);
+        write_rule_coverage(path, &rules, &coverage, num_transactions, itemizer)
+            .map_err(|e| ArmError::io(path, e))?;
+        println!("Wrote rule coverage to {}.", path);
+    }
+
+    run.memory.rules = Some(rules_bytes(&rules));
+    if let Some(ref path) = args.html_report_path {
+        run.memory.peak_rss = peak_rss_bytes();
+        let summary = ReportSummary {
+            input_path: &args.input_file_path,
+            num_transactions,
+            min_support: args.min_support,
+            min_confidence: args.min_confidence,
+            num_itemsets: mined.frequent().len(),
+            num_rules,
+            num_incomplete_items: mined.incomplete_items.len(),
+            missing_counts: dataset.missing_counts,
+            memory: run.memory,
+            privacy: thresholds.privacy.as_ref(),
+        };
+        write_html_report(path, &summary, mined.frequent(), &rules, itemizer)
+            .map_err(|e| ArmError::io(path, e))?;
+        println!("Wrote HTML report to {}.", path);
+    }
+
+    run.manifest.add_timing("rule_generation", generation_ms);
+    run.manifest.add_count("num_rules", num_rules);
+
+    let timer = Instant::now();
+    info_span!("writing_rules")
+        .in_scope(|| {
+            write_rules(
+                &rules,
+                &args.output_rules_path,
+                &output_options(args),
+                itemizer,
+            )
+        })
+        .map_err(|e| ArmError::io(&args.output_rules_path, e))?;
+    println!(
+        "Wrote rules to disk in {} ms.",
+        duration_as_ms(&timer.elapsed())
+    );
+    run.manifest
+        .add_timing("writing_rules", duration_as_ms(&timer.elapsed()));
+    Ok(())
+}
+
+// Generates rules, writing each as it's generated.
+fn stream_and_write_rules(
+    args: &Arguments,
+    dataset: &Dataset,
+    rule_params: &RuleParameters,
+    mined: &MinedItemsets,
+    run: &mut Run,
+) -> Result<(), ArmError> {
+    let timer = Instant::now();
+    println!("Generating rules and writing them to disk...");
+    let num_rules = info_span!("rule_generation")
+        .in_scope(|| {
+            stream_rules(
+                &mined.itemsets,
+                dataset.num_transactions as u32,
+                rule_params,
+                args.deterministic_order,
+                &args.output_rules_path,
+                &output_options(args),
+                &dataset.itemizer,
+            )
+        })
+        .map_err(|e| ArmError::io(&args.output_rules_path, e))?;
+    // The rules were written as they were generated, so are removed if
+    // they're incomplete and not wanted.
+    if let Err(e) = check_max_rules(args, rule_params) {
+        fs::remove_file(&args.output_rules_path)
+            .map_err(|e| ArmError::io(&args.output_rules_path, e))?;
+        return Err(e);
+    }
+    println!(
+        "Generated and wrote {} rules in {} ms.",
+        num_rules.iter().sum::<usize>(),
+        duration_as_ms(&timer.elapsed())
+    );
+    run.manifest
+        .add_timing("rule_generation", duration_as_ms(&timer.elapsed()));
+    run.manifest.add_count("num_rules", num_rules.iter().sum());
+    println!(
+        "Rules by antecedent length: {}.",
+        format_length_counts(&num_rules)
+    );
+    Ok(())
+}
+
+pub fn mine_fp_growth(args: &Arguments) -> Result<(), ArmError> {
+    // Declared first, so the stacks are written after every span closes.
+    let _flame_stacks = match args.flame_stacks_path {
+        Some(ref path) => Some(write_flame_stacks(path)?),
+        None => None,
+    };
+    let _span = info_span!("mine").entered();
+    println!("Mining data set: {}", args.input_file_path);
+    let mut run = Run {
+        start: Instant::now(),
+        manifest: Manifest::new(&args.input_file_path),
+        memory: MemoryUsage::default(),
+    };
+    let mut dataset = first_pass(args, &mut run)?;
+    let thresholds = thresholds(args, &dataset, &mut run)?;
+    let self_check_transactions = dataset
+        .transactions
+        .as_ref()
+        .filter(|_| args.self_check)
+        .cloned();
+    let mut mined = mine_itemsets(args, &mut dataset, &thresholds, &mut run)?;
+    write_itemset_outputs(args, &dataset, &mined.itemsets)?;
+    mined.prepare_for_rules();
+
+    // The rules are only self-checked if the itemsets could be.
+    let mut self_checked = false;
+    if let Some(ref transactions) = self_check_transactions {
+        println!("Self-checking itemsets against brute force...");
+        let timer = Instant::now();
+        match naive_itemsets(transactions, mined.min_count, args.max_itemset_size) {
+            Ok(expected) => {
+                let num_discrepancies =
+                    check_itemsets(mined.frequent(), &expected, &dataset.itemizer);
+                println!(
+                    "Self-check found {} itemset discrepancies in {} ms.",
+                    num_discrepancies,
+                    duration_as_ms(&timer.elapsed())
+                );
+                self_checked = true;
+            }
+            Err(message) => println!("Skipping self-check: {}.", message),
+        }
+    }
+
+    if let (Some(baseline_path), Some(contrasts_path)) =
+        (&args.baseline_input_path, &args.output_contrasts_path)
+    {
+        write_emerging_patterns(
+            args,
+            baseline_path,
+            contrasts_path,
+            &mut dataset,
+            mined.frequent(),
+        )?;
+    }
+
+    if args.no_rules {
+        if let Some(index) = mined.index.as_deref().filter(|_| args.verify) {
+            println!("Verifying supports against the transaction index...");
+            let timer = Instant::now();
+            let num_discrepancies = verify_itemsets(
+                mined.frequent(),
+                index,
+                args.verify_sample,
+                &dataset.itemizer,
+            );
+            println!(
+                "Verification found {} discrepancies in {} ms.",
+                num_discrepancies,
+                duration_as_ms(&timer.elapsed())
+            );
+        }
+        let manifest_beside = args
+            .output_itemsets_path
+            .as_deref()
+            .filter(|_| args.manifest);
+        return run.finish(manifest_beside);
+    }
+
+    let filter = args.filter.clone().map(|mut filter| {
+        filter.resolve(&dataset.itemizer);
+        filter
+    });
+    let rule_params = rule_parameters(
+        args,
+        filter,
+        thresholds.item_min_counts.clone(),
+        thresholds.target_consequent.clone(),
+    );
+    if let Some(max_rules) = args.max_rules {
+        rule_params.cancellation.cancel_above_count(max_rules);
+    }
+    if args.verify
+        || args.html_report_path.is_some()
+        || args.top_per_consequent.is_some()
+        || args.select_covering.is_some()
+        || args.permutations.is_some()
+        || args.coverage_report_path.is_some()
+        || self_checked
+    {
+        // Verification, the report, grouping by consequent, selecting
+        // covering rules, the permutation test, coverage and the self-check
+        // need all rules, so collect them in memory before writing them.
+        collect_and_write_rules(
+            args,
+            &dataset,
+            &thresholds,
+            &rule_params,
+            &mined,
+            self_checked,
+            &mut run,
+        )?;
+    } else {
+        stream_and_write_rules(args, &dataset, &rule_params, &mined, &mut run)?;
+    }
+    let file_size = fs::metadata(&args.output_rules_path)
+        .map_err(|e| ArmError::io(&args.output_rules_path, e))?
+        .len();
+    println!("Rules file is {} bytes.", file_size);
+
+    if let Some(n) = args.preview {
+        let rules = top_rules_by_lift(&args.output_rules_path, n)?;
+        println!("\nTop {} rules by lift:", rules.len());
+        println!("{}", format_preview(&rules, args.preview_color.enabled()));
+    }
+
+    let manifest_beside = Some(args.output_rules_path.as_str()).filter(|_| args.manifest);
+    run.finish(manifest_beside)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mine_fp_growth;
+    use command_line_args::parse_mine_args_or_exit;
+    use std::fs;
+    use test_util::TempFile;
+
+    #[test]
+    fn test_suppress_below_count() {
+        let (input, itemsets_path, rules_path) = (
+            TempFile::new("suppress-below-count-input.csv"),
+            TempFile::new("suppress-below-count-itemsets.csv"),
+            TempFile::new("suppress-below-count-rules.csv"),
+        );
+        fs::write(&input, "a,b\na,b\na,c\na,c\na,c\nb,d\nb\na\nc\nd\n").unwrap();
+        // A minimum support of 0.1 is a count of 1, but d and {a,b} occur
+        // only twice, so they and the rules between a and b are withheld.
+        let arguments: Vec<String> = [
+            "--input",
+            input.path(),
+            "--min-support",
+            "0.1",
+            "--min-confidence",
+            "0.1",
+            "--suppress-below-count",
+            "3",
+            "--output-itemsets",
+            itemsets_path.path(),
+            "--output",
+            rules_path.path(),
+        ]
+        .iter()
+        .map(|&s| String::from(s))
+        .collect();
+        mine_fp_growth(&parse_mine_args_or_exit("arm", &arguments)).unwrap();
+
+        let itemsets = fs::read_to_string(&itemsets_path).unwrap();
+        let itemsets: Vec<&str> = itemsets.lines().skip(1).collect();
+        assert_eq!(itemsets, vec!["a,0.6,6", "b,0.4,4", "c,0.4,4", "a c,0.3,3"]);
+        let rules = fs::read_to_string(&rules_path).unwrap();
+        let mut rules: Vec<&str> = rules
+            .lines()
+            .skip(1)
+            .map(|line| line.split(',').next().unwrap())
+            .collect();
+        rules.sort();
+        assert_eq!(rules, vec!["a => c", "c => a"]);
+    }
+
+    #[test]
+    fn test_privacy_withholds_tree_size() {
+        let (input, rules_path, report_path) = (
+            TempFile::new("privacy-input.csv"),
+            TempFile::new("privacy-rules.csv"),
+            TempFile::new("privacy-report.html"),
+        );
+        fs::write(
+            &input,
+            "a,b\na,b\na,c\na,c\na,c\nb,d\nb\na\nc\nd\n".repeat(10),
+        )
+        .unwrap();
+        let arguments = |privacy: &[&str]| -> Vec<String> {
+            [
+                "--input",
+                input.path(),
+                "--min-support",
+                "0.1",
+                "--min-confidence",
+                "0.1",
+                "--output",
+                rules_path.path(),
+                "--html-report",
+                report_path.path(),
+            ]
+            .iter()
+            .chain(privacy)
+            .map(|&s| String::from(s))
+            .collect()
+        };
+        mine_fp_growth(&parse_mine_args_or_exit("arm", &arguments(&[]))).unwrap();
+        assert!(fs::read_to_string(&report_path)
+            .unwrap()
+            .contains("FP-tree"));
+
+        // The FPTree's size depends on exact counts, so isn't reported under
+        // differential privacy.
+        let privacy = ["--dp-epsilon", "1", "--dp-max-length", "2"];
+        mine_fp_growth(&parse_mine_args_or_exit("arm", &arguments(&privacy))).unwrap();
+        assert!(!fs::read_to_string(&report_path)
+            .unwrap()
+            .contains("FP-tree"));
+    }
+}
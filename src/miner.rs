@@ -0,0 +1,605 @@
+// Copyright 2018 Chris Pearce
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use cancellation::CancellationToken;
+use error::ArmError;
+use fptree::{fp_growth, FPGrowthParameters, FPTree, ItemSet};
+use generate_rules::{generate_rules_iter, par_generate_rules_iter, RuleParameters};
+use item::Item;
+use item_counter::ItemCounter;
+use itemizer::Itemizer;
+use lattice::{closed_itemsets, minimal_generators};
+use mining::{
+    build_initial_fptree, count_item_frequencies, min_count, remap_items, TreeBuildOptions,
+};
+use rayon::prelude::*;
+use rule::{Rule, DEFAULT_LAPLACE_K};
+use transaction_source::TransactionSource;
+
+pub struct MinerParameters {
+    pub min_support: f64,
+    pub min_confidence: f64,
+    pub min_lift: Option<f64>,
+    pub max_lift: Option<f64>,
+    pub max_itemset_size: Option<usize>,
+    // Cancelling this aborts mining; itemsets() and rules() then return
+    // only what was found before cancellation.
+    pub cancellation: CancellationToken,
+}
+
+impl Default for MinerParameters {
+    fn default() -> MinerParameters {
+        MinerParameters {
+            min_support: 0.05,
+            min_confidence: 0.05,
+            min_lift: None,
+            max_lift: None,
+            max_itemset_size: None,
+            cancellation: CancellationToken::new(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct FrequentItemset {
+    pub items: Vec<String>,
+    pub count: u32,
+    pub support: f64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AssociationRule {
+    pub antecedent: Vec<String>,
+    pub consequent: Vec<String>,
+    pub confidence: f64,
+    pub lift: f64,
+    pub support: f64,
+    pub count: u32,
+}
+
+// Mines frequent itemsets and association rules from transactions held in
+// memory. The initial FPTree is built once, when the Miner is created.
+pub struct Miner {
+    itemizer: Itemizer,
+    fptree: FPTree,
+    num_transactions: usize,
+    min_count: u32,
+    params: MinerParameters,
+}
+
+// Trims item names, drops empty names and duplicate items, and returns the
+// ids of the remaining items, in order.
+fn itemize(itemizer: &mut Itemizer, transaction: &[String]) -> Vec<Item> {
+    let mut items: Vec<Item> = transaction
+        .iter()
+        .map(|item| item.trim())
+        .filter(|item| !item.is_empty())
+        .map(|item| itemizer.id_of(item))
+        .collect();
+    items.sort();
+    items.dedup();
+    items
+}
+
+// Itemizes the transactions from source, skipping those with no items.
+fn itemized<'a, S: TransactionSource>(
+    source: &'a mut S,
+    itemizer: &'a mut Itemizer,
+) -> impl Iterator<Item = Result<(Vec<Item>, u32), ArmError>> + 'a {
+    source.filter_map(move |transaction| match transaction {
+        Ok(transaction) => {
+            let items = itemize(itemizer, &transaction);
+            if items.is_empty() {
+                None
+            } else {
+                Some(Ok((items, 1)))
+            }
+        }
+        Err(e) => Some(Err(e)),
+    })
+}
+
+fn tree_build_options() -> TreeBuildOptions {
+    TreeBuildOptions {
+        parallel: false,
+        aggregate_duplicates: false,
+        item_order: None,
+    }
+}
+
+impl Miner {
+    pub fn from_transactions<I>(transactions: I, params: MinerParameters) -> Miner
+    where
+        I: IntoIterator<Item = Vec<String>>,
+    {
+        let mut itemizer = Itemizer::new();
+        let mut itemized: Vec<(Vec<Item>, u32)> = transactions
+            .into_iter()
+            .map(|transaction| (itemize(&mut itemizer, &transaction), 1))
+            .filter(|(items, _)| !items.is_empty())
+            .collect();
+
+        let (mut item_count, num_transactions): (ItemCounter, usize) =
+            count_item_frequencies(itemized.iter().map(|&(ref t, count)| Ok((t, count))))
+                .expect("in memory transactions can't fail to read");
+        // Number items in lexicographic order, so sorted itemsets are sorted
+        // lexicographically, as in the command line tool.
+        let new_ids = itemizer.reorder_sorted(&mut item_count);
+        remap_items(&mut itemized, &new_ids);
+
+        let min_count = min_count(params.min_support, num_transactions);
+        let fptree = build_initial_fptree(
+            itemized.into_iter().map(Ok),
+            &item_count,
+            min_count,
+            &tree_build_options(),
+            None,
+        )
+        .expect("in memory transactions can't fail to read");
+
+        Miner {
+            itemizer,
+            fptree,
+            num_transactions,
+            min_count,
+            params,
+        }
+    }
+
+    // Reads source twice, first counting items and then building the tree,
+    // so the transactions needn't all fit in memory at once.
+    pub fn from_source<S: TransactionSource>(
+        mut source: S,
+        params: MinerParameters,
+    ) -> Result<Miner, ArmError> {
+        let mut itemizer = Itemizer::new();
+        let (mut item_count, num_transactions) =
+            count_item_frequencies(itemized(&mut source, &mut itemizer))?;
+        // Items read after reordering are given their new ids.
+        itemizer.reorder_sorted(&mut item_count);
+
+        source.rescan()?;
+        let min_count = min_count(params.min_support, num_transactions);
+        let fptree = build_initial_fptree(
+            itemized(&mut source, &mut itemizer),
+            &item_count,
+            min_count,
+            &tree_build_options(),
+            None,
+        )?;
+
+        Ok(Miner {
+            itemizer,
+            fptree,
+            num_transactions,
+            min_count,
+            params,
+        })
+    }
+
+    pub fn num_transactions(&self) -> usize {
+        self.num_transactions
+    }
+
+    fn fp_growth_parameters(&self) -> FPGrowthParameters {
+        FPGrowthParameters {
+            min_count: self.min_count,
+            max_itemset_size: self.params.max_itemset_size,
+            cancellation: self.params.cancellation.clone(),
+            cross_support: None,
+            item_min_counts: None,
+        }
+    }
+
+    // Returns a ConditionalMiner over the transactions containing item, or
+    // None if item isn't frequent.
+    pub fn conditional(&self, item: &str) -> Option<ConditionalMiner<'_>> {
+        self.drill_down(&self.fptree, &[], item)
+    }
+
+    fn drill_down(
+        &self,
+        fptree: &FPTree,
+        path: &[Item],
+        item: &str,
+    ) -> Option<ConditionalMiner<'_>> {
+        let item = self.itemizer.get_id(item)?;
+        let count = fptree.count_of(item);
+        if path.contains(&item) || count == 0 || count < self.min_count {
+            return None;
+        }
+        let mut path = path.to_vec();
+        path.push(item);
+        Some(ConditionalMiner {
+            miner: self,
+            fptree: fptree.construct_projected_tree(item, self.min_count),
+            path,
+            path_count: count,
+        })
+    }
+
+    fn mine_itemsets(&self) -> Vec<ItemSet> {
+        let mut itemsets = fp_growth(
+            &self.fptree,
+            &self.fp_growth_parameters(),
+            &[],
+            self.num_transactions as u32,
+        );
+        itemsets.sort();
+        itemsets
+    }
+
+    fn names_of(&self, items: &[Item]) -> Vec<String> {
+        items
+            .iter()
+            .map(|&item| String::from(self.itemizer.str_of(item)))
+            .collect()
+    }
+
+    fn frequent_itemsets(&self, itemsets: &[ItemSet]) -> Vec<FrequentItemset> {
+        itemsets
+            .iter()
+            .map(|itemset| FrequentItemset {
+                items: self.names_of(&itemset.items),
+                count: itemset.count,
+                support: itemset.count as f64 / self.num_transactions as f64,
+            })
+            .collect()
+    }
+
+    // Returns the frequent itemsets, shortest first.
+    pub fn itemsets(&self) -> Vec<FrequentItemset> {
+        self.frequent_itemsets(&self.mine_itemsets())
+    }
+
+    // Returns the closed frequent itemsets, those with no superset in the
+    // same transactions, shortest first. Requires max_itemset_size to be
+    // None, as an itemset's supersets must have been mined to know whether
+    // it's closed.
+    pub fn closed_itemsets(&self) -> Vec<FrequentItemset> {
+        let closed = closed_itemsets(&self.mine_itemsets(), self.num_transactions as u32);
+        self.frequent_itemsets(&closed)
+    }
+
+    // Returns the minimal generators of the closed frequent itemsets, each
+    // paired with its closure; see lattice::minimal_generators. Like
+    // closed_itemsets(), requires max_itemset_size to be None.
+    pub fn generators(&self) -> Vec<(FrequentItemset, FrequentItemset)> {
+        minimal_generators(&self.mine_itemsets(), self.num_transactions as u32)
+            .into_iter()
+            .map(|(generator, closure)| {
+                let mut pair = self.frequent_itemsets(&[generator, closure]);
+                let closure = pair.pop().unwrap();
+                (pair.pop().unwrap(), closure)
+            })
+            .collect()
+    }
+
+    fn rule_parameters(&self) -> RuleParameters {
+        RuleParameters {
+            min_confidence: self.params.min_confidence,
+            min_confidence_by_length: None,
+            min_lift: self.params.min_lift,
+            max_lift: self.params.max_lift,
+            min_confidence_lower_bound: None,
+            laplace_k: DEFAULT_LAPLACE_K,
+            min_laplace: None,
+            min_added_value: None,
+            min_gini_gain: None,
+            min_info_gain: None,
+            min_odds_ratio: None,
+            min_yules_q: None,
+            min_phi: None,
+            filter: None,
+            min_itemset_size: 1,
+            cancellation: self.params.cancellation.clone(),
+            item_min_counts: None,
+            target_consequent: None,
+        }
+    }
+
+    fn association_rule(&self, rule: &Rule) -> AssociationRule {
+        AssociationRule {
+            antecedent: self.names_of(&rule.antecedent),
+            consequent: self.names_of(&rule.consequent),
+            confidence: rule.confidence,
+            lift: rule.lift,
+            support: rule.support,
+            count: rule.count,
+        }
+    }
+
+    pub fn rules(&self) -> Vec<AssociationRule> {
+        self.par_rules_iter().collect()
+    }
+
+    // Rules are generated lazily as the iterator is consumed, so callers can
+    // stop early. Itemsets are still mined up front.
+    pub fn rules_iter(&self) -> impl Iterator<Item = AssociationRule> + '_ {
+        generate_rules_iter(
+            self.mine_itemsets(),
+            self.num_transactions as u32,
+            &self.rule_parameters(),
+        )
+        .map(move |rule| self.association_rule(&rule))
+    }
+
+    // Generates rules in parallel, in no particular order.
+    pub fn par_rules_iter(&self) -> impl ParallelIterator<Item = AssociationRule> + '_ {
+        par_generate_rules_iter(
+            self.mine_itemsets(),
+            self.num_transactions as u32,
+            &self.rule_parameters(),
+        )
+        .map(move |rule| self.association_rule(&rule))
+    }
+}
+
+// The transactions containing a set of items, for exploring the patterns
+// around those items without re-mining the whole dataset. Created by
+// Miner::conditional(), and narrowed further by conditional(). Only the
+// projected tree for the items is built, and it's mined only on request.
+pub struct ConditionalMiner<'a> {
+    miner: &'a Miner,
+    // Transactions containing all of path, with path's items removed.
+    fptree: FPTree,
+    path: Vec<Item>,
+    path_count: u32,
+}
+
+impl<'a> ConditionalMiner<'a> {
+    // The items every transaction here contains, in the order drilled into.
+    pub fn items(&self) -> Vec<String> {
+        self.miner.names_of(&self.path)
+    }
+
+    // Number of transactions containing all of items().
+    pub fn count(&self) -> u32 {
+        self.path_count
+    }
+
+    pub fn conditional(&self, item: &str) -> Option<ConditionalMiner<'a>> {
+        self.miner.drill_down(&self.fptree, &self.path, item)
+    }
+
+    // Returns the frequent itemsets which contain all of items(), including
+    // items() itself, shortest first. Counts and supports are relative to
+    // the whole dataset, as in Miner::itemsets().
+    pub fn itemsets(&self) -> Vec<FrequentItemset> {
+        let mut itemsets = fp_growth(
+            &self.fptree,
+            &self.miner.fp_growth_parameters(),
+            &self.path,
+            self.path_count,
+        );
+        itemsets.push(ItemSet::new(self.path.clone(), self.path_count));
+        if let Some(max) = self.miner.params.max_itemset_size {
+            itemsets.retain(|itemset| itemset.len() <= max);
+        }
+        itemsets.sort();
+        self.miner.frequent_itemsets(&itemsets)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Miner, MinerParameters};
+    use cancellation::CancellationToken;
+    use error::ArmError;
+    use transaction_source::TransactionSource;
+
+    fn transactions(rows: &[&str]) -> Vec<Vec<String>> {
+        rows.iter()
+            .map(|row| row.split(',').map(String::from).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_miner() {
+        let miner = Miner::from_transactions(
+            transactions(&["milk,bread", "milk,bread,eggs", "bread,eggs", "milk", ""]),
+            MinerParameters {
+                min_support: 0.25,
+                min_confidence: 0.6,
+                ..MinerParameters::default()
+            },
+        );
+        assert_eq!(miner.num_transactions(), 4);
+
+        let itemsets: Vec<(Vec<String>, u32)> = miner
+            .itemsets()
+            .into_iter()
+            .map(|itemset| (itemset.items, itemset.count))
+            .collect();
+        assert_eq!(
+            itemsets,
+            vec![
+                (vec![String::from("bread")], 3),
+                (vec![String::from("eggs")], 2),
+                (vec![String::from("milk")], 3),
+                (vec![String::from("bread"), String::from("eggs")], 2),
+                (vec![String::from("bread"), String::from("milk")], 2),
+                (vec![String::from("eggs"), String::from("milk")], 1),
+                (
+                    vec![
+                        String::from("bread"),
+                        String::from("eggs"),
+                        String::from("milk")
+                    ],
+                    1
+                ),
+            ]
+        );
+
+        let mut rules: Vec<(String, String)> = miner
+            .rules()
+            .into_iter()
+            .map(|rule| (rule.antecedent.join(" "), rule.consequent.join(" ")))
+            .collect();
+        rules.sort();
+        assert_eq!(
+            rules,
+            vec![
+                (String::from("bread"), String::from("eggs")),
+                (String::from("bread"), String::from("milk")),
+                (String::from("eggs"), String::from("bread")),
+                (String::from("eggs milk"), String::from("bread")),
+                (String::from("milk"), String::from("bread")),
+            ]
+        );
+        assert_eq!(miner.rules_iter().count(), rules.len());
+        assert_eq!(miner.rules_iter().take(2).count(), 2);
+
+        // Eggs are always with bread, and eggs and milk only together with
+        // bread.
+        let closed: Vec<String> = miner
+            .closed_itemsets()
+            .into_iter()
+            .map(|itemset| itemset.items.join(" "))
+            .collect();
+        assert_eq!(
+            closed,
+            vec![
+                "",
+                "bread",
+                "milk",
+                "bread eggs",
+                "bread milk",
+                "bread eggs milk"
+            ]
+        );
+        let generators: Vec<(String, String)> = miner
+            .generators()
+            .into_iter()
+            .map(|(generator, closure)| (generator.items.join(" "), closure.items.join(" ")))
+            .collect();
+        assert_eq!(
+            generators,
+            vec![
+                (String::from(""), String::from("")),
+                (String::from("bread"), String::from("bread")),
+                (String::from("eggs"), String::from("bread eggs")),
+                (String::from("milk"), String::from("milk")),
+                (String::from("bread milk"), String::from("bread milk")),
+                (String::from("eggs milk"), String::from("bread eggs milk")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_from_source() {
+        // An in memory source, which fails if it's rescanned more than once.
+        struct Rows {
+            rows: Vec<Vec<String>>,
+            next: usize,
+            scans: usize,
+        }
+        impl Iterator for Rows {
+            type Item = Result<Vec<String>, ArmError>;
+            fn next(&mut self) -> Option<Result<Vec<String>, ArmError>> {
+                self.next += 1;
+                self.rows.get(self.next - 1).cloned().map(Ok)
+            }
+        }
+        impl TransactionSource for Rows {
+            fn rescan(&mut self) -> Result<(), ArmError> {
+                self.scans += 1;
+                if self.scans > 1 {
+                    return Err(ArmError::parse("rows", 0, "rescanned twice"));
+                }
+                self.next = 0;
+                Ok(())
+            }
+        }
+
+        let rows = transactions(&["milk,bread", "milk,bread,eggs", "bread,eggs", "milk", ""]);
+        let params = || MinerParameters {
+            min_support: 0.25,
+            min_confidence: 0.6,
+            ..MinerParameters::default()
+        };
+        let expected = Miner::from_transactions(rows.clone(), params());
+        let miner = Miner::from_source(
+            Rows {
+                rows,
+                next: 0,
+                scans: 0,
+            },
+            params(),
+        )
+        .unwrap();
+        assert_eq!(miner.num_transactions(), 4);
+        assert_eq!(miner.itemsets(), expected.itemsets());
+        assert_eq!(miner.rules_iter().count(), expected.rules_iter().count());
+    }
+
+    #[test]
+    fn test_conditional() {
+        let miner = Miner::from_transactions(
+            transactions(&[
+                "a,b,c", "a,b,d", "a,c,d", "b,c,d", "a,b,c,d", "a,e", "b,e", "c,d,e",
+            ]),
+            MinerParameters {
+                min_support: 0.2,
+                ..MinerParameters::default()
+            },
+        );
+        let all = miner.itemsets();
+        for item in &["a", "b", "c", "d", "e"] {
+            let expected: Vec<_> = all
+                .iter()
+                .filter(|itemset| itemset.items.contains(&String::from(*item)))
+                .cloned()
+                .collect();
+            let conditional = miner.conditional(item).unwrap();
+            assert_eq!(conditional.itemsets(), expected);
+        }
+
+        let a = miner.conditional("a").unwrap();
+        assert_eq!(a.count(), 5);
+        let ad = a.conditional("d").unwrap();
+        assert_eq!(ad.items(), vec![String::from("a"), String::from("d")]);
+        assert_eq!(ad.count(), 3);
+        let expected: Vec<_> = all
+            .iter()
+            .filter(|itemset| {
+                itemset.items.contains(&String::from("a"))
+                    && itemset.items.contains(&String::from("d"))
+            })
+            .cloned()
+            .collect();
+        assert_eq!(ad.itemsets(), expected);
+
+        assert!(miner.conditional("unknown").is_none());
+        assert!(a.conditional("a").is_none());
+    }
+
+    #[test]
+    fn test_cancellation() {
+        let token = CancellationToken::new();
+        let miner = Miner::from_transactions(
+            transactions(&["a,b", "a,b,c", "b,c"]),
+            MinerParameters {
+                min_support: 0.1,
+                min_confidence: 0.1,
+                cancellation: token.clone(),
+                ..MinerParameters::default()
+            },
+        );
+        assert!(!miner.itemsets().is_empty());
+        token.cancel();
+        assert!(miner.itemsets().is_empty());
+        assert!(miner.rules().is_empty());
+    }
+}
@@ -0,0 +1,423 @@
+// Copyright 2018 Chris Pearce
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use counter::Counter;
+use error::ArmError;
+use fnv::FnvHashMap;
+use fptree::FPTree;
+use index::TransactionIndex;
+use item::Item;
+use item_counter::ItemCounter;
+use itemizer::Itemizer;
+use rayon::prelude::*;
+use std::str::FromStr;
+use std::time::Duration;
+use transaction_reader::TransactionReader;
+
+// Transactions are (transaction, count) pairs, as yielded by
+// TransactionReader.
+pub fn count_item_frequencies<T, I>(transactions: I) -> Result<(ItemCounter, usize), ArmError>
+where
+    T: AsRef<[Item]>,
+    I: IntoIterator<Item = Result<(T, u32), ArmError>>,
+{
+    let mut item_count: ItemCounter = ItemCounter::new();
+    let mut num_transactions = 0;
+    for transaction in transactions {
+        let (transaction, count) = transaction?;
+        num_transactions += count as usize;
+        for item in transaction.as_ref().iter() {
+            item_count.add(item, count);
+        }
+    }
+    Ok((item_count, num_transactions))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Algorithm {
+    FPGrowth,
+    // FPGrowth mining the tree from the root down, without building
+    // conditional trees.
+    TopDownFPGrowth,
+    // Eclat, using diffsets if the dataset is dense.
+    Eclat,
+    // Eclat, always using diffsets.
+    DEclat,
+    // Toivonen's algorithm: mine a sample, then count the candidates found
+    // exactly in an index of the dataset.
+    Toivonen,
+}
+
+impl FromStr for Algorithm {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Algorithm, String> {
+        match s {
+            "fpgrowth" => Ok(Algorithm::FPGrowth),
+            "tdfpgrowth" => Ok(Algorithm::TopDownFPGrowth),
+            "eclat" => Ok(Algorithm::Eclat),
+            "declat" => Ok(Algorithm::DEclat),
+            "toivonen" => Ok(Algorithm::Toivonen),
+            _ => Err(format!("Unknown algorithm {}", s)),
+        }
+    }
+}
+
+impl Algorithm {
+    pub fn builds_fptree(&self) -> bool {
+        matches!(self, Algorithm::FPGrowth | Algorithm::TopDownFPGrowth)
+    }
+}
+
+// Order in which items are inserted into the initial FPTree. Frequency
+// order, most frequent first, usually gives the smallest tree, as
+// transactions are more likely to share prefixes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ItemOrder {
+    Frequency,
+    Lexicographic,
+}
+
+impl FromStr for ItemOrder {
+    type Err = String;
+    fn from_str(s: &str) -> Result<ItemOrder, String> {
+        match s {
+            "frequency" => Ok(ItemOrder::Frequency),
+            "lexicographic" => Ok(ItemOrder::Lexicographic),
+            _ => Err(format!("Unknown item order {}", s)),
+        }
+    }
+}
+
+// Returns a counter which, passed as TreeBuildOptions::item_order, sorts
+// items into lexicographic order of their names.
+pub fn lexicographic_order(itemizer: &Itemizer, mut items: Vec<Item>) -> ItemCounter {
+    items.sort_by(|&a, &b| itemizer.str_of(a).cmp(itemizer.str_of(b)));
+    let mut order = ItemCounter::new();
+    for (rank, item) in items.iter().enumerate() {
+        order.set(item, (items.len() - rank) as u32);
+    }
+    order
+}
+
+pub fn min_count(min_support: f64, num_transactions: usize) -> u32 {
+    1.max((min_support * (num_transactions as f64)).ceil() as u32)
+}
+
+// Number of transactions buffered before building partial trees in parallel
+// and merging them into the initial tree. Bounds the memory used to hold
+// transactions while still giving each thread a decent amount of work.
+const TREE_BUILD_BATCH_SIZE: usize = 1 << 20;
+
+pub struct TreeBuildOptions {
+    // Build partial trees in parallel and merge them.
+    pub parallel: bool,
+    // Collapse identical transactions into one weighted transaction before
+    // inserting into the tree.
+    pub aggregate_duplicates: bool,
+    // If set, items are sorted into descending order of this rather than of
+    // their counts before inserting into the tree.
+    pub item_order: Option<ItemCounter>,
+}
+
+// Inserts transactions into an FPTree, and optionally an index, either
+// directly or via batches built in parallel.
+struct TreeBuilder<'a> {
+    fptree: FPTree,
+    batch: Vec<(Vec<Item>, u32)>,
+    parallel: bool,
+    index: Option<&'a mut dyn TransactionIndex>,
+}
+
+impl<'a> TreeBuilder<'a> {
+    fn add(&mut self, transaction: Vec<Item>, count: u32) {
+        if let Some(ref mut index) = self.index {
+            index.insert(&transaction, count);
+        }
+        if !self.parallel {
+            self.fptree.insert(&transaction, count);
+            return;
+        }
+        self.batch.push((transaction, count));
+        if self.batch.len() == TREE_BUILD_BATCH_SIZE {
+            self.fptree.merge(&build_fptree_in_parallel(&self.batch));
+            self.batch.clear();
+        }
+    }
+
+    fn finish(mut self) -> FPTree {
+        if !self.batch.is_empty() {
+            self.fptree.merge(&build_fptree_in_parallel(&self.batch));
+        }
+        if let Some(index) = self.index {
+            index.finish();
+        }
+        self.fptree
+    }
+}
+
+// Load the initial tree, by reading the data set and inserting each
+// transaction into the tree sorted by item frequency. If an index is
+// passed, the filtered transactions are also added to it.
+pub fn build_initial_fptree<I>(
+    transactions: I,
+    item_count: &ItemCounter,
+    min_count: u32,
+    options: &TreeBuildOptions,
+    index: Option<&mut dyn TransactionIndex>,
+) -> Result<FPTree, ArmError>
+where
+    I: IntoIterator<Item = Result<(Vec<Item>, u32), ArmError>>,
+{
+    let mut builder = TreeBuilder {
+        fptree: FPTree::new(),
+        batch: vec![],
+        parallel: options.parallel,
+        index,
+    };
+    let mut aggregated: FnvHashMap<Vec<Item>, u32> = FnvHashMap::default();
+    for transaction in transactions {
+        let (mut filtered_transaction, count) = transaction?;
+        // Strip out infrequent items from the transaction. This can
+        // drastically reduce the tree size, and speed up loading the
+        // initial tree. Filtering in place reuses the transaction's
+        // allocation rather than making another per transaction.
+        filtered_transaction.retain(|item| item_count.get(item) >= min_count);
+        options
+            .item_order
+            .as_ref()
+            .unwrap_or(item_count)
+            .sort_descending(&mut filtered_transaction);
+        if options.aggregate_duplicates {
+            // Filtering out infrequent items can make transactions which
+            // differed identical, so aggregate after filtering.
+            *aggregated.entry(filtered_transaction).or_insert(0) += count;
+            continue;
+        }
+        builder.add(filtered_transaction, count);
+    }
+    for (transaction, count) in aggregated {
+        builder.add(transaction, count);
+    }
+    Ok(builder.finish())
+}
+
+// Builds an FPTree from transactions by splitting them into one chunk per
+// thread, building a partial tree from each chunk, and merging the partial
+// trees together.
+fn build_fptree_in_parallel(transactions: &[(Vec<Item>, u32)]) -> FPTree {
+    let chunk_size = 1.max(transactions.len().div_ceil(rayon::current_num_threads()));
+    transactions
+        .par_chunks(chunk_size)
+        .map(|chunk| {
+            let mut tree = FPTree::new();
+            for &(ref transaction, count) in chunk {
+                tree.insert(transaction, count);
+            }
+            tree
+        })
+        .reduce(FPTree::new, |a, b| {
+            // Merge the smaller tree into the larger; merge cost is
+            // proportional to the size of the tree being merged in.
+            let (mut larger, smaller) = if a.num_nodes() < b.num_nodes() {
+                (b, a)
+            } else {
+                (a, b)
+            };
+            larger.merge(&smaller);
+            larger
+        })
+}
+
+// Reads all transactions into memory, so that they can be passed over more
+// than once without re-reading the input.
+pub fn read_transactions(
+    reader: &mut TransactionReader,
+) -> Result<Vec<(Vec<Item>, u32)>, ArmError> {
+    reader.collect()
+}
+
+// Renumbers the items in transactions after the itemizer has been reordered.
+pub fn remap_items(transactions: &mut [(Vec<Item>, u32)], new_ids: &[Item]) {
+    transactions.par_iter_mut().for_each(|(transaction, _)| {
+        for item in transaction.iter_mut() {
+            *item = new_ids[item.as_index()];
+        }
+    });
+}
+
+// Counts lengths in parallel. Returns the number of each length, indexed by
+// length.
+pub fn count_by_length<I>(lengths: I) -> Vec<usize>
+where
+    I: ParallelIterator<Item = usize>,
+{
+    lengths
+        .fold(Vec::new, |mut counts, length| {
+            if counts.len() <= length {
+                counts.resize(length + 1, 0);
+            }
+            counts[length] += 1;
+            counts
+        })
+        .reduce(Vec::new, |mut a, b| {
+            if a.len() < b.len() {
+                a.resize(b.len(), 0);
+            }
+            for (count, other) in a.iter_mut().zip(b) {
+                *count += other;
+            }
+            a
+        })
+}
+
+// Formats counts indexed by length as "length: count" pairs, skipping
+// lengths with no count.
+pub fn format_length_counts(counts: &[usize]) -> String {
+    let counts: Vec<String> = counts
+        .iter()
+        .enumerate()
+        .filter(|&(_, &count)| count > 0)
+        .map(|(length, count)| format!("{}: {}", length, count))
+        .collect();
+    if counts.is_empty() {
+        String::from("none")
+    } else {
+        counts.join(", ")
+    }
+}
+
+pub fn duration_as_ms(duration: &Duration) -> u64 {
+    (duration.as_secs() * 1_000) + u64::from(duration.subsec_millis())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        build_initial_fptree, count_by_length, count_item_frequencies, format_length_counts,
+        lexicographic_order, min_count, TreeBuildOptions,
+    };
+    use cancellation::CancellationToken;
+    use counter::Counter;
+    use eclat::{eclat, VerticalDatabase};
+    use fptree::{fp_growth, FPGrowthParameters, ItemSet};
+    use index::Index;
+    use item::Item;
+    use itemizer::Itemizer;
+    use pairs::count_pairs;
+    use rayon::prelude::*;
+    use self_check::naive_itemsets;
+    use toivonen::toivonen;
+
+    #[test]
+    fn test_count_by_length() {
+        let lengths: Vec<usize> = vec![1, 3, 1, 2, 3, 3];
+        let counts = count_by_length(lengths.into_par_iter());
+        assert_eq!(counts, vec![0, 2, 1, 3]);
+        assert_eq!(format_length_counts(&counts), "1: 2, 2: 1, 3: 3");
+        assert_eq!(format_length_counts(&[]), "none");
+    }
+
+    #[test]
+    fn test_lexicographic_order() {
+        let mut itemizer = Itemizer::new();
+        let mut items: Vec<_> = ["pear", "apple", "zucchini", "banana"]
+            .iter()
+            .map(|name| itemizer.id_of(name))
+            .collect();
+        let order = lexicographic_order(&itemizer, items.clone());
+        order.sort_descending(&mut items);
+        let names: Vec<&str> = items.iter().map(|&item| itemizer.str_of(item)).collect();
+        assert_eq!(names, vec!["apple", "banana", "pear", "zucchini"]);
+    }
+
+    // Items and itemsets whose counts equal the minimum count are frequent,
+    // whichever way they're mined.
+    #[test]
+    fn test_count_equal_to_min_count() {
+        let mut itemizer = Itemizer::new();
+        let transactions: Vec<(Vec<Item>, u32)> = ["a,b", "a,b", "a,c", "b,c", "a,b,c", "d"]
+            .iter()
+            .map(|t| (t.split(',').map(|name| itemizer.id_of(name)).collect(), 1))
+            .collect();
+        let (item_count, num_transactions) =
+            count_item_frequencies(transactions.iter().map(|(t, c)| Ok((t, *c)))).unwrap();
+        let min_count = min_count(0.5, num_transactions);
+        let c = itemizer.id_of("c");
+        assert_eq!(item_count.get(&c), min_count);
+
+        let mut expected = naive_itemsets(&transactions, min_count, None).unwrap();
+        expected.sort();
+        assert!(expected.contains(&ItemSet::new(vec![c], min_count)));
+        let params = FPGrowthParameters {
+            min_count,
+            max_itemset_size: None,
+            cancellation: CancellationToken::new(),
+            cross_support: None,
+            item_min_counts: None,
+        };
+        let options = TreeBuildOptions {
+            parallel: false,
+            aggregate_duplicates: false,
+            item_order: None,
+        };
+        let fptree = build_initial_fptree(
+            transactions.iter().cloned().map(Ok),
+            &item_count,
+            min_count,
+            &options,
+            None,
+        )
+        .unwrap();
+        let mut itemsets = fp_growth(&fptree, &params, &[], num_transactions as u32);
+        itemsets.sort();
+        assert_eq!(itemsets, expected);
+
+        let database = VerticalDatabase::build(
+            transactions.iter().cloned().map(Ok),
+            &item_count,
+            min_count,
+            None,
+        )
+        .unwrap();
+        let mut itemsets = eclat(&database, &params, false).itemsets;
+        itemsets.sort();
+        assert_eq!(itemsets, expected);
+
+        let mut index = Index::new();
+        let (progress, _) = toivonen(
+            transactions.iter().cloned().map(Ok),
+            &item_count,
+            &params,
+            num_transactions,
+            num_transactions,
+            1,
+            &mut index,
+        )
+        .unwrap();
+        assert_eq!(progress.itemsets, expected);
+
+        let mut pairs = count_pairs(
+            transactions.iter().cloned().map(Ok),
+            &item_count,
+            min_count,
+            None,
+            None,
+        )
+        .unwrap();
+        pairs.sort();
+        let mut expected_pairs = naive_itemsets(&transactions, min_count, Some(2)).unwrap();
+        expected_pairs.sort();
+        assert_eq!(pairs, expected_pairs);
+    }
+}
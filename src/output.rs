@@ -0,0 +1,490 @@
+// Copyright 2018 Chris Pearce
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use flate2::write::GzEncoder;
+use fptree::ItemSet;
+use generate_rules::{RuleGenerator, RuleParameters};
+use item::Item;
+use itemizer::Itemizer;
+use rayon::prelude::*;
+use rule::Rule;
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::mem;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+
+pub const RULES_HEADER: &str =
+    "Antecedent => Consequent,Confidence,Lift,Support,AntecedentCount,ConsequentCount,Count,\
+     ConfidenceLowerBound,ConfidenceUpperBound,LiftStdError,Laplace,AddedValue,GiniGain,InfoGain,OddsRatio,YulesQ,Phi,PValue";
+
+// Maximum number of serialized buffers of rules waiting to be written when
+// streaming. Bounds memory use if rules are generated faster than they can
+// be written.
+const RULE_CHANNEL_CAPACITY: usize = 256;
+
+// Size at which a thread's buffer of serialized rules is sent to the writer.
+const RULE_BUFFER_SIZE: usize = 1 << 16;
+
+// When writing in deterministic order, itemsets are processed in batches of
+// this many, and within a batch in chunks of RULE_CHUNK_SIZE. Each chunk is
+// serialized into its own buffer in parallel, and the buffers are written in
+// order.
+const RULE_BATCH_SIZE: usize = 1 << 14;
+const RULE_CHUNK_SIZE: usize = 64;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    // Infers compression from the file extension.
+    pub fn from_path(path: &str) -> Compression {
+        if path.ends_with(".gz") {
+            Compression::Gzip
+        } else if path.ends_with(".zst") {
+            Compression::Zstd
+        } else {
+            Compression::None
+        }
+    }
+}
+
+impl FromStr for Compression {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Compression, String> {
+        match s {
+            "none" => Ok(Compression::None),
+            "gzip" => Ok(Compression::Gzip),
+            "zstd" => Ok(Compression::Zstd),
+            _ => Err(format!("Unknown compression {}", s)),
+        }
+    }
+}
+
+// How floating point stats are written.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Notation {
+    Fixed,
+    Scientific,
+}
+
+impl FromStr for Notation {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Notation, String> {
+        match s {
+            "fixed" => Ok(Notation::Fixed),
+            "scientific" => Ok(Notation::Scientific),
+            _ => Err(format!("Unknown number format {}", s)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NumberFormat {
+    pub notation: Notation,
+    // Digits after the decimal point. If None, numbers are written with as
+    // many digits as are needed to read them back exactly.
+    pub precision: Option<usize>,
+}
+
+impl Default for NumberFormat {
+    fn default() -> NumberFormat {
+        NumberFormat {
+            notation: Notation::Fixed,
+            precision: None,
+        }
+    }
+}
+
+impl NumberFormat {
+    fn write<W: Write>(&self, output: &mut W, value: f64) -> io::Result<()> {
+        match (self.notation, self.precision) {
+            (Notation::Fixed, None) => write!(output, "{}", value),
+            (Notation::Fixed, Some(precision)) => write!(output, "{:.*}", precision, value),
+            (Notation::Scientific, None) => write!(output, "{:e}", value),
+            (Notation::Scientific, Some(precision)) => write!(output, "{:.*e}", precision, value),
+        }
+    }
+}
+
+// Options for writing rules and itemsets files.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct OutputOptions {
+    // If None, compression is inferred from the file extension.
+    pub compression: Option<Compression>,
+    pub number_format: NumberFormat,
+}
+
+// An output file, optionally compressed with a streaming encoder. Call
+// finish() when done, so that errors writing the end of the compressed
+// stream are reported.
+pub enum OutputFile {
+    Plain(BufWriter<File>),
+    Gzip(GzEncoder<BufWriter<File>>),
+    Zstd(zstd::Encoder<'static, BufWriter<File>>),
+}
+
+impl OutputFile {
+    // If compression is None, it's inferred from path's extension.
+    pub fn create(path: &str, compression: Option<Compression>) -> io::Result<OutputFile> {
+        let file = BufWriter::new(File::create(path)?);
+        Ok(
+            match compression.unwrap_or_else(|| Compression::from_path(path)) {
+                Compression::None => OutputFile::Plain(file),
+                Compression::Gzip => {
+                    OutputFile::Gzip(GzEncoder::new(file, flate2::Compression::default()))
+                }
+                Compression::Zstd => OutputFile::Zstd(zstd::Encoder::new(file, 0)?),
+            },
+        )
+    }
+
+    pub fn finish(self) -> io::Result<()> {
+        let mut file = match self {
+            OutputFile::Plain(file) => file,
+            OutputFile::Gzip(encoder) => encoder.finish()?,
+            OutputFile::Zstd(encoder) => encoder.finish()?,
+        };
+        file.flush()
+    }
+}
+
+impl Write for OutputFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match *self {
+            OutputFile::Plain(ref mut file) => file.write(buf),
+            OutputFile::Gzip(ref mut encoder) => encoder.write(buf),
+            OutputFile::Zstd(ref mut encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match *self {
+            OutputFile::Plain(ref mut file) => file.flush(),
+            OutputFile::Gzip(ref mut encoder) => encoder.flush(),
+            OutputFile::Zstd(ref mut encoder) => encoder.flush(),
+        }
+    }
+}
+
+pub fn write_rules(
+    rules: &[Vec<Rule>],
+    output_rules_path: &str,
+    options: &OutputOptions,
+    itemizer: &Itemizer,
+) -> io::Result<()> {
+    let mut output = OutputFile::create(output_rules_path, options.compression)?;
+    writeln!(output, "{}", RULES_HEADER)?;
+    for batch in rules.chunks(RULE_BATCH_SIZE) {
+        let buffers: Vec<Vec<u8>> = batch
+            .par_chunks(RULE_CHUNK_SIZE)
+            .map(|chunk| {
+                let mut buffer = vec![];
+                for rules in chunk {
+                    write_rule_lines(&mut buffer, rules, options.number_format, itemizer);
+                }
+                buffer
+            })
+            .collect();
+        for buffer in buffers {
+            output.write_all(&buffer)?;
+        }
+    }
+    output.finish()
+}
+
+// Generates rules and writes them to output_rules_path as they're
+// generated, rather than collecting them all in memory first. Worker
+// threads serialize rules into their own buffers, and send them to a writer
+// thread over a bounded channel. Unless deterministic_order is set, the order
+// of rules in the output depends on thread scheduling. Returns the number of
+// rules written with each antecedent length, indexed by length.
+pub fn stream_rules(
+    itemsets: &[ItemSet],
+    dataset_size: u32,
+    params: &RuleParameters,
+    deterministic_order: bool,
+    output_rules_path: &str,
+    options: &OutputOptions,
+    itemizer: &Itemizer,
+) -> io::Result<Vec<usize>> {
+    let mut output = OutputFile::create(output_rules_path, options.compression)?;
+    writeln!(output, "{}", RULES_HEADER)?;
+    let generator = RuleGenerator::new(itemsets, dataset_size, params);
+    let max_length = itemsets
+        .iter()
+        .map(|itemset| itemset.len())
+        .max()
+        .unwrap_or(0);
+    let num_rules: Vec<AtomicUsize> = (0..max_length).map(|_| AtomicUsize::new(0)).collect();
+    let (sender, receiver) = mpsc::sync_channel::<Vec<u8>>(RULE_CHANNEL_CAPACITY);
+    thread::scope(|scope| {
+        let writer = scope.spawn(move || -> io::Result<()> {
+            for buffer in receiver {
+                output.write_all(&buffer)?;
+            }
+            output.finish()
+        });
+        let serialize = |buffer: &mut Vec<u8>, itemset: &ItemSet| {
+            let rules = generator.rules_for(itemset);
+            for rule in &rules {
+                num_rules[rule.antecedent.len()].fetch_add(1, Ordering::Relaxed);
+            }
+            write_rule_lines(buffer, &rules, options.number_format, itemizer);
+        };
+        // If the writer fails it hangs up, and sends fail; its error is
+        // reported when it's joined below.
+        if deterministic_order {
+            for batch in itemsets.chunks(RULE_BATCH_SIZE) {
+                let buffers: Vec<Vec<u8>> = batch
+                    .par_chunks(RULE_CHUNK_SIZE)
+                    .map(|chunk| {
+                        let mut buffer = vec![];
+                        for itemset in chunk {
+                            serialize(&mut buffer, itemset);
+                        }
+                        buffer
+                    })
+                    .collect();
+                for buffer in buffers {
+                    if sender.send(buffer).is_err() {
+                        break;
+                    }
+                }
+            }
+        } else {
+            itemsets
+                .par_iter()
+                .fold(Vec::new, |mut buffer, itemset| {
+                    serialize(&mut buffer, itemset);
+                    if buffer.len() >= RULE_BUFFER_SIZE {
+                        let _ = sender.send(mem::take(&mut buffer));
+                    }
+                    buffer
+                })
+                .for_each(|buffer| {
+                    if !buffer.is_empty() {
+                        let _ = sender.send(buffer);
+                    }
+                });
+        }
+        drop(sender);
+        writer.join().expect("Rule writer thread panicked")
+    })?;
+    Ok(num_rules
+        .into_iter()
+        .map(|count| count.into_inner())
+        .collect())
+}
+
+// Serializes rules into buffer. Writing to a Vec can't fail.
+pub fn write_rule_lines(
+    buffer: &mut Vec<u8>,
+    rules: &[Rule],
+    number_format: NumberFormat,
+    itemizer: &Itemizer,
+) {
+    for rule in rules.iter() {
+        write_item_slice(buffer, &rule.antecedent, itemizer).unwrap();
+        buffer.extend_from_slice(b" => ");
+        write_item_slice(buffer, &rule.consequent, itemizer).unwrap();
+        let write_numbers = |buffer: &mut Vec<u8>, values: &[f64]| {
+            for &value in values {
+                buffer.push(b',');
+                number_format.write(buffer, value).unwrap();
+            }
+        };
+        write_numbers(buffer, &[rule.confidence, rule.lift, rule.support]);
+        write!(
+            buffer,
+            ",{},{},{}",
+            rule.antecedent_count, rule.consequent_count, rule.count
+        )
+        .unwrap();
+        write_numbers(
+            buffer,
+            &[
+                rule.confidence_lower_bound,
+                rule.confidence_upper_bound,
+                rule.lift_std_error,
+                rule.laplace,
+                rule.added_value,
+                rule.gini_gain,
+                rule.info_gain,
+                rule.odds_ratio,
+                rule.yules_q,
+                rule.phi,
+            ],
+        );
+        buffer.push(b',');
+        // Rules only have a p-value if a permutation test was run.
+        if let Some(p_value) = rule.p_value {
+            number_format.write(buffer, p_value).unwrap();
+        }
+        buffer.push(b'\n');
+    }
+}
+
+pub fn write_itemsets(
+    itemsets: &[ItemSet],
+    num_transactions: usize,
+    output_itemsets_path: &str,
+    options: &OutputOptions,
+    itemizer: &Itemizer,
+) -> io::Result<()> {
+    let mut output = OutputFile::create(output_itemsets_path, options.compression)?;
+    writeln!(output, "Itemset,Support,Count")?;
+    for itemset in itemsets.iter() {
+        write_item_slice(&mut output, &itemset.items, itemizer)?;
+        write!(output, ",")?;
+        options
+            .number_format
+            .write(&mut output, itemset.count as f64 / num_transactions as f64)?;
+        writeln!(output, ",{}", itemset.count)?;
+    }
+    output.finish()
+}
+
+// Writes minimal generators with their closures, as found by
+// lattice::minimal_generators.
+pub fn write_generators(
+    generators: &[(ItemSet, ItemSet)],
+    num_transactions: usize,
+    path: &str,
+    options: &OutputOptions,
+    itemizer: &Itemizer,
+) -> io::Result<()> {
+    let mut output = OutputFile::create(path, options.compression)?;
+    writeln!(output, "Generator,Closure,Support,Count")?;
+    for (generator, closure) in generators {
+        write_item_slice(&mut output, &generator.items, itemizer)?;
+        write!(output, ",")?;
+        write_item_slice(&mut output, &closure.items, itemizer)?;
+        write!(output, ",")?;
+        options
+            .number_format
+            .write(&mut output, closure.count as f64 / num_transactions as f64)?;
+        writeln!(output, ",{}", closure.count)?;
+    }
+    output.finish()
+}
+
+pub fn write_item_slice<W: Write>(
+    output: &mut W,
+    items: &[Item],
+    itemizer: &Itemizer,
+) -> io::Result<()> {
+    let mut first = true;
+    for item in items.iter().map(|&id| itemizer.str_of(id)) {
+        if !first {
+            write!(output, " ")?;
+        } else {
+            first = false;
+        }
+        output.write_all(quote_item(item).as_bytes())?;
+    }
+    Ok(())
+}
+
+// Quotes an item name containing whitespace, commas or double quotes in
+// double quotes, doubling any double quotes, so that it can be read back
+// from a space separated list of items in a CSV field by
+// rule_file::parse_items.
+pub fn quote_item(name: &str) -> Cow<'_, str> {
+    if name.contains(|c: char| c.is_whitespace() || c == ',' || c == '"') {
+        Cow::Owned(format!("\"{}\"", name.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(name)
+    }
+}
+
+// Joins item names with spaces, quoting them as needed.
+pub fn join_items<S: AsRef<str>>(names: &[S]) -> String {
+    names
+        .iter()
+        .map(|name| quote_item(name.as_ref()))
+        .collect::<Vec<Cow<str>>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{write_itemsets, Compression, Notation, NumberFormat, OutputOptions};
+    use flate2::read::GzDecoder;
+    use fptree::ItemSet;
+    use itemizer::Itemizer;
+    use std::fs::{self, File};
+    use std::io::Read;
+    use test_util::TempFile;
+
+    #[test]
+    fn test_compressed_output() {
+        let mut itemizer = Itemizer::new();
+        let itemsets = vec![ItemSet::new(
+            vec![itemizer.id_of("a"), itemizer.id_of("b")],
+            2,
+        )];
+        let expected = "Itemset,Support,Count\na b,0.5,2\n";
+        let (gzip_path, zstd_path) = (
+            TempFile::new("itemsets.csv.gz"),
+            TempFile::new("itemsets.csv"),
+        );
+
+        let path = gzip_path.path();
+        assert_eq!(Compression::from_path(path), Compression::Gzip);
+        write_itemsets(&itemsets, 4, path, &OutputOptions::default(), &itemizer).unwrap();
+        let mut contents = String::new();
+        GzDecoder::new(File::open(path).unwrap())
+            .read_to_string(&mut contents)
+            .unwrap();
+        assert_eq!(contents, expected);
+
+        let path = zstd_path.path();
+        let options = OutputOptions {
+            compression: Some(Compression::Zstd),
+            ..OutputOptions::default()
+        };
+        write_itemsets(&itemsets, 4, path, &options, &itemizer).unwrap();
+        let contents = zstd::decode_all(File::open(path).unwrap()).unwrap();
+        assert_eq!(String::from_utf8(contents).unwrap(), expected);
+
+        write_itemsets(&itemsets, 4, path, &OutputOptions::default(), &itemizer).unwrap();
+        assert_eq!(fs::read_to_string(path).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_number_format() {
+        let format = |notation, precision| {
+            let mut buffer = vec![];
+            NumberFormat {
+                notation,
+                precision,
+            }
+            .write(&mut buffer, 0.0123456)
+            .unwrap();
+            String::from_utf8(buffer).unwrap()
+        };
+        assert_eq!(format(Notation::Fixed, None), "0.0123456");
+        assert_eq!(format(Notation::Fixed, Some(3)), "0.012");
+        assert_eq!(format(Notation::Scientific, None), "1.23456e-2");
+        assert_eq!(format(Notation::Scientific, Some(2)), "1.23e-2");
+    }
+}
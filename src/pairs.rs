@@ -0,0 +1,233 @@
+// Copyright 2018 Chris Pearce
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use counter::Counter;
+use error::ArmError;
+use fnv::FnvHashMap;
+use fptree::{CrossSupportPruning, ItemSet};
+use index::TransactionIndex;
+use item::Item;
+use item_counter::ItemCounter;
+
+// Rank of items which aren't frequent.
+const NO_RANK: u32 = u32::MAX;
+
+// Largest number of pairs to count in a triangular array; beyond this only
+// pairs which occur are counted, in a hash map.
+const MAX_TRIANGULAR_PAIRS: usize = 1 << 28;
+
+// Counts of pairs of frequent items, identified by their ranks.
+enum PairCounts {
+    // Counts of all pairs (i, j) with i < j, row by row, so (i, j) is at
+    // i * (2n - i - 1) / 2 + (j - i - 1).
+    Triangular { num_items: usize, counts: Vec<u32> },
+    Sparse(FnvHashMap<(u32, u32), u32>),
+}
+
+impl PairCounts {
+    fn new(num_items: usize) -> PairCounts {
+        let num_pairs = num_items * num_items.saturating_sub(1) / 2;
+        if num_pairs <= MAX_TRIANGULAR_PAIRS {
+            PairCounts::Triangular {
+                num_items,
+                counts: vec![0; num_pairs],
+            }
+        } else {
+            PairCounts::Sparse(FnvHashMap::default())
+        }
+    }
+
+    fn add(&mut self, i: u32, j: u32, count: u32) {
+        match *self {
+            PairCounts::Triangular {
+                num_items,
+                ref mut counts,
+            } => {
+                let (i, j) = (i as usize, j as usize);
+                counts[i * (2 * num_items - i - 1) / 2 + (j - i - 1)] += count;
+            }
+            PairCounts::Sparse(ref mut counts) => *counts.entry((i, j)).or_insert(0) += count,
+        }
+    }
+
+    // Returns the pairs counted at least min_count times.
+    fn frequent(&self, min_count: u32) -> Vec<(u32, u32, u32)> {
+        match *self {
+            PairCounts::Triangular {
+                num_items,
+                ref counts,
+            } => {
+                let mut pairs = vec![];
+                let mut index = 0;
+                for i in 0..num_items {
+                    for j in i + 1..num_items {
+                        if counts[index] >= min_count {
+                            pairs.push((i as u32, j as u32, counts[index]));
+                        }
+                        index += 1;
+                    }
+                }
+                pairs
+            }
+            PairCounts::Sparse(ref counts) => counts
+                .iter()
+                .filter(|&(_, &count)| count >= min_count)
+                .map(|(&(i, j), &count)| (i, j, count))
+                .collect(),
+        }
+    }
+}
+
+// Finds the frequent items and pairs of items in one pass over the
+// transactions, without building an FPTree. When only itemsets of up to two
+// items are wanted, this is much faster than FPGrowth. As when mining,
+// items and pairs are frequent if they occur at least min_count times, and
+// if cross_support is set, pairs must also pass it. If an index is passed,
+// the filtered transactions are also added to it.
+pub fn count_pairs<I>(
+    transactions: I,
+    item_count: &ItemCounter,
+    min_count: u32,
+    cross_support: Option<&CrossSupportPruning>,
+    index: Option<&mut dyn TransactionIndex>,
+) -> Result<Vec<ItemSet>, ArmError>
+where
+    I: IntoIterator<Item = Result<(Vec<Item>, u32), ArmError>>,
+{
+    let mut items: Vec<Item> = item_count.items_with_count_at_least(min_count);
+    items.sort();
+    let mut ranks: Vec<u32> = vec![NO_RANK; items.last().map_or(0, |item| item.as_index() + 1)];
+    for (rank, item) in items.iter().enumerate() {
+        ranks[item.as_index()] = rank as u32;
+    }
+
+    let mut index = index;
+    let mut pair_counts = PairCounts::new(items.len());
+    let mut transaction_ranks: Vec<u32> = vec![];
+    for transaction in transactions {
+        let (transaction, count) = transaction?;
+        transaction_ranks.clear();
+        transaction_ranks.extend(
+            transaction
+                .iter()
+                .filter_map(|item| ranks.get(item.as_index()))
+                .filter(|&&rank| rank != NO_RANK),
+        );
+        transaction_ranks.sort();
+        for (k, &i) in transaction_ranks.iter().enumerate() {
+            for &j in &transaction_ranks[k + 1..] {
+                pair_counts.add(i, j, count);
+            }
+        }
+        if let Some(ref mut index) = index {
+            let filtered: Vec<Item> = transaction_ranks
+                .iter()
+                .map(|&r| items[r as usize])
+                .collect();
+            index.insert(&filtered, count);
+        }
+    }
+    if let Some(index) = index {
+        index.finish();
+    }
+
+    let mut itemsets: Vec<ItemSet> = items
+        .iter()
+        .map(|&item| ItemSet::new(vec![item], item_count.get(&item)))
+        .collect();
+    itemsets.extend(
+        pair_counts
+            .frequent(min_count)
+            .into_iter()
+            .map(|(i, j, count)| ItemSet::new(vec![items[i as usize], items[j as usize]], count))
+            .filter(|pair| cross_support.is_none_or(|c| c.admits(&pair.items, pair.count))),
+    );
+    Ok(itemsets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{count_pairs, PairCounts};
+    use cancellation::CancellationToken;
+    use counter::Counter;
+    use fptree::{fp_growth, FPGrowthParameters, FPTree, ItemSet};
+    use item::Item;
+    use item_counter::ItemCounter;
+
+    #[test]
+    fn test_count_pairs() {
+        let transactions: Vec<Vec<Item>> = [
+            vec![1, 2, 3, 5],
+            vec![1, 2, 4],
+            vec![1, 3, 4, 5],
+            vec![2, 3],
+            vec![5, 4, 3, 2, 1],
+            vec![4, 5],
+            vec![1, 5, 6],
+            vec![2, 4, 5],
+        ]
+        .iter()
+        .map(|t| t.iter().map(|&i| Item::with_id(i)).collect())
+        .collect();
+        let mut item_count = ItemCounter::new();
+        for transaction in &transactions {
+            for item in transaction {
+                item_count.add(item, 1);
+            }
+        }
+        let min_count = 3;
+        let mut tree = FPTree::new();
+        for transaction in &transactions {
+            let mut transaction: Vec<Item> = transaction
+                .iter()
+                .cloned()
+                .filter(|item| item_count.get(item) >= min_count)
+                .collect();
+            item_count.sort_descending(&mut transaction);
+            tree.insert(&transaction, 1);
+        }
+        let params = FPGrowthParameters {
+            min_count,
+            max_itemset_size: Some(2),
+            cancellation: CancellationToken::new(),
+            cross_support: None,
+            item_min_counts: None,
+        };
+        let mut expected = fp_growth(&tree, &params, &[], transactions.len() as u32);
+        expected.sort();
+
+        let mut itemsets: Vec<ItemSet> = count_pairs(
+            transactions.into_iter().map(|t| Ok((t, 1))),
+            &item_count,
+            min_count,
+            None,
+            None,
+        )
+        .unwrap();
+        itemsets.sort();
+        assert_eq!(itemsets, expected);
+
+        // The sparse counts find the same pairs as the triangular array.
+        let mut triangular = PairCounts::new(4);
+        let mut sparse = PairCounts::Sparse(Default::default());
+        for &(i, j) in &[(0, 1), (0, 3), (2, 3), (0, 1)] {
+            triangular.add(i, j, 1);
+            sparse.add(i, j, 1);
+        }
+        let mut pairs = sparse.frequent(1);
+        pairs.sort();
+        assert_eq!(triangular.frequent(1), pairs);
+        assert_eq!(triangular.frequent(2), vec![(0, 1, 2)]);
+    }
+}
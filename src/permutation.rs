@@ -0,0 +1,158 @@
+// Copyright 2018 Chris Pearce
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use counter::Counter;
+use fnv::FnvHashMap;
+use item::Item;
+use item_counter::ItemCounter;
+use rand::rngs::StdRng;
+use rand::seq::index::sample;
+use rand::SeedableRng;
+use rayon::prelude::*;
+use roaring::RoaringBitmap;
+use rule::Rule;
+
+// Relative tolerance when comparing lifts, so that a permutation which
+// reproduces the observed counts counts as at least as extreme.
+const LIFT_TOLERANCE: f64 = 1e-9;
+
+// Returns the bitmap of transactions containing all of items.
+fn transactions_containing(
+    items: &[Item],
+    bitmaps: &FnvHashMap<Item, RoaringBitmap>,
+) -> RoaringBitmap {
+    let mut result = bitmaps[&items[0]].clone();
+    for item in &items[1..] {
+        result &= &bitmaps[item];
+    }
+    result
+}
+
+// For one permutation, returns for each rule whether its lift in the
+// permuted dataset is at least its observed lift.
+fn exceedances(
+    rules: &[&Rule],
+    items: &[(Item, u32)],
+    num_transactions: u32,
+    seed: u64,
+) -> Vec<u32> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    // Shuffling which transactions an item occurs in is equivalent to
+    // picking a random set of transactions of the same size.
+    let bitmaps: FnvHashMap<Item, RoaringBitmap> = items
+        .iter()
+        .map(|&(item, count)| {
+            let mut tids: Vec<u32> = sample(&mut rng, num_transactions as usize, count as usize)
+                .into_iter()
+                .map(|tid| tid as u32)
+                .collect();
+            tids.sort_unstable();
+            (item, RoaringBitmap::from_sorted_iter(tids).unwrap())
+        })
+        .collect();
+    let n = num_transactions as f64;
+    rules
+        .iter()
+        .map(|rule| {
+            let antecedent = transactions_containing(&rule.antecedent, &bitmaps);
+            let consequent = transactions_containing(&rule.consequent, &bitmaps);
+            let count = antecedent.intersection_len(&consequent) as f64;
+            let denominator = antecedent.len() as f64 * consequent.len() as f64;
+            let lift = if denominator > 0.0 {
+                count * n / denominator
+            } else {
+                0.0
+            };
+            u32::from(lift >= rule.lift * (1.0 - LIFT_TOLERANCE))
+        })
+        .collect()
+}
+
+// Estimates the significance of rules' lifts with a permutation test, and
+// sets their p_value. Each permutation independently shuffles which
+// transactions each item occurs in, which keeps items' supports but breaks
+// any association between them. A rule's p-value is the proportion of
+// permutations, counting the observed dataset as one, in which its lift is
+// at least its observed lift. Permutation i is seeded with seed ^ i, so
+// results don't depend on the number of threads.
+pub fn permutation_test(
+    rules: &mut [Vec<Rule>],
+    item_count: &ItemCounter,
+    num_transactions: u32,
+    num_permutations: usize,
+    seed: u64,
+) {
+    let mut items: Vec<Item> = rules
+        .iter()
+        .flatten()
+        .flat_map(|rule| rule.antecedent.iter().chain(rule.consequent.iter()))
+        .cloned()
+        .collect();
+    items.sort();
+    items.dedup();
+    let items: Vec<(Item, u32)> = items
+        .into_iter()
+        .map(|item| (item, item_count.get(&item).min(num_transactions)))
+        .collect();
+    let flattened: Vec<&Rule> = rules.iter().flatten().collect();
+    let num_rules = flattened.len();
+    let counts = (0..num_permutations)
+        .into_par_iter()
+        .map(|i| exceedances(&flattened, &items, num_transactions, seed ^ i as u64))
+        .reduce(
+            || vec![0; num_rules],
+            |mut a, b| {
+                for (count, other) in a.iter_mut().zip(b) {
+                    *count += other;
+                }
+                a
+            },
+        );
+    for (rule, count) in rules.iter_mut().flatten().zip(counts) {
+        rule.p_value = Some((count as f64 + 1.0) / (num_permutations as f64 + 1.0));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::permutation_test;
+    use counter::Counter;
+    use item::Item;
+    use item_counter::ItemCounter;
+    use rule::Rule;
+
+    #[test]
+    fn test_permutation_test() {
+        let items = |ids: &[u32]| -> Vec<Item> { ids.iter().map(|&id| Item::from(id)).collect() };
+        let mut item_count = ItemCounter::new();
+        for (id, count) in [(1, 20), (2, 20), (3, 50), (4, 50)] {
+            item_count.add(&Item::from(id), count);
+        }
+        let mut rules = vec![vec![
+            // Items 1 and 2 always occur together; far more often than by
+            // chance.
+            Rule::new(items(&[1]), items(&[2]), 20, 20, 20, 100),
+            // Items 3 and 4 occur together as often as expected by chance.
+            Rule::new(items(&[3]), items(&[4]), 25, 50, 50, 100),
+        ]];
+        permutation_test(&mut rules, &item_count, 100, 99, 7);
+        let p_values: Vec<f64> = rules[0].iter().map(|rule| rule.p_value.unwrap()).collect();
+        assert_eq!(p_values[0], 0.01);
+        assert!(p_values[1] > 0.2);
+
+        // The same seed gives the same p-values.
+        permutation_test(&mut rules, &item_count, 100, 99, 7);
+        assert_eq!(rules[0][1].p_value.unwrap(), p_values[1]);
+    }
+}
@@ -0,0 +1,150 @@
+// Copyright 2018 Chris Pearce
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use error::ArmError;
+use input::csv_line;
+use postgres::{Client, NoTls};
+use std::io::{self, Read};
+
+// Number of rows fetched from the cursor at a time.
+const FETCH_SIZE: usize = 10_000;
+
+// Groups (transaction id, item) rows, sorted by transaction id, into lines
+// of CSV. Rows with a null item are skipped.
+#[derive(Default)]
+struct RowGrouper {
+    transaction_id: Option<Option<String>>,
+    items: Vec<String>,
+}
+
+impl RowGrouper {
+    fn add(&mut self, id: Option<String>, item: Option<String>, lines: &mut Vec<u8>) {
+        if self.transaction_id.as_ref() != Some(&id) {
+            self.finish(lines);
+            self.transaction_id = Some(id);
+        }
+        if let Some(item) = item {
+            self.items.push(item);
+        }
+    }
+
+    // Writes the current transaction, if it has any items.
+    fn finish(&mut self, lines: &mut Vec<u8>) {
+        if !self.items.is_empty() {
+            lines.extend_from_slice(csv_line(&self.items).as_bytes());
+            self.items.clear();
+        }
+        self.transaction_id = None;
+    }
+}
+
+// Reads the rows returned by a query as lines of transactions. The query
+// returns (transaction id, item) rows, of any types which can be cast to
+// text, and is run in a read only transaction with its results sorted by
+// transaction id and fetched through a cursor, so they needn't fit in
+// memory.
+pub struct PostgresReader {
+    client: Client,
+    grouper: RowGrouper,
+    done: bool,
+    buffer: Vec<u8>,
+    position: usize,
+}
+
+impl PostgresReader {
+    pub fn open(url: &str, query: &str) -> Result<PostgresReader, ArmError> {
+        let error = |e: postgres::Error| ArmError::io(url, io::Error::other(e));
+        let mut client = Client::connect(url, NoTls).map_err(error)?;
+        client.batch_execute("BEGIN READ ONLY").map_err(error)?;
+        client
+            .batch_execute(&format!(
+                "DECLARE arm_rows NO SCROLL CURSOR FOR \
+                 SELECT id::text, item::text FROM ({}) AS rows (id, item) ORDER BY 1",
+                query
+            ))
+            .map_err(error)?;
+        Ok(PostgresReader {
+            client,
+            grouper: RowGrouper::default(),
+            done: false,
+            buffer: vec![],
+            position: 0,
+        })
+    }
+
+    // Fetches rows into the buffer until it holds at least one complete
+    // transaction, or the rows run out.
+    fn fetch(&mut self) -> io::Result<()> {
+        self.buffer.clear();
+        self.position = 0;
+        while self.buffer.is_empty() && !self.done {
+            let rows = self
+                .client
+                .query(format!("FETCH {} FROM arm_rows", FETCH_SIZE).as_str(), &[])
+                .map_err(io::Error::other)?;
+            for row in &rows {
+                let id = row.try_get(0).map_err(io::Error::other)?;
+                let item = row.try_get(1).map_err(io::Error::other)?;
+                self.grouper.add(id, item, &mut self.buffer);
+            }
+            if rows.len() < FETCH_SIZE {
+                self.grouper.finish(&mut self.buffer);
+                self.client
+                    .batch_execute("COMMIT")
+                    .map_err(io::Error::other)?;
+                self.done = true;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Read for PostgresReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.position == self.buffer.len() {
+            self.fetch()?;
+        }
+        let n = buf.len().min(self.buffer.len() - self.position);
+        buf[..n].copy_from_slice(&self.buffer[self.position..self.position + n]);
+        self.position += n;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RowGrouper;
+
+    #[test]
+    fn test_row_grouper() {
+        let rows = [
+            (Some("1"), Some("milk")),
+            (Some("1"), Some("bread, white")),
+            (Some("2"), None),
+            (Some("3"), Some("eggs")),
+            (None, Some("jam")),
+            (None, Some("tea")),
+        ];
+        let mut grouper = RowGrouper::default();
+        let mut lines = vec![];
+        for &(id, item) in rows.iter() {
+            grouper.add(id.map(String::from), item.map(String::from), &mut lines);
+        }
+        grouper.finish(&mut lines);
+        assert_eq!(
+            String::from_utf8(lines).unwrap(),
+            "milk,\"bread, white\"\neggs\njam,tea\n"
+        );
+    }
+}
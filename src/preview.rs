@@ -0,0 +1,202 @@
+// Copyright 2018 Chris Pearce
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use error::ArmError;
+use input::open_input;
+use rule_file::RuleRecord;
+use std::cmp::Ordering;
+use std::env;
+use std::io::{self, BufRead, IsTerminal};
+use std::str::FromStr;
+
+// Rules wider than this are truncated in the preview.
+const MAX_RULE_WIDTH: usize = 70;
+
+const BOLD: &str = "\x1b[1m";
+const CYAN: &str = "\x1b[36m";
+const GREEN: &str = "\x1b[32m";
+const RESET: &str = "\x1b[0m";
+
+// Whether the preview is colored with ANSI escape codes. Auto colors it if
+// stdout is a terminal, unless the NO_COLOR environment variable is set.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    pub fn enabled(self) -> bool {
+        match self {
+            ColorChoice::Auto => io::stdout().is_terminal() && env::var_os("NO_COLOR").is_none(),
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+        }
+    }
+}
+
+impl FromStr for ColorChoice {
+    type Err = String;
+    fn from_str(s: &str) -> Result<ColorChoice, String> {
+        match s {
+            "auto" => Ok(ColorChoice::Auto),
+            "always" => Ok(ColorChoice::Always),
+            "never" => Ok(ColorChoice::Never),
+            _ => Err(format!("Unknown color choice {}", s)),
+        }
+    }
+}
+
+fn by_lift(a: &RuleRecord, b: &RuleRecord) -> Ordering {
+    (b.lift, b.confidence)
+        .partial_cmp(&(a.lift, a.confidence))
+        .unwrap_or(Ordering::Equal)
+}
+
+// Reads the n rules with the highest lift, ties broken by confidence, from
+// a rules file written by `arm mine`, which may be compressed. Only about
+// 2n rules are held at once, so the file may be larger than memory.
+pub fn top_rules_by_lift(path: &str, n: usize) -> Result<Vec<RuleRecord>, ArmError> {
+    let mut top: Vec<RuleRecord> = vec![];
+    for (index, line) in open_input(path, None)?.lines().enumerate() {
+        let line = line.map_err(|e| ArmError::io(path, e))?;
+        if (index == 0 && line.starts_with("Antecedent")) || line.trim().is_empty() {
+            continue;
+        }
+        let rule = line
+            .parse::<RuleRecord>()
+            .map_err(|message| ArmError::parse(path, index + 1, &message))?;
+        top.push(rule);
+        if top.len() >= 2 * n.max(1) {
+            top.sort_by(by_lift);
+            top.truncate(n);
+        }
+    }
+    top.sort_by(by_lift);
+    top.truncate(n);
+    Ok(top)
+}
+
+// Truncates s to width characters, ending it with "..." if shortened.
+fn truncate(s: &str, width: usize) -> String {
+    if s.chars().count() <= width {
+        return String::from(s);
+    }
+    if width <= 3 {
+        return s.chars().take(width).collect();
+    }
+    let mut truncated: String = s.chars().take(width - 3).collect();
+    truncated.push_str("...");
+    truncated
+}
+
+// Formats rules as a table aligned in columns, optionally colored.
+pub fn format_preview(rules: &[RuleRecord], color: bool) -> String {
+    let paint = |text: String, code: &str| {
+        if color {
+            format!("{}{}{}", code, text, RESET)
+        } else {
+            text
+        }
+    };
+    let sides: Vec<(String, String)> = rules
+        .iter()
+        .map(|rule| (rule.antecedent.join(" "), rule.consequent.join(" ")))
+        .collect();
+    let width = sides
+        .iter()
+        .map(|(antecedent, consequent)| antecedent.chars().count() + consequent.chars().count() + 4)
+        .max()
+        .unwrap_or(0)
+        .clamp("Rule".len(), MAX_RULE_WIDTH);
+
+    let mut table = paint(
+        format!(
+            "{:<width$}  {:>10}  {:>10}  {:>10}",
+            "Rule",
+            "Lift",
+            "Confidence",
+            "Support",
+            width = width
+        ),
+        BOLD,
+    );
+    table.push('\n');
+    for (rule, (antecedent, consequent)) in rules.iter().zip(sides) {
+        // If the rule is too wide, the longer side is truncated, leaving
+        // each side at least half the width.
+        let available = width - 4;
+        let antecedent_width = available
+            .saturating_sub(consequent.chars().count())
+            .max(available / 2);
+        let antecedent = truncate(&antecedent, antecedent_width);
+        let consequent = truncate(&consequent, available - antecedent.chars().count());
+        let padding = available - antecedent.chars().count() - consequent.chars().count();
+        table += &format!(
+            "{} => {}{}  {:>10.4}  {:>10.4}  {:>10.4}\n",
+            paint(antecedent, CYAN),
+            paint(consequent, GREEN),
+            " ".repeat(padding),
+            rule.lift,
+            rule.confidence,
+            rule.support
+        );
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_preview, top_rules_by_lift};
+    use std::fs;
+    use test_util::TempFile;
+
+    #[test]
+    fn test_preview() {
+        let path = TempFile::new("preview.csv");
+        let mut contents = String::from("Antecedent => Consequent,Confidence,Lift,Support\n");
+        for i in 0..10 {
+            contents += &format!("a{} => b,0.5,{},0.1\n", i, i % 5);
+        }
+        contents += "bread milk => eggs,0.9,4,0.2\n";
+        fs::write(&path, contents).unwrap();
+        let rules = top_rules_by_lift(path.path(), 3).unwrap();
+        let lines: Vec<String> = rules.iter().map(|rule| rule.line.clone()).collect();
+        assert_eq!(
+            lines,
+            vec![
+                "bread milk => eggs,0.9,4,0.2",
+                "a4 => b,0.5,4,0.1",
+                "a9 => b,0.5,4,0.1"
+            ]
+        );
+
+        let table = format_preview(&rules, false);
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(
+            lines[0],
+            "Rule                      Lift  Confidence     Support"
+        );
+        assert_eq!(
+            lines[1],
+            "bread milk => eggs      4.0000      0.9000      0.2000"
+        );
+        assert_eq!(
+            lines[2],
+            "a4 => b                 4.0000      0.5000      0.1000"
+        );
+        assert!(format_preview(&rules, true).contains("\x1b[36mbread milk\x1b[0m"));
+    }
+}
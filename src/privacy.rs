@@ -0,0 +1,403 @@
+// Copyright 2018 Chris Pearce
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use error::ArmError;
+use fnv::{FnvHashMap, FnvHashSet};
+use fptree::ItemSet;
+use item::Item;
+use rand::seq::index::sample;
+use rand::Rng;
+use serde_json::{json, Value};
+
+// Probability that selection noise lifts an itemset whose count is the
+// candidate minimum count over the minimum count, which sets how far below
+// the minimum count candidates are mined.
+const MISSED_NOISE_PROBABILITY: f64 = 0.05;
+
+// Fraction of epsilon spent choosing which itemsets to release; the rest is
+// spent on the counts released for them.
+const SELECTION_BUDGET_FRACTION: f64 = 0.5;
+
+// Selecting more than this many itemsets which weren't candidates means the
+// noise swamps the data; a larger epsilon or minimum support is needed.
+const MAX_NOISE_ITEMSETS: f64 = 1e6;
+
+// Parameters of differentially private mining. Datasets are neighbours if
+// they differ by replacing one transaction, so the number of transactions
+// is public. Transactions are truncated to max_transaction_length items, so
+// replacing one changes at most sensitivity itemset counts, each by one.
+// The set of items is treated as public, like the column names of a table.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PrivacyParameters {
+    pub epsilon: f64,
+    pub max_transaction_length: usize,
+    // Itemsets larger than this are never released.
+    pub max_itemset_size: usize,
+    pub sensitivity: f64,
+}
+
+impl PrivacyParameters {
+    // The sensitivity is twice the number of itemsets of at most
+    // max_itemset_size items in a transaction of max_transaction_length,
+    // as the replaced transaction's itemsets each lose one and the new
+    // transaction's each gain one.
+    pub fn new(
+        epsilon: f64,
+        max_transaction_length: usize,
+        max_itemset_size: Option<usize>,
+    ) -> PrivacyParameters {
+        let max_size = max_itemset_size.map_or(max_transaction_length, |max_size| {
+            max_size.min(max_transaction_length)
+        });
+        PrivacyParameters {
+            epsilon,
+            max_transaction_length,
+            max_itemset_size: max_size,
+            sensitivity: 2.0 * num_itemsets(max_transaction_length, max_size).round(),
+        }
+    }
+
+    // Budget spent selecting the itemsets to release.
+    pub fn selection_epsilon(&self) -> f64 {
+        self.epsilon * SELECTION_BUDGET_FRACTION
+    }
+
+    // Budget spent on the counts of the itemsets released.
+    pub fn count_epsilon(&self) -> f64 {
+        self.epsilon - self.selection_epsilon()
+    }
+
+    // Parameter of the two-sided geometric distribution of the noise added
+    // with budget epsilon; the probability of noise n is proportional to
+    // alpha^|n|.
+    fn alpha(&self, epsilon: f64) -> f64 {
+        (-epsilon / self.sensitivity).exp()
+    }
+
+    // The minimum count to mine candidates at. Counts below it are raised
+    // to it before noise is added, so itemsets which aren't mined all have
+    // the same count and needn't be enumerated. It's chosen so that
+    // selection noise lifts an itemset with this count over min_count with
+    // probability at most MISSED_NOISE_PROBABILITY, but it's lowered by at
+    // most half, so that small epsilons don't give too many candidates.
+    pub fn candidate_min_count(&self, min_count: u32) -> u32 {
+        let alpha = self.alpha(self.selection_epsilon());
+        let margin = (MISSED_NOISE_PROBABILITY * (1.0 + alpha)).ln() / alpha.ln();
+        let lowered = (min_count as f64 - margin.ceil()).max((min_count / 2) as f64);
+        1.max(lowered as u32)
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "mechanism": "geometric",
+            "neighbouring": "substitution",
+            "epsilon": self.epsilon,
+            "selection_epsilon": self.selection_epsilon(),
+            "count_epsilon": self.count_epsilon(),
+            "max_transaction_length": self.max_transaction_length,
+            "max_itemset_size": self.max_itemset_size,
+            "sensitivity": self.sensitivity,
+        })
+    }
+}
+
+// Number of itemsets of 1 to max_size items from num_items items.
+fn num_itemsets(num_items: usize, max_size: usize) -> f64 {
+    let mut total = 0.0;
+    let mut combinations = 1.0;
+    for size in 1..=max_size.min(num_items) {
+        combinations *= (num_items - size + 1) as f64 / size as f64;
+        total += combinations;
+    }
+    total
+}
+
+// Number of failures before a success of probability 1 - alpha, by
+// inverting its distribution function.
+fn geometric<R: Rng>(alpha: f64, rng: &mut R) -> i64 {
+    let uniform: f64 = 1.0 - rng.gen::<f64>();
+    (uniform.ln() / alpha.ln()).floor() as i64
+}
+
+// Draws from the two-sided geometric distribution, the discrete Laplace
+// distribution, as the difference of two geometric variables.
+fn two_sided_geometric<R: Rng>(alpha: f64, rng: &mut R) -> i64 {
+    geometric(alpha, rng) - geometric(alpha, rng)
+}
+
+// Probability that two-sided geometric noise is at least d.
+fn probability_at_least(alpha: f64, d: i64) -> f64 {
+    if d >= 1 {
+        alpha.powi(d as i32) / (1.0 + alpha)
+    } else {
+        1.0 - probability_at_least(alpha, 1 - d)
+    }
+}
+
+// Draws from the binomial distribution of trials with success probability
+// p, by skipping geometric runs of failures, so it takes time proportional
+// to the number of successes even for an astronomical number of trials.
+fn binomial<R: Rng>(trials: f64, p: f64, rng: &mut R) -> usize {
+    if p <= 0.0 {
+        return 0;
+    }
+    let mut successes = 0;
+    let mut position = 0.0;
+    loop {
+        let uniform: f64 = 1.0 - rng.gen::<f64>();
+        position += (uniform.ln() / (-p).ln_1p()).floor() + 1.0;
+        if position > trials {
+            return successes;
+        }
+        successes += 1;
+    }
+}
+
+// Picks num_picked distinct itemsets of at most max_size of items uniformly
+// at random from those not in excluded.
+fn pick_itemsets<R: Rng>(
+    num_picked: usize,
+    items: &[Item],
+    max_size: usize,
+    excluded: &FnvHashSet<Vec<Item>>,
+    rng: &mut R,
+) -> Vec<Vec<Item>> {
+    // Cumulative number of itemsets of each size, to pick sizes in
+    // proportion to how many itemsets there are of each.
+    let mut cumulative = vec![];
+    let mut combinations = 1.0;
+    for size in 1..=max_size.min(items.len()) {
+        combinations *= (items.len() - size + 1) as f64 / size as f64;
+        cumulative.push(cumulative.last().unwrap_or(&0.0) + combinations);
+    }
+    let mut picked: FnvHashSet<Vec<Item>> = FnvHashSet::default();
+    while picked.len() < num_picked {
+        let point = rng.gen::<f64>() * cumulative.last().unwrap_or(&0.0);
+        let size = 1 + cumulative
+            .iter()
+            .position(|&total| point < total)
+            .unwrap_or(cumulative.len() - 1);
+        let mut itemset: Vec<Item> = sample(rng, items.len(), size)
+            .into_iter()
+            .map(|index| items[index])
+            .collect();
+        itemset.sort();
+        if !excluded.contains(&itemset) {
+            picked.insert(itemset);
+        }
+    }
+    picked.into_iter().collect()
+}
+
+// Releases frequent itemsets with differential privacy. Candidates must be
+// every itemset of at most params.max_itemset_size items whose count is at
+// least candidate_min_count, with exact counts, and items every item; both
+// come from mining at candidate_min_count.
+//
+// Each itemset's count is raised to candidate_min_count if it's below it,
+// which changes no count by more than the transaction replaced does. Then:
+//
+// 1. Selection: noise with the selection budget is added to the count of
+//    every possible itemset, and those whose noisy counts are at least
+//    min_count are selected. Itemsets which weren't mined all have the same
+//    count, so how many of them are selected is drawn from the binomial
+//    distribution, and which ones uniformly, rather than enumerating them.
+// 2. Counts: fresh noise with the rest of the budget is added to the counts
+//    of the selected itemsets.
+//
+// Everything after is post-processing, which costs no privacy: itemsets
+// are kept only if their subsets are, and noisy counts are capped at the
+// noisy counts of their subsets and kept if at least min_count, so that
+// rules can be generated from the itemsets released.
+pub fn add_noise<R: Rng>(
+    candidates: Vec<ItemSet>,
+    candidate_min_count: u32,
+    min_count: u32,
+    items: &[Item],
+    num_transactions: u32,
+    params: &PrivacyParameters,
+    rng: &mut R,
+) -> Result<Vec<ItemSet>, ArmError> {
+    let selection_alpha = params.alpha(params.selection_epsilon());
+    let count_alpha = params.alpha(params.count_epsilon());
+    let floor = candidate_min_count as i64;
+    let threshold = min_count as i64;
+
+    let mut selected: Vec<(Vec<Item>, i64)> = vec![];
+    let mut mined: FnvHashSet<Vec<Item>> = FnvHashSet::default();
+    for itemset in candidates {
+        let count = (itemset.count as i64).max(floor);
+        if count + two_sided_geometric(selection_alpha, rng) >= threshold {
+            selected.push((itemset.items.clone(), count));
+        }
+        mined.insert(itemset.items);
+    }
+    let num_unmined = num_itemsets(items.len(), params.max_itemset_size) - mined.len() as f64;
+    let p = probability_at_least(selection_alpha, threshold - floor);
+    if num_unmined * p > MAX_NOISE_ITEMSETS {
+        return Err(ArmError::limit_exceeded(&format!(
+            "Differential privacy noise would select about {:.0} itemsets which weren't mined; \
+             increase --dp-epsilon or --min-support",
+            num_unmined * p
+        )));
+    }
+    let num_noise_itemsets = binomial(num_unmined, p, rng);
+    selected.extend(
+        pick_itemsets(
+            num_noise_itemsets,
+            items,
+            params.max_itemset_size,
+            &mined,
+            rng,
+        )
+        .into_iter()
+        .map(|itemset| (itemset, floor)),
+    );
+
+    // Shorter itemsets first, so subsets are released before supersets.
+    selected.sort_by(|a, b| (a.0.len(), &a.0).cmp(&(b.0.len(), &b.0)));
+    let mut released: FnvHashMap<Vec<Item>, u32> = FnvHashMap::default();
+    let mut itemsets = vec![];
+    for (items, count) in selected {
+        let noisy = (count + two_sided_geometric(count_alpha, rng))
+            .clamp(0, num_transactions as i64) as u32;
+        // An itemset with a subset which wasn't released isn't either.
+        let cap = if items.len() == 1 {
+            num_transactions
+        } else {
+            (0..items.len())
+                .map(|skip| {
+                    let mut subset = items.clone();
+                    subset.remove(skip);
+                    released.get(&subset).cloned().unwrap_or(0)
+                })
+                .min()
+                .unwrap_or(0)
+        };
+        let count = noisy.min(cap);
+        if count >= min_count {
+            released.insert(items.clone(), count);
+            itemsets.push(ItemSet::new(items, count));
+        }
+    }
+    itemsets.sort();
+    Ok(itemsets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{add_noise, binomial, num_itemsets, PrivacyParameters};
+    use fnv::FnvHashSet;
+    use fptree::ItemSet;
+    use item::Item;
+    use rand::prelude::*;
+
+    #[test]
+    fn test_privacy() {
+        // 3 items, 3 pairs and 1 triple in a transaction of 3 items, each
+        // of which may be removed and another added.
+        let params = PrivacyParameters::new(1.0, 3, None);
+        assert_eq!(params.sensitivity, 14.0);
+        assert_eq!(PrivacyParameters::new(1.0, 10, Some(2)).sensitivity, 110.0);
+        assert_eq!(num_itemsets(4, 2), 10.0);
+        // The budget is split between selection and counts.
+        assert_eq!(params.selection_epsilon() + params.count_epsilon(), 1.0);
+        assert!(params.selection_epsilon() > 0.0 && params.count_epsilon() > 0.0);
+        let json = params.to_json();
+        assert_eq!(json["max_transaction_length"], 3);
+        assert_eq!(json["selection_epsilon"], 0.5);
+        assert_eq!(json["count_epsilon"], 0.5);
+        let min_count = params.candidate_min_count(1000);
+        assert!((500..1000).contains(&min_count));
+        assert_eq!(params.candidate_min_count(10), 5);
+
+        let mut rng = StdRng::seed_from_u64(1);
+        let mean = |trials, p, rng: &mut StdRng| {
+            (0..200).map(|_| binomial(trials, p, rng)).sum::<usize>() as f64 / 200.0
+        };
+        assert!((mean(1000.0, 0.01, &mut rng) - 10.0).abs() < 2.0);
+        assert!((mean(1e15, 1e-14, &mut rng) - 10.0).abs() < 2.0);
+
+        let a = Item::from(1);
+        let b = Item::from(2);
+        let c = Item::from(3);
+        let items = vec![a, b, c];
+        let itemsets = vec![
+            ItemSet::new(vec![a, b], 400),
+            ItemSet::new(vec![a], 900),
+            ItemSet::new(vec![b], 500),
+        ];
+        let release = |min_count, rng: &mut StdRng| {
+            let candidate_min_count = params.candidate_min_count(min_count);
+            add_noise(
+                itemsets.clone(),
+                candidate_min_count,
+                min_count,
+                &items,
+                1000,
+                &params,
+                rng,
+            )
+            .unwrap()
+        };
+        let mut total_error = 0;
+        let mut num_changed = 0;
+        for _ in 0..200 {
+            let noisy = release(100, &mut rng);
+            let count_of = |items: &[Item]| {
+                noisy
+                    .iter()
+                    .find(|itemset| itemset.items == items)
+                    .unwrap()
+                    .count
+            };
+            // Supersets are never counted more than their subsets.
+            assert!(count_of(&[a, b]) <= count_of(&[a]).min(count_of(&[b])));
+            total_error += (count_of(&[a]) as i64 - 900).abs();
+            if count_of(&[a]) != 900 {
+                num_changed += 1;
+            }
+        }
+        // Noise is added with half the budget; its mean absolute value is
+        // about 2 * sensitivity / epsilon = 28.
+        assert!(num_changed > 150);
+        let mean_error = total_error as f64 / 200.0;
+        assert!(mean_error > 10.0 && mean_error < 50.0, "{}", mean_error);
+
+        // Only itemsets whose noisy counts reach the minimum count are kept,
+        // and itemsets far below it are almost never selected, whether they
+        // were mined or not.
+        let mut num_kept = 0;
+        for _ in 0..50 {
+            let noisy = release(700, &mut rng);
+            assert!(noisy.iter().all(|itemset| itemset.count >= 700));
+            assert!(noisy.iter().all(|itemset| itemset.items != vec![c]));
+            num_kept += noisy
+                .iter()
+                .filter(|itemset| itemset.items == vec![a])
+                .count();
+        }
+        assert!(num_kept > 45);
+
+        // With a tiny budget, itemsets which weren't mined are selected too,
+        // as their counts are indistinguishable from the candidates'.
+        let params = PrivacyParameters::new(0.01, 3, None);
+        let mut seen: FnvHashSet<Vec<Item>> = FnvHashSet::default();
+        for _ in 0..20 {
+            let noisy = add_noise(vec![], 50, 100, &items, 1000, &params, &mut rng).unwrap();
+            seen.extend(noisy.into_iter().map(|itemset| itemset.items));
+        }
+        assert!(seen.contains(&vec![c]));
+    }
+}
@@ -0,0 +1,177 @@
+// Copyright 2018 Chris Pearce
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use command_line_args::{QueryArguments, RecommendArguments};
+use error::ArmError;
+use fnv::{FnvHashMap, FnvHashSet};
+use output::RULES_HEADER;
+use rule_file::{read_rules, RuleRecord};
+use std::cmp::Ordering;
+
+fn metric(rule: &RuleRecord, name: &str) -> f64 {
+    match name {
+        "confidence" => rule.confidence,
+        "support" => rule.support,
+        _ => rule.lift,
+    }
+}
+
+fn matches(rule: &RuleRecord, args: &QueryArguments) -> bool {
+    let side_contains = |item: &String| match args.side.as_str() {
+        "antecedent" => rule.antecedent.contains(item),
+        "consequent" => rule.consequent.contains(item),
+        _ => rule.contains(item),
+    };
+    args.items.iter().all(side_contains)
+        && rule.confidence >= args.min_confidence
+        && rule.lift >= args.min_lift
+}
+
+pub fn run_query(args: &QueryArguments) -> Result<(), ArmError> {
+    let mut rules: Vec<RuleRecord> = read_rules(&args.rules_path)?
+        .into_iter()
+        .filter(|rule| matches(rule, args))
+        .collect();
+    rules.sort_by(|a, b| {
+        metric(b, &args.sort_by)
+            .partial_cmp(&metric(a, &args.sort_by))
+            .unwrap_or(Ordering::Equal)
+    });
+    if let Some(limit) = args.limit {
+        rules.truncate(limit);
+    }
+    println!("{}", RULES_HEADER);
+    for rule in &rules {
+        println!("{}", rule.line);
+    }
+    Ok(())
+}
+
+pub struct Recommendation<'a> {
+    pub item: &'a str,
+    pub confidence: f64,
+    pub lift: f64,
+    // The rule which gave the item its score.
+    pub rule: &'a RuleRecord,
+}
+
+// Scores items not in the basket by the highest confidence of the rules
+// whose antecedent is in the basket and whose consequent contains the item.
+// Ties are broken by lift.
+pub fn recommend<'a, I>(rules: I, basket: &[String], top: usize) -> Vec<Recommendation<'a>>
+where
+    I: IntoIterator<Item = &'a RuleRecord>,
+{
+    let basket: FnvHashSet<&str> = basket.iter().map(|item| item.as_str()).collect();
+    let mut by_item: FnvHashMap<&str, Recommendation> = FnvHashMap::default();
+    let applicable = rules.into_iter().filter(|rule| {
+        rule.antecedent
+            .iter()
+            .all(|item| basket.contains(item.as_str()))
+    });
+    for rule in applicable {
+        for item in rule
+            .consequent
+            .iter()
+            .filter(|item| !basket.contains(item.as_str()))
+        {
+            let better = |r: &Recommendation| (rule.confidence, rule.lift) > (r.confidence, r.lift);
+            match by_item.get_mut(item.as_str()) {
+                Some(r) if better(r) => {
+                    r.confidence = rule.confidence;
+                    r.lift = rule.lift;
+                    r.rule = rule;
+                }
+                Some(_) => {}
+                None => {
+                    by_item.insert(
+                        item,
+                        Recommendation {
+                            item,
+                            confidence: rule.confidence,
+                            lift: rule.lift,
+                            rule,
+                        },
+                    );
+                }
+            }
+        }
+    }
+    let mut recommendations: Vec<Recommendation> = by_item.into_values().collect();
+    recommendations.sort_by(|a, b| {
+        (b.confidence, b.lift)
+            .partial_cmp(&(a.confidence, a.lift))
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| a.item.cmp(b.item))
+    });
+    recommendations.truncate(top);
+    recommendations
+}
+
+pub fn run_recommend(args: &RecommendArguments) -> Result<(), ArmError> {
+    let rules = read_rules(&args.rules_path)?;
+    let basket: Vec<String> = args
+        .basket
+        .split(',')
+        .map(|item| String::from(item.trim()))
+        .filter(|item| !item.is_empty())
+        .collect();
+    println!("{:<30} {:>10} {:>10}  Rule", "Item", "Confidence", "Lift");
+    for r in recommend(&rules, &basket, args.top) {
+        println!(
+            "{:<30} {:>10.4} {:>10.4}  {} => {}",
+            r.item,
+            r.confidence,
+            r.lift,
+            r.rule.antecedent.join(" "),
+            r.rule.consequent.join(" ")
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::recommend;
+    use rule_file::RuleRecord;
+
+    fn rule(antecedent: &[&str], consequent: &[&str], confidence: f64) -> RuleRecord {
+        RuleRecord {
+            antecedent: antecedent.iter().map(|&s| String::from(s)).collect(),
+            consequent: consequent.iter().map(|&s| String::from(s)).collect(),
+            confidence,
+            lift: 1.0,
+            support: 0.1,
+            line: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_recommend() {
+        let rules = vec![
+            rule(&["a"], &["b"], 0.5),
+            rule(&["a"], &["c"], 0.6),
+            rule(&["a", "d"], &["b"], 0.9),
+            rule(&["e"], &["f"], 1.0),
+            rule(&["a"], &["c", "d"], 0.4),
+        ];
+        let basket = vec![String::from("a"), String::from("c")];
+        let items: Vec<(&str, f64)> = recommend(&rules, &basket, 10)
+            .iter()
+            .map(|r| (r.item, r.confidence))
+            .collect();
+        assert_eq!(items, vec![("b", 0.5), ("d", 0.4)]);
+        assert_eq!(recommend(&rules, &basket, 1).len(), 1);
+    }
+}
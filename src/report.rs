@@ -0,0 +1,364 @@
+// Copyright 2018 Chris Pearce
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use fptree::ItemSet;
+use item::Item;
+use itemizer::Itemizer;
+use memory::{megabytes, MemoryUsage};
+use privacy::PrivacyParameters;
+use rule::Rule;
+use std::cmp::Ordering;
+use std::fmt::Write as FmtWrite;
+use std::fs;
+use std::io;
+use table::MissingCounts;
+
+// The report is meant to be read by people, so only includes the rules with
+// the highest lift, and the most frequent items.
+const MAX_REPORT_RULES: usize = 1000;
+const MAX_REPORT_ITEMS: usize = 20;
+
+const CHART_WIDTH: f64 = 640.0;
+const CHART_HEIGHT: f64 = 400.0;
+const CHART_MARGIN: f64 = 50.0;
+
+pub struct ReportSummary<'a> {
+    pub input_path: &'a str,
+    pub num_transactions: usize,
+    pub min_support: f64,
+    pub min_confidence: f64,
+    pub num_itemsets: usize,
+    pub num_rules: usize,
+    // Number of items left not fully mined when mining was stopped early.
+    pub num_incomplete_items: usize,
+    // Rows of a table input with missing values, and how many were dropped.
+    pub missing_counts: MissingCounts,
+    // Peak memory use, and the sizes of the largest data structures.
+    pub memory: MemoryUsage,
+    // Parameters of differentially private mining, if the counts are noisy.
+    pub privacy: Option<&'a PrivacyParameters>,
+}
+
+const STYLE: &str = "body{font-family:sans-serif;margin:2em;color:#222}\
+table{border-collapse:collapse;font-size:0.9em}\
+th,td{border:1px solid #ccc;padding:4px 8px;text-align:left}\
+th{background:#eee}#rules th{cursor:pointer}\
+td.n{text-align:right}.partial{color:#a00}\
+svg{border:1px solid #ccc;margin-bottom:1em}";
+
+// Sorts the rule table by the clicked column; clicking again reverses the
+// order. Numeric columns are sorted numerically.
+const SCRIPT: &str = "document.querySelectorAll('#rules th').forEach(function(th,col){\
+th.addEventListener('click',function(){\
+var body=document.querySelector('#rules tbody');\
+var rows=Array.from(body.rows);\
+var asc=th.dataset.asc!=='1';th.dataset.asc=asc?'1':'0';\
+rows.sort(function(a,b){\
+var x=a.cells[col].textContent,y=b.cells[col].textContent;\
+var c=(isNaN(x)||isNaN(y))?x.localeCompare(y):x-y;\
+return asc?c:-c;});\
+rows.forEach(function(r){body.appendChild(r);});});});";
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn item_names(items: &[Item], itemizer: &Itemizer) -> String {
+    let names: Vec<&str> = items.iter().map(|&item| itemizer.str_of(item)).collect();
+    escape(&names.join(" "))
+}
+
+fn top_rules(rules: &[Vec<Rule>]) -> Vec<&Rule> {
+    let mut top: Vec<&Rule> = rules.iter().flat_map(|chunk| chunk.iter()).collect();
+    top.sort_by(|a, b| b.lift.partial_cmp(&a.lift).unwrap_or(Ordering::Equal));
+    top.truncate(MAX_REPORT_RULES);
+    top
+}
+
+// Plots rules' support against confidence, as an SVG.
+fn scatter_plot(html: &mut String, rules: &[&Rule], itemizer: &Itemizer) {
+    let max_support = rules.iter().map(|rule| rule.support).fold(0.0, f64::max);
+    let max_support = if max_support > 0.0 { max_support } else { 1.0 };
+    let plot_width = CHART_WIDTH - 2.0 * CHART_MARGIN;
+    let plot_height = CHART_HEIGHT - 2.0 * CHART_MARGIN;
+    writeln!(
+        html,
+        "<svg width=\"{w}\" height=\"{h}\" xmlns=\"http://www.w3.org/2000/svg\">\
+         <line x1=\"{m}\" y1=\"{b}\" x2=\"{r}\" y2=\"{b}\" stroke=\"#222\"/>\
+         <line x1=\"{m}\" y1=\"{m}\" x2=\"{m}\" y2=\"{b}\" stroke=\"#222\"/>\
+         <text x=\"{cx}\" y=\"{h}\" dy=\"-10\" text-anchor=\"middle\">Support (max {max:.4})</text>\
+         <text x=\"15\" y=\"{cy}\" transform=\"rotate(-90 15 {cy})\" text-anchor=\"middle\">Confidence</text>",
+        w = CHART_WIDTH,
+        h = CHART_HEIGHT,
+        m = CHART_MARGIN,
+        b = CHART_HEIGHT - CHART_MARGIN,
+        r = CHART_WIDTH - CHART_MARGIN,
+        cx = CHART_WIDTH / 2.0,
+        cy = CHART_HEIGHT / 2.0,
+        max = max_support,
+    )
+    .unwrap();
+    for rule in rules {
+        writeln!(
+            html,
+            "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"3\" fill=\"#36c\" fill-opacity=\"0.5\">\
+             <title>{} =&gt; {} (lift {:.3})</title></circle>",
+            CHART_MARGIN + rule.support / max_support * plot_width,
+            CHART_HEIGHT - CHART_MARGIN - rule.confidence * plot_height,
+            item_names(&rule.antecedent, itemizer),
+            item_names(&rule.consequent, itemizer),
+            rule.lift
+        )
+        .unwrap();
+    }
+    html.push_str("</svg>\n");
+}
+
+// Plots the supports of the most frequent items, as an SVG bar chart.
+fn item_bar_chart(
+    html: &mut String,
+    itemsets: &[ItemSet],
+    num_transactions: usize,
+    itemizer: &Itemizer,
+) {
+    let mut items: Vec<&ItemSet> = itemsets
+        .iter()
+        .filter(|itemset| itemset.len() == 1)
+        .collect();
+    items.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.items.cmp(&b.items)));
+    items.truncate(MAX_REPORT_ITEMS);
+    let bar_height = 20.0;
+    let label_width = 200.0;
+    let bar_width = CHART_WIDTH - label_width - CHART_MARGIN;
+    writeln!(
+        html,
+        "<svg width=\"{}\" height=\"{}\" xmlns=\"http://www.w3.org/2000/svg\">",
+        CHART_WIDTH,
+        bar_height * items.len() as f64 + 10.0
+    )
+    .unwrap();
+    for (i, itemset) in items.iter().enumerate() {
+        let support = itemset.count as f64 / num_transactions as f64;
+        let y = 5.0 + i as f64 * bar_height;
+        writeln!(
+            html,
+            "<text x=\"{lx}\" y=\"{ty:.1}\" text-anchor=\"end\">{name}</text>\
+             <rect x=\"{x}\" y=\"{y:.1}\" width=\"{bw:.1}\" height=\"{bh}\" fill=\"#36c\"/>\
+             <text x=\"{tx:.1}\" y=\"{ty:.1}\">{support:.3}</text>",
+            lx = label_width - 5.0,
+            ty = y + bar_height * 0.7,
+            name = item_names(&itemset.items, itemizer),
+            x = label_width,
+            y = y + 2.0,
+            bw = support * bar_width,
+            bh = bar_height - 4.0,
+            tx = label_width + support * bar_width + 5.0,
+            support = support,
+        )
+        .unwrap();
+    }
+    html.push_str("</svg>\n");
+}
+
+fn rule_table(html: &mut String, rules: &[&Rule], itemizer: &Itemizer) {
+    html.push_str(
+        "<table id=\"rules\"><thead><tr><th>Antecedent</th><th>Consequent</th>\
+         <th>Confidence</th><th>Lift</th><th>Support</th><th>Count</th>\
+         <th>Confidence lower bound</th></tr></thead><tbody>\n",
+    );
+    for rule in rules {
+        writeln!(
+            html,
+            "<tr><td>{}</td><td>{}</td><td class=\"n\">{:.4}</td><td class=\"n\">{:.4}</td>\
+             <td class=\"n\">{:.4}</td><td class=\"n\">{}</td><td class=\"n\">{:.4}</td></tr>",
+            item_names(&rule.antecedent, itemizer),
+            item_names(&rule.consequent, itemizer),
+            rule.confidence,
+            rule.lift,
+            rule.support,
+            rule.count,
+            rule.confidence_lower_bound
+        )
+        .unwrap();
+    }
+    html.push_str("</tbody></table>\n");
+}
+
+// Renders a report as a single HTML file with no external dependencies, so
+// it can be shared as an attachment.
+pub fn html_report(
+    summary: &ReportSummary,
+    itemsets: &[ItemSet],
+    rules: &[Vec<Rule>],
+    itemizer: &Itemizer,
+) -> String {
+    let top = top_rules(rules);
+    let mut html = String::new();
+    writeln!(
+        html,
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\">\
+         <title>Association rules for {input}</title><style>{style}</style></head><body>\n\
+         <h1>Association rules for {input}</h1>\n\
+         <table><tr><th>Transactions</th><td class=\"n\">{transactions}</td></tr>\
+         <tr><th>Minimum support</th><td class=\"n\">{support}</td></tr>\
+         <tr><th>Minimum confidence</th><td class=\"n\">{confidence}</td></tr>\
+         <tr><th>Frequent itemsets</th><td class=\"n\">{itemsets}</td></tr>\
+         <tr><th>Rules</th><td class=\"n\">{rules}</td></tr></table>",
+        input = escape(summary.input_path),
+        style = STYLE,
+        transactions = summary.num_transactions,
+        support = summary.min_support,
+        confidence = summary.min_confidence,
+        itemsets = summary.num_itemsets,
+        rules = summary.num_rules,
+    )
+    .unwrap();
+    if summary.num_incomplete_items > 0 {
+        writeln!(
+            html,
+            "<p class=\"partial\"><strong>Partial results:</strong> mining stopped \
+             before {} items were fully mined, so some itemsets and rules are missing.</p>",
+            summary.num_incomplete_items
+        )
+        .unwrap();
+    }
+    if let Some(privacy) = summary.privacy {
+        writeln!(
+            html,
+            "<p>Itemsets were selected and counted with differential privacy, with \
+             epsilon {} and sensitivity {}; transactions were truncated to {} items.</p>",
+            privacy.epsilon, privacy.sensitivity, privacy.max_transaction_length
+        )
+        .unwrap();
+    }
+    let missing = summary.missing_counts;
+    if missing.rows_with_missing > 0 {
+        writeln!(
+            html,
+            "<p>{} rows had missing values; {} of them were dropped.</p>",
+            missing.rows_with_missing, missing.dropped_rows
+        )
+        .unwrap();
+    }
+    writeln!(html, "<h2>Memory</h2>\n<table>").unwrap();
+    for (name, bytes) in summary.memory.sizes() {
+        if let Some(bytes) = bytes {
+            writeln!(
+                html,
+                "<tr><th>{}</th><td class=\"n\">{}</td></tr>",
+                name,
+                megabytes(bytes)
+            )
+            .unwrap();
+        }
+    }
+    writeln!(html, "</table>").unwrap();
+    writeln!(html, "<h2>Most frequent items</h2>").unwrap();
+    item_bar_chart(&mut html, itemsets, summary.num_transactions, itemizer);
+    writeln!(
+        html,
+        "<h2>Support and confidence of top {} rules by lift</h2>",
+        top.len()
+    )
+    .unwrap();
+    scatter_plot(&mut html, &top, itemizer);
+    writeln!(
+        html,
+        "<h2>Top {} rules by lift</h2><p>Click a column heading to sort.</p>",
+        top.len()
+    )
+    .unwrap();
+    rule_table(&mut html, &top, itemizer);
+    writeln!(html, "<script>{}</script>\n</body></html>", SCRIPT).unwrap();
+    html
+}
+
+pub fn write_html_report(
+    path: &str,
+    summary: &ReportSummary,
+    itemsets: &[ItemSet],
+    rules: &[Vec<Rule>],
+    itemizer: &Itemizer,
+) -> io::Result<()> {
+    fs::write(path, html_report(summary, itemsets, rules, itemizer))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{html_report, ReportSummary};
+    use fptree::ItemSet;
+    use itemizer::Itemizer;
+    use memory::MemoryUsage;
+    use privacy::PrivacyParameters;
+    use rule::Rule;
+    use table::MissingCounts;
+
+    #[test]
+    fn test_html_report() {
+        let mut itemizer = Itemizer::new();
+        let a = itemizer.id_of("a<b");
+        let b = itemizer.id_of("c");
+        let itemsets = vec![
+            ItemSet::new(vec![a], 3),
+            ItemSet::new(vec![b], 2),
+            ItemSet::new(vec![a, b], 2),
+        ];
+        let rules = vec![vec![Rule::new(vec![a], vec![b], 2, 3, 2, 4)]];
+        let summary = ReportSummary {
+            input_path: "test.csv",
+            num_transactions: 4,
+            min_support: 0.5,
+            min_confidence: 0.1,
+            num_itemsets: itemsets.len(),
+            num_rules: 1,
+            num_incomplete_items: 0,
+            missing_counts: MissingCounts::default(),
+            memory: MemoryUsage {
+                itemizer: 2_000_000,
+                ..MemoryUsage::default()
+            },
+            privacy: None,
+        };
+        let html = html_report(&summary, &itemsets, &rules, &itemizer);
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.ends_with("</html>\n"));
+        assert!(html.contains("<td>a&lt;b</td><td>c</td>"));
+        assert!(!html.contains("a<b"));
+        assert_eq!(html.matches("<circle").count(), 1);
+        assert_eq!(html.matches("<rect").count(), 2);
+        assert!(!html.contains("Partial results"));
+        assert!(!html.contains("missing values"));
+        assert!(html.contains("<tr><th>Item dictionary</th><td class=\"n\">2.0 MB</td></tr>"));
+        assert!(!html.contains("FP-tree"));
+        assert!(!html.contains("differential privacy"));
+
+        let privacy = PrivacyParameters::new(0.5, 2, None);
+        let summary = ReportSummary {
+            num_incomplete_items: 1,
+            missing_counts: MissingCounts {
+                rows_with_missing: 3,
+                dropped_rows: 1,
+            },
+            privacy: Some(&privacy),
+            ..summary
+        };
+        let html = html_report(&summary, &itemsets, &rules, &itemizer);
+        assert!(html.contains("Partial results"));
+        assert!(html.contains("3 rows had missing values; 1 of them were dropped."));
+        assert!(html.contains("epsilon 0.5 and sensitivity 6;"));
+    }
+}
@@ -0,0 +1,210 @@
+// Copyright 2018 Chris Pearce
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use command_line_args::RevalidateArguments;
+use error::ArmError;
+use fnv::FnvHashSet;
+use index::TransactionIndex;
+use item::Item;
+use itemizer::Itemizer;
+use mining::duration_as_ms;
+use output::{join_items, OutputFile};
+use rayon::prelude::*;
+use rule_file::{read_rules, RuleRecord};
+use std::io::Write;
+use std::time::Instant;
+use support::index_dataset;
+use vec_sets::union;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct RuleMetrics {
+    confidence: f64,
+    lift: f64,
+    support: f64,
+}
+
+impl RuleMetrics {
+    fn of_record(rule: &RuleRecord) -> RuleMetrics {
+        RuleMetrics {
+            confidence: rule.confidence,
+            lift: rule.lift,
+            support: rule.support,
+        }
+    }
+
+    fn max_drift(&self, other: &RuleMetrics) -> f64 {
+        (self.confidence - other.confidence)
+            .abs()
+            .max((self.lift - other.lift).abs())
+            .max((self.support - other.support).abs())
+    }
+}
+
+fn ratio(numerator: f64, denominator: f64) -> f64 {
+    if denominator == 0.0 {
+        0.0
+    } else {
+        numerator / denominator
+    }
+}
+
+// Recomputes a rule's metrics from the index. Rules whose antecedent or
+// consequent no longer occur get a confidence and lift of 0.
+fn recount(antecedent: &[Item], consequent: &[Item], index: &dyn TransactionIndex) -> RuleMetrics {
+    let n = index.transaction_count() as f64;
+    let support = ratio(index.count(&union(antecedent, consequent)) as f64, n);
+    let antecedent_support = ratio(index.count(antecedent) as f64, n);
+    let consequent_support = ratio(index.count(consequent) as f64, n);
+    let confidence = ratio(support, antecedent_support);
+    RuleMetrics {
+        confidence,
+        lift: ratio(confidence, consequent_support),
+        support,
+    }
+}
+
+fn itemize(items: &[String], itemizer: &mut Itemizer) -> Vec<Item> {
+    let mut items: Vec<Item> = items.iter().map(|item| itemizer.id_of(item)).collect();
+    items.sort();
+    items.dedup();
+    items
+}
+
+pub fn run_revalidate(args: &RevalidateArguments) -> Result<(), ArmError> {
+    let start = Instant::now();
+    let rules = read_rules(&args.rules_path)?;
+    println!("Read {} rules.", rules.len());
+
+    let mut itemizer = Itemizer::new();
+    let sides: Vec<(Vec<Item>, Vec<Item>)> = rules
+        .iter()
+        .map(|rule| {
+            (
+                itemize(&rule.antecedent, &mut itemizer),
+                itemize(&rule.consequent, &mut itemizer),
+            )
+        })
+        .collect();
+    let rule_items: FnvHashSet<String> = rules
+        .iter()
+        .flat_map(|rule| rule.antecedent.iter().chain(rule.consequent.iter()))
+        .cloned()
+        .collect();
+
+    let timer = Instant::now();
+    let index = index_dataset(
+        &args.input_file_path,
+        args.weighted,
+        args.input_format,
+        args.encoding,
+        args.index_backend,
+        &rule_items,
+        &mut itemizer,
+    )?;
+    println!(
+        "Indexed {} transactions in {} ms.",
+        index.transaction_count(),
+        duration_as_ms(&timer.elapsed())
+    );
+
+    let recounted: Vec<RuleMetrics> = sides
+        .par_iter()
+        .map(|(antecedent, consequent)| recount(antecedent, consequent, index.as_ref()))
+        .collect();
+
+    let mut output = OutputFile::create(&args.output_path, None)
+        .map_err(|e| ArmError::io(&args.output_path, e))?;
+    writeln!(
+        output,
+        "Antecedent => Consequent,OldConfidence,NewConfidence,ConfidenceDrift,\
+         OldLift,NewLift,LiftDrift,OldSupport,NewSupport,SupportDrift,Drifted"
+    )
+    .map_err(|e| ArmError::io(&args.output_path, e))?;
+    let mut num_drifted = 0;
+    for (rule, new) in rules.iter().zip(recounted.iter()) {
+        let old = RuleMetrics::of_record(rule);
+        let drift = old.max_drift(new);
+        if drift > args.tolerance {
+            num_drifted += 1;
+        }
+        writeln!(
+            output,
+            "{} => {},{},{},{},{},{},{},{},{},{},{}",
+            join_items(&rule.antecedent),
+            join_items(&rule.consequent),
+            old.confidence,
+            new.confidence,
+            new.confidence - old.confidence,
+            old.lift,
+            new.lift,
+            new.lift - old.lift,
+            old.support,
+            new.support,
+            new.support - old.support,
+            drift > args.tolerance
+        )
+        .map_err(|e| ArmError::io(&args.output_path, e))?;
+    }
+    output
+        .finish()
+        .map_err(|e| ArmError::io(&args.output_path, e))?;
+
+    println!(
+        "{} of {} rules drifted by more than {}.",
+        num_drifted,
+        rules.len(),
+        args.tolerance
+    );
+    println!("Total runtime: {} ms", duration_as_ms(&start.elapsed()));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{recount, RuleMetrics};
+    use index::{Index, TransactionIndex};
+    use itemizer::Itemizer;
+
+    #[test]
+    fn test_recount() {
+        let mut itemizer = Itemizer::new();
+        let a = itemizer.id_of("a");
+        let b = itemizer.id_of("b");
+        let c = itemizer.id_of("c");
+        let mut index = Index::new();
+        index.insert(&[a, b], 1);
+        index.insert(&[a, b, c], 1);
+        index.insert(&[a], 1);
+        index.insert(&[b], 1);
+        index.finish();
+
+        let metrics = recount(&[a], &[b], &index);
+        assert_eq!(
+            metrics,
+            RuleMetrics {
+                confidence: 2.0 / 3.0,
+                lift: (2.0 / 3.0) / 0.75,
+                support: 0.5,
+            }
+        );
+        assert!(metrics.max_drift(&metrics) == 0.0);
+
+        // Rules whose items no longer occur have zero metrics.
+        let d = itemizer.id_of("d");
+        let metrics = recount(&[d], &[a], &index);
+        assert_eq!(metrics.confidence, 0.0);
+        assert_eq!(metrics.lift, 0.0);
+        assert_eq!(metrics.support, 0.0);
+    }
+}
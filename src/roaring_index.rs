@@ -0,0 +1,137 @@
+// Copyright 2018 Chris Pearce
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use index::TransactionIndex;
+use item::Item;
+use roaring::RoaringBitmap;
+
+// Transaction index backed by compressed roaring bitmaps. Uses much less
+// memory than tid-lists on large, sparse datasets, and intersects large
+// sets of tids quickly.
+pub struct RoaringIndex {
+    index: Vec<RoaringBitmap>,
+    // Number of times each transaction occurred, indexed by tid.
+    weights: Vec<u32>,
+    unit_weights: bool,
+    transaction_count: usize,
+}
+
+impl RoaringIndex {
+    pub fn new() -> RoaringIndex {
+        RoaringIndex {
+            index: Vec::new(),
+            weights: Vec::new(),
+            unit_weights: true,
+            transaction_count: 0,
+        }
+    }
+
+    // Bitmap of the transactions containing all of items, or None if items
+    // is empty or an item is in no transactions.
+    fn intersection(&self, items: &[Item]) -> Option<RoaringBitmap> {
+        let mut bitmaps: Vec<&RoaringBitmap> = vec![];
+        for &item in items {
+            bitmaps.push(self.index.get(item.as_index())?);
+        }
+        // Intersect starting with the smallest bitmaps, so intermediate
+        // results stay small.
+        bitmaps.sort_by_key(|bitmap| bitmap.len());
+        match bitmaps.len() {
+            0 => None,
+            1 => Some(bitmaps[0].clone()),
+            _ => Some(
+                bitmaps[2..]
+                    .iter()
+                    .fold(bitmaps[0] & bitmaps[1], |tids, &bitmap| tids & bitmap),
+            ),
+        }
+    }
+}
+
+impl TransactionIndex for RoaringIndex {
+    fn insert(&mut self, transaction: &[Item], count: u32) {
+        let tid = self.weights.len() as u32;
+        self.weights.push(count);
+        self.unit_weights &= count == 1;
+        self.transaction_count += count as usize;
+        for &item in transaction {
+            while self.index.len() <= item.as_index() {
+                self.index.push(RoaringBitmap::new());
+            }
+            // Tids are increasing, so this is always an append.
+            self.index[item.as_index()].push(tid);
+        }
+    }
+
+    // Roaring bitmaps pick the representation of each chunk of tids as
+    // they're built, so there's nothing left to do.
+    fn finish(&mut self) {}
+
+    fn count(&self, items: &[Item]) -> usize {
+        let tids = match self.intersection(items) {
+            Some(tids) => tids,
+            None => return 0,
+        };
+        if self.unit_weights {
+            tids.len() as usize
+        } else {
+            tids.iter()
+                .map(|tid| self.weights[tid as usize] as usize)
+                .sum()
+        }
+    }
+
+    fn tids(&self, items: &[Item]) -> Vec<u32> {
+        self.intersection(items)
+            .map(|tids| tids.iter().collect())
+            .unwrap_or_default()
+    }
+
+    fn weight(&self, tid: u32) -> u32 {
+        self.weights[tid as usize]
+    }
+
+    fn transaction_count(&self) -> usize {
+        self.transaction_count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RoaringIndex;
+    use index::{Index, TransactionIndex};
+    use item::Item;
+
+    #[test]
+    fn test_roaring_index() {
+        let mut index = Index::new();
+        let mut roaring = RoaringIndex::new();
+        for i in 0..500u32 {
+            let transaction: Vec<Item> = (1..8)
+                .filter(|&item| i % item == 0)
+                .map(Item::from)
+                .collect();
+            index.insert(&transaction, 1 + i % 3);
+            roaring.insert(&transaction, 1 + i % 3);
+        }
+        index.finish();
+        roaring.finish();
+        assert_eq!(roaring.transaction_count(), index.transaction_count());
+        for items in &[vec![1], vec![2, 3], vec![2, 3, 5], vec![7], vec![9], vec![]] {
+            let items: Vec<Item> = items.iter().map(|&i| Item::from(i)).collect();
+            assert_eq!(roaring.count(&items), index.count(&items));
+            assert_eq!(roaring.tids(&items), index.tids(&items));
+        }
+    }
+}
@@ -13,7 +13,13 @@
 // limitations under the License.
 
 use item::Item;
+use itemizer::Itemizer;
+use output::{write_rule_lines, NumberFormat};
+use rule_file::{parse_items, split_rule, split_unquoted};
+use std::fmt;
 use std::hash::{Hash, Hasher};
+use std::slice;
+use std::str;
 
 #[derive(Clone, Debug)]
 pub struct Rule {
@@ -22,6 +28,285 @@ pub struct Rule {
     pub confidence: f64,
     pub lift: f64,
     pub support: f64,
+    // Number of transactions containing the antecedent, the consequent,
+    // and both the antecedent and consequent together respectively.
+    pub antecedent_count: u32,
+    pub consequent_count: u32,
+    pub count: u32,
+    // 95% Wilson score interval of the confidence.
+    pub confidence_lower_bound: f64,
+    pub confidence_upper_bound: f64,
+    // Standard error of the lift, estimated via the delta method on log(lift).
+    pub lift_std_error: f64,
+    // Laplace accuracy, (count + 1) / (antecedent_count + k). Confidence
+    // smoothed towards 1/k, so rules with small antecedent counts don't
+    // score as highly as their confidence suggests.
+    pub laplace: f64,
+    // Confidence minus the consequent's support; positive if the antecedent
+    // raises the probability of the consequent, negative if it lowers it.
+    pub added_value: f64,
+    // Reductions in the Gini impurity and entropy (in bits) of whether
+    // transactions contain the consequent, from splitting them on whether
+    // they contain the antecedent, as when choosing decision tree splits.
+    pub gini_gain: f64,
+    pub info_gain: f64,
+    // Measures of association from the 2x2 contingency table of the
+    // antecedent and consequent. The odds ratio and Yule's Q use the
+    // Haldane-Anscombe correction, adding 0.5 to each cell, if any cell is
+    // zero, so that the odds ratio is always finite and Q is always
+    // (OR - 1) / (OR + 1). Yule's Q and phi are in range [-1,1], and are 0
+    // for independent antecedents and consequents.
+    pub odds_ratio: f64,
+    pub yules_q: f64,
+    pub phi: f64,
+    // Empirical p-value of the lift from a permutation test, if one was run.
+    pub p_value: Option<f64>,
+}
+
+// Number of classes assumed by Laplace accuracy unless set otherwise.
+pub const DEFAULT_LAPLACE_K: f64 = 2.0;
+
+fn laplace_accuracy(count: u32, antecedent_count: u32, k: f64) -> f64 {
+    (count as f64 + 1.0) / (antecedent_count as f64 + k)
+}
+
+// Gini impurity of a two class group, p of which is in one class.
+fn gini(p: f64) -> f64 {
+    2.0 * p * (1.0 - p)
+}
+
+// Entropy in bits of a two class group, p of which is in one class.
+fn entropy(p: f64) -> f64 {
+    let term = |p: f64| if p > 0.0 { -p * p.log2() } else { 0.0 };
+    term(p) + term(1.0 - p)
+}
+
+// Impurity of the consequent before splitting transactions on the
+// antecedent, minus the impurity of the two groups after, weighted by size.
+fn impurity_gain<F: Fn(f64) -> f64>(
+    count: u32,
+    antecedent_count: u32,
+    consequent_count: u32,
+    dataset_size: u32,
+    impurity: F,
+) -> f64 {
+    let n = dataset_size as f64;
+    let with = antecedent_count as f64;
+    let without = n - with;
+    let mut gain = impurity(consequent_count as f64 / n) - with / n * impurity(count as f64 / with);
+    if without > 0.0 {
+        gain -= without / n * impurity((consequent_count - count) as f64 / without);
+    }
+    // Rounding can leave independent items with a tiny negative gain.
+    gain.max(0.0)
+}
+
+// Cells of the 2x2 contingency table of transactions containing the
+// antecedent or not, by containing the consequent or not: (both, antecedent
+// only, consequent only, neither).
+fn contingency_table(
+    count: u32,
+    antecedent_count: u32,
+    consequent_count: u32,
+    dataset_size: u32,
+) -> (f64, f64, f64, f64) {
+    let both = count as f64;
+    let antecedent_only = antecedent_count as f64 - both;
+    let consequent_only = consequent_count as f64 - both;
+    let neither = dataset_size as f64 - both - antecedent_only - consequent_only;
+    (both, antecedent_only, consequent_only, neither)
+}
+
+// The Haldane-Anscombe correction: adds 0.5 to each cell if any is zero.
+fn haldane_corrected(table: (f64, f64, f64, f64)) -> (f64, f64, f64, f64) {
+    let (a, b, c, d) = table;
+    if a == 0.0 || b == 0.0 || c == 0.0 || d == 0.0 {
+        (a + 0.5, b + 0.5, c + 0.5, d + 0.5)
+    } else {
+        table
+    }
+}
+
+fn odds_ratio(table: (f64, f64, f64, f64)) -> f64 {
+    let (a, b, c, d) = haldane_corrected(table);
+    (a * d) / (b * c)
+}
+
+fn yules_q(table: (f64, f64, f64, f64)) -> f64 {
+    let (a, b, c, d) = haldane_corrected(table);
+    (a * d - b * c) / (a * d + b * c)
+}
+
+fn phi(table: (f64, f64, f64, f64)) -> f64 {
+    let (a, b, c, d) = table;
+    let denominator = ((a + b) * (c + d) * (a + c) * (b + d)).sqrt();
+    if denominator == 0.0 {
+        0.0
+    } else {
+        (a * d - b * c) / denominator
+    }
+}
+
+// z-score for a 95% confidence interval.
+const Z_95: f64 = 1.959964;
+
+// Returns the Wilson score interval of the proportion successes/trials.
+fn wilson_interval(successes: u32, trials: u32) -> (f64, f64) {
+    let n = trials as f64;
+    let p = successes as f64 / n;
+    let z2 = Z_95 * Z_95;
+    let denominator = 1.0 + z2 / n;
+    let center = p + z2 / (2.0 * n);
+    let spread = Z_95 * (p * (1.0 - p) / n + z2 / (4.0 * n * n)).sqrt();
+    (
+        ((center - spread) / denominator).max(0.0),
+        ((center + spread) / denominator).min(1.0),
+    )
+}
+
+impl Rule {
+    pub fn new(
+        antecedent: Vec<Item>,
+        consequent: Vec<Item>,
+        count: u32,
+        antecedent_count: u32,
+        consequent_count: u32,
+        dataset_size: u32,
+    ) -> Rule {
+        let n = dataset_size as f64;
+        let support = count as f64 / n;
+        let confidence = count as f64 / antecedent_count as f64;
+        let lift = confidence / (consequent_count as f64 / n);
+        let (confidence_lower_bound, confidence_upper_bound) =
+            wilson_interval(count, antecedent_count);
+        let log_lift_variance = 1.0 / count as f64 - 1.0 / antecedent_count as f64
+            + 1.0 / consequent_count as f64
+            - 1.0 / n;
+        let lift_std_error = lift * log_lift_variance.max(0.0).sqrt();
+        let table = contingency_table(count, antecedent_count, consequent_count, dataset_size);
+        Rule {
+            antecedent,
+            consequent,
+            confidence,
+            lift,
+            support,
+            antecedent_count,
+            consequent_count,
+            count,
+            confidence_lower_bound,
+            confidence_upper_bound,
+            lift_std_error,
+            laplace: laplace_accuracy(count, antecedent_count, DEFAULT_LAPLACE_K),
+            added_value: confidence - consequent_count as f64 / n,
+            gini_gain: impurity_gain(
+                count,
+                antecedent_count,
+                consequent_count,
+                dataset_size,
+                gini,
+            ),
+            info_gain: impurity_gain(
+                count,
+                antecedent_count,
+                consequent_count,
+                dataset_size,
+                entropy,
+            ),
+            odds_ratio: odds_ratio(table),
+            yules_q: yules_q(table),
+            phi: phi(table),
+            p_value: None,
+        }
+    }
+
+    // Recalculates the rule's Laplace accuracy with k classes.
+    pub fn with_laplace_k(mut self, k: f64) -> Rule {
+        self.laplace = laplace_accuracy(self.count, self.antecedent_count, k);
+        self
+    }
+
+    // Displays the rule as a line of a rules file, naming its items with
+    // itemizer, and with numbers written exactly.
+    pub fn display<'a>(&'a self, itemizer: &'a Itemizer) -> RuleDisplay<'a> {
+        RuleDisplay {
+            rule: self,
+            itemizer,
+        }
+    }
+
+    // Parses a line of a rules file, as written by display or `arm mine`,
+    // assigning its items ids with itemizer.
+    pub fn parse(line: &str, itemizer: &mut Itemizer) -> Result<Rule, String> {
+        let error = || format!("Expected {}, found {}", RULE_FORMAT, line);
+        let (antecedent, rest) = split_rule(line).ok_or_else(error)?;
+        let mut fields = split_unquoted(rest, ',').into_iter();
+        let consequent = fields.next().ok_or_else(error)?;
+        let mut items = |names: &str| {
+            let mut items: Vec<Item> = parse_items(names)
+                .iter()
+                .map(|name| itemizer.id_of(name))
+                .collect();
+            items.sort();
+            items
+        };
+        let antecedent = items(antecedent);
+        let consequent = items(consequent);
+        let mut number = || fields.next().and_then(|field| field.parse::<f64>().ok());
+        let mut next = || number().ok_or_else(error);
+        let confidence = next()?;
+        let lift = next()?;
+        let support = next()?;
+        let antecedent_count = next()? as u32;
+        let consequent_count = next()? as u32;
+        let count = next()? as u32;
+        let rule = Rule {
+            antecedent,
+            consequent,
+            confidence,
+            lift,
+            support,
+            antecedent_count,
+            consequent_count,
+            count,
+            confidence_lower_bound: next()?,
+            confidence_upper_bound: next()?,
+            lift_std_error: next()?,
+            laplace: next()?,
+            added_value: next()?,
+            gini_gain: next()?,
+            info_gain: next()?,
+            odds_ratio: next()?,
+            yules_q: next()?,
+            phi: next()?,
+            // Empty unless a permutation test was run.
+            p_value: number(),
+        };
+        Ok(rule)
+    }
+}
+
+const RULE_FORMAT: &str = "antecedent => consequent,confidence,lift,support,antecedent_count,\
+     consequent_count,count,confidence_lower_bound,confidence_upper_bound,lift_std_error,laplace,\
+     added_value,gini_gain,info_gain,odds_ratio,yules_q,phi,p_value";
+
+pub struct RuleDisplay<'a> {
+    rule: &'a Rule,
+    itemizer: &'a Itemizer,
+}
+
+impl<'a> fmt::Display for RuleDisplay<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut line = vec![];
+        write_rule_lines(
+            &mut line,
+            slice::from_ref(self.rule),
+            NumberFormat::default(),
+            self.itemizer,
+        );
+        // Without the line's newline.
+        line.pop();
+        f.write_str(str::from_utf8(&line).map_err(|_| fmt::Error)?)
+    }
 }
 
 // Custom hash that excludes floating point values which aren't hashable.
@@ -40,3 +325,121 @@ impl PartialEq for Rule {
 }
 
 impl Eq for Rule {}
+
+#[cfg(test)]
+mod tests {
+    use super::{laplace_accuracy, wilson_interval, Rule};
+    use item::Item;
+    use itemizer::Itemizer;
+
+    #[test]
+    fn test_wilson_interval() {
+        let (lower, upper) = wilson_interval(8, 10);
+        assert!((lower - 0.4902).abs() < 1e-4);
+        assert!((upper - 0.9433).abs() < 1e-4);
+        assert_eq!(wilson_interval(0, 5).0, 0.0);
+        assert_eq!(wilson_interval(5, 5).1, 1.0);
+    }
+
+    #[test]
+    fn test_laplace_accuracy() {
+        assert_eq!(laplace_accuracy(2, 2, 2.0), 0.75);
+        assert_eq!(laplace_accuracy(200, 200, 2.0), 201.0 / 202.0);
+        assert_eq!(laplace_accuracy(0, 4, 3.0), 1.0 / 7.0);
+    }
+
+    #[test]
+    fn test_display_and_parse() {
+        let mut itemizer = Itemizer::new();
+        let (a, b, c) = (
+            itemizer.id_of("a"),
+            itemizer.id_of("b"),
+            itemizer.id_of("c"),
+        );
+        let mut rule = Rule::new(vec![a, b], vec![c], 3, 4, 5, 10);
+        let line = rule.display(&itemizer).to_string();
+        assert!(line.starts_with("a b => c,0.75,1.5,0.3,4,5,3,"));
+        assert!(line.ends_with(','));
+
+        // Every field round-trips exactly, into a fresh itemizer too.
+        let parsed = Rule::parse(&line, &mut itemizer).unwrap();
+        assert_eq!(parsed.display(&itemizer).to_string(), line);
+        let mut fresh = Itemizer::new();
+        let parsed = Rule::parse(&line, &mut fresh).unwrap();
+        assert_eq!(parsed.display(&fresh).to_string(), line);
+        rule.p_value = Some(0.01);
+        let line = rule.display(&itemizer).to_string();
+        let parsed = Rule::parse(&line, &mut itemizer).unwrap();
+        assert_eq!(parsed.p_value, Some(0.01));
+        assert_eq!(parsed.odds_ratio, rule.odds_ratio);
+        assert_eq!(parsed, rule);
+
+        assert!(Rule::parse("a => c,0.75,1.5", &mut itemizer).is_err());
+        assert!(Rule::parse("a c", &mut itemizer).is_err());
+
+        // Names which need quoting round-trip too.
+        let (d, e) = (itemizer.id_of("ice cream"), itemizer.id_of("x,\"y\""));
+        let rule = Rule::new(vec![a, d], vec![e], 3, 4, 5, 10);
+        let line = rule.display(&itemizer).to_string();
+        assert!(line.starts_with(r#"a "ice cream" => "x,""y""","#));
+        let mut fresh = Itemizer::new();
+        let parsed = Rule::parse(&line, &mut fresh).unwrap();
+        assert_eq!(parsed.display(&fresh).to_string(), line);
+    }
+
+    #[test]
+    fn test_added_value() {
+        let items = |ids: &[u32]| ids.iter().map(|&id| Item::from(id)).collect();
+        // Antecedent in 4 of 10 transactions, consequent in 5, both in 3.
+        let rule = Rule::new(items(&[1]), items(&[2]), 3, 4, 5, 10);
+        assert!((rule.added_value - 0.25).abs() < 1e-9);
+        let rule = Rule::new(items(&[1]), items(&[2]), 1, 4, 5, 10);
+        assert!((rule.added_value + 0.25).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_impurity_gain() {
+        let items = |ids: &[u32]| ids.iter().map(|&id| Item::from(id)).collect();
+        // The antecedent and consequent always occur together, so splitting
+        // on the antecedent leaves no impurity.
+        let rule = Rule::new(items(&[1]), items(&[2]), 5, 5, 5, 10);
+        assert!((rule.gini_gain - 0.5).abs() < 1e-9);
+        assert!((rule.info_gain - 1.0).abs() < 1e-9);
+        // Independent, so splitting gains nothing.
+        let rule = Rule::new(items(&[1]), items(&[2]), 2, 4, 5, 10);
+        assert!(rule.gini_gain.abs() < 1e-9);
+        assert!(rule.info_gain.abs() < 1e-9);
+        // The antecedent is in every transaction.
+        let rule = Rule::new(items(&[1]), items(&[2]), 5, 10, 5, 10);
+        assert!(rule.gini_gain.abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_association_measures() {
+        let items = |ids: &[u32]| ids.iter().map(|&id| Item::from(id)).collect();
+        // Both 3, antecedent only 1, consequent only 2, neither 4.
+        let rule = Rule::new(items(&[1]), items(&[2]), 3, 4, 5, 10);
+        assert!((rule.odds_ratio - 6.0).abs() < 1e-9);
+        assert!((rule.yules_q - 5.0 / 7.0).abs() < 1e-9);
+        assert!((rule.phi - 10.0 / 24f64.sqrt() / 5.0).abs() < 1e-9);
+        // Independent.
+        let rule = Rule::new(items(&[1]), items(&[2]), 2, 4, 5, 10);
+        assert!((rule.odds_ratio - 1.0).abs() < 1e-9);
+        assert!(rule.yules_q.abs() < 1e-9);
+        assert!(rule.phi.abs() < 1e-9);
+        // No transactions contain only the antecedent, so the odds ratio is
+        // corrected.
+        let rule = Rule::new(items(&[1]), items(&[2]), 4, 4, 5, 10);
+        let odds_ratio = 4.5 * 5.5 / (0.5 * 1.5);
+        assert!((rule.odds_ratio - odds_ratio).abs() < 1e-9);
+        // Yule's Q is corrected the same way, so agrees with the odds ratio.
+        assert!(rule.yules_q < 1.0);
+        assert!((rule.yules_q - (odds_ratio - 1.0) / (odds_ratio + 1.0)).abs() < 1e-9);
+        // No transactions contain both, so the odds ratio is small but not
+        // zero, and Q isn't -1.
+        let rule = Rule::new(items(&[1]), items(&[2]), 0, 4, 5, 10);
+        assert!(rule.odds_ratio > 0.0 && rule.odds_ratio < 1.0);
+        assert!(rule.yules_q > -1.0);
+        assert!((rule.yules_q - (rule.odds_ratio - 1.0) / (rule.odds_ratio + 1.0)).abs() < 1e-9);
+    }
+}
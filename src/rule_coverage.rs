@@ -0,0 +1,180 @@
+// Copyright 2018 Chris Pearce
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use index::TransactionIndex;
+use itemizer::Itemizer;
+use output::OutputFile;
+use rayon::prelude::*;
+use rule::Rule;
+use std::io::{self, Write};
+use vec_sets::union;
+use verify::items_to_string;
+
+// How much of the dataset a set of rules applies to. A rule's antecedent
+// matches a transaction which contains the antecedent, and the whole rule
+// matches one which contains both the antecedent and the consequent.
+pub struct RuleCoverage {
+    // Number of transactions, including repeats, matched by at least one
+    // rule's antecedent, and by at least one whole rule.
+    pub num_antecedent_covered: usize,
+    pub num_rule_covered: usize,
+    // For each rule, the number of transactions it matches which no other
+    // rule matches, so removing the rule would uncover them.
+    pub unique_counts: Vec<usize>,
+}
+
+// Adds one to the number of matches of each transaction in tids.
+fn add_matches(matches: &mut Vec<u32>, tids: &[u32]) {
+    if let Some(&last) = tids.last() {
+        if matches.len() <= last as usize {
+            matches.resize(last as usize + 1, 0);
+        }
+    }
+    for &tid in tids {
+        matches[tid as usize] = matches[tid as usize].saturating_add(1);
+    }
+}
+
+fn sum_matches(a: Vec<u32>, b: Vec<u32>) -> Vec<u32> {
+    let (mut longer, shorter) = if a.len() >= b.len() { (a, b) } else { (b, a) };
+    for (sum, count) in longer.iter_mut().zip(shorter) {
+        *sum = sum.saturating_add(count);
+    }
+    longer
+}
+
+// Counts the transactions in index matched by rules. Tids are ids of
+// distinct transactions, each weighted by how often it occurred.
+pub fn rule_coverage(rules: &[Vec<Rule>], index: &dyn TransactionIndex) -> RuleCoverage {
+    let rules: Vec<&Rule> = rules.iter().flatten().collect();
+    let rule_items = |rule: &Rule| union(&rule.antecedent, &rule.consequent);
+    let (antecedent_matches, rule_matches) = rules
+        .par_iter()
+        .fold(
+            || (vec![], vec![]),
+            |(mut antecedent_matches, mut rule_matches), rule| {
+                add_matches(&mut antecedent_matches, &index.tids(&rule.antecedent));
+                add_matches(&mut rule_matches, &index.tids(&rule_items(rule)));
+                (antecedent_matches, rule_matches)
+            },
+        )
+        .reduce(
+            || (vec![], vec![]),
+            |(a1, r1), (a2, r2)| (sum_matches(a1, a2), sum_matches(r1, r2)),
+        );
+    let num_covered = |matches: &[u32]| -> usize {
+        matches
+            .iter()
+            .enumerate()
+            .filter(|&(_, &count)| count > 0)
+            .map(|(tid, _)| index.weight(tid as u32) as usize)
+            .sum()
+    };
+    let unique_counts = rules
+        .par_iter()
+        .map(|rule| {
+            index
+                .tids(&rule_items(rule))
+                .into_iter()
+                .filter(|&tid| rule_matches[tid as usize] == 1)
+                .map(|tid| index.weight(tid) as usize)
+                .sum()
+        })
+        .collect();
+    RuleCoverage {
+        num_antecedent_covered: num_covered(&antecedent_matches),
+        num_rule_covered: num_covered(&rule_matches),
+        unique_counts,
+    }
+}
+
+// Writes each rule's coverage as a fraction of num_transactions: of its
+// antecedent, of the whole rule, and of the transactions only it matches.
+pub fn write_rule_coverage(
+    path: &str,
+    rules: &[Vec<Rule>],
+    coverage: &RuleCoverage,
+    num_transactions: usize,
+    itemizer: &Itemizer,
+) -> io::Result<()> {
+    let mut output = OutputFile::create(path, None)?;
+    writeln!(
+        output,
+        "Antecedent => Consequent,AntecedentCoverage,RuleCoverage,UniqueCoverage"
+    )?;
+    let n = num_transactions as f64;
+    for (rule, &unique_count) in rules.iter().flatten().zip(&coverage.unique_counts) {
+        writeln!(
+            output,
+            "{} => {},{},{},{}",
+            items_to_string(&rule.antecedent, itemizer),
+            items_to_string(&rule.consequent, itemizer),
+            rule.antecedent_count as f64 / n,
+            rule.count as f64 / n,
+            unique_count as f64 / n
+        )?;
+    }
+    output.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::rule_coverage;
+    use index::{Index, TransactionIndex};
+    use item::Item;
+    use rule::Rule;
+    use vec_sets::union;
+
+    #[test]
+    fn test_rule_coverage() {
+        let item = Item::from;
+        let mut index = Index::new();
+        let transactions = vec![
+            (vec![item(1), item(2)], 3),
+            (vec![item(1), item(3)], 1),
+            (vec![item(2), item(3)], 2),
+            (vec![item(1), item(2), item(3)], 1),
+            (vec![item(4)], 5),
+        ];
+        for (transaction, count) in &transactions {
+            index.insert(transaction, *count);
+        }
+        index.finish();
+        let rule = |antecedent: Vec<Item>, consequent: Vec<Item>| {
+            let count = |items: &[Item]| index.count(items) as u32;
+            let both = union(&antecedent, &consequent);
+            let (count, antecedent_count, consequent_count) =
+                (count(&both), count(&antecedent), count(&consequent));
+            Rule::new(
+                antecedent,
+                consequent,
+                count,
+                antecedent_count,
+                consequent_count,
+                12,
+            )
+        };
+        let rules = vec![
+            vec![rule(vec![item(1)], vec![item(2)])],
+            vec![rule(vec![item(3)], vec![item(2)])],
+        ];
+        let coverage = rule_coverage(&rules, &index);
+        // Every transaction but {4} contains 1 or 3.
+        assert_eq!(coverage.num_antecedent_covered, 7);
+        // {1,2}, {2,3} and {1,2,3}.
+        assert_eq!(coverage.num_rule_covered, 6);
+        // {1,2,3} is matched by both rules.
+        assert_eq!(coverage.unique_counts, vec![3, 2]);
+    }
+}
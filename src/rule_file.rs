@@ -0,0 +1,189 @@
+// Copyright 2018 Chris Pearce
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use error::ArmError;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::mem;
+use std::str::FromStr;
+
+// A rule read back from a rules file written by `arm mine`. Items are kept
+// as strings, as there's no Itemizer for the dataset the rules came from.
+pub struct RuleRecord {
+    pub antecedent: Vec<String>,
+    pub consequent: Vec<String>,
+    pub confidence: f64,
+    pub lift: f64,
+    pub support: f64,
+    // The line the rule was read from, so it can be printed unchanged.
+    pub line: String,
+}
+
+impl RuleRecord {
+    pub fn contains(&self, item: &str) -> bool {
+        self.antecedent.iter().any(|i| i == item) || self.consequent.iter().any(|i| i == item)
+    }
+}
+
+// Splits s at each separator which isn't inside double quotes.
+pub fn split_unquoted(s: &str, separator: char) -> Vec<&str> {
+    let mut fields = vec![];
+    let mut start = 0;
+    let mut quoted = false;
+    for (index, c) in s.char_indices() {
+        if c == '"' {
+            // A doubled quote inside quotes toggles twice, so stays quoted.
+            quoted = !quoted;
+        } else if c == separator && !quoted {
+            fields.push(&s[start..index]);
+            start = index + c.len_utf8();
+        }
+    }
+    fields.push(&s[start..]);
+    fields
+}
+
+// Items in rules and itemsets files are separated by spaces. Names which
+// contain spaces, commas or double quotes are written in double quotes, with
+// double quotes doubled, as by output::quote_item.
+pub fn parse_items(items: &str) -> Vec<String> {
+    let mut names = vec![];
+    let mut name = String::new();
+    let mut quoted = false;
+    let mut chars = items.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                name.push('"');
+            }
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !name.is_empty() {
+                    names.push(mem::take(&mut name));
+                }
+            }
+            c => name.push(c),
+        }
+    }
+    if !name.is_empty() {
+        names.push(name);
+    }
+    names
+}
+
+// Splits a line of a rules file at the " => " between the antecedent and
+// the rest of the line, outside any quoted item names.
+pub fn split_rule(line: &str) -> Option<(&str, &str)> {
+    let mut quoted = false;
+    for (index, c) in line.char_indices() {
+        if c == '"' {
+            quoted = !quoted;
+        } else if !quoted && line[index..].starts_with(" => ") {
+            return Some((&line[..index], &line[index + " => ".len()..]));
+        }
+    }
+    None
+}
+
+fn parse_rule(line: &str) -> Option<RuleRecord> {
+    let (antecedent, rest) = split_rule(line)?;
+    let antecedent = parse_items(antecedent);
+    let mut fields = split_unquoted(rest, ',').into_iter();
+    let consequent = parse_items(fields.next()?);
+    let confidence = fields.next()?.parse().ok()?;
+    let lift = fields.next()?.parse().ok()?;
+    let support = fields.next()?.parse().ok()?;
+    Some(RuleRecord {
+        antecedent,
+        consequent,
+        confidence,
+        lift,
+        support,
+        line: String::from(line),
+    })
+}
+
+// Parses a line of a rules file. Only the items, confidence, lift and
+// support are needed; see Rule::parse to read every field.
+impl FromStr for RuleRecord {
+    type Err = String;
+    fn from_str(line: &str) -> Result<RuleRecord, String> {
+        parse_rule(line).ok_or_else(|| {
+            format!(
+                "Expected antecedent => consequent,confidence,lift,support,..., found {}",
+                line
+            )
+        })
+    }
+}
+
+// Displays the line the rule was read from.
+impl fmt::Display for RuleRecord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.line)
+    }
+}
+
+pub fn read_rules(path: &str) -> Result<Vec<RuleRecord>, ArmError> {
+    let file = File::open(path).map_err(|e| ArmError::io(path, e))?;
+    let mut rules = vec![];
+    for (index, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|e| ArmError::io(path, e))?;
+        if (index == 0 && line.starts_with("Antecedent")) || line.trim().is_empty() {
+            continue;
+        }
+        match line.parse::<RuleRecord>() {
+            Ok(rule) => rules.push(rule),
+            Err(_) => {
+                return Err(ArmError::parse(
+                    path,
+                    index + 1,
+                    "expected antecedent => consequent,confidence,lift,support,...",
+                ))
+            }
+        }
+    }
+    Ok(rules)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_rule, RuleRecord};
+
+    #[test]
+    fn test_parse_rule() {
+        let rule = parse_rule("a b => c,0.5,2,0.25,4,2,2").unwrap();
+        assert_eq!(rule.antecedent, vec!["a", "b"]);
+        assert_eq!(rule.consequent, vec!["c"]);
+        assert_eq!(rule.confidence, 0.5);
+        assert_eq!(rule.lift, 2.0);
+        assert_eq!(rule.support, 0.25);
+        assert!(rule.contains("b") && !rule.contains("d"));
+        assert!(parse_rule("a b,0.5").is_none());
+        assert!(parse_rule("a => b,x,2,0.25").is_none());
+
+        let line = "a => b c,1,1.5,0.5";
+        let rule: RuleRecord = line.parse().unwrap();
+        assert_eq!(rule.to_string(), line);
+        assert!("a => b".parse::<RuleRecord>().is_err());
+
+        // Names with spaces, commas or quotes are quoted.
+        let rule = parse_rule(r#""ice cream" "a => b" => "x,""y""",1,1.5,0.5"#).unwrap();
+        assert_eq!(rule.antecedent, vec!["ice cream", "a => b"]);
+        assert_eq!(rule.consequent, vec!["x,\"y\""]);
+        assert_eq!(rule.support, 0.5);
+    }
+}
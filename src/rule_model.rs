@@ -0,0 +1,247 @@
+// Copyright 2018 Chris Pearce
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use error::ArmError;
+use fnv::{FnvHashMap, FnvHashSet};
+use miner::AssociationRule;
+use rule_file::{read_rules, RuleRecord};
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+// How the rules which predict an item are combined into its score.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Aggregation {
+    // The highest confidence of the rules predicting the item.
+    MaxConfidence,
+    // The sum of the lifts of the rules predicting the item, favouring items
+    // predicted by many rules.
+    SumLift,
+}
+
+impl FromStr for Aggregation {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Aggregation, String> {
+        match s {
+            "max-confidence" => Ok(Aggregation::MaxConfidence),
+            "sum-lift" => Ok(Aggregation::SumLift),
+            _ => Err(format!("Unknown aggregation {}", s)),
+        }
+    }
+}
+
+struct ModelRule {
+    antecedent_len: usize,
+    consequent: Vec<u32>,
+    confidence: f64,
+    lift: f64,
+}
+
+// Rules held for suggesting the next items to add to a basket. Item names
+// are interned, and each rule is listed under every item of its antecedent,
+// so completing a basket only visits rules sharing an item with it.
+pub struct RuleModel {
+    aggregation: Aggregation,
+    item_ids: FnvHashMap<String, u32>,
+    item_names: Vec<String>,
+    rules: Vec<ModelRule>,
+    rules_by_item: Vec<Vec<u32>>,
+    // Rules with empty antecedents, which apply to every basket.
+    unconditional: Vec<u32>,
+}
+
+impl RuleModel {
+    pub fn new(aggregation: Aggregation) -> RuleModel {
+        RuleModel {
+            aggregation,
+            item_ids: FnvHashMap::default(),
+            item_names: vec![],
+            rules: vec![],
+            rules_by_item: vec![],
+            unconditional: vec![],
+        }
+    }
+
+    // Builds a model from rules mined by Miner::rules().
+    pub fn from_rules(rules: &[AssociationRule], aggregation: Aggregation) -> RuleModel {
+        let mut model = RuleModel::new(aggregation);
+        for rule in rules {
+            model.add(
+                &rule.antecedent,
+                &rule.consequent,
+                rule.confidence,
+                rule.lift,
+            );
+        }
+        model
+    }
+
+    pub fn from_records(rules: &[RuleRecord], aggregation: Aggregation) -> RuleModel {
+        let mut model = RuleModel::new(aggregation);
+        for rule in rules {
+            model.add(
+                &rule.antecedent,
+                &rule.consequent,
+                rule.confidence,
+                rule.lift,
+            );
+        }
+        model
+    }
+
+    // Builds a model from a rules file written by `arm mine`.
+    pub fn from_file(path: &str, aggregation: Aggregation) -> Result<RuleModel, ArmError> {
+        Ok(RuleModel::from_records(&read_rules(path)?, aggregation))
+    }
+
+    fn item_id(&mut self, item: &str) -> u32 {
+        if let Some(&id) = self.item_ids.get(item) {
+            return id;
+        }
+        let id = self.item_names.len() as u32;
+        self.item_ids.insert(String::from(item), id);
+        self.item_names.push(String::from(item));
+        self.rules_by_item.push(vec![]);
+        id
+    }
+
+    pub fn add(
+        &mut self,
+        antecedent: &[String],
+        consequent: &[String],
+        confidence: f64,
+        lift: f64,
+    ) {
+        let rule_id = self.rules.len() as u32;
+        let mut antecedent: Vec<u32> = antecedent.iter().map(|item| self.item_id(item)).collect();
+        antecedent.sort_unstable();
+        antecedent.dedup();
+        let consequent = consequent.iter().map(|item| self.item_id(item)).collect();
+        for &item in &antecedent {
+            self.rules_by_item[item as usize].push(rule_id);
+        }
+        if antecedent.is_empty() {
+            self.unconditional.push(rule_id);
+        }
+        self.rules.push(ModelRule {
+            antecedent_len: antecedent.len(),
+            consequent,
+            confidence,
+            lift,
+        });
+    }
+
+    pub fn num_rules(&self) -> usize {
+        self.rules.len()
+    }
+
+    // Suggests up to k items to add to basket, with their scores, highest
+    // first and ties broken by name. Items are scored by the rules whose
+    // antecedents are in the basket, from those rules' consequents; items
+    // already in the basket aren't suggested, and items the model hasn't
+    // seen are ignored.
+    pub fn complete(&self, basket: &[&str], k: usize) -> Vec<(String, f64)> {
+        let basket: FnvHashSet<u32> = basket
+            .iter()
+            .filter_map(|item| self.item_ids.get(*item).cloned())
+            .collect();
+        // Number of each rule's antecedent items in the basket; the rule
+        // applies once they all are.
+        let mut matched: FnvHashMap<u32, usize> = FnvHashMap::default();
+        let mut applicable: Vec<u32> = self.unconditional.clone();
+        for &item in &basket {
+            for &rule_id in &self.rules_by_item[item as usize] {
+                let count = matched.entry(rule_id).or_insert(0);
+                *count += 1;
+                if *count == self.rules[rule_id as usize].antecedent_len {
+                    applicable.push(rule_id);
+                }
+            }
+        }
+
+        let mut scores: FnvHashMap<u32, f64> = FnvHashMap::default();
+        for rule in applicable
+            .iter()
+            .map(|&rule_id| &self.rules[rule_id as usize])
+        {
+            for &item in rule.consequent.iter().filter(|item| !basket.contains(item)) {
+                match self.aggregation {
+                    Aggregation::MaxConfidence => {
+                        let score = scores.entry(item).or_insert(rule.confidence);
+                        *score = score.max(rule.confidence);
+                    }
+                    Aggregation::SumLift => {
+                        *scores.entry(item).or_insert(0.0) += rule.lift;
+                    }
+                }
+            }
+        }
+
+        let mut suggestions: Vec<(&str, f64)> = scores
+            .into_iter()
+            .map(|(item, score)| (self.item_names[item as usize].as_str(), score))
+            .collect();
+        suggestions.sort_by(|a, b| {
+            b.1.partial_cmp(&a.1)
+                .unwrap_or(Ordering::Equal)
+                .then_with(|| a.0.cmp(b.0))
+        });
+        suggestions.truncate(k);
+        suggestions
+            .into_iter()
+            .map(|(item, score)| (String::from(item), score))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Aggregation, RuleModel};
+    use rule_file::RuleRecord;
+
+    #[test]
+    fn test_rule_model() {
+        let rules: Vec<RuleRecord> = [
+            "a => b,0.5,2,0.1",
+            "a => c,0.6,1,0.1",
+            "a d => b,0.9,3,0.1",
+            "e => f,1,4,0.1",
+            "a => c d,0.4,1.5,0.1",
+        ]
+        .iter()
+        .map(|line| line.parse().unwrap())
+        .collect();
+
+        let model = RuleModel::from_records(&rules, Aggregation::MaxConfidence);
+        assert_eq!(model.num_rules(), 5);
+        assert_eq!(
+            model.complete(&["a", "c", "unknown"], 10),
+            vec![(String::from("b"), 0.5), (String::from("d"), 0.4)]
+        );
+        // Both antecedent items are needed for the rule with "a d".
+        assert_eq!(
+            model.complete(&["d", "a"], 10),
+            vec![(String::from("b"), 0.9), (String::from("c"), 0.6)]
+        );
+        assert_eq!(model.complete(&["a"], 1).len(), 1);
+        assert!(model.complete(&["b"], 10).is_empty());
+
+        let model = RuleModel::from_records(&rules, Aggregation::SumLift);
+        assert_eq!(
+            model.complete(&["a", "d"], 10),
+            vec![(String::from("b"), 5.0), (String::from("c"), 2.5)]
+        );
+        assert_eq!("sum-lift".parse(), Ok(Aggregation::SumLift));
+        assert!("median".parse::<Aggregation>().is_err());
+    }
+}
@@ -0,0 +1,381 @@
+// Copyright 2018 Chris Pearce
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use cancellation::CancellationToken;
+use command_line_args::RulesArguments;
+use error::ArmError;
+use fnv::FnvHashSet;
+use fptree::ItemSet;
+use generate_rules::RuleParameters;
+use index::IndexBackend;
+use item::Item;
+use itemizer::Itemizer;
+use mining::duration_as_ms;
+use output::{stream_rules, OutputOptions};
+use rayon::prelude::*;
+use rule::DEFAULT_LAPLACE_K;
+use rule_file::parse_items;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::str::FromStr;
+use std::time::Instant;
+use support::index_dataset;
+use transaction_reader::{Encoding, InputFormat};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ItemsetsFormat {
+    // Written by `arm mine --output-itemsets`: "item1 item2 ...,support,count".
+    Arm,
+    // SPMF's output format: "item1 item2 ... #SUP: count".
+    Spmf,
+    // A pandas DataFrame of mlxtend's frequent itemsets written with
+    // to_csv(): "[index,]support,frozenset({'item1', 'item2'})".
+    Mlxtend,
+}
+
+impl FromStr for ItemsetsFormat {
+    type Err = String;
+    fn from_str(s: &str) -> Result<ItemsetsFormat, String> {
+        match s {
+            "arm" => Ok(ItemsetsFormat::Arm),
+            "spmf" => Ok(ItemsetsFormat::Spmf),
+            "mlxtend" => Ok(ItemsetsFormat::Mlxtend),
+            _ => Err(format!("Unknown itemsets format {}", s)),
+        }
+    }
+}
+
+// An itemset read from an itemsets file, with whichever of its count and
+// support the file gives.
+pub struct ItemsetRecord {
+    pub items: Vec<String>,
+    pub count: Option<u32>,
+    pub support: Option<f64>,
+}
+
+fn parse_arm_line(line: &str) -> Option<ItemsetRecord> {
+    let mut fields = line.rsplitn(3, ',');
+    let count = fields.next()?.trim().parse::<u32>().ok()?;
+    let support = fields.next()?.trim().parse::<f64>().ok()?;
+    Some(ItemsetRecord {
+        items: parse_items(fields.next()?),
+        count: Some(count),
+        support: Some(support),
+    })
+}
+
+fn parse_spmf_line(line: &str) -> Option<ItemsetRecord> {
+    let mut fields = line.splitn(2, "#SUP:");
+    let items = fields
+        .next()?
+        .split_whitespace()
+        .map(String::from)
+        .collect();
+    let count = fields.next()?.trim().parse::<u32>().ok()?;
+    Some(ItemsetRecord {
+        items,
+        count: Some(count),
+        support: None,
+    })
+}
+
+fn parse_mlxtend_line(line: &str) -> Option<ItemsetRecord> {
+    let start = line.find("frozenset({")?;
+    let set = &line[start + "frozenset({".len()..];
+    let set = &set[..set.find("})")?];
+    let items = set
+        .split(',')
+        .map(|item| item.trim().trim_matches(['\'', '"']))
+        .filter(|item| !item.is_empty())
+        .map(String::from)
+        .collect();
+    // The support is the last field before the itemset, after the index.
+    let support = line[..start]
+        .trim_end_matches(['"', ','])
+        .rsplit(',')
+        .next()?
+        .trim()
+        .parse::<f64>()
+        .ok()?;
+    Some(ItemsetRecord {
+        items,
+        count: None,
+        support: Some(support),
+    })
+}
+
+pub fn read_itemset_records(
+    path: &str,
+    format: ItemsetsFormat,
+) -> Result<Vec<ItemsetRecord>, ArmError> {
+    let (parse, expected): (fn(&str) -> Option<ItemsetRecord>, &str) = match format {
+        ItemsetsFormat::Arm => (parse_arm_line, "expected item1 item2 ...,support,count"),
+        ItemsetsFormat::Spmf => (parse_spmf_line, "expected item1 item2 ... #SUP: count"),
+        ItemsetsFormat::Mlxtend => (parse_mlxtend_line, "expected support,frozenset({...})"),
+    };
+    let file = File::open(path).map_err(|e| ArmError::io(path, e))?;
+    let mut records = vec![];
+    for (index, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|e| ArmError::io(path, e))?;
+        let is_header = line.starts_with("Itemset") || line.contains("itemsets");
+        if (index == 0 && is_header) || line.trim().is_empty() {
+            continue;
+        }
+        match parse(&line) {
+            Some(record) => records.push(record),
+            None => return Err(ArmError::parse(path, index + 1, expected)),
+        }
+    }
+    Ok(records)
+}
+
+// Assigns ids to the records' items in lexicographic order, so that sorted
+// itemsets are also sorted lexicographically when written, as when mining.
+fn itemize_records(records: &[ItemsetRecord], itemizer: &mut Itemizer) -> Vec<Vec<Item>> {
+    let mut names: Vec<&String> = records.iter().flat_map(|r| r.items.iter()).collect();
+    names.sort();
+    names.dedup();
+    for name in names {
+        itemizer.id_of(name);
+    }
+    records
+        .iter()
+        .map(|record| {
+            let mut items: Vec<Item> = record.items.iter().map(|n| itemizer.id_of(n)).collect();
+            items.sort();
+            items.dedup();
+            items
+        })
+        .collect()
+}
+
+// The number of transactions implied by the first record with both a count
+// and a non-zero support.
+fn transactions_from_supports(records: &[ItemsetRecord]) -> Option<usize> {
+    records
+        .iter()
+        .find_map(|record| match (record.count, record.support) {
+            (Some(count), Some(support)) if support > 0.0 => {
+                Some((count as f64 / support).round() as usize)
+            }
+            _ => None,
+        })
+}
+
+// Returns the subsets of itemsets which aren't themselves in itemsets. Rule
+// generation needs the counts of every subset of every itemset. Checking
+// immediate subsets, including those of missing subsets, finds them all.
+fn missing_subsets(itemsets: &[Vec<Item>]) -> Vec<Vec<Item>> {
+    let mut present: FnvHashSet<Vec<Item>> = itemsets.iter().cloned().collect();
+    let mut missing: Vec<Vec<Item>> = vec![];
+    let mut pending: Vec<Vec<Item>> = itemsets.to_vec();
+    while let Some(itemset) = pending.pop() {
+        if itemset.len() < 2 {
+            continue;
+        }
+        for skip in 0..itemset.len() {
+            let mut subset = itemset.clone();
+            subset.remove(skip);
+            if present.insert(subset.clone()) {
+                missing.push(subset.clone());
+                pending.push(subset);
+            }
+        }
+    }
+    missing.sort();
+    missing
+}
+
+pub fn run_rules(args: &RulesArguments) -> Result<(), ArmError> {
+    let start = Instant::now();
+    let mut itemizer = Itemizer::new();
+    let records = read_itemset_records(&args.itemsets_path, args.itemsets_format)?;
+    let mut items = itemize_records(&records, &mut itemizer);
+    println!("Read {} itemsets.", records.len());
+
+    // With the dataset, itemsets and subsets without counts can be counted.
+    let index = match args.input_file_path {
+        Some(ref path) => {
+            let timer = Instant::now();
+            let names: FnvHashSet<String> = records
+                .iter()
+                .flat_map(|r| r.items.iter())
+                .cloned()
+                .collect();
+            let index = index_dataset(
+                path,
+                args.weighted,
+                InputFormat::Csv,
+                Encoding::Utf8,
+                IndexBackend::TidList,
+                &names,
+                &mut itemizer,
+            )?;
+            println!(
+                "Indexed {} transactions in {} ms.",
+                index.transaction_count(),
+                duration_as_ms(&timer.elapsed())
+            );
+            Some(index)
+        }
+        None => None,
+    };
+    let num_transactions = match (&index, args.num_transactions) {
+        (Some(index), _) => index.transaction_count(),
+        (None, Some(num_transactions)) => num_transactions,
+        (None, None) => transactions_from_supports(&records).ok_or_else(|| {
+            ArmError::parse(
+                &args.itemsets_path,
+                0,
+                "can't tell the number of transactions; pass --input or --num-transactions",
+            )
+        })?,
+    };
+    println!("Dataset has {} transactions.", num_transactions);
+
+    let mut counts: Vec<Option<u32>> = records
+        .iter()
+        .map(|record| {
+            record.count.or_else(|| {
+                record
+                    .support
+                    .map(|support| (support * num_transactions as f64).round() as u32)
+            })
+        })
+        .collect();
+    let missing = missing_subsets(&items);
+    if !missing.is_empty() {
+        if index.is_none() {
+            let names: Vec<&str> = missing[0].iter().map(|&i| itemizer.str_of(i)).collect();
+            return Err(ArmError::parse(
+                &args.itemsets_path,
+                0,
+                &format!(
+                    "subset {} of an itemset is missing; rules need all frequent \
+                     itemsets, or pass --input to count missing ones",
+                    names.join(" ")
+                ),
+            ));
+        }
+        println!("Counting {} missing subsets in the dataset.", missing.len());
+        counts.extend(missing.iter().map(|_| None));
+        items.extend(missing);
+    }
+    let itemsets: Vec<ItemSet> = items
+        .into_par_iter()
+        .zip(counts.into_par_iter())
+        .map(|(items, count)| {
+            let count = count.unwrap_or_else(|| match index {
+                Some(ref index) => index.count(&items) as u32,
+                None => 0,
+            });
+            ItemSet::new(items, count)
+        })
+        .collect();
+
+    let params = RuleParameters {
+        min_confidence: args.min_confidence,
+        min_confidence_by_length: args.min_confidence_by_length.clone(),
+        min_lift: args.min_lift,
+        max_lift: args.max_lift,
+        min_confidence_lower_bound: None,
+        laplace_k: DEFAULT_LAPLACE_K,
+        min_laplace: None,
+        min_added_value: None,
+        min_gini_gain: None,
+        min_info_gain: None,
+        min_odds_ratio: None,
+        min_yules_q: None,
+        min_phi: None,
+        filter: None,
+        min_itemset_size: 1,
+        cancellation: CancellationToken::new(),
+        item_min_counts: None,
+        target_consequent: None,
+    };
+    let num_rules: usize = stream_rules(
+        &itemsets,
+        num_transactions as u32,
+        &params,
+        true,
+        &args.output_rules_path,
+        &OutputOptions::default(),
+        &itemizer,
+    )
+    .map_err(|e| ArmError::io(&args.output_rules_path, e))?
+    .iter()
+    .sum();
+    println!(
+        "Generated and wrote {} rules in {} ms.",
+        num_rules,
+        duration_as_ms(&start.elapsed())
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        itemize_records, missing_subsets, parse_mlxtend_line, parse_spmf_line,
+        read_itemset_records, transactions_from_supports, ItemsetsFormat,
+    };
+    use item::Item;
+    use itemizer::Itemizer;
+    use std::fs;
+    use test_util::TempFile;
+
+    #[test]
+    fn test_read_itemsets() {
+        let path = TempFile::new("read-itemsets.csv");
+        fs::write(
+            &path,
+            "Itemset,Support,Count\nb,0.5,2\na,0.75,3\na b,0.5,2\n",
+        )
+        .unwrap();
+        let records = read_itemset_records(path.path(), ItemsetsFormat::Arm).unwrap();
+        assert_eq!(transactions_from_supports(&records), Some(4));
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[2].count, Some(2));
+        let mut itemizer = Itemizer::new();
+        let items = itemize_records(&records, &mut itemizer);
+        // Ids are assigned in lexicographic order.
+        assert!(itemizer.id_of("a") < itemizer.id_of("b"));
+        assert!(missing_subsets(&items).is_empty());
+        assert_eq!(
+            missing_subsets(&items[1..]),
+            vec![vec![itemizer.id_of("b")]]
+        );
+    }
+
+    #[test]
+    fn test_other_formats() {
+        assert_eq!("spmf".parse(), Ok(ItemsetsFormat::Spmf));
+        let record = parse_spmf_line("1 5 #SUP: 3").unwrap();
+        assert_eq!(record.items, vec!["1", "5"]);
+        assert_eq!(record.count, Some(3));
+
+        let record = parse_mlxtend_line("4,0.25,\"frozenset({'b', 'a c'})\"").unwrap();
+        assert_eq!(record.items, vec!["b", "a c"]);
+        assert_eq!(record.support, Some(0.25));
+        let record = parse_mlxtend_line("0.5,frozenset({'x'})").unwrap();
+        assert_eq!(record.items, vec!["x"]);
+        assert_eq!(record.support, Some(0.5));
+        assert!(parse_mlxtend_line("0.5,x").is_none());
+
+        // {a, b, c} is missing all its subsets but {a, b}.
+        let item = Item::with_id;
+        let missing = missing_subsets(&[vec![item(1), item(2)], vec![item(1), item(2), item(3)]]);
+        assert_eq!(missing.len(), 5);
+    }
+}
@@ -0,0 +1,242 @@
+// Copyright 2018 Chris Pearce
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use command_line_args::ScoreArguments;
+use error::ArmError;
+use fnv::{FnvHashMap, FnvHashSet};
+use input::csv_line;
+use output::{join_items, OutputFile};
+use rayon::prelude::*;
+use rule_file::{read_rules, RuleRecord};
+use std::cmp::Ordering;
+use std::io::{self, Write};
+use transaction_reader::LineReader;
+
+// Number of transactions scored in parallel at once, bounding the memory used
+// while streaming the transactions.
+const BATCH_SIZE: usize = 1 << 16;
+
+// How anomalous a transaction is: the rules it violates, that is, whose
+// antecedents it contains but not all of whose consequents.
+pub struct Score<'a> {
+    // Sum of the confidences of the rules violated; the expected number of
+    // those rules' predictions which should have held.
+    pub score: f64,
+    pub num_violated: usize,
+    // The violated rule with the highest confidence.
+    pub top_rule: Option<&'a RuleRecord>,
+}
+
+// Rules indexed by the first item of their antecedents, which must be in a
+// transaction for the rule to apply.
+pub struct RuleIndex<'a> {
+    rules_by_item: FnvHashMap<&'a str, Vec<&'a RuleRecord>>,
+}
+
+impl<'a> RuleIndex<'a> {
+    pub fn new(rules: &'a [RuleRecord]) -> RuleIndex<'a> {
+        let mut rules_by_item: FnvHashMap<&str, Vec<&RuleRecord>> = FnvHashMap::default();
+        for rule in rules {
+            if let Some(item) = rule.antecedent.first() {
+                rules_by_item.entry(item).or_default().push(rule);
+            }
+        }
+        RuleIndex { rules_by_item }
+    }
+
+    pub fn score(&self, transaction: &[&str]) -> Score<'a> {
+        let items: FnvHashSet<&str> = transaction.iter().cloned().collect();
+        let contains_all = |side: &[String]| side.iter().all(|item| items.contains(item.as_str()));
+        let mut score = Score {
+            score: 0.0,
+            num_violated: 0,
+            top_rule: None,
+        };
+        for rule in items
+            .iter()
+            .filter_map(|item| self.rules_by_item.get(item))
+            .flatten()
+        {
+            if contains_all(&rule.antecedent) && !contains_all(&rule.consequent) {
+                score.score += rule.confidence;
+                score.num_violated += 1;
+                if score
+                    .top_rule
+                    .is_none_or(|top| rule.confidence > top.confidence)
+                {
+                    score.top_rule = Some(rule);
+                }
+            }
+        }
+        score
+    }
+}
+
+// The part of a rule's line before its metrics: "antecedent => consequent".
+// Quoted item names may contain commas, so this must be quoted as a field
+// of CSV output.
+fn rule_text(rule: &RuleRecord) -> String {
+    format!(
+        "{} => {}",
+        join_items(&rule.antecedent),
+        join_items(&rule.consequent)
+    )
+}
+
+// Writes a transaction's number and score as a line of CSV.
+fn write_score<W: Write>(output: &mut W, number: usize, score: &Score) -> io::Result<()> {
+    let fields = [
+        number.to_string(),
+        score.score.to_string(),
+        score.num_violated.to_string(),
+        score.top_rule.map(rule_text).unwrap_or_default(),
+    ];
+    output.write_all(csv_line(&fields).as_bytes())
+}
+
+pub fn run_score(args: &ScoreArguments) -> Result<(), ArmError> {
+    let rules: Vec<RuleRecord> = read_rules(&args.rules_path)?
+        .into_iter()
+        .filter(|rule| rule.confidence >= args.min_confidence)
+        .filter(|rule| args.min_lift.is_none_or(|min_lift| rule.lift >= min_lift))
+        .collect();
+    println!(
+        "Scoring transactions against {} rules with confidence at least {}...",
+        rules.len(),
+        args.min_confidence
+    );
+    let index = RuleIndex::new(&rules);
+
+    let path = &args.output_path;
+    let mut output = OutputFile::create(path, None).map_err(|e| ArmError::io(path, e))?;
+    writeln!(output, "Transaction,Score,Violations,TopRule").map_err(|e| ArmError::io(path, e))?;
+    let mut lines = LineReader::open(&args.input_file_path, None)?;
+    lines.set_format(args.input_format);
+    lines.set_encoding(args.encoding);
+    let mut line = String::new();
+    let mut num_transactions = 0;
+    let mut num_flagged = 0;
+    // The most anomalous transactions, as (score, transaction number,
+    // items). Each transaction is scored once, whatever its count in
+    // counted input.
+    let mut top: Vec<(f64, usize, Vec<String>)> = vec![];
+    loop {
+        let mut batch: Vec<Vec<String>> = vec![];
+        while batch.len() < BATCH_SIZE {
+            match lines.read_items(&mut line) {
+                Some(items) => batch.push(items?.0),
+                None => break,
+            }
+        }
+        if batch.is_empty() {
+            break;
+        }
+        let scores: Vec<Score> = batch
+            .par_iter()
+            .map(|items| {
+                let items: Vec<&str> = items
+                    .iter()
+                    .map(|item| item.as_str())
+                    .filter(|item| !item.is_empty())
+                    .collect();
+                index.score(&items)
+            })
+            .collect();
+        for (items, score) in batch.into_iter().zip(scores) {
+            num_transactions += 1;
+            write_score(&mut output, num_transactions, &score)
+                .map_err(|e| ArmError::io(path, e))?;
+            if score.num_violated > 0 {
+                num_flagged += 1;
+                top.push((score.score, num_transactions, items));
+                if top.len() >= 2 * args.top.max(1) {
+                    top.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(Ordering::Equal));
+                    top.truncate(args.top);
+                }
+            }
+        }
+    }
+    output.finish().map_err(|e| ArmError::io(path, e))?;
+    println!(
+        "{} of {} transactions violated at least one rule. Wrote scores to {}.",
+        num_flagged, num_transactions, path
+    );
+    top.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(Ordering::Equal));
+    top.truncate(args.top);
+    if !top.is_empty() {
+        println!("Most anomalous transactions:");
+        for (score, number, items) in top {
+            println!(
+                "{:>10.4}  transaction {}: {}",
+                score,
+                number,
+                csv_line(&items).trim_end()
+            );
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{rule_text, write_score, RuleIndex};
+    use rule_file::{split_unquoted, RuleRecord};
+
+    #[test]
+    fn test_score() {
+        let rules: Vec<RuleRecord> = [
+            "milk => bread,0.9,1.2,0.3",
+            "bread eggs => milk,0.8,1.5,0.1",
+            "eggs => bacon cheese,0.6,2,0.1",
+        ]
+        .iter()
+        .map(|line| line.parse().unwrap())
+        .collect();
+        let index = RuleIndex::new(&rules);
+
+        let score = index.score(&["milk", "bread", "eggs"]);
+        assert_eq!(score.num_violated, 1);
+        assert_eq!(score.score, 0.6);
+
+        // Both consequent items are needed for the rule to hold.
+        let score = index.score(&["bread", "eggs", "bacon"]);
+        assert_eq!(score.num_violated, 2);
+        assert!((score.score - 1.4).abs() < 1e-9);
+        assert_eq!(score.top_rule.map(rule_text).unwrap(), "bread eggs => milk");
+
+        let score = index.score(&["bread"]);
+        assert_eq!(score.num_violated, 0);
+        assert_eq!(score.score, 0.0);
+        assert!(score.top_rule.is_none());
+    }
+
+    #[test]
+    fn test_write_score_quotes_top_rule() {
+        let rules: Vec<RuleRecord> = [r#""salt, coarse" => pepper,0.9,1.2,0.3"#]
+            .iter()
+            .map(|line| line.parse().unwrap())
+            .collect();
+        let index = RuleIndex::new(&rules);
+        let score = index.score(&["salt, coarse"]);
+        assert_eq!(score.num_violated, 1);
+
+        let mut output = vec![];
+        write_score(&mut output, 7, &score).unwrap();
+        let line = String::from_utf8(output).unwrap();
+        assert_eq!(line, "7,0.9,1,\"\"\"salt, coarse\"\" => pepper\"\n");
+        // The item's comma is inside the quoted field, so the row still has
+        // four fields.
+        assert_eq!(split_unquoted(line.trim_end(), ',').len(), 4);
+    }
+}
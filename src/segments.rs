@@ -0,0 +1,263 @@
+// Copyright 2018 Chris Pearce
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use command_line_args::SegmentsArguments;
+use error::ArmError;
+use fnv::FnvHashMap;
+use miner::{AssociationRule, Miner, MinerParameters};
+use output::{join_items, OutputFile};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+
+// A dataset split into segments, such as store regions, in order of each
+// segment's first appearance in the dataset.
+pub struct SegmentedDataset {
+    pub segments: Vec<(String, Vec<Vec<String>>)>,
+}
+
+impl SegmentedDataset {
+    // Reads a dataset in which column segment_column of each line names the
+    // line's segment, and the other columns are the transaction's items.
+    pub fn read(path: &str, segment_column: usize) -> Result<SegmentedDataset, ArmError> {
+        let file = File::open(path).map_err(|e| ArmError::io(path, e))?;
+        let mut segments: Vec<(String, Vec<Vec<String>>)> = vec![];
+        let mut segment_index: FnvHashMap<String, usize> = FnvHashMap::default();
+        for (index, line) in BufReader::new(file).lines().enumerate() {
+            let line = line.map_err(|e| ArmError::io(path, e))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let mut items: Vec<String> = line.split(',').map(|s| String::from(s.trim())).collect();
+            if segment_column >= items.len() {
+                return Err(ArmError::parse(
+                    path,
+                    index + 1,
+                    &format!("expected a segment in column {}", segment_column),
+                ));
+            }
+            let segment = items.remove(segment_column);
+            let next_index = segments.len();
+            let segment_index = *segment_index.entry(segment.clone()).or_insert(next_index);
+            if segment_index == next_index {
+                segments.push((segment, vec![]));
+            }
+            segments[segment_index].1.push(items);
+        }
+        Ok(SegmentedDataset { segments })
+    }
+}
+
+// A rule and its metrics in each segment, or None in segments in which
+// the rule wasn't generated.
+pub struct RuleComparison<'a> {
+    pub antecedent: &'a [String],
+    pub consequent: &'a [String],
+    pub by_segment: Vec<Option<&'a AssociationRule>>,
+}
+
+// Lines up the same rule across segments. Rules are in order of their first
+// appearance in rules, which is indexed by segment.
+pub fn compare_segments(rules: &[Vec<AssociationRule>]) -> Vec<RuleComparison<'_>> {
+    let mut comparisons: Vec<RuleComparison> = vec![];
+    let mut index: FnvHashMap<(&[String], &[String]), usize> = FnvHashMap::default();
+    for (segment, segment_rules) in rules.iter().enumerate() {
+        for rule in segment_rules {
+            let key = (rule.antecedent.as_slice(), rule.consequent.as_slice());
+            let next_index = comparisons.len();
+            let rule_index = *index.entry(key).or_insert(next_index);
+            if rule_index == next_index {
+                comparisons.push(RuleComparison {
+                    antecedent: &rule.antecedent,
+                    consequent: &rule.consequent,
+                    by_segment: vec![None; rules.len()],
+                });
+            }
+            comparisons[rule_index].by_segment[segment] = Some(rule);
+        }
+    }
+    comparisons
+}
+
+fn write_segment_rules<W: Write>(
+    output: &mut W,
+    segments: &[&str],
+    rules: &[Vec<AssociationRule>],
+) -> io::Result<()> {
+    writeln!(
+        output,
+        "Segment,Antecedent => Consequent,Confidence,Lift,Support,Count"
+    )?;
+    for (segment, segment_rules) in segments.iter().zip(rules) {
+        for rule in segment_rules {
+            writeln!(
+                output,
+                "{},{} => {},{},{},{},{}",
+                segment,
+                join_items(&rule.antecedent),
+                join_items(&rule.consequent),
+                rule.confidence,
+                rule.lift,
+                rule.support,
+                rule.count
+            )?;
+        }
+    }
+    Ok(())
+}
+
+// Writes one row per rule, with the rule's confidence, lift and support in
+// each segment. Cells are empty for segments in which the rule wasn't
+// generated.
+fn write_comparison<W: Write>(
+    output: &mut W,
+    segments: &[&str],
+    comparisons: &[RuleComparison],
+) -> io::Result<()> {
+    write!(output, "Antecedent => Consequent")?;
+    for segment in segments {
+        write!(output, ",{0} Confidence,{0} Lift,{0} Support", segment)?;
+    }
+    writeln!(output)?;
+    for comparison in comparisons {
+        write!(
+            output,
+            "{} => {}",
+            join_items(comparison.antecedent),
+            join_items(comparison.consequent)
+        )?;
+        for rule in &comparison.by_segment {
+            match *rule {
+                Some(rule) => write!(
+                    output,
+                    ",{},{},{}",
+                    rule.confidence, rule.lift, rule.support
+                )?,
+                None => write!(output, ",,,")?,
+            }
+        }
+        writeln!(output)?;
+    }
+    Ok(())
+}
+
+pub fn run_segments(args: &SegmentsArguments) -> Result<(), ArmError> {
+    println!("Reading segmented data set: {}", args.input_file_path);
+    let dataset = SegmentedDataset::read(&args.input_file_path, args.segment_column)?;
+    let segments: Vec<&str> = dataset
+        .segments
+        .iter()
+        .map(|(segment, _)| segment.as_str())
+        .collect();
+    println!("Read {} segments.", segments.len());
+
+    let mut rules: Vec<Vec<AssociationRule>> = vec![];
+    for (segment, transactions) in &dataset.segments {
+        let miner = Miner::from_transactions(
+            transactions.iter().cloned(),
+            MinerParameters {
+                min_support: args.min_support,
+                min_confidence: args.min_confidence,
+                min_lift: args.min_lift,
+                max_lift: args.max_lift,
+                max_itemset_size: args.max_itemset_size,
+                ..MinerParameters::default()
+            },
+        );
+        let segment_rules = miner.rules();
+        println!(
+            "Segment {}: {} transactions, {} rules.",
+            segment,
+            miner.num_transactions(),
+            segment_rules.len()
+        );
+        rules.push(segment_rules);
+    }
+
+    let write = || -> io::Result<()> {
+        let mut output = OutputFile::create(&args.output_rules_path, None)?;
+        write_segment_rules(&mut output, &segments, &rules)?;
+        output.finish()
+    };
+    write().map_err(|e| ArmError::io(&args.output_rules_path, e))?;
+
+    if let Some(ref path) = args.comparison_path {
+        let comparisons = compare_segments(&rules);
+        let write = || -> io::Result<()> {
+            let mut output = OutputFile::create(path, None)?;
+            write_comparison(&mut output, &segments, &comparisons)?;
+            output.finish()
+        };
+        write().map_err(|e| ArmError::io(path, e))?;
+        println!(
+            "Wrote comparison of {} rules across segments to {}.",
+            comparisons.len(),
+            path
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compare_segments, SegmentedDataset};
+    use miner::{Miner, MinerParameters};
+    use std::fs;
+    use test_util::TempFile;
+
+    #[test]
+    fn test_segments() {
+        let path = TempFile::new("segments.csv");
+        fs::write(
+            &path,
+            "a,north,b\na,south,b\nc,north,a\n\na,south,c\na,north,b\n\
+             b,north,a\nc,south,a\nb,south,a\n",
+        )
+        .unwrap();
+        let dataset = SegmentedDataset::read(path.path(), 1).unwrap();
+        let segments: Vec<&str> = dataset.segments.iter().map(|s| s.0.as_str()).collect();
+        assert_eq!(segments, vec!["north", "south"]);
+        assert_eq!(dataset.segments[0].1.len(), 4);
+        assert_eq!(dataset.segments[1].1[1], vec!["a", "c"]);
+        assert!(SegmentedDataset::read(path.path(), 3).is_err());
+
+        let rules: Vec<_> = dataset
+            .segments
+            .iter()
+            .map(|(_, transactions)| {
+                let params = MinerParameters {
+                    min_support: 0.25,
+                    min_confidence: 0.5,
+                    ..MinerParameters::default()
+                };
+                Miner::from_transactions(transactions.iter().cloned(), params).rules()
+            })
+            .collect();
+        let comparisons = compare_segments(&rules);
+        let b_to_a = comparisons
+            .iter()
+            .find(|c| c.antecedent == ["b"] && c.consequent == ["a"])
+            .unwrap();
+        // b => a holds in every transaction with b in both segments.
+        assert!(b_to_a
+            .by_segment
+            .iter()
+            .all(|rule| rule.is_some_and(|rule| rule.confidence == 1.0)));
+        let a_to_c = comparisons
+            .iter()
+            .find(|c| c.antecedent == ["a"] && c.consequent == ["c"])
+            .unwrap();
+        assert!(a_to_c.by_segment[0].is_none());
+        assert_eq!(a_to_c.by_segment[1].unwrap().confidence, 0.5);
+    }
+}
@@ -0,0 +1,263 @@
+// Copyright 2018 Chris Pearce
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use fnv::{FnvHashMap, FnvHashSet};
+use fptree::ItemSet;
+use generate_rules::RuleGenerator;
+use item::Item;
+use itemizer::Itemizer;
+use rule::Rule;
+use verify::items_to_string;
+
+// Transactions with more frequent items than this are too long to
+// enumerate every subset of.
+pub const MAX_SELF_CHECK_LENGTH: usize = 20;
+
+// Maximum number of discrepancies of each kind printed.
+const MAX_REPORTED: usize = 10;
+
+fn add_subsets(
+    items: &[Item],
+    subset: &mut Vec<Item>,
+    max_size: usize,
+    count: u32,
+    counts: &mut FnvHashMap<Vec<Item>, u32>,
+) {
+    if items.is_empty() || subset.len() == max_size {
+        return;
+    }
+    for (index, &item) in items.iter().enumerate() {
+        subset.push(item);
+        *counts.entry(subset.clone()).or_insert(0) += count;
+        add_subsets(&items[index + 1..], subset, max_size, count, counts);
+        subset.pop();
+    }
+}
+
+// Brute force itemset mining, which counts every subset of every
+// transaction, and keeps those which occur at least min_count times. No
+// subset containing an item which occurs fewer than min_count times can, so
+// such items are dropped before enumerating subsets. Returns an error if a
+// transaction is too long to enumerate.
+pub fn naive_itemsets(
+    transactions: &[(Vec<Item>, u32)],
+    min_count: u32,
+    max_itemset_size: Option<usize>,
+) -> Result<Vec<ItemSet>, String> {
+    let max_size = max_itemset_size.unwrap_or(usize::MAX);
+    let mut item_count: FnvHashMap<Item, u32> = FnvHashMap::default();
+    for &(ref transaction, count) in transactions {
+        let items: FnvHashSet<Item> = transaction.iter().cloned().collect();
+        for item in items {
+            *item_count.entry(item).or_insert(0) += count;
+        }
+    }
+    let mut counts: FnvHashMap<Vec<Item>, u32> = FnvHashMap::default();
+    for &(ref transaction, count) in transactions {
+        let mut items: Vec<Item> = transaction
+            .iter()
+            .cloned()
+            .filter(|item| item_count[item] >= min_count)
+            .collect();
+        if items.len() > MAX_SELF_CHECK_LENGTH {
+            return Err(format!(
+                "a transaction has {} frequent items, but --self-check only \
+                 supports up to {}",
+                items.len(),
+                MAX_SELF_CHECK_LENGTH
+            ));
+        }
+        items.sort();
+        items.dedup();
+        add_subsets(&items, &mut vec![], max_size, count, &mut counts);
+    }
+    Ok(counts
+        .into_iter()
+        .filter(|&(_, count)| count >= min_count)
+        .map(|(items, count)| ItemSet::new(items, count))
+        .collect())
+}
+
+// Adds every rule which can be made by splitting items between antecedent
+// and consequent, and which generator accepts.
+fn naive_add_rules_for(
+    rules: &mut FnvHashSet<Rule>,
+    items: &[Item],
+    antecedent: &mut Vec<Item>,
+    consequent: &mut Vec<Item>,
+    count: u32,
+    generator: &RuleGenerator,
+) {
+    if items.is_empty() {
+        if antecedent.is_empty() || consequent.is_empty() {
+            return;
+        }
+        let rule = generator.make_rule(antecedent.to_vec(), consequent.to_vec(), count);
+        if generator.accepts(&rule) {
+            rules.insert(rule);
+        }
+        return;
+    }
+    let item = items[0];
+
+    antecedent.push(item);
+    naive_add_rules_for(rules, &items[1..], antecedent, consequent, count, generator);
+    antecedent.pop();
+
+    consequent.push(item);
+    naive_add_rules_for(rules, &items[1..], antecedent, consequent, count, generator);
+    consequent.pop();
+}
+
+// Naive implementation of rule generation which simply tries all
+// combinations of rules. Compare cleverer approach with this to ensure
+// the cleverer approach isn't over-pruning.
+pub fn naive_rules(itemsets: &[ItemSet], generator: &RuleGenerator) -> FnvHashSet<Rule> {
+    let mut rules = FnvHashSet::default();
+    for itemset in itemsets
+        .iter()
+        .filter(|itemset| generator.generates_rules_for(itemset))
+    {
+        naive_add_rules_for(
+            &mut rules,
+            &itemset.items,
+            &mut vec![],
+            &mut vec![],
+            itemset.count,
+            generator,
+        );
+    }
+    rules
+}
+
+// Compares mined itemsets against those found by brute force, and prints
+// any differences. Returns the number of discrepancies.
+pub fn check_itemsets(mined: &[ItemSet], expected: &[ItemSet], itemizer: &Itemizer) -> usize {
+    let sorted = |itemset: &ItemSet| {
+        let mut items = itemset.items.clone();
+        items.sort();
+        (items, itemset.count)
+    };
+    let mined: FnvHashMap<Vec<Item>, u32> = mined.iter().map(sorted).collect();
+    let expected: FnvHashMap<Vec<Item>, u32> = expected.iter().map(sorted).collect();
+    let mut discrepancies = vec![];
+    for (items, &count) in &expected {
+        match mined.get(items) {
+            None => discrepancies.push(format!(
+                "Itemset [{}] with count {} wasn't mined.",
+                items_to_string(items, itemizer),
+                count
+            )),
+            Some(&mined_count) if mined_count != count => discrepancies.push(format!(
+                "Itemset [{}] was mined with count {} but has count {}.",
+                items_to_string(items, itemizer),
+                mined_count,
+                count
+            )),
+            _ => {}
+        }
+    }
+    for (items, &count) in mined
+        .iter()
+        .filter(|(items, _)| !expected.contains_key(*items))
+    {
+        discrepancies.push(format!(
+            "Itemset [{}] was mined with count {} but isn't frequent.",
+            items_to_string(items, itemizer),
+            count
+        ));
+    }
+    report(discrepancies)
+}
+
+// Compares generated rules against those generated by brute force, and
+// prints any differences. Returns the number of discrepancies.
+pub fn check_rules(rules: &[Vec<Rule>], expected: &FnvHashSet<Rule>, itemizer: &Itemizer) -> usize {
+    let generated: FnvHashSet<&Rule> = rules.iter().flatten().collect();
+    let describe = |rule: &Rule| {
+        format!(
+            "{} => {}",
+            items_to_string(&rule.antecedent, itemizer),
+            items_to_string(&rule.consequent, itemizer)
+        )
+    };
+    let mut discrepancies = vec![];
+    for rule in expected {
+        match generated.get(rule) {
+            None => discrepancies.push(format!("Rule {} wasn't generated.", describe(rule))),
+            Some(generated) if generated.count != rule.count => discrepancies.push(format!(
+                "Rule {} was generated with count {} but has count {}.",
+                describe(rule),
+                generated.count,
+                rule.count
+            )),
+            _ => {}
+        }
+    }
+    for rule in generated.iter().filter(|rule| !expected.contains(**rule)) {
+        discrepancies.push(format!(
+            "Rule {} was generated but doesn't pass the thresholds.",
+            describe(rule)
+        ));
+    }
+    report(discrepancies)
+}
+
+fn report(mut discrepancies: Vec<String>) -> usize {
+    discrepancies.sort();
+    for discrepancy in discrepancies.iter().take(MAX_REPORTED) {
+        println!("{}", discrepancy);
+    }
+    if discrepancies.len() > MAX_REPORTED {
+        println!("...");
+    }
+    discrepancies.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_itemsets, naive_itemsets};
+    use fptree::ItemSet;
+    use item::Item;
+    use itemizer::Itemizer;
+
+    #[test]
+    fn test_naive_itemsets() {
+        let mut itemizer = Itemizer::new();
+        let mut items = |names: &[&str]| -> Vec<Item> {
+            names.iter().map(|name| itemizer.id_of(name)).collect()
+        };
+        let transactions = vec![
+            (items(&["a", "b", "c"]), 1),
+            (items(&["a", "b"]), 2),
+            (items(&["b", "c", "d"]), 1),
+        ];
+        // Item c has count 2, which is exactly the minimum count, so it's
+        // frequent, as is b c. Item d isn't, so no itemset containing it is.
+        let expected = vec![
+            ItemSet::new(items(&["a"]), 3),
+            ItemSet::new(items(&["a", "b"]), 3),
+            ItemSet::new(items(&["b"]), 4),
+            ItemSet::new(items(&["b", "c"]), 2),
+            ItemSet::new(items(&["c"]), 2),
+        ];
+        let mut itemsets = naive_itemsets(&transactions, 2, None).unwrap();
+        itemsets.sort_by(|a, b| a.items.cmp(&b.items));
+        assert_eq!(itemsets.len(), expected.len());
+        assert_eq!(check_itemsets(&itemsets, &expected, &itemizer), 0);
+        let singletons = naive_itemsets(&transactions, 2, Some(1)).unwrap();
+        assert_eq!(singletons.len(), 3);
+        assert_eq!(check_itemsets(&singletons, &expected, &itemizer), 2);
+    }
+}
@@ -0,0 +1,98 @@
+// Copyright 2018 Chris Pearce
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use fnv::FnvHasher;
+use std::hash::{Hash, Hasher};
+
+// Count-Min sketch of item frequencies. Estimates are never less than an
+// item's true count, so the items with estimates at or above a threshold are
+// a superset of the items which are frequent. Memory use is fixed at
+// width * depth counters, regardless of how many distinct items there are.
+pub struct CountMinSketch {
+    width: usize,
+    depth: usize,
+    counters: Vec<u32>,
+}
+
+// Default sketch of 4 rows of 2^20 counters uses 16MB.
+pub const DEFAULT_SKETCH_WIDTH: usize = 1 << 20;
+pub const DEFAULT_SKETCH_DEPTH: usize = 4;
+
+impl CountMinSketch {
+    pub fn new(width: usize, depth: usize) -> CountMinSketch {
+        assert!(width > 0 && depth > 0);
+        CountMinSketch {
+            width,
+            depth,
+            counters: vec![0; width * depth],
+        }
+    }
+
+    // Index of item's counter in each row. Rows' hash functions are derived
+    // from the two halves of one 64 bit hash, as h1 + row * h2.
+    fn indices(&self, item: &str) -> impl Iterator<Item = usize> {
+        let mut hasher = FnvHasher::default();
+        item.hash(&mut hasher);
+        let hash = hasher.finish();
+        let h1 = hash & 0xffff_ffff;
+        // Odd, so that h2 is never zero.
+        let h2 = (hash >> 32) | 1;
+        let width = self.width;
+        (0..self.depth).map(move |row| {
+            let column = h1.wrapping_add((row as u64).wrapping_mul(h2)) % width as u64;
+            row * width + column as usize
+        })
+    }
+
+    pub fn add(&mut self, item: &str, count: u32) {
+        for index in self.indices(item) {
+            self.counters[index] = self.counters[index].saturating_add(count);
+        }
+    }
+
+    pub fn estimate(&self, item: &str) -> u32 {
+        self.indices(item)
+            .map(|index| self.counters[index])
+            .min()
+            .unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CountMinSketch;
+
+    #[test]
+    fn test_count_min_sketch() {
+        // Small enough that items collide.
+        let mut sketch = CountMinSketch::new(16, 3);
+        let mut counts = vec![];
+        for i in 0..100 {
+            let item = format!("item{}", i);
+            sketch.add(&item, i);
+            counts.push((item, i));
+        }
+        for (item, count) in counts {
+            assert!(sketch.estimate(&item) >= count);
+        }
+        assert_eq!(CountMinSketch::new(1024, 4).estimate("missing"), 0);
+
+        let mut sketch = CountMinSketch::new(1 << 12, 4);
+        sketch.add("a", 5);
+        sketch.add("b", 2);
+        sketch.add("a", 1);
+        assert_eq!(sketch.estimate("a"), 6);
+        assert_eq!(sketch.estimate("b"), 2);
+    }
+}
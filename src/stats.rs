@@ -0,0 +1,161 @@
+// Copyright 2018 Chris Pearce
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use command_line_args::StatsArguments;
+use counter::Counter;
+use error::ArmError;
+use item::Item;
+use item_counter::ItemCounter;
+use itemizer::Itemizer;
+use mining::{build_initial_fptree, lexicographic_order, TreeBuildOptions};
+use transaction_reader::TransactionReader;
+
+pub struct DatasetStats {
+    // Number of transactions, counting weighted transactions multiple times.
+    pub num_transactions: usize,
+    pub num_distinct_items: usize,
+    pub max_length: usize,
+    // Sum of transaction lengths.
+    pub total_length: usize,
+    pub item_count: ItemCounter,
+}
+
+impl DatasetStats {
+    pub fn average_length(&self) -> f64 {
+        self.total_length as f64 / self.num_transactions as f64
+    }
+
+    // Average fraction of the distinct items in each transaction.
+    pub fn density(&self) -> f64 {
+        self.average_length() / self.num_distinct_items as f64
+    }
+}
+
+pub fn dataset_stats(reader: TransactionReader) -> Result<DatasetStats, ArmError> {
+    let mut stats = DatasetStats {
+        num_transactions: 0,
+        num_distinct_items: 0,
+        max_length: 0,
+        total_length: 0,
+        item_count: ItemCounter::new(),
+    };
+    for transaction in reader {
+        let (transaction, count) = transaction?;
+        stats.num_transactions += count as usize;
+        stats.total_length += transaction.len() * count as usize;
+        stats.max_length = stats.max_length.max(transaction.len());
+        for item in &transaction {
+            if stats.item_count.get(item) == 0 {
+                stats.num_distinct_items += 1;
+            }
+            stats.item_count.add(item, count);
+        }
+    }
+    Ok(stats)
+}
+
+pub fn run_stats(args: &StatsArguments) -> Result<(), ArmError> {
+    let mut itemizer = Itemizer::new();
+    let stats = dataset_stats(
+        TransactionReader::new(&args.input_file_path, &mut itemizer)?
+            .with_weights(args.weighted)
+            .with_format(args.input_format)
+            .with_encoding(args.encoding),
+    )?;
+    println!("Dataset: {}", args.input_file_path);
+    println!("Transactions: {}", stats.num_transactions);
+    println!("Distinct items: {}", stats.num_distinct_items);
+    if stats.num_transactions == 0 {
+        return Ok(());
+    }
+    println!("Average transaction length: {:.2}", stats.average_length());
+    println!("Maximum transaction length: {}", stats.max_length);
+    println!("Density: {:.6}", stats.density());
+
+    if args.compare_item_orders {
+        // Every item is kept, as if mining with no minimum support.
+        let orders = [
+            ("frequency", None),
+            (
+                "lexicographic",
+                Some(lexicographic_order(
+                    &itemizer,
+                    stats.item_count.items_with_count_at_least(1),
+                )),
+            ),
+        ];
+        for (name, item_order) in orders {
+            let options = TreeBuildOptions {
+                parallel: false,
+                aggregate_duplicates: false,
+                item_order,
+            };
+            let fptree = build_initial_fptree(
+                TransactionReader::new(&args.input_file_path, &mut itemizer)?
+                    .with_weights(args.weighted)
+                    .with_format(args.input_format)
+                    .with_encoding(args.encoding),
+                &stats.item_count,
+                0,
+                &options,
+                None,
+            )?;
+            println!(
+                "FPTree nodes with {} item order: {}",
+                name,
+                fptree.num_nodes()
+            );
+        }
+    }
+
+    let mut items: Vec<Item> = stats.item_count.items_with_count_at_least(1);
+    stats.item_count.sort_descending(&mut items);
+    items.truncate(args.top_items);
+    println!("Most frequent items:");
+    println!("{:<30} {:>10} {:>10}", "Item", "Count", "Support");
+    for item in items {
+        let count = stats.item_count.get(&item);
+        println!(
+            "{:<30} {:>10} {:>10.4}",
+            itemizer.str_of(item),
+            count,
+            count as f64 / stats.num_transactions as f64
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::dataset_stats;
+    use counter::Counter;
+    use itemizer::Itemizer;
+    use std::fs;
+    use test_util::TempFile;
+    use transaction_reader::TransactionReader;
+
+    #[test]
+    fn test_dataset_stats() {
+        let path = TempFile::new("stats.csv");
+        fs::write(&path, "a,b,c\na\nb,a\n").unwrap();
+        let mut itemizer = Itemizer::new();
+        let stats =
+            dataset_stats(TransactionReader::new(path.path(), &mut itemizer).unwrap()).unwrap();
+        assert_eq!(stats.num_transactions, 3);
+        assert_eq!(stats.num_distinct_items, 3);
+        assert_eq!(stats.max_length, 3);
+        assert_eq!(stats.total_length, 6);
+        assert_eq!(stats.item_count.get(&itemizer.id_of("a")), 3);
+    }
+}
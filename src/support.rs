@@ -0,0 +1,180 @@
+// Copyright 2018 Chris Pearce
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use command_line_args::SupportArguments;
+use error::ArmError;
+use fnv::FnvHashSet;
+use fptree::ItemSet;
+use index::{IndexBackend, TransactionIndex};
+use item::Item;
+use itemizer::Itemizer;
+use mining::duration_as_ms;
+use output::{write_itemsets, OutputOptions};
+use rayon::prelude::*;
+use rule_file::{parse_items, split_unquoted};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::time::Instant;
+use transaction_reader::{Encoding, InputFormat, TransactionReader};
+
+// Reads candidate itemsets, one per line with items separated by spaces,
+// and quoted if they contain spaces or commas. Anything after the first
+// comma outside quotes is ignored, so itemsets files written by
+// `arm mine --output-itemsets` can be recounted too.
+pub fn read_candidates(path: &str) -> Result<Vec<Vec<String>>, ArmError> {
+    let file = File::open(path).map_err(|e| ArmError::io(path, e))?;
+    let mut candidates = vec![];
+    for (index, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|e| ArmError::io(path, e))?;
+        if index == 0 && line.starts_with("Itemset") {
+            continue;
+        }
+        let items = parse_items(split_unquoted(&line, ',')[0]);
+        if !items.is_empty() {
+            candidates.push(items);
+        }
+    }
+    Ok(candidates)
+}
+
+// Builds an index of the dataset at path, containing only the given items.
+// Items not already in itemizer are assigned ids as they're read.
+pub fn index_dataset(
+    path: &str,
+    weighted: bool,
+    input_format: InputFormat,
+    encoding: Encoding,
+    backend: IndexBackend,
+    items: &FnvHashSet<String>,
+    itemizer: &mut Itemizer,
+) -> Result<Box<dyn TransactionIndex>, ArmError> {
+    let item_filter = |item: &str| items.contains(item);
+    let mut index = backend.new_index();
+    let reader = TransactionReader::new(path, itemizer)?
+        .with_weights(weighted)
+        .with_format(input_format)
+        .with_encoding(encoding)
+        .with_item_filter(Some(&item_filter));
+    for transaction in reader {
+        let (transaction, count) = transaction?;
+        index.insert(&transaction, count);
+    }
+    index.finish();
+    Ok(index)
+}
+
+pub fn run_support(args: &SupportArguments) -> Result<(), ArmError> {
+    let start = Instant::now();
+    let candidates = read_candidates(&args.itemsets_path)?;
+    println!("Read {} candidate itemsets.", candidates.len());
+
+    // Assign ids to the candidates' items in lexicographic order, so their
+    // items are written sorted. Only these items need to be indexed.
+    let mut itemizer = Itemizer::new();
+    let mut names: Vec<&String> = candidates.iter().flat_map(|items| items.iter()).collect();
+    names.sort();
+    names.dedup();
+    for &name in &names {
+        itemizer.id_of(name);
+    }
+    let candidate_items: FnvHashSet<String> = names.into_iter().cloned().collect();
+    let candidates: Vec<Vec<Item>> = candidates
+        .iter()
+        .map(|items| {
+            let mut items: Vec<Item> = items.iter().map(|name| itemizer.id_of(name)).collect();
+            items.sort();
+            items.dedup();
+            items
+        })
+        .collect();
+
+    let timer = Instant::now();
+    let index = index_dataset(
+        &args.input_file_path,
+        args.weighted,
+        args.input_format,
+        args.encoding,
+        args.index_backend,
+        &candidate_items,
+        &mut itemizer,
+    )?;
+    let num_transactions = index.transaction_count();
+    println!(
+        "Indexed {} transactions in {} ms.",
+        num_transactions,
+        duration_as_ms(&timer.elapsed())
+    );
+
+    let timer = Instant::now();
+    let itemsets: Vec<ItemSet> = candidates
+        .into_par_iter()
+        .map(|items| {
+            let count = index.count(&items) as u32;
+            ItemSet::new(items, count)
+        })
+        .collect();
+    println!(
+        "Counted {} itemsets in {} ms.",
+        itemsets.len(),
+        duration_as_ms(&timer.elapsed())
+    );
+
+    write_itemsets(
+        &itemsets,
+        num_transactions,
+        &args.output_path,
+        &OutputOptions::default(),
+        &itemizer,
+    )
+    .map_err(|e| ArmError::io(&args.output_path, e))?;
+    println!("Total runtime: {} ms", duration_as_ms(&start.elapsed()));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::run_support;
+    use command_line_args::SupportArguments;
+    use index::IndexBackend;
+    use std::fs;
+    use test_util::TempFile;
+    use transaction_reader::{Encoding, InputFormat};
+
+    #[test]
+    fn test_support() {
+        let (input, itemsets, output) = (
+            TempFile::new("support-input.csv"),
+            TempFile::new("support-itemsets.csv"),
+            TempFile::new("support-output.csv"),
+        );
+        fs::write(&input, "a,b,c\na,b\nb,c\nd\n").unwrap();
+        fs::write(&itemsets, "Itemset,Support,Count\nb a,0.5,1\nc\n\nb c\nx\n").unwrap();
+        for &index_backend in &[IndexBackend::TidList, IndexBackend::Roaring] {
+            run_support(&SupportArguments {
+                input_file_path: String::from(input.path()),
+                weighted: false,
+                input_format: InputFormat::Csv,
+                encoding: Encoding::Utf8,
+                itemsets_path: String::from(itemsets.path()),
+                output_path: String::from(output.path()),
+                index_backend,
+            })
+            .unwrap();
+            assert_eq!(
+                fs::read_to_string(&output).unwrap(),
+                "Itemset,Support,Count\na b,0.5,2\nc,0.5,2\nb c,0.5,2\nx,0,0\n"
+            );
+        }
+    }
+}
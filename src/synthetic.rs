@@ -0,0 +1,94 @@
+// Copyright 2018 Chris Pearce
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use command_line_args::GenArguments;
+use error::ArmError;
+use rand::distributions::WeightedIndex;
+use rand::prelude::*;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+// Writes num_transactions random transactions to output. Item i is chosen
+// with probability proportional to 1/(i+1), so a few items are very common
+// and most are rare, as in real retail data. Transaction lengths are
+// uniformly distributed with mean avg_length, and capped at num_items.
+pub fn generate_dataset<W: Write>(output: &mut W, args: &GenArguments) -> io::Result<()> {
+    let mut rng = StdRng::seed_from_u64(args.seed);
+    let weights: Vec<f64> = (0..args.num_items).map(|i| 1.0 / (i + 1) as f64).collect();
+    let items = WeightedIndex::new(&weights).expect("weights are positive");
+    let max_length = (2 * args.avg_length - 1).min(args.num_items);
+    let mut transaction: Vec<usize> = vec![];
+    for _ in 0..args.num_transactions {
+        let length = rng.gen_range(1..=max_length);
+        transaction.clear();
+        while transaction.len() < length {
+            let item = items.sample(&mut rng);
+            if !transaction.contains(&item) {
+                transaction.push(item);
+            }
+        }
+        transaction.sort_unstable();
+        for (i, item) in transaction.iter().enumerate() {
+            if i > 0 {
+                output.write_all(b",")?;
+            }
+            write!(output, "i{}", item)?;
+        }
+        output.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+pub fn run_gen(args: &GenArguments) -> Result<(), ArmError> {
+    let path = &args.output_path;
+    let file = File::create(path).map_err(|e| ArmError::io(path, e))?;
+    let mut output = BufWriter::new(file);
+    generate_dataset(&mut output, args)
+        .and_then(|_| output.flush())
+        .map_err(|e| ArmError::io(path, e))?;
+    println!(
+        "Wrote {} transactions over {} items to {}.",
+        args.num_transactions, args.num_items, path
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::generate_dataset;
+    use command_line_args::GenArguments;
+
+    #[test]
+    fn test_generate_dataset() {
+        let args = GenArguments {
+            output_path: String::new(),
+            num_transactions: 100,
+            num_items: 20,
+            avg_length: 4,
+            seed: 1,
+        };
+        let mut first = vec![];
+        generate_dataset(&mut first, &args).unwrap();
+        let mut second = vec![];
+        generate_dataset(&mut second, &args).unwrap();
+        assert_eq!(first, second);
+
+        let text = String::from_utf8(first).unwrap();
+        assert_eq!(text.lines().count(), 100);
+        for line in text.lines() {
+            let items: Vec<&str> = line.split(',').collect();
+            assert!(!items.is_empty() && items.len() <= 7);
+        }
+    }
+}
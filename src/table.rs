@@ -0,0 +1,552 @@
+// Copyright 2018 Chris Pearce
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use error::ArmError;
+use std::borrow::Cow;
+use std::str::FromStr;
+use transaction_reader::{Encoding, InputFormat, LineReader};
+
+// How a numeric column's values are grouped into bins, so that its items
+// are ranges of values rather than every distinct value.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Binning {
+    // This many bins of equal width between the column's least and greatest
+    // values.
+    EqualWidth(usize),
+    // This many bins with about the same number of values in each, fewer if
+    // many values are the same.
+    EqualFrequency(usize),
+    // Bins between these increasing edges, with a bin below the first edge
+    // and a bin from the last edge up.
+    Edges(Vec<f64>),
+}
+
+// A column's binning, parsed from e.g. "age=width:5", "age=quantile:4" or
+// "age=edges:18,30,65".
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColumnBinning {
+    pub column: String,
+    pub binning: Binning,
+}
+
+impl FromStr for ColumnBinning {
+    type Err = String;
+    fn from_str(s: &str) -> Result<ColumnBinning, String> {
+        let invalid = || {
+            format!(
+                "Expected column=width:bins, column=quantile:bins or \
+                 column=edges:edge1,edge2,..., got \"{}\"",
+                s
+            )
+        };
+        let (column, spec) = s.rsplit_once('=').ok_or_else(invalid)?;
+        let (strategy, parameters) = spec.split_once(':').ok_or_else(invalid)?;
+        let num_bins = || match parameters.trim().parse::<usize>() {
+            Ok(num_bins) if num_bins >= 2 => Ok(num_bins),
+            _ => Err(format!("Number of bins in \"{}\" must be at least 2", s)),
+        };
+        let binning = match strategy.trim() {
+            "width" => Binning::EqualWidth(num_bins()?),
+            "quantile" => Binning::EqualFrequency(num_bins()?),
+            "edges" => {
+                let edges = parameters
+                    .split(',')
+                    .map(|edge| edge.trim().parse::<f64>())
+                    .collect::<Result<Vec<f64>, _>>()
+                    .map_err(|_| format!("Invalid edge in \"{}\"", s))?;
+                if !edges.windows(2).all(|pair| pair[0] < pair[1]) {
+                    return Err(format!("Edges in \"{}\" must be increasing", s));
+                }
+                Binning::Edges(edges)
+            }
+            _ => return Err(invalid()),
+        };
+        Ok(ColumnBinning {
+            column: String::from(column.trim()),
+            binning,
+        })
+    }
+}
+
+// What to do with a missing value, an empty or NA cell, in a table.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MissingValues {
+    // The cell has no item.
+    Skip,
+    // The cell's item is column=NA.
+    Encode,
+    // The row isn't converted into a transaction.
+    Drop,
+}
+
+impl FromStr for MissingValues {
+    type Err = String;
+    fn from_str(s: &str) -> Result<MissingValues, String> {
+        match s {
+            "skip" => Ok(MissingValues::Skip),
+            "encode" => Ok(MissingValues::Encode),
+            "drop" => Ok(MissingValues::Drop),
+            _ => Err(format!("Unknown missing value policy {}", s)),
+        }
+    }
+}
+
+// The missing value policy of a column, or of every column without its own
+// policy if column is None; parsed from e.g. "age=drop" or "encode".
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColumnMissingValues {
+    pub column: Option<String>,
+    pub policy: MissingValues,
+}
+
+impl FromStr for ColumnMissingValues {
+    type Err = String;
+    fn from_str(s: &str) -> Result<ColumnMissingValues, String> {
+        let (column, policy) = match s.rsplit_once('=') {
+            Some((column, policy)) => (Some(String::from(column.trim())), policy),
+            None => (None, s),
+        };
+        Ok(ColumnMissingValues {
+            column,
+            policy: policy.trim().parse()?,
+        })
+    }
+}
+
+// Numbers of rows of a table with missing values in converted columns.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MissingCounts {
+    pub rows_with_missing: usize,
+    // Rows with missing values which were dropped.
+    pub dropped_rows: usize,
+}
+
+fn is_missing(value: &str) -> bool {
+    value.is_empty() || value.eq_ignore_ascii_case("NA")
+}
+
+// Which columns of a table are converted into items, and how. A table is a
+// CSV file whose first line is a header naming its columns; each following
+// row is a transaction of column=value items, one per converted column.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TableOptions {
+    // If not empty, only these columns are converted.
+    pub columns: Vec<String>,
+    // These columns aren't converted.
+    pub exclude_columns: Vec<String>,
+    // Numeric columns whose values are binned.
+    pub bins: Vec<ColumnBinning>,
+    // Policies for missing values. Columns without a policy use the last
+    // policy without a column, or skip missing values if there's none.
+    pub missing: Vec<ColumnMissingValues>,
+}
+
+impl TableOptions {
+    fn missing_values_policy(&self, column: &str) -> MissingValues {
+        let policy = |column: Option<&str>| {
+            self.missing
+                .iter()
+                .rev()
+                .find(|missing| missing.column.as_deref() == column)
+                .map(|missing| missing.policy)
+        };
+        policy(Some(column))
+            .or_else(|| policy(None))
+            .unwrap_or(MissingValues::Skip)
+    }
+
+    // Whether any column's bins depend on the values in the table, so must
+    // be fitted by reading it before it's converted.
+    pub fn needs_fitting(&self) -> bool {
+        self.bins
+            .iter()
+            .any(|bins| !matches!(bins.binning, Binning::Edges(_)))
+    }
+}
+
+// Returns options with each column's bins fitted to the values of the
+// table at path, so that every binning is by edges. Values which aren't
+// numbers are ignored.
+pub fn fit_bins(
+    path: &str,
+    encoding: Encoding,
+    options: &TableOptions,
+) -> Result<TableOptions, ArmError> {
+    let mut lines = LineReader::open(path, None)?;
+    lines.set_format(InputFormat::Csv);
+    lines.set_encoding(encoding);
+    let mut line = String::new();
+    let header = match lines.read_items(&mut line) {
+        Some(header) => header?.0,
+        None => return Ok(options.clone()),
+    };
+    let indexes = options
+        .bins
+        .iter()
+        .map(|bins| {
+            header
+                .iter()
+                .position(|name| *name == bins.column)
+                .ok_or_else(|| {
+                    ArmError::parse(
+                        path,
+                        1,
+                        &format!("column {} isn't in the header", bins.column),
+                    )
+                })
+        })
+        .collect::<Result<Vec<usize>, ArmError>>()?;
+    let mut values: Vec<Vec<f64>> = vec![vec![]; options.bins.len()];
+    while let Some(row) = lines.read_items(&mut line) {
+        let (cells, _) = row?;
+        for (column_values, &index) in values.iter_mut().zip(&indexes) {
+            if let Some(Ok(value)) = cells.get(index).map(|cell| cell.parse::<f64>()) {
+                if value.is_finite() {
+                    column_values.push(value);
+                }
+            }
+        }
+    }
+
+    let mut fitted = options.clone();
+    for (bins, mut values) in fitted.bins.iter_mut().zip(values) {
+        values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let edges = match bins.binning {
+            Binning::EqualWidth(num_bins) => match (values.first(), values.last()) {
+                (Some(&min), Some(&max)) if min < max => (1..num_bins)
+                    .map(|bin| min + (max - min) * bin as f64 / num_bins as f64)
+                    .collect(),
+                _ => vec![],
+            },
+            Binning::EqualFrequency(_) if values.is_empty() => vec![],
+            Binning::EqualFrequency(num_bins) => {
+                let mut edges: Vec<f64> = (1..num_bins)
+                    .map(|bin| values[values.len() * bin / num_bins])
+                    .filter(|&edge| values.first().is_some_and(|&min| edge > min))
+                    .collect();
+                edges.dedup();
+                edges
+            }
+            Binning::Edges(ref edges) => edges.clone(),
+        };
+        bins.binning = Binning::Edges(edges);
+    }
+    Ok(fitted)
+}
+
+// Formats a bin edge, rounding fitted edges to a readable precision.
+fn format_edge(edge: f64) -> String {
+    let rounded = format!("{:.4}", edge);
+    let trimmed = rounded.trim_end_matches('0').trim_end_matches('.');
+    String::from(trimmed)
+}
+
+// Names the bin value falls into: <e1, [e1,e2), ..., >=en.
+fn bin_label(edges: &[f64], value: f64) -> String {
+    let bin = edges.partition_point(|&edge| edge <= value);
+    if bin == 0 {
+        format!("<{}", format_edge(edges[0]))
+    } else if bin == edges.len() {
+        format!(">={}", format_edge(edges[bin - 1]))
+    } else {
+        format!(
+            "[{},{})",
+            format_edge(edges[bin - 1]),
+            format_edge(edges[bin])
+        )
+    }
+}
+
+// A converted column of a table.
+struct Column {
+    name: String,
+    // Edges of the column's bins, if it's binned.
+    edges: Option<Vec<f64>>,
+    missing: MissingValues,
+}
+
+// The converted columns of a table, from its header.
+pub struct Table {
+    // Indexed by column; None for columns which aren't converted.
+    columns: Vec<Option<Column>>,
+    missing: MissingCounts,
+}
+
+impl Table {
+    // Returns an error if options name a column which isn't in header. Bins
+    // must have been fitted; see fit_bins.
+    pub fn new(header: &[Cow<'_, str>], options: &TableOptions) -> Result<Table, String> {
+        let binned = options.bins.iter().map(|bins| &bins.column);
+        let with_missing_policy = options
+            .missing
+            .iter()
+            .filter_map(|missing| missing.column.as_ref());
+        for column in options
+            .columns
+            .iter()
+            .chain(&options.exclude_columns)
+            .chain(binned)
+            .chain(with_missing_policy)
+        {
+            if !header.iter().any(|name| name == column) {
+                return Err(format!("column {} isn't in the header", column));
+            }
+        }
+        let columns = header
+            .iter()
+            .map(|name| {
+                let included = options.columns.is_empty()
+                    || options.columns.iter().any(|column| column == name);
+                let excluded = options.exclude_columns.iter().any(|column| column == name);
+                let edges = options
+                    .bins
+                    .iter()
+                    .find(|bins| bins.column == *name)
+                    .map(|bins| match bins.binning {
+                        Binning::Edges(ref edges) => edges.clone(),
+                        _ => panic!("Bins of column {} haven't been fitted", name),
+                    })
+                    .filter(|edges| !edges.is_empty());
+                (included && !excluded).then(|| Column {
+                    name: name.clone().into_owned(),
+                    edges,
+                    missing: options.missing_values_policy(name),
+                })
+            })
+            .collect();
+        Ok(Table {
+            columns,
+            missing: MissingCounts::default(),
+        })
+    }
+
+    // Converts the cells of a row into column=value items, where a binned
+    // column's value is its bin. Values in binned columns which aren't
+    // numbers are kept as they are. Missing values are handled by their
+    // column's policy; returns None if the row is dropped.
+    pub fn items<'a>(&mut self, cells: Vec<Cow<'a, str>>) -> Option<Vec<Cow<'a, str>>> {
+        let mut items = vec![];
+        let mut has_missing = false;
+        for (index, column) in self.columns.iter().enumerate() {
+            let column = match *column {
+                Some(ref column) => column,
+                None => continue,
+            };
+            let value = cells.get(index).map_or("", |value| value);
+            if is_missing(value) {
+                has_missing = true;
+                match column.missing {
+                    MissingValues::Skip => {}
+                    MissingValues::Encode => {
+                        items.push(Cow::Owned(format!("{}=NA", column.name)));
+                    }
+                    MissingValues::Drop => {
+                        self.missing.rows_with_missing += 1;
+                        self.missing.dropped_rows += 1;
+                        return None;
+                    }
+                }
+                continue;
+            }
+            let bin = column.edges.as_ref().and_then(|edges| {
+                value
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|value| !value.is_nan())
+                    .map(|value| bin_label(edges, value))
+            });
+            items.push(Cow::Owned(format!(
+                "{}={}",
+                column.name,
+                bin.as_deref().unwrap_or(value)
+            )));
+        }
+        if has_missing {
+            self.missing.rows_with_missing += 1;
+        }
+        Some(items)
+    }
+
+    // Converts the cells of a one-hot row into the names of the columns of
+    // the items it contains.
+    pub fn one_hot_items<'a>(&self, cells: &[Cow<str>]) -> Vec<Cow<'a, str>> {
+        self.columns
+            .iter()
+            .zip(cells)
+            .filter_map(|(column, value)| {
+                let present =
+                    !is_missing(value) && value != "0" && !value.eq_ignore_ascii_case("false");
+                column
+                    .as_ref()
+                    .filter(|_| present)
+                    .map(|column| Cow::Owned(column.name.clone()))
+            })
+            .collect()
+    }
+
+    // Counts of the rows converted so far with missing values.
+    pub fn missing_counts(&self) -> MissingCounts {
+        self.missing
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        fit_bins, Binning, ColumnBinning, ColumnMissingValues, MissingCounts, MissingValues, Table,
+        TableOptions,
+    };
+    use std::borrow::Cow;
+    use std::fs;
+    use test_util::TempFile;
+    use transaction_reader::Encoding;
+
+    fn cells(values: &[&'static str]) -> Vec<Cow<'static, str>> {
+        values.iter().map(|&value| Cow::Borrowed(value)).collect()
+    }
+
+    #[test]
+    fn test_table() {
+        let header = cells(&["age", "sex", "churn"]);
+        let mut table = Table::new(&header, &TableOptions::default()).unwrap();
+        assert_eq!(
+            table.items(cells(&["30", "f", "yes"])),
+            Some(cells(&["age=30", "sex=f", "churn=yes"]))
+        );
+        assert_eq!(table.items(cells(&["", "m"])), Some(cells(&["sex=m"])));
+
+        let options = TableOptions {
+            columns: vec![String::from("sex"), String::from("churn")],
+            exclude_columns: vec![String::from("sex")],
+            bins: vec![],
+            missing: vec![],
+        };
+        let mut table = Table::new(&header, &options).unwrap();
+        assert_eq!(
+            table.items(cells(&["30", "f", "yes"])),
+            Some(cells(&["churn=yes"]))
+        );
+
+        let options = TableOptions {
+            columns: vec![],
+            exclude_columns: vec![String::from("income")],
+            bins: vec![],
+            missing: vec![],
+        };
+        assert!(Table::new(&header, &options).is_err());
+    }
+
+    #[test]
+    fn test_bins() {
+        let parse = |s: &str| s.parse::<ColumnBinning>().map(|bins| bins.binning);
+        assert_eq!(parse("age=width:5"), Ok(Binning::EqualWidth(5)));
+        assert_eq!(parse("age=quantile:4"), Ok(Binning::EqualFrequency(4)));
+        assert_eq!(
+            parse("a=b=edges:1, 2.5"),
+            Ok(Binning::Edges(vec![1.0, 2.5]))
+        );
+        for invalid in &[
+            "age",
+            "age=width",
+            "age=width:1",
+            "age=edges:2,1",
+            "age=log:2",
+        ] {
+            assert!(parse(invalid).is_err());
+        }
+
+        let path = TempFile::new("bins.csv");
+        fs::write(&path, "age,income\n10,1\n20,1\n30,1\n40,2\nNA,\n").unwrap();
+        let options = TableOptions {
+            columns: vec![],
+            exclude_columns: vec![],
+            bins: vec![
+                "age=width:3".parse().unwrap(),
+                "income=quantile:4".parse().unwrap(),
+            ],
+            missing: vec![],
+        };
+        assert!(options.needs_fitting());
+        let fitted = fit_bins(path.path(), Encoding::Utf8, &options).unwrap();
+        assert!(!fitted.needs_fitting());
+        assert_eq!(fitted.bins[0].binning, Binning::Edges(vec![20.0, 30.0]));
+        // Most incomes are the same, so there are fewer bins than asked for.
+        assert_eq!(fitted.bins[1].binning, Binning::Edges(vec![2.0]));
+
+        let header = cells(&["age", "income"]);
+        let mut table = Table::new(&header, &fitted).unwrap();
+        assert_eq!(
+            table.items(cells(&["10", "1"])),
+            Some(cells(&["age=<20", "income=<2"]))
+        );
+        assert_eq!(
+            table.items(cells(&["25.5", "2"])),
+            Some(cells(&["age=[20,30)", "income=>=2"]))
+        );
+        // Missing values are skipped by default.
+        assert_eq!(table.items(cells(&["NA", ""])), Some(vec![]));
+    }
+
+    #[test]
+    fn test_missing_values() {
+        let parse = |s: &str| s.parse::<ColumnMissingValues>();
+        assert_eq!(
+            parse("age=drop"),
+            Ok(ColumnMissingValues {
+                column: Some(String::from("age")),
+                policy: MissingValues::Drop,
+            })
+        );
+        assert_eq!(
+            parse("encode"),
+            Ok(ColumnMissingValues {
+                column: None,
+                policy: MissingValues::Encode,
+            })
+        );
+        assert!(parse("age=fill").is_err());
+
+        let header = cells(&["age", "sex", "churn"]);
+        let options = TableOptions {
+            columns: vec![],
+            exclude_columns: vec![],
+            bins: vec![],
+            missing: vec![parse("encode").unwrap(), parse("churn=drop").unwrap()],
+        };
+        let mut table = Table::new(&header, &options).unwrap();
+        assert_eq!(
+            table.items(cells(&["na", "", "no"])),
+            Some(cells(&["age=NA", "sex=NA", "churn=no"]))
+        );
+        assert_eq!(table.items(cells(&["30", "f", "NA"])), None);
+        assert_eq!(table.items(cells(&["30", "f"])), None);
+        assert_eq!(
+            table.items(cells(&["30", "f", "yes"])),
+            Some(cells(&["age=30", "sex=f", "churn=yes"]))
+        );
+        assert_eq!(
+            table.missing_counts(),
+            MissingCounts {
+                rows_with_missing: 3,
+                dropped_rows: 2,
+            }
+        );
+
+        let options = TableOptions {
+            missing: vec![parse("income=drop").unwrap()],
+            ..TableOptions::default()
+        };
+        assert!(Table::new(&header, &options).is_err());
+    }
+}
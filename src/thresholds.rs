@@ -0,0 +1,248 @@
+// Copyright 2018 Chris Pearce
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use cancellation::CancellationToken;
+use command_line_args::ThresholdsArguments;
+use error::ArmError;
+use fptree::{fp_growth, FPGrowthParameters};
+use generate_rules::{count_rules, RuleParameters};
+use item::Item;
+use itemizer::Itemizer;
+use mining::{
+    build_initial_fptree, count_item_frequencies, duration_as_ms, min_count, TreeBuildOptions,
+};
+use rand::distributions::Open01;
+use rand::prelude::*;
+use rule::DEFAULT_LAPLACE_K;
+use std::time::Instant;
+use transaction_reader::TransactionReader;
+
+// Samples k transactions uniformly at random from a stream of transactions
+// in one pass, by reservoir sampling. A transaction with a count of n is n
+// transactions, each of which may be sampled. Once the sample is full, the
+// position of the next transaction to replace one in the sample is drawn
+// directly (Algorithm L), so the transactions skipped over cost nothing.
+pub struct Reservoir {
+    sample: Vec<Vec<Item>>,
+    k: usize,
+    num_transactions: usize,
+    // Position in the stream of the next transaction to enter the sample.
+    next_replacement: usize,
+    // Largest of the k random keys of the sampled transactions, in Algorithm
+    // L's terms.
+    w: f64,
+    rng: StdRng,
+}
+
+impl Reservoir {
+    pub fn new(k: usize, seed: u64) -> Reservoir {
+        Reservoir {
+            sample: Vec::with_capacity(k),
+            k,
+            num_transactions: 0,
+            next_replacement: k.saturating_sub(1),
+            w: 1.0,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    pub fn add(&mut self, transaction: &[Item], count: u32) {
+        let end = self.num_transactions + count as usize;
+        while self.num_transactions < end && self.sample.len() < self.k {
+            self.sample.push(transaction.to_vec());
+            self.num_transactions += 1;
+            if self.sample.len() == self.k {
+                self.skip();
+            }
+        }
+        if self.k > 0 {
+            while self.next_replacement < end {
+                let index = self.rng.gen_range(0..self.k);
+                self.sample[index] = transaction.to_vec();
+                self.skip();
+            }
+        }
+        self.num_transactions = end;
+    }
+
+    // Draws how many transactions to skip before the next replacement.
+    fn skip(&mut self) {
+        let k = self.k as f64;
+        self.w *= (self.rng.sample::<f64, _>(Open01).ln() / k).exp();
+        let skip = (self.rng.sample::<f64, _>(Open01).ln() / (-self.w).ln_1p()).floor();
+        // Saturates rather than overflows when the skip is past any stream.
+        self.next_replacement = self
+            .next_replacement
+            .saturating_add((skip as usize).saturating_add(1));
+    }
+
+    // Number of transactions added, including repeats.
+    pub fn num_transactions(&self) -> usize {
+        self.num_transactions
+    }
+
+    pub fn into_sample(self) -> Vec<Vec<Item>> {
+        self.sample
+    }
+}
+
+// Samples k of transactions. Returns the sample and the number of
+// transactions read.
+pub fn reservoir_sample<I>(
+    transactions: I,
+    k: usize,
+    seed: u64,
+) -> Result<(Vec<Vec<Item>>, usize), ArmError>
+where
+    I: IntoIterator<Item = Result<(Vec<Item>, u32), ArmError>>,
+{
+    let mut reservoir = Reservoir::new(k, seed);
+    for transaction in transactions {
+        let (transaction, count) = transaction?;
+        reservoir.add(&transaction, count);
+    }
+    let num_transactions = reservoir.num_transactions();
+    Ok((reservoir.into_sample(), num_transactions))
+}
+
+fn rule_parameters(args: &ThresholdsArguments) -> RuleParameters {
+    RuleParameters {
+        min_confidence: args.min_confidence,
+        min_confidence_by_length: None,
+        min_lift: args.min_lift,
+        max_lift: None,
+        min_confidence_lower_bound: None,
+        laplace_k: DEFAULT_LAPLACE_K,
+        min_laplace: None,
+        min_added_value: None,
+        min_gini_gain: None,
+        min_info_gain: None,
+        min_odds_ratio: None,
+        min_yules_q: None,
+        min_phi: None,
+        filter: None,
+        min_itemset_size: 1,
+        cancellation: CancellationToken::new(),
+        item_min_counts: None,
+        target_consequent: None,
+    }
+}
+
+// Mines a sample of the dataset at each candidate minimum support, and
+// prints how many itemsets and rules each gives, to choose thresholds
+// before mining the whole dataset. The sample's tree is built once, for the
+// lowest support, and mined again at each higher support.
+pub fn run_thresholds(args: &ThresholdsArguments) -> Result<(), ArmError> {
+    let timer = Instant::now();
+    let mut itemizer = Itemizer::new();
+    let (sample, num_transactions) = reservoir_sample(
+        TransactionReader::new(&args.input_file_path, &mut itemizer)?
+            .with_weights(args.weighted)
+            .with_format(args.input_format)
+            .with_encoding(args.encoding),
+        args.sample_size,
+        args.seed,
+    )?;
+    println!(
+        "Sampled {} of {} transactions in {} ms.",
+        sample.len(),
+        num_transactions,
+        duration_as_ms(&timer.elapsed())
+    );
+
+    let (mut item_count, sample_size) =
+        count_item_frequencies(sample.iter().map(|transaction| Ok((transaction, 1))))?;
+    itemizer.reorder_sorted(&mut item_count);
+    let lowest_support = args
+        .min_supports
+        .iter()
+        .cloned()
+        .fold(f64::INFINITY, f64::min);
+    let fptree = build_initial_fptree(
+        sample.into_iter().map(|transaction| Ok((transaction, 1))),
+        &item_count,
+        min_count(lowest_support, sample_size),
+        &TreeBuildOptions {
+            parallel: false,
+            aggregate_duplicates: false,
+            item_order: None,
+        },
+        None,
+    )?;
+
+    let rule_params = rule_parameters(args);
+    println!(
+        "{:>9} {:>10} {:>10} {:>10} {:>10}",
+        "Support", "Min count", "Itemsets", "Rules", "ms"
+    );
+    for &min_support in &args.min_supports {
+        let timer = Instant::now();
+        let params = FPGrowthParameters {
+            min_count: min_count(min_support, sample_size),
+            max_itemset_size: args.max_itemset_size,
+            cancellation: CancellationToken::new(),
+            cross_support: None,
+            item_min_counts: None,
+        };
+        let itemsets = fp_growth(&fptree, &params, &[], sample_size as u32);
+        let num_rules = count_rules(&itemsets, sample_size as u32, &rule_params);
+        println!(
+            "{:>9} {:>10} {:>10} {:>10} {:>10}",
+            min_support,
+            params.min_count,
+            itemsets.len(),
+            num_rules,
+            duration_as_ms(&timer.elapsed())
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::reservoir_sample;
+    use item::Item;
+
+    #[test]
+    fn test_reservoir_sample() {
+        let transactions: Vec<(Vec<Item>, u32)> = (0..100u32)
+            .map(|i| (vec![Item::from(i)], if i == 0 { 50 } else { 1 }))
+            .collect();
+        let sample =
+            |k, seed| reservoir_sample(transactions.iter().cloned().map(Ok), k, seed).unwrap();
+        let (first, num_transactions) = sample(20, 7);
+        assert_eq!(num_transactions, 149);
+        assert_eq!(first.len(), 20);
+        assert_eq!(sample(20, 7).0, first);
+        assert_ne!(sample(20, 8).0, first);
+
+        // Fewer transactions than the sample size are all sampled.
+        let (all, _) = sample(1000, 7);
+        assert_eq!(all.len(), 149);
+
+        // Transaction 0 is a third of the transactions, so should be about
+        // a third of a large sample.
+        let (large, _) = sample(90, 1);
+        let zeros = large.iter().filter(|t| t[0] == Item::from(0)).count();
+        assert!((15..=45).contains(&zeros), "{} zeros", zeros);
+
+        // Copies of a transaction which aren't sampled are skipped over
+        // rather than drawn for one by one, so huge counts are cheap.
+        let heavy = vec![(vec![Item::from(1)], 1), (vec![Item::from(2)], u32::MAX)];
+        let (sample, num_transactions) =
+            reservoir_sample(heavy.into_iter().map(Ok), 10, 7).unwrap();
+        assert_eq!(num_transactions, u32::MAX as usize + 1);
+        assert_eq!(sample.len(), 10);
+    }
+}
@@ -0,0 +1,298 @@
+// Copyright 2018 Chris Pearce
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use counter::Counter;
+use error::ArmError;
+use fnv::{FnvHashMap, FnvHashSet};
+use fptree::{fp_growth, FPGrowthParameters, FPGrowthProgress, FPTree, ItemSet};
+use index::TransactionIndex;
+use item::Item;
+use item_counter::ItemCounter;
+use mining::min_count as support_min_count;
+use rayon::prelude::*;
+use thresholds::Reservoir;
+
+// Probability that a frequent itemset is missed by the sample, which sets
+// how far the sample's minimum support is lowered. Missed itemsets are
+// still found, but cost another round of counting.
+const MISS_PROBABILITY: f64 = 0.01;
+
+// Number of transactions sampled, unless --sample-size is passed.
+pub const DEFAULT_SAMPLE_SIZE: usize = 10_000;
+
+pub struct ToivonenSummary {
+    pub sample_size: usize,
+    pub sample_min_count: u32,
+    // Number of itemsets whose support was counted in the whole dataset.
+    pub num_candidates: usize,
+    // Rounds of counting needed; more than one if the sample missed
+    // frequent itemsets.
+    pub num_rounds: usize,
+}
+
+// The minimum count for mining a sample of sample_size transactions, lowered
+// from min_support by the Hoeffding bound so that an itemset with
+// min_support is frequent in the sample with probability 1 -
+// MISS_PROBABILITY. It's lowered by at most half, so that small samples
+// don't give too many candidates.
+fn sample_min_count(min_support: f64, sample_size: usize) -> u32 {
+    let bound = ((1.0 / MISS_PROBABILITY).ln() / (2.0 * sample_size as f64)).sqrt();
+    support_min_count((min_support - bound).max(min_support / 2.0), sample_size)
+}
+
+// The negative border of itemsets, which must be downward closed: the
+// itemsets not among them all of whose immediate subsets are. Each is made
+// by joining two itemsets of the same length which differ only in their
+// last items.
+fn negative_border(
+    itemsets: &FnvHashSet<Vec<Item>>,
+    max_itemset_size: Option<usize>,
+) -> Vec<Vec<Item>> {
+    let mut by_length: Vec<Vec<&Vec<Item>>> = vec![];
+    for itemset in itemsets {
+        if by_length.len() < itemset.len() {
+            by_length.resize(itemset.len(), vec![]);
+        }
+        by_length[itemset.len() - 1].push(itemset);
+    }
+    let mut border = vec![];
+    for level in &mut by_length {
+        if level.is_empty() || max_itemset_size.is_some_and(|max| level[0].len() >= max) {
+            continue;
+        }
+        level.sort();
+        let length = level[0].len();
+        let mut start = 0;
+        while start < level.len() {
+            let prefix = &level[start][..length - 1];
+            let end = start
+                + level[start..]
+                    .iter()
+                    .take_while(|itemset| &itemset[..length - 1] == prefix)
+                    .count();
+            for i in start..end {
+                for j in i + 1..end {
+                    let mut candidate = level[i].clone();
+                    candidate.push(level[j][length - 1]);
+                    let subsets_present = (0..length - 1).all(|skip| {
+                        let mut subset = candidate.clone();
+                        subset.remove(skip);
+                        itemsets.contains(&subset)
+                    });
+                    if subsets_present && !itemsets.contains(&candidate) {
+                        border.push(candidate);
+                    }
+                }
+            }
+            start = end;
+        }
+    }
+    border
+}
+
+// Toivonen's sampling algorithm. Reads transactions once, sampling
+// sample_size of them and adding them to index, then mines the sample with
+// a lowered minimum support. The itemsets frequent in the sample, and their
+// negative border, are counted exactly with the index. If an itemset in the
+// border is frequent, the sample missed some frequent itemsets, so it's
+// added and the new border counted, until none is. The result is exactly
+// the frequent itemsets, with only the sample mined recursively.
+pub fn toivonen<I>(
+    transactions: I,
+    item_count: &ItemCounter,
+    params: &FPGrowthParameters,
+    num_transactions: usize,
+    sample_size: usize,
+    seed: u64,
+    index: &mut dyn TransactionIndex,
+) -> Result<(FPGrowthProgress, ToivonenSummary), ArmError>
+where
+    I: IntoIterator<Item = Result<(Vec<Item>, u32), ArmError>>,
+{
+    let min_count = params.min_count;
+    let mut reservoir = Reservoir::new(sample_size, seed);
+    for transaction in transactions {
+        let (mut transaction, count) = transaction?;
+        // Keep only the items which would be kept in the initial FPTree.
+        transaction.retain(|item| item_count.get(item) >= min_count);
+        index.insert(&transaction, count);
+        reservoir.add(&transaction, count);
+    }
+    index.finish();
+    let sample = reservoir.into_sample();
+
+    let mut sample_item_count = ItemCounter::new();
+    for transaction in &sample {
+        for item in transaction {
+            sample_item_count.add(item, 1);
+        }
+    }
+    let lowered_min_count = sample_min_count(
+        min_count as f64 / num_transactions.max(1) as f64,
+        sample.len(),
+    );
+    let mut sample_tree = FPTree::new();
+    for mut transaction in sample.iter().cloned() {
+        transaction.retain(|item| sample_item_count.get(item) >= lowered_min_count);
+        sample_item_count.sort_descending(&mut transaction);
+        sample_tree.insert(&transaction, 1);
+    }
+    let sample_params = FPGrowthParameters {
+        min_count: lowered_min_count,
+        max_itemset_size: params.max_itemset_size,
+        cancellation: params.cancellation.clone(),
+        cross_support: None,
+        item_min_counts: None,
+    };
+
+    // Every frequent item is known from the first pass, so only longer
+    // itemsets need the sample.
+    let items = item_count.items_with_count_at_least(min_count);
+    let mut candidates: FnvHashSet<Vec<Item>> = items.iter().map(|&item| vec![item]).collect();
+    candidates.extend(
+        fp_growth(&sample_tree, &sample_params, &[], sample.len() as u32)
+            .into_iter()
+            .map(|itemset| itemset.items),
+    );
+    let mut counts: FnvHashMap<Vec<Item>, u32> = items
+        .iter()
+        .map(|&item| (vec![item], item_count.get(&item)))
+        .collect();
+    let is_frequent = |items: &[Item], count: u32| {
+        count >= min_count
+            && params
+                .cross_support
+                .as_ref()
+                .is_none_or(|cross_support| cross_support.admits(items, count))
+    };
+    let mut uncounted: Vec<Vec<Item>> = candidates
+        .iter()
+        .filter(|itemset| !counts.contains_key(*itemset))
+        .cloned()
+        .collect();
+    let mut num_rounds = 0;
+    loop {
+        num_rounds += 1;
+        let border = negative_border(&candidates, params.max_itemset_size);
+        uncounted.extend(
+            border
+                .into_iter()
+                .filter(|itemset| !counts.contains_key(itemset)),
+        );
+        let new_counts: Vec<u32> = uncounted
+            .par_iter()
+            .map(|itemset| index.count(itemset) as u32)
+            .collect();
+        let mut missed = false;
+        for (itemset, count) in uncounted.drain(..).zip(new_counts) {
+            if !candidates.contains(&itemset) && is_frequent(&itemset, count) {
+                candidates.insert(itemset.clone());
+                missed = true;
+            }
+            counts.insert(itemset, count);
+        }
+        if !missed {
+            break;
+        }
+    }
+
+    let mut itemsets: Vec<ItemSet> = candidates
+        .into_iter()
+        .filter_map(|items| {
+            let count = counts[&items];
+            is_frequent(&items, count).then(|| ItemSet::new(items, count))
+        })
+        .collect();
+    itemsets.sort();
+    params.cancellation.add_to_count(itemsets.len());
+    let summary = ToivonenSummary {
+        sample_size: sample.len(),
+        sample_min_count: lowered_min_count,
+        num_candidates: counts.len(),
+        num_rounds,
+    };
+    let progress = FPGrowthProgress {
+        num_items: items.len(),
+        itemsets,
+        incomplete_items: vec![],
+    };
+    Ok((progress, summary))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::toivonen;
+    use cancellation::CancellationToken;
+    use counter::Counter;
+    use fptree::{fp_growth, FPGrowthParameters, FPTree, ItemSet};
+    use index::Index;
+    use item::Item;
+    use mining::count_item_frequencies;
+    use rand::prelude::*;
+
+    #[test]
+    fn test_toivonen() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let transactions: Vec<(Vec<Item>, u32)> = (0..500)
+            .map(|_| {
+                let mut transaction: Vec<Item> = (0..8u32)
+                    .filter(|&i| rng.gen_bool(0.7 / (1.0 + i as f64 / 2.0)))
+                    .map(Item::from)
+                    .collect();
+                transaction.sort();
+                (transaction, rng.gen_range(1..3))
+            })
+            .collect();
+        let (item_count, num_transactions) =
+            count_item_frequencies(transactions.iter().map(|(t, c)| Ok((t, *c)))).unwrap();
+        let params = FPGrowthParameters {
+            min_count: 60,
+            max_itemset_size: None,
+            cancellation: CancellationToken::new(),
+            cross_support: None,
+            item_min_counts: None,
+        };
+        let mut fptree = FPTree::new();
+        for (transaction, count) in &transactions {
+            let mut transaction = transaction.clone();
+            transaction.retain(|item| item_count.get(item) >= params.min_count);
+            item_count.sort_descending(&mut transaction);
+            fptree.insert(&transaction, *count);
+        }
+        let mut expected = fp_growth(&fptree, &params, &[], num_transactions as u32);
+        expected.sort();
+
+        // A tiny sample misses frequent itemsets, which are found in later
+        // rounds; a larger one finds them all in the first.
+        for &(sample_size, min_rounds) in &[(5, 2), (1000, 1)] {
+            let mut index = Index::new();
+            let (progress, summary) = toivonen(
+                transactions.iter().cloned().map(Ok),
+                &item_count,
+                &params,
+                num_transactions,
+                sample_size,
+                1,
+                &mut index,
+            )
+            .unwrap();
+            let itemsets: Vec<ItemSet> = progress.itemsets;
+            assert_eq!(itemsets, expected);
+            assert!(summary.num_rounds >= min_rounds);
+            if sample_size > num_transactions {
+                assert_eq!(summary.num_rounds, 1);
+            }
+        }
+    }
+}
@@ -0,0 +1,193 @@
+// Copyright 2018 Chris Pearce
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use fnv::FnvHashMap;
+use fptree::{collect_progress, FPGrowthParameters, FPGrowthProgress, FPTree, ItemSet};
+use item::Item;
+use rayon::prelude::*;
+use tracing::{info_span, Span};
+
+// Top-down FP-Growth (TD-FP-Growth) mines the tree from the root down,
+// rather than building a conditional tree for each item from the leaves up.
+// Every transaction through a node contains the whole path from the root to
+// the node, so an itemset's count is the sum of the counts of the nodes of
+// its item furthest from the root which have its other items above them;
+// these nodes' counts are the transactions participating in the itemset. An
+// itemset is extended with the items of the nodes below its participating
+// nodes, so each itemset is found once, from the items above its lowest
+// item, and no trees are built.
+//
+// Nodes are referred to by their position in the tree's pre-order, so that
+// each node's subtree is the range of positions following it.
+type Nodes = [(Item, u32, u32)];
+
+// The items in the subtrees of node_list's nodes, with their counts and
+// participating nodes in increasing order of position. node_list's nodes
+// must be in increasing order of position, and none may be in another's
+// subtree.
+fn extensions(nodes: &Nodes, node_list: &[u32]) -> Vec<(Item, u32, Vec<u32>)> {
+    let mut index_of: FnvHashMap<Item, usize> = FnvHashMap::default();
+    let mut extensions: Vec<(Item, u32, Vec<u32>)> = vec![];
+    for &position in node_list {
+        let end = nodes[position as usize].2;
+        for descendant in position + 1..end {
+            let (item, count, _) = nodes[descendant as usize];
+            let index = *index_of.entry(item).or_insert_with(|| {
+                extensions.push((item, 0, vec![]));
+                extensions.len() - 1
+            });
+            extensions[index].1 += count;
+            extensions[index].2.push(descendant);
+        }
+    }
+    extensions
+}
+
+// Mines the itemsets extending path with item, whose participating nodes
+// are node_list.
+fn mine_itemset(
+    nodes: &Nodes,
+    params: &FPGrowthParameters,
+    path: &[Item],
+    item: Item,
+    count: u32,
+    node_list: &[u32],
+) -> Vec<ItemSet> {
+    if params.cancellation.is_cancelled() || count < params.min_count {
+        return vec![];
+    }
+    let mut itemset: Vec<Item> = Vec::from(path);
+    itemset.push(item);
+    if let Some(ref cross_support) = params.cross_support {
+        if !cross_support.admits(&itemset, count) {
+            return vec![];
+        }
+    }
+
+    let at_max_size = params
+        .max_itemset_size
+        .is_some_and(|max| itemset.len() >= max);
+    let mut result = if at_max_size {
+        vec![]
+    } else {
+        extensions(nodes, node_list)
+            .into_par_iter()
+            .flat_map(|(extension, extension_count, extension_nodes)| {
+                mine_itemset(
+                    nodes,
+                    params,
+                    &itemset,
+                    extension,
+                    extension_count,
+                    &extension_nodes,
+                )
+            })
+            .collect()
+    };
+
+    // Under per-item minimum supports, the item furthest from the root has
+    // the lowest minimum count, so decides whether the itemset is frequent.
+    // Extensions may still be frequent when this itemset isn't, so they're
+    // pruned by the lowest minimum count instead.
+    if count >= params.min_count_for(itemset.last()) {
+        result.push(ItemSet::new(itemset, count));
+        params.cancellation.add_to_count(1);
+    }
+    result
+}
+
+// Mines the whole of fptree top-down, finding the same itemsets as
+// fp_growth_with_progress. Each frequent item is mined as a separate task,
+// with the itemsets whose item nearest the root is that item.
+pub fn top_down_fp_growth(fptree: &FPTree, params: &FPGrowthParameters) -> FPGrowthProgress {
+    let nodes = fptree.pre_order();
+    let items: Vec<(Item, u32, Vec<u32>)> = extensions(&nodes, &[0])
+        .into_iter()
+        .filter(|&(_, count, _)| count >= params.min_count)
+        .collect();
+
+    let parent = Span::current();
+    let results: Vec<(Item, Vec<ItemSet>, bool)> = items
+        .par_iter()
+        .map(|(item, count, node_list)| {
+            let _span = info_span!(parent: &parent, "mine_item", item = item.as_index()).entered();
+            let itemsets = mine_itemset(&nodes, params, &[], *item, *count, node_list);
+            // Cancellation is never undone, so if the token isn't cancelled
+            // after mining, it wasn't cancelled during mining either.
+            (*item, itemsets, !params.cancellation.is_cancelled())
+        })
+        .collect();
+    collect_progress(items.len(), results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::top_down_fp_growth;
+    use cancellation::CancellationToken;
+    use counter::Counter;
+    use fptree::{fp_growth, FPGrowthParameters, FPTree, ItemSet};
+    use item::Item;
+    use item_counter::ItemCounter;
+
+    #[test]
+    fn test_top_down_matches_fp_growth() {
+        let transactions: Vec<(Vec<Item>, u32)> = (0..60u32)
+            .map(|i| {
+                let items = (1..9)
+                    .filter(|&item| (i * 5 + item * 3) % (item + 2) != 0)
+                    .map(Item::from)
+                    .collect();
+                (items, 1 + i % 3)
+            })
+            .collect();
+        let mut item_count = ItemCounter::new();
+        for (transaction, count) in &transactions {
+            for item in transaction {
+                item_count.add(item, *count);
+            }
+        }
+        let min_count = 25;
+        let mut fptree = FPTree::new();
+        for (transaction, count) in &transactions {
+            let mut transaction: Vec<Item> = transaction
+                .iter()
+                .cloned()
+                .filter(|item| item_count.get(item) >= min_count)
+                .collect();
+            item_count.sort_descending(&mut transaction);
+            fptree.insert(&transaction, *count);
+        }
+        for &max_itemset_size in &[None, Some(2)] {
+            let params = FPGrowthParameters {
+                min_count,
+                max_itemset_size,
+                cancellation: CancellationToken::new(),
+                cross_support: None,
+                item_min_counts: None,
+            };
+            let mut expected = fp_growth(&fptree, &params, &[], 120);
+            expected.sort();
+
+            let progress = top_down_fp_growth(&fptree, &params);
+            let mut itemsets: Vec<ItemSet> = progress.itemsets;
+            itemsets.sort();
+            assert_eq!(itemsets, expected);
+            assert!(progress.incomplete_items.is_empty());
+            assert_eq!(
+                progress.num_items,
+                item_count.items_with_count_at_least(min_count).len()
+            );
+        }
+    }
+}
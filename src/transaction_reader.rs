@@ -12,49 +12,580 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use error::ArmError;
+use fnv::FnvHashMap;
+use input::{csv_line, open_input};
 use item::Item;
+use item_map::ItemMap;
 use itemizer::Itemizer;
-use std::fs::File;
+use serde_json;
+use sketch::CountMinSketch;
+use std::borrow::Cow;
 use std::io::prelude::*;
-use std::io::BufReader;
+use std::str::{self, FromStr};
+use table::{MissingCounts, Table, TableOptions};
+
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Encoding {
+    Utf8,
+    // UTF-8, with invalid sequences replaced by U+FFFD rather than being
+    // an error.
+    Utf8Lossy,
+    // ISO-8859-1, in which every byte is the character with that code point.
+    Latin1,
+}
+
+impl FromStr for Encoding {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Encoding, String> {
+        match s {
+            "utf8" => Ok(Encoding::Utf8),
+            "utf8-lossy" => Ok(Encoding::Utf8Lossy),
+            "latin1" => Ok(Encoding::Latin1),
+            _ => Err(format!("Unknown encoding {}", s)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InputFormat {
+    // Comma separated items, which may be quoted with double quotes so that
+    // they can contain commas, newlines or double quotes, written as "".
+    Csv,
+    // Comma separated items, with no quoting. Slightly faster than Csv.
+    Plain,
+    // CSV with a header row naming its columns. Each row is converted into
+    // a transaction of column=value items; see table::Table.
+    Table,
+    // CSV with a header row naming items, and a row per transaction whose
+    // cells say whether it contains each item. Cells which are empty, NA, 0
+    // or false mean the item is absent.
+    OneHot,
+    // SPMF's format: space separated integer items. Lines of the form
+    // @ITEM=id=name name the items; other lines starting with @, # or % are
+    // skipped.
+    Spmf,
+    // A JSON array of item names per line, e.g. ["milk","bread"].
+    Jsonl,
+    // Csv with the number of times the transaction occurred after a colon
+    // at the end of the line, e.g. milk,bread:42, as in exports of baskets
+    // grouped by their items.
+    Counted,
+}
+
+impl InputFormat {
+    // Whether the format's records are CSV, in which a quoted item can span
+    // lines.
+    fn is_csv(self) -> bool {
+        matches!(
+            self,
+            InputFormat::Csv | InputFormat::Table | InputFormat::OneHot | InputFormat::Counted
+        )
+    }
+
+    // Whether the input starts with a header row naming its columns.
+    pub fn has_header(self) -> bool {
+        matches!(self, InputFormat::Table | InputFormat::OneHot)
+    }
+}
+
+impl FromStr for InputFormat {
+    type Err = String;
+    fn from_str(s: &str) -> Result<InputFormat, String> {
+        match s {
+            "csv" => Ok(InputFormat::Csv),
+            "plain" => Ok(InputFormat::Plain),
+            "table" => Ok(InputFormat::Table),
+            "onehot" => Ok(InputFormat::OneHot),
+            "spmf" => Ok(InputFormat::Spmf),
+            "jsonl" => Ok(InputFormat::Jsonl),
+            "counted" => Ok(InputFormat::Counted),
+            _ => Err(format!("Unknown input format {}", s)),
+        }
+    }
+}
+
+// Splits a line of comma separated items into items, trimming whitespace
+// around them. Whitespace inside quotes is kept.
+fn split_items(items: &str, format: InputFormat) -> Vec<Cow<'_, str>> {
+    if format == InputFormat::Plain || !items.contains('"') {
+        return items.split(',').map(|s| Cow::Borrowed(s.trim())).collect();
+    }
+    let mut fields = vec![];
+    let mut rest = items;
+    loop {
+        let field = rest.trim_start();
+        let quoted = match field.strip_prefix('"') {
+            Some(quoted) => quoted,
+            None => {
+                let end = field.find(',').unwrap_or(field.len());
+                fields.push(Cow::Borrowed(field[..end].trim()));
+                if end == field.len() {
+                    break;
+                }
+                rest = &field[end + 1..];
+                continue;
+            }
+        };
+        let mut value = String::new();
+        let mut remaining = quoted;
+        let mut closed = false;
+        while let Some(quote) = remaining.find('"') {
+            value.push_str(&remaining[..quote]);
+            remaining = &remaining[quote + 1..];
+            if !remaining.starts_with('"') {
+                closed = true;
+                break;
+            }
+            value.push('"');
+            remaining = &remaining[1..];
+        }
+        if !closed {
+            // Unterminated quote at the end of the input; take the rest.
+            value.push_str(remaining.trim_end());
+            remaining = "";
+        }
+        fields.push(Cow::Owned(value));
+        // Anything between the closing quote and the next comma is dropped.
+        match remaining.find(',') {
+            Some(comma) => rest = &remaining[comma + 1..],
+            None => break,
+        }
+    }
+    fields
+}
+
+// Reads lines of transactions from a file or stdin, decoding them, joining
+// quoted items which span lines, and parsing their weights.
+pub struct LineReader {
+    reader: Box<dyn BufRead>,
+    format: InputFormat,
+    encoding: Encoding,
+    // Raw bytes of the line being read, before decoding.
+    bytes: Vec<u8>,
+    weighted: bool,
+    path: String,
+    line_number: usize,
+    table_options: TableOptions,
+    // The table's columns, once its header has been read.
+    table: Option<Table>,
+    // Names of SPMF items, from @ITEM lines, by id.
+    spmf_names: FnvHashMap<String, String>,
+}
+
+impl LineReader {
+    // Reads from stdin if path is "-"; see input::open_input.
+    pub fn open(path: &str, query: Option<&str>) -> Result<LineReader, ArmError> {
+        Ok(LineReader {
+            reader: open_input(path, query)?,
+            format: InputFormat::Csv,
+            encoding: Encoding::Utf8,
+            bytes: Vec::new(),
+            weighted: false,
+            path: String::from(path),
+            line_number: 0,
+            table_options: TableOptions::default(),
+            table: None,
+            spmf_names: FnvHashMap::default(),
+        })
+    }
+
+    pub fn set_format(&mut self, format: InputFormat) {
+        self.format = format;
+    }
+
+    pub fn set_table_options(&mut self, options: &TableOptions) {
+        self.table_options = options.clone();
+    }
+
+    pub fn set_encoding(&mut self, encoding: Encoding) {
+        self.encoding = encoding;
+    }
+
+    // Reads the next line of input, decodes it, and appends it to line.
+    fn append_line(&mut self, line: &mut String) -> Option<Result<(), ArmError>> {
+        self.bytes.clear();
+        self.line_number += 1;
+        match self.reader.read_until(b'\n', &mut self.bytes) {
+            Ok(0) => return None,
+            Ok(_) => {}
+            Err(e) => return Some(Err(ArmError::io(&self.path, e))),
+        }
+        let mut bytes = &self.bytes[..];
+        if self.line_number == 1 && bytes.starts_with(UTF8_BOM) {
+            bytes = &bytes[UTF8_BOM.len()..];
+        }
+        match self.encoding {
+            Encoding::Utf8 => match str::from_utf8(bytes) {
+                Ok(decoded) => line.push_str(decoded),
+                Err(_) => {
+                    return Some(Err(ArmError::parse(
+                        &self.path,
+                        self.line_number,
+                        "line is not valid UTF-8; see --encoding",
+                    )));
+                }
+            },
+            Encoding::Utf8Lossy => line.push_str(&String::from_utf8_lossy(bytes)),
+            Encoding::Latin1 => line.extend(bytes.iter().map(|&byte| byte as char)),
+        }
+        Some(Ok(()))
+    }
+
+    // Reads the next record into line. In CSV input, a quoted item can span
+    // lines.
+    fn read_record(&mut self, line: &mut String) -> Option<Result<(), ArmError>> {
+        line.clear();
+        if let Err(e) = self.append_line(line)? {
+            return Some(Err(e));
+        }
+        while self.format.is_csv() && line.matches('"').count() % 2 == 1 {
+            match self.append_line(line) {
+                Some(Ok(())) => {}
+                Some(Err(e)) => return Some(Err(e)),
+                None => break,
+            }
+        }
+        Some(Ok(()))
+    }
+
+    // Reads a table's header from its first record.
+    fn read_header(&mut self, line: &mut String) -> Option<Result<(), ArmError>> {
+        if let Err(e) = self.read_record(line)? {
+            return Some(Err(e));
+        }
+        match Table::new(&split_items(line, self.format), &self.table_options) {
+            Ok(table) => self.table = Some(table),
+            Err(message) => {
+                return Some(Err(ArmError::parse(&self.path, self.line_number, &message)))
+            }
+        }
+        Some(Ok(()))
+    }
+
+    // Records an SPMF metadata or comment line, returning false if line is
+    // a transaction.
+    fn read_spmf_metadata(&mut self, line: &str) -> bool {
+        if let Some(item) = line.strip_prefix("@ITEM=") {
+            if let Some((id, name)) = item.trim_end().split_once('=') {
+                self.spmf_names
+                    .insert(String::from(id.trim()), String::from(name));
+            }
+        }
+        line.starts_with(['@', '#', '%'])
+    }
+
+    // Rewrites the items of a JSON lines or SPMF transaction in line, from
+    // offset, as CSV.
+    fn convert_to_csv(&self, line: &mut String, offset: usize) -> Result<(), ArmError> {
+        let items: Vec<String> = match self.format {
+            InputFormat::Jsonl if line[offset..].trim().is_empty() => vec![],
+            InputFormat::Jsonl => serde_json::from_str(&line[offset..]).map_err(|e| {
+                ArmError::parse(
+                    &self.path,
+                    self.line_number,
+                    &format!("expected a JSON array of item names: {}", e),
+                )
+            })?,
+            InputFormat::Spmf => line[offset..]
+                .split_whitespace()
+                .map(|id| String::from(self.spmf_names.get(id).map_or(id, |name| name)))
+                .collect(),
+            _ => return Ok(()),
+        };
+        line.truncate(offset);
+        line.push_str(&csv_line(&items));
+        Ok(())
+    }
+
+    // Removes the :count suffix from a Counted transaction in line,
+    // returning the count. The count follows the last colon, so items may
+    // contain colons.
+    fn strip_count(&self, line: &mut String) -> Result<u32, ArmError> {
+        let parsed = line.rsplit_once(':').and_then(|(items, count)| {
+            count
+                .trim()
+                .parse::<u32>()
+                .ok()
+                .map(|count| (items.len(), count))
+        });
+        match parsed {
+            Some((end, count)) => {
+                line.truncate(end);
+                Ok(count)
+            }
+            None => Err(ArmError::parse(
+                &self.path,
+                self.line_number,
+                "expected item1,item2,...:count",
+            )),
+        }
+    }
+
+    // Reads the next transaction into line, returning the transaction's
+    // count and the offset in line at which its items start.
+    fn read_line(&mut self, line: &mut String) -> Option<Result<(u32, usize), ArmError>> {
+        if self.format.has_header() && self.table.is_none() {
+            if let Err(e) = self.read_header(line)? {
+                return Some(Err(e));
+            }
+        }
+        loop {
+            if let Err(e) = self.read_record(line)? {
+                return Some(Err(e));
+            }
+            if self.format != InputFormat::Spmf || !self.read_spmf_metadata(line) {
+                break;
+            }
+        }
+        let (count, offset) = if self.weighted {
+            let mut parts = line.splitn(2, ';');
+            let count = parts.next().unwrap_or("");
+            let offset = count.len() + 1;
+            match (count.trim().parse::<u32>(), parts.next()) {
+                (Ok(count), Some(_)) => (count, offset),
+                _ => {
+                    return Some(Err(ArmError::parse(
+                        &self.path,
+                        self.line_number,
+                        "expected count;item1,item2,...",
+                    )))
+                }
+            }
+        } else {
+            (1, 0)
+        };
+        let count = if self.format == InputFormat::Counted {
+            match self.strip_count(line) {
+                Ok(count) => count,
+                Err(e) => return Some(Err(e)),
+            }
+        } else {
+            count
+        };
+        if let Err(e) = self.convert_to_csv(line, offset) {
+            return Some(Err(e));
+        }
+        Some(Ok((count, offset)))
+    }
+
+    // Splits the items of a transaction read by read_line, converting a
+    // table's cells into items. Returns None if the transaction is a row
+    // dropped for its missing values.
+    fn split<'l>(&mut self, items: &'l str) -> Option<Vec<Cow<'l, str>>> {
+        let items = split_items(items, self.format);
+        match self.table {
+            Some(ref table) if self.format == InputFormat::OneHot => {
+                Some(table.one_hot_items(&items))
+            }
+            Some(ref mut table) => table.items(items),
+            None => Some(items),
+        }
+    }
+
+    // Counts of the rows read so far with missing values, if the input is a
+    // table.
+    pub fn missing_counts(&self) -> MissingCounts {
+        self.table
+            .as_ref()
+            .map_or(MissingCounts::default(), |table| table.missing_counts())
+    }
+
+    // Reads the next transaction's items, before any item map or filter is
+    // applied, and its count.
+    pub fn read_items(
+        &mut self,
+        line: &mut String,
+    ) -> Option<Result<(Vec<String>, u32), ArmError>> {
+        loop {
+            let (count, offset) = match self.read_line(line)? {
+                Ok(result) => result,
+                Err(e) => return Some(Err(e)),
+            };
+            if let Some(items) = self.split(&line[offset..]) {
+                let items = items.into_iter().map(|item| item.into_owned()).collect();
+                return Some(Ok((items, count)));
+            }
+        }
+    }
+}
 
 pub struct TransactionReader<'a> {
-    reader: BufReader<File>,
+    lines: LineReader,
     itemizer: &'a mut Itemizer,
+    item_map: Option<&'a ItemMap>,
+    item_filter: Option<&'a dyn Fn(&str) -> bool>,
+    max_length: Option<usize>,
+    // Reused for each line read, to save allocating per transaction.
+    line: String,
 }
 
 impl<'a> TransactionReader<'a> {
-    pub fn new(path: &str, itemizer: &'a mut Itemizer) -> TransactionReader<'a> {
-        let file = File::open(path).unwrap();
-        let reader = BufReader::new(file);
-        TransactionReader {
-            reader: reader,
+    // Reads from stdin if path is "-".
+    pub fn new(path: &str, itemizer: &'a mut Itemizer) -> Result<TransactionReader<'a>, ArmError> {
+        TransactionReader::open(path, None, itemizer)
+    }
+
+    // Like new, but with the query which selects transactions from a
+    // database; see input::open_input.
+    pub fn open(
+        path: &str,
+        query: Option<&str>,
+        itemizer: &'a mut Itemizer,
+    ) -> Result<TransactionReader<'a>, ArmError> {
+        Ok(TransactionReader {
+            lines: LineReader::open(path, query)?,
             itemizer,
+            item_map: None,
+            item_filter: None,
+            max_length: None,
+            line: String::new(),
+        })
+    }
+
+    // Items are renamed according to the item map as they're read.
+    pub fn with_item_map(mut self, item_map: Option<&'a ItemMap>) -> TransactionReader<'a> {
+        self.item_map = item_map;
+        self
+    }
+
+    // If weighted, lines are expected to be of the form "count;a,b,c",
+    // meaning the transaction "a,b,c" occurred count times.
+    pub fn with_weights(mut self, weighted: bool) -> TransactionReader<'a> {
+        self.lines.weighted = weighted;
+        self
+    }
+
+    pub fn with_format(mut self, format: InputFormat) -> TransactionReader<'a> {
+        self.lines.set_format(format);
+        self
+    }
+
+    // Which columns are converted into items, if the input is a table.
+    pub fn with_table_options(mut self, options: &TableOptions) -> TransactionReader<'a> {
+        self.lines.set_table_options(options);
+        self
+    }
+
+    // A UTF-8 byte order mark at the start of the input is skipped whatever
+    // the encoding, as spreadsheet exports often begin with one.
+    pub fn with_encoding(mut self, encoding: Encoding) -> TransactionReader<'a> {
+        self.lines.set_encoding(encoding);
+        self
+    }
+
+    // Only items for which filter returns true are itemized and included in
+    // transactions; others are dropped as they're read. This may leave
+    // transactions empty.
+    pub fn with_item_filter(
+        mut self,
+        item_filter: Option<&'a dyn Fn(&str) -> bool>,
+    ) -> TransactionReader<'a> {
+        self.item_filter = item_filter;
+        self
+    }
+
+    // Transactions are truncated to their first max_length distinct items,
+    // in the order they're listed, so each is the same whatever ids its
+    // items have.
+    pub fn with_max_length(mut self, max_length: Option<usize>) -> TransactionReader<'a> {
+        self.max_length = max_length;
+        self
+    }
+
+    // Counts of the rows read so far with missing values, if the input is a
+    // table.
+    pub fn missing_counts(&self) -> MissingCounts {
+        self.lines.missing_counts()
+    }
+
+    // Reads all transactions, adding their items to sketch rather than
+    // itemizing them. Returns the number of transactions.
+    pub fn sketch_items(&mut self, sketch: &mut CountMinSketch) -> Result<usize, ArmError> {
+        let mut line = String::new();
+        let mut num_transactions = 0;
+        while let Some(result) = self.lines.read_line(&mut line) {
+            let (count, offset) = result?;
+            if count == 0 {
+                continue;
+            }
+            let items = match self.lines.split(&line[offset..]) {
+                Some(items) => items,
+                None => continue,
+            };
+            num_transactions += count as usize;
+            for item in items {
+                match self.item_map {
+                    Some(item_map) => sketch.add(item_map.canonical(&item), count),
+                    None => sketch.add(&item, count),
+                }
+            }
         }
+        Ok(num_transactions)
     }
 }
 
+// Yields (transaction, count) pairs; count is the number of times the
+// transaction occurred, which is 1 unless the input is weighted.
 impl<'a> Iterator for TransactionReader<'a> {
-    type Item = Vec<Item>;
-    fn next(&mut self) -> Option<Vec<Item>> {
-        let mut line = String::new();
+    type Item = Result<(Vec<Item>, u32), ArmError>;
+    fn next(&mut self) -> Option<Result<(Vec<Item>, u32), ArmError>> {
         loop {
-            let len = self.reader.read_line(&mut line).unwrap();
-            if len == 0 {
-                return None;
+            let (count, offset) = match self.lines.read_line(&mut self.line)? {
+                Ok(result) => result,
+                Err(e) => return Some(Err(e)),
+            };
+            let itemizer = &mut self.itemizer;
+            let item_map = self.item_map;
+            let item_filter = self.item_filter;
+            let items = match self.lines.split(&self.line[offset..]) {
+                Some(items) => items,
+                None => continue,
+            };
+            let splits = items
+                .iter()
+                .map(|s| match item_map {
+                    Some(item_map) => item_map.canonical(s),
+                    None => s,
+                })
+                .filter(|s| item_filter.is_none_or(|item_filter| item_filter(s)))
+                .map(|s| itemizer.try_id_of(s).ok_or(s))
+                .collect::<Result<Vec<Item>, &str>>();
+            let mut splits = match splits {
+                Ok(splits) => splits,
+                Err(item) => {
+                    return Some(Err(ArmError::parse(
+                        &self.lines.path,
+                        self.lines.line_number,
+                        &format!("item {} isn't in the dictionary", item),
+                    )))
+                }
+            };
+
+            if let Some(max_length) = self.max_length {
+                let mut kept: Vec<Item> = Vec::with_capacity(max_length);
+                for item in splits.drain(..) {
+                    if kept.len() == max_length {
+                        break;
+                    }
+                    if !kept.contains(&item) {
+                        kept.push(item);
+                    }
+                }
+                splits = kept;
             }
-            let mut splits = line
-                .split(",")
-                .map(|s| self.itemizer.id_of(s.trim()))
-                .collect::<Vec<Item>>();
 
             // Some input files have transactions with duplicates items.
             // Remove any duplicates here.
             splits.sort();
             dedupe_sorted(&mut splits);
 
-            if splits.len() > 0 {
-                return Some(splits);
+            // Transactions whose items were all filtered out are still
+            // yielded, so that they're counted in the number of transactions.
+            if (!splits.is_empty() || item_filter.is_some()) && count > 0 {
+                return Some(Ok((splits, count)));
             }
         }
     }
@@ -77,10 +608,15 @@ fn dedupe_sorted(v: &mut Vec<Item>) {
 #[cfg(test)]
 mod tests {
 
+    use error::ArmError;
     use item::Item;
+    use itemizer::Itemizer;
+    use sketch::CountMinSketch;
+    use std::fs;
+    use test_util::TempFile;
 
     fn to_item_vec(nums: &[u32]) -> Vec<Item> {
-        nums.iter().map(|&i| Item::with_id(i)).collect()
+        nums.iter().map(|&i| Item::from(i)).collect()
     }
     #[test]
     fn test_dedupe_sorted() {
@@ -99,4 +635,283 @@ mod tests {
             assert!(v == e);
         }
     }
+
+    #[test]
+    fn test_invalid_utf8_reports_line() {
+        let file = TempFile::new("invalid-utf8.csv");
+        fs::write(&file, b"a,b\nc,\xff\xfe\n").unwrap();
+        let path = file.path();
+        let mut itemizer = Itemizer::new();
+        let results: Vec<_> = super::TransactionReader::new(path, &mut itemizer)
+            .unwrap()
+            .collect();
+        assert!(results[0].is_ok());
+        match results[1] {
+            Err(ArmError::Parse { line, .. }) => assert_eq!(line, 2),
+            _ => panic!("Expected parse error on line 2"),
+        }
+    }
+
+    #[test]
+    fn test_encodings() {
+        use super::Encoding;
+        let file = TempFile::new("encodings.csv");
+        fs::write(&file, b"\xEF\xBB\xBFcaf\xE9,b\n").unwrap();
+        let path = file.path();
+        let read = |encoding: &str| -> Vec<String> {
+            let mut itemizer = Itemizer::new();
+            let encoding: Encoding = encoding.parse().unwrap();
+            let transaction = super::TransactionReader::new(path, &mut itemizer)
+                .unwrap()
+                .with_encoding(encoding)
+                .next()
+                .unwrap()
+                .unwrap()
+                .0;
+            let mut items: Vec<String> = transaction
+                .iter()
+                .map(|&item| String::from(itemizer.str_of(item)))
+                .collect();
+            items.sort();
+            items
+        };
+        assert_eq!(read("latin1"), vec!["b", "caf\u{e9}"]);
+        assert_eq!(read("utf8-lossy"), vec!["b", "caf\u{fffd}"]);
+        let mut itemizer = Itemizer::new();
+        assert!(super::TransactionReader::new(path, &mut itemizer)
+            .unwrap()
+            .next()
+            .unwrap()
+            .is_err());
+
+        fs::write(path, "\u{feff}a,b\n").unwrap();
+        assert_eq!(read("utf8"), vec!["a", "b"]);
+        assert!("ascii".parse::<Encoding>().is_err());
+    }
+
+    #[test]
+    fn test_split_items() {
+        use super::{split_items, InputFormat};
+        let split = |items: &str, format: &str| -> Vec<String> {
+            split_items(items, format.parse().unwrap())
+                .into_iter()
+                .map(|item| item.into_owned())
+                .collect()
+        };
+        assert_eq!(split(" a, b ,c\n", "csv"), vec!["a", "b", "c"]);
+        assert_eq!(
+            split("a,\"b, c\", \"say \"\"hi\"\"\" ,d\n", "csv"),
+            vec!["a", "b, c", "say \"hi\"", "d"]
+        );
+        assert_eq!(split("\"a,b\n", "csv"), vec!["a,b"]);
+        assert_eq!(split("\"a,b\",c", "plain"), vec!["\"a", "b\"", "c"]);
+        assert!("tsv".parse::<InputFormat>().is_err());
+
+        // A quoted item containing a newline spans two lines.
+        let file = TempFile::new("quoted.csv");
+        fs::write(&file, "\"x\ny\",z\nz\n").unwrap();
+        let path = file.path();
+        let mut itemizer = Itemizer::new();
+        let lengths: Vec<usize> = super::TransactionReader::new(path, &mut itemizer)
+            .unwrap()
+            .map(|t| t.unwrap().0.len())
+            .collect();
+        assert_eq!(lengths, vec![2, 1]);
+        assert!(itemizer.get_id("x\ny").is_some());
+    }
+
+    #[test]
+    fn test_missing_file() {
+        let mut itemizer = Itemizer::new();
+        match super::TransactionReader::new("does-not-exist.csv", &mut itemizer) {
+            Err(ArmError::Io { ref path, .. }) => assert_eq!(path, "does-not-exist.csv"),
+            _ => panic!("Expected io error"),
+        }
+    }
+
+    #[test]
+    fn test_weighted() {
+        let file = TempFile::new("weighted.csv");
+        fs::write(&file, "3;a,b\n1; b,c\n").unwrap();
+        let path = file.path();
+        let mut itemizer = Itemizer::new();
+        let counts: Vec<u32> = super::TransactionReader::new(path, &mut itemizer)
+            .unwrap()
+            .with_weights(true)
+            .map(|t| t.unwrap().1)
+            .collect();
+        assert_eq!(counts, vec![3, 1]);
+
+        fs::write(path, "a,b\n").unwrap();
+        let mut reader = super::TransactionReader::new(path, &mut itemizer)
+            .unwrap()
+            .with_weights(true);
+        match reader.next() {
+            Some(Err(ArmError::Parse { line, .. })) => assert_eq!(line, 1),
+            _ => panic!("Expected parse error on line 1"),
+        }
+    }
+
+    #[test]
+    fn test_max_length() {
+        let path = TempFile::new("max-length.csv");
+        fs::write(&path, "d,a,d,c,b\nb\n").unwrap();
+        let mut itemizer = Itemizer::new();
+        let transactions: Vec<Vec<&str>> =
+            super::TransactionReader::new(path.path(), &mut itemizer)
+                .unwrap()
+                .with_max_length(Some(3))
+                .map(|t| t.unwrap().0)
+                .collect::<Vec<Vec<Item>>>()
+                .iter()
+                .map(|t| t.iter().map(|&item| itemizer.str_of(item)).collect())
+                .collect();
+        assert_eq!(transactions, vec![vec!["d", "a", "c"], vec!["b"]]);
+    }
+
+    #[test]
+    fn test_counted() {
+        use super::InputFormat;
+        let file = TempFile::new("counted.csv");
+        fs::write(&file, "a,b:42\n\"c:d\", e : 3\n").unwrap();
+        let path = file.path();
+        let mut itemizer = Itemizer::new();
+        let transactions: Vec<(Vec<String>, u32)> =
+            super::TransactionReader::new(path, &mut itemizer)
+                .unwrap()
+                .with_format(InputFormat::Counted)
+                .map(|t| t.unwrap())
+                .collect::<Vec<_>>()
+                .into_iter()
+                .map(|(items, count)| {
+                    let names = items
+                        .iter()
+                        .map(|&item| String::from(itemizer.str_of(item)))
+                        .collect();
+                    (names, count)
+                })
+                .collect();
+        assert_eq!(
+            transactions,
+            vec![
+                (vec![String::from("a"), String::from("b")], 42),
+                (vec![String::from("c:d"), String::from("e")], 3),
+            ]
+        );
+
+        fs::write(path, "a,b\n").unwrap();
+        let mut reader = super::TransactionReader::new(path, &mut itemizer)
+            .unwrap()
+            .with_format(InputFormat::Counted);
+        match reader.next() {
+            Some(Err(ArmError::Parse { line, .. })) => assert_eq!(line, 1),
+            _ => panic!("Expected parse error on line 1"),
+        }
+    }
+
+    #[test]
+    fn test_table() {
+        use super::InputFormat;
+        use table::TableOptions;
+        let file = TempFile::new("table.csv");
+        fs::write(&file, "id,\"plan, tier\",churn\n1,gold,yes\n2,,no\n").unwrap();
+        let path = file.path();
+        let read = |options: &TableOptions| -> Vec<Vec<String>> {
+            let mut itemizer = Itemizer::new();
+            let transactions: Vec<Vec<Item>> = super::TransactionReader::new(path, &mut itemizer)
+                .unwrap()
+                .with_format(InputFormat::Table)
+                .with_table_options(options)
+                .map(|t| t.unwrap().0)
+                .collect();
+            transactions
+                .iter()
+                .map(|transaction| {
+                    let mut items: Vec<String> = transaction
+                        .iter()
+                        .map(|&item| String::from(itemizer.str_of(item)))
+                        .collect();
+                    items.sort();
+                    items
+                })
+                .collect()
+        };
+        assert_eq!(
+            read(&TableOptions::default()),
+            vec![
+                vec!["churn=yes", "id=1", "plan, tier=gold"],
+                vec!["churn=no", "id=2"],
+            ]
+        );
+        let options = TableOptions {
+            columns: vec![],
+            exclude_columns: vec![String::from("id")],
+            bins: vec![],
+            missing: vec![],
+        };
+        assert_eq!(
+            read(&options),
+            vec![vec!["churn=yes", "plan, tier=gold"], vec!["churn=no"]]
+        );
+
+        let options = TableOptions {
+            missing: vec!["drop".parse().unwrap()],
+            ..TableOptions::default()
+        };
+        assert_eq!(
+            read(&options),
+            vec![vec!["churn=yes", "id=1", "plan, tier=gold"]]
+        );
+        let mut itemizer = Itemizer::new();
+        let mut reader = super::TransactionReader::new(path, &mut itemizer)
+            .unwrap()
+            .with_format(InputFormat::Table)
+            .with_table_options(&options);
+        assert_eq!(reader.by_ref().count(), 1);
+        assert_eq!(reader.missing_counts().dropped_rows, 1);
+
+        let options = TableOptions {
+            columns: vec![String::from("region")],
+            exclude_columns: vec![],
+            bins: vec![],
+            missing: vec![],
+        };
+        let mut itemizer = Itemizer::new();
+        let mut reader = super::TransactionReader::new(path, &mut itemizer)
+            .unwrap()
+            .with_format(InputFormat::Table)
+            .with_table_options(&options);
+        match reader.next() {
+            Some(Err(ArmError::Parse { line, .. })) => assert_eq!(line, 1),
+            _ => panic!("Expected parse error on line 1"),
+        }
+    }
+
+    #[test]
+    fn test_sketch_and_item_filter() {
+        let file = TempFile::new("sketch.csv");
+        fs::write(&file, "a,b\na,c\nc\nd\n").unwrap();
+        let path = file.path();
+        let mut itemizer = Itemizer::new();
+        let mut sketch = CountMinSketch::new(1 << 10, 4);
+        let num_transactions = super::TransactionReader::new(path, &mut itemizer)
+            .unwrap()
+            .sketch_items(&mut sketch)
+            .unwrap();
+        assert_eq!(num_transactions, 4);
+        assert_eq!(sketch.estimate("a"), 2);
+        assert_eq!(sketch.estimate("b"), 1);
+
+        let is_frequent = |item: &str| sketch.estimate(item) >= 2;
+        let transactions: Vec<Vec<Item>> = super::TransactionReader::new(path, &mut itemizer)
+            .unwrap()
+            .with_item_filter(Some(&is_frequent))
+            .map(|t| t.unwrap().0)
+            .collect();
+        assert_eq!(transactions.len(), 4);
+        assert!(transactions[3].is_empty());
+        assert_eq!(transactions[0].len(), 1);
+        assert_eq!(itemizer.str_of(transactions[0][0]), "a");
+        assert_eq!(itemizer.str_of(transactions[2][0]), "c");
+    }
 }
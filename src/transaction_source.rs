@@ -0,0 +1,119 @@
+// Copyright 2018 Chris Pearce
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use error::ArmError;
+use std::io;
+pub use table::TableOptions;
+use transaction_reader::LineReader;
+pub use transaction_reader::{Encoding, InputFormat};
+
+// A source of transactions which can be read more than once, such as a
+// file, a database query or a message queue topic. Mining reads the
+// transactions twice: once to count items, and again to build the tree from
+// the frequent items, so a source must be able to start again from its first
+// transaction. Transactions are lists of item names.
+pub trait TransactionSource: Iterator<Item = Result<Vec<String>, ArmError>> {
+    // Restarts from the first transaction.
+    fn rescan(&mut self) -> Result<(), ArmError>;
+}
+
+// Transactions read from a file in the same format as the command line tool
+// reads. Stdin can't be rescanned, so isn't supported.
+pub struct FileSource {
+    path: String,
+    format: InputFormat,
+    encoding: Encoding,
+    table_options: TableOptions,
+    lines: LineReader,
+    line: String,
+}
+
+impl FileSource {
+    pub fn open(path: &str) -> Result<FileSource, ArmError> {
+        if path == "-" {
+            return Err(ArmError::io(
+                path,
+                io::Error::new(io::ErrorKind::InvalidInput, "stdin can't be rescanned"),
+            ));
+        }
+        Ok(FileSource {
+            path: String::from(path),
+            format: InputFormat::Csv,
+            encoding: Encoding::Utf8,
+            table_options: TableOptions::default(),
+            lines: LineReader::open(path, None)?,
+            line: String::new(),
+        })
+    }
+
+    pub fn with_format(mut self, format: InputFormat) -> FileSource {
+        self.format = format;
+        self.lines.set_format(format);
+        self
+    }
+
+    pub fn with_encoding(mut self, encoding: Encoding) -> FileSource {
+        self.encoding = encoding;
+        self.lines.set_encoding(encoding);
+        self
+    }
+
+    // Which columns are converted into items, if the format is a table.
+    pub fn with_table_options(mut self, options: TableOptions) -> FileSource {
+        self.lines.set_table_options(&options);
+        self.table_options = options;
+        self
+    }
+}
+
+impl Iterator for FileSource {
+    type Item = Result<Vec<String>, ArmError>;
+    fn next(&mut self) -> Option<Result<Vec<String>, ArmError>> {
+        Some(
+            self.lines
+                .read_items(&mut self.line)?
+                .map(|(items, _)| items),
+        )
+    }
+}
+
+impl TransactionSource for FileSource {
+    fn rescan(&mut self) -> Result<(), ArmError> {
+        self.lines = LineReader::open(&self.path, None)?;
+        self.lines.set_format(self.format);
+        self.lines.set_encoding(self.encoding);
+        self.lines.set_table_options(&self.table_options);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FileSource, TransactionSource};
+    use std::fs;
+    use test_util::TempFile;
+
+    #[test]
+    fn test_file_source() {
+        let path = TempFile::new("file-source.csv");
+        fs::write(&path, "a, b\n\"c,d\"\n").unwrap();
+        let mut source = FileSource::open(path.path()).unwrap();
+        let first: Vec<Vec<String>> = source.by_ref().map(|t| t.unwrap()).collect();
+        assert_eq!(first, vec![vec!["a", "b"], vec!["c,d"]]);
+        source.rescan().unwrap();
+        let second: Vec<Vec<String>> = source.map(|t| t.unwrap()).collect();
+        assert_eq!(second, first);
+        assert!(FileSource::open("-").is_err());
+    }
+}
@@ -0,0 +1,452 @@
+// Copyright 2018 Chris Pearce
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use command_line_args::TrendsArguments;
+use error::ArmError;
+use index::{IndexBackend, TransactionIndex};
+use item::Item;
+use itemizer::Itemizer;
+use miner::{AssociationRule, Miner, MinerParameters};
+use output::{join_items, OutputFile};
+use segments::compare_segments;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::str::FromStr;
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+// Length of the time windows a dataset is split into, parsed from a count
+// and a unit of s, m, h, d or w, e.g. "1w".
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Window {
+    pub seconds: i64,
+}
+
+impl FromStr for Window {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Window, String> {
+        let error = || format!("Invalid window {}; expected e.g. 12h, 1d or 1w", s);
+        let unit_start = s.find(|c: char| !c.is_ascii_digit()).ok_or_else(error)?;
+        let count: i64 = s[..unit_start].parse().map_err(|_| error())?;
+        let unit = match &s[unit_start..] {
+            "s" => 1,
+            "m" => 60,
+            "h" => 60 * 60,
+            "d" => SECONDS_PER_DAY,
+            "w" => 7 * SECONDS_PER_DAY,
+            _ => return Err(error()),
+        };
+        if count <= 0 {
+            return Err(error());
+        }
+        Ok(Window {
+            seconds: count * unit,
+        })
+    }
+}
+
+// Days since 1970-01-01 of a date in the proleptic Gregorian calendar.
+// From Howard Hinnant's date algorithms.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+// Inverse of days_from_civil; returns (year, month, day).
+pub fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+// Parses a timestamp in Unix seconds, or as a UTC date and optional time,
+// "YYYY-MM-DD", "YYYY-MM-DD HH:MM" or "YYYY-MM-DDTHH:MM:SS". Returns Unix
+// seconds.
+pub fn parse_timestamp(s: &str) -> Option<i64> {
+    if let Ok(seconds) = s.parse::<i64>() {
+        return Some(seconds);
+    }
+    let mut parts = s.splitn(2, ['T', ' ']);
+    let date: Vec<i64> = parts
+        .next()?
+        .split('-')
+        .map(|part| part.parse().ok())
+        .collect::<Option<_>>()?;
+    let time: Vec<i64> = match parts.next() {
+        Some(time) => time
+            .split(':')
+            .map(|part| part.parse().ok())
+            .collect::<Option<_>>()?,
+        None => vec![],
+    };
+    if date.len() != 3
+        || !(1..=12).contains(&date[1])
+        || !(1..=31).contains(&date[2])
+        || time.len() > 3
+        || (!time.is_empty() && time.len() < 2)
+    {
+        return None;
+    }
+    let time_of_day = time
+        .iter()
+        .zip(&[60 * 60, 60, 1])
+        .map(|(value, unit)| value * unit)
+        .sum::<i64>();
+    Some(days_from_civil(date[0], date[1], date[2]) * SECONDS_PER_DAY + time_of_day)
+}
+
+// Formats the start of a window as a date, with a time if it doesn't start
+// at midnight.
+fn format_window_start(seconds: i64) -> String {
+    let (year, month, day) = civil_from_days(seconds.div_euclid(SECONDS_PER_DAY));
+    let time_of_day = seconds.rem_euclid(SECONDS_PER_DAY);
+    if time_of_day == 0 {
+        format!("{:04}-{:02}-{:02}", year, month, day)
+    } else {
+        format!(
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            year,
+            month,
+            day,
+            time_of_day / 3600,
+            time_of_day % 3600 / 60,
+            time_of_day % 60
+        )
+    }
+}
+
+// Reads a dataset in which column timestamp_column of each line holds the
+// line's timestamp, and the other columns are the transaction's items.
+// Returns the transactions in each window, keyed by the window's start.
+pub fn read_windows(
+    path: &str,
+    timestamp_column: usize,
+    window: Window,
+) -> Result<BTreeMap<i64, Vec<Vec<String>>>, ArmError> {
+    let file = File::open(path).map_err(|e| ArmError::io(path, e))?;
+    let mut windows: BTreeMap<i64, Vec<Vec<String>>> = BTreeMap::new();
+    for (index, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|e| ArmError::io(path, e))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let mut items: Vec<String> = line.split(',').map(|s| String::from(s.trim())).collect();
+        let timestamp = if timestamp_column < items.len() {
+            parse_timestamp(&items.remove(timestamp_column))
+        } else {
+            None
+        };
+        let timestamp = timestamp.ok_or_else(|| {
+            ArmError::parse(
+                path,
+                index + 1,
+                &format!("expected a timestamp in column {}", timestamp_column),
+            )
+        })?;
+        let start = timestamp.div_euclid(window.seconds) * window.seconds;
+        windows.entry(start).or_default().push(items);
+    }
+    Ok(windows)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Trend {
+    Emerging,
+    Fading,
+    Stable,
+}
+
+impl Trend {
+    fn name(self) -> &'static str {
+        match self {
+            Trend::Emerging => "emerging",
+            Trend::Fading => "fading",
+            Trend::Stable => "stable",
+        }
+    }
+}
+
+// Returns the least squares slope of supports against window number,
+// relative to the mean support, so that it's the fraction of its average
+// support a rule gains or loses per window.
+fn relative_slope(supports: &[f64]) -> f64 {
+    let n = supports.len() as f64;
+    let mean_x = (n - 1.0) / 2.0;
+    let mean_y = supports.iter().sum::<f64>() / n;
+    let (mut covariance, mut variance) = (0.0, 0.0);
+    for (x, &y) in supports.iter().enumerate() {
+        let dx = x as f64 - mean_x;
+        covariance += dx * (y - mean_y);
+        variance += dx * dx;
+    }
+    if variance == 0.0 || mean_y == 0.0 {
+        return 0.0;
+    }
+    covariance / variance / mean_y
+}
+
+fn classify(slope: f64, threshold: f64) -> Trend {
+    if slope > threshold {
+        Trend::Emerging
+    } else if slope < -threshold {
+        Trend::Fading
+    } else {
+        Trend::Stable
+    }
+}
+
+// A rule's support and confidence in every window, and its trend.
+pub struct RuleSeries<'a> {
+    pub antecedent: &'a [String],
+    pub consequent: &'a [String],
+    pub supports: Vec<f64>,
+    pub confidences: Vec<f64>,
+    pub slope: f64,
+    pub trend: Trend,
+}
+
+fn itemize(items: &[String], itemizer: &mut Itemizer) -> Vec<Item> {
+    let mut items: Vec<Item> = items
+        .iter()
+        .filter(|item| !item.is_empty())
+        .map(|item| itemizer.id_of(item))
+        .collect();
+    items.sort();
+    items.dedup();
+    items
+}
+
+// Aligns the rules mined in each window, and counts each rule's support and
+// confidence in every window, including windows in which it wasn't mined.
+pub fn rule_series<'a>(
+    windows: &[&[Vec<String>]],
+    rules: &'a [Vec<AssociationRule>],
+    trend_threshold: f64,
+) -> Vec<RuleSeries<'a>> {
+    let mut itemizer = Itemizer::new();
+    let indexes: Vec<Box<dyn TransactionIndex>> = windows
+        .iter()
+        .map(|transactions| {
+            let mut index = IndexBackend::TidList.new_index();
+            for transaction in transactions.iter() {
+                let transaction = itemize(transaction, &mut itemizer);
+                if !transaction.is_empty() {
+                    index.insert(&transaction, 1);
+                }
+            }
+            index.finish();
+            index
+        })
+        .collect();
+
+    compare_segments(rules)
+        .into_iter()
+        .map(|comparison| {
+            let antecedent = itemize(comparison.antecedent, &mut itemizer);
+            let mut items = antecedent.clone();
+            items.extend(itemize(comparison.consequent, &mut itemizer));
+            let (mut supports, mut confidences) = (vec![], vec![]);
+            for index in &indexes {
+                let count = index.count(&items) as f64;
+                let antecedent_count = index.count(&antecedent) as f64;
+                let num_transactions = index.transaction_count() as f64;
+                supports.push(if num_transactions > 0.0 {
+                    count / num_transactions
+                } else {
+                    0.0
+                });
+                confidences.push(if antecedent_count > 0.0 {
+                    count / antecedent_count
+                } else {
+                    0.0
+                });
+            }
+            let slope = relative_slope(&supports);
+            RuleSeries {
+                antecedent: comparison.antecedent,
+                consequent: comparison.consequent,
+                supports,
+                confidences,
+                slope,
+                trend: classify(slope, trend_threshold),
+            }
+        })
+        .collect()
+}
+
+fn write_series<W: Write>(
+    output: &mut W,
+    window_names: &[String],
+    series: &[RuleSeries],
+) -> io::Result<()> {
+    write!(output, "Antecedent => Consequent,Trend,RelativeSlope")?;
+    for name in window_names {
+        write!(output, ",{0} Support,{0} Confidence", name)?;
+    }
+    writeln!(output)?;
+    for rule in series {
+        write!(
+            output,
+            "{} => {},{},{}",
+            join_items(rule.antecedent),
+            join_items(rule.consequent),
+            rule.trend.name(),
+            rule.slope
+        )?;
+        for (support, confidence) in rule.supports.iter().zip(&rule.confidences) {
+            write!(output, ",{},{}", support, confidence)?;
+        }
+        writeln!(output)?;
+    }
+    Ok(())
+}
+
+pub fn run_trends(args: &TrendsArguments) -> Result<(), ArmError> {
+    println!("Reading timestamped data set: {}", args.input_file_path);
+    let windows = read_windows(&args.input_file_path, args.timestamp_column, args.window)?;
+    println!("Read {} windows.", windows.len());
+
+    let mut window_names = vec![];
+    let mut rules: Vec<Vec<AssociationRule>> = vec![];
+    for (&start, transactions) in &windows {
+        let miner = Miner::from_transactions(
+            transactions.iter().cloned(),
+            MinerParameters {
+                min_support: args.min_support,
+                min_confidence: args.min_confidence,
+                min_lift: args.min_lift,
+                max_itemset_size: args.max_itemset_size,
+                ..MinerParameters::default()
+            },
+        );
+        let window_rules = miner.rules();
+        let name = format_window_start(start);
+        println!(
+            "Window {}: {} transactions, {} rules.",
+            name,
+            miner.num_transactions(),
+            window_rules.len()
+        );
+        window_names.push(name);
+        rules.push(window_rules);
+    }
+
+    let transactions: Vec<&[Vec<String>]> = windows.values().map(|t| t.as_slice()).collect();
+    let series = rule_series(&transactions, &rules, args.trend_threshold);
+    let write = || -> io::Result<()> {
+        let mut output = OutputFile::create(&args.output_path, None)?;
+        write_series(&mut output, &window_names, &series)?;
+        output.finish()
+    };
+    write().map_err(|e| ArmError::io(&args.output_path, e))?;
+
+    let count = |trend: Trend| series.iter().filter(|rule| rule.trend == trend).count();
+    println!(
+        "{} rules: {} emerging, {} fading, {} stable.",
+        series.len(),
+        count(Trend::Emerging),
+        count(Trend::Fading),
+        count(Trend::Stable)
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        format_window_start, parse_timestamp, read_windows, relative_slope, rule_series, Trend,
+        Window,
+    };
+    use miner::{Miner, MinerParameters};
+    use std::fs;
+    use test_util::TempFile;
+
+    #[test]
+    fn test_timestamps() {
+        assert_eq!("1w".parse(), Ok(Window { seconds: 604_800 }));
+        assert_eq!("12h".parse(), Ok(Window { seconds: 43_200 }));
+        assert!("w".parse::<Window>().is_err());
+        assert!("0d".parse::<Window>().is_err());
+        assert_eq!(parse_timestamp("86400"), Some(86_400));
+        assert_eq!(parse_timestamp("1970-01-02"), Some(86_400));
+        assert_eq!(parse_timestamp("2024-03-01T01:02:03"), Some(1_709_254_923));
+        assert_eq!(parse_timestamp("2024-03-01 01:02"), Some(1_709_254_920));
+        assert_eq!(parse_timestamp("2024-13-01"), None);
+        assert_eq!(parse_timestamp("yesterday"), None);
+        assert_eq!(format_window_start(1_709_251_200), "2024-03-01");
+        assert_eq!(format_window_start(1_709_254_923), "2024-03-01T01:02:03");
+        assert_eq!(format_window_start(-86_400), "1969-12-31");
+    }
+
+    #[test]
+    fn test_rule_series() {
+        let path = TempFile::new("trends.csv");
+        fs::write(
+            &path,
+            "2024-01-01,a,b\n2024-01-02,a,c\n2024-01-08,a,b\n2024-01-09,a,b\n\
+             2024-01-15,a,b\n2024-01-16,a,b\n2024-01-17,c\n",
+        )
+        .unwrap();
+        // 2024-01-01 is a Monday, but windows start on Thursdays, like the
+        // Unix epoch.
+        let windows = read_windows(path.path(), 0, "1d".parse().unwrap()).unwrap();
+        assert_eq!(windows.len(), 7);
+        let windows = read_windows(path.path(), 0, "1w".parse().unwrap()).unwrap();
+        let sizes: Vec<usize> = windows.values().map(|t| t.len()).collect();
+        assert_eq!(sizes, vec![2, 2, 3]);
+
+        let transactions: Vec<&[Vec<String>]> = windows.values().map(|t| t.as_slice()).collect();
+        let rules: Vec<_> = transactions
+            .iter()
+            .map(|transactions| {
+                let params = MinerParameters {
+                    min_support: 0.1,
+                    min_confidence: 0.1,
+                    ..MinerParameters::default()
+                };
+                Miner::from_transactions(transactions.iter().cloned(), params).rules()
+            })
+            .collect();
+        let series = rule_series(&transactions, &rules, 0.1);
+        let a_to_b = series
+            .iter()
+            .find(|s| s.antecedent == ["a"] && s.consequent == ["b"])
+            .unwrap();
+        assert_eq!(a_to_b.supports, vec![0.5, 1.0, 2.0 / 3.0]);
+        assert_eq!(a_to_b.confidences, vec![0.5, 1.0, 1.0]);
+        // Support rises by about 12% of its mean per window.
+        assert!((a_to_b.slope - 0.115_384_6).abs() < 1e-6);
+        assert_eq!(a_to_b.trend, Trend::Emerging);
+
+        assert!((relative_slope(&[0.1, 0.2, 0.3]) - 0.5).abs() < 1e-9);
+        assert_eq!(relative_slope(&[0.2, 0.2]), 0.0);
+    }
+}
@@ -0,0 +1,318 @@
+// Copyright 2018 Chris Pearce
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use command_line_args::TuiArguments;
+use error::ArmError;
+#[cfg(feature = "tui")]
+use explorer::Explorer;
+#[cfg(feature = "tui")]
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
+#[cfg(feature = "tui")]
+use ratatui::layout::{Constraint, Layout};
+#[cfg(feature = "tui")]
+use ratatui::style::{Modifier, Style};
+#[cfg(feature = "tui")]
+use ratatui::widgets::{Block, Cell, Paragraph, Row, Table, TableState};
+#[cfg(feature = "tui")]
+use ratatui::{DefaultTerminal, Frame};
+#[cfg(feature = "tui")]
+use rule_file::read_rules;
+#[cfg(feature = "tui")]
+use rules_from_itemsets::read_itemset_records;
+use std::io;
+
+// What keys typed do: move around the table, edit the filter, or edit the
+// path to export the view to.
+#[cfg(feature = "tui")]
+enum Mode {
+    Browse,
+    Filter,
+    Export(String),
+}
+
+#[cfg(feature = "tui")]
+struct App {
+    explorer: Explorer,
+    selected: Option<usize>,
+    // Index of the first row on screen. Only the rows on screen are
+    // rendered, as files can have millions of rules.
+    offset: usize,
+    // Index of the selected item among the selected row's items, which
+    // Enter drills into.
+    item: usize,
+    mode: Mode,
+    // Result of the last export, shown until the next key.
+    status: Option<String>,
+    quit: bool,
+}
+
+#[cfg(feature = "tui")]
+const HELP: &str = "q quit  / filter  1-9 sort  \u{2190}\u{2192} item  Enter drill  \
+                    Esc back  e export";
+
+#[cfg(feature = "tui")]
+impl App {
+    fn new(explorer: Explorer) -> App {
+        let selected = if explorer.is_empty() { None } else { Some(0) };
+        App {
+            explorer,
+            selected,
+            offset: 0,
+            item: 0,
+            mode: Mode::Browse,
+            status: None,
+            quit: false,
+        }
+    }
+
+    fn selected_item(&self) -> Option<&str> {
+        let row = self.explorer.row(self.selected?);
+        row.items.get(self.item).map(|item| item.as_str())
+    }
+
+    // Selects the first row after the view changes.
+    fn reset_selection(&mut self) {
+        self.selected = if self.explorer.is_empty() {
+            None
+        } else {
+            Some(0)
+        };
+        self.offset = 0;
+        self.item = 0;
+    }
+
+    fn move_selection(&mut self, delta: isize) {
+        if self.explorer.is_empty() {
+            return;
+        }
+        let last = self.explorer.len() as isize - 1;
+        let current = self.selected.unwrap_or(0) as isize;
+        self.selected = Some((current + delta).clamp(0, last) as usize);
+        self.item = 0;
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) {
+        self.status = None;
+        match self.mode {
+            Mode::Browse => self.browse(key.code),
+            Mode::Filter => {
+                let mut filter = String::from(self.explorer.filter());
+                match key.code {
+                    KeyCode::Enter | KeyCode::Esc => self.mode = Mode::Browse,
+                    KeyCode::Backspace => {
+                        filter.pop();
+                    }
+                    KeyCode::Char(c) => filter.push(c),
+                    _ => {}
+                }
+                if filter != self.explorer.filter() {
+                    self.explorer.set_filter(&filter);
+                    self.reset_selection();
+                }
+            }
+            Mode::Export(ref mut path) => match key.code {
+                KeyCode::Enter => {
+                    let path = path.clone();
+                    self.status = Some(match self.explorer.export(&path) {
+                        Ok(()) => format!("Wrote {} rows to {}", self.explorer.len(), path),
+                        Err(e) => format!("Error: {}", e),
+                    });
+                    self.mode = Mode::Browse;
+                }
+                KeyCode::Esc => self.mode = Mode::Browse,
+                KeyCode::Backspace => {
+                    path.pop();
+                }
+                KeyCode::Char(c) => path.push(c),
+                _ => {}
+            },
+        }
+    }
+
+    fn browse(&mut self, code: KeyCode) {
+        let page = 20;
+        match code {
+            KeyCode::Char('q') => self.quit = true,
+            KeyCode::Char('/') => self.mode = Mode::Filter,
+            KeyCode::Char('e') => self.mode = Mode::Export(String::new()),
+            KeyCode::Char(c @ '1'..='9') => {
+                let column = c as usize - '1' as usize;
+                if column < self.explorer.columns().len() {
+                    self.explorer.sort_by(column);
+                    self.reset_selection();
+                }
+            }
+            KeyCode::Up => self.move_selection(-1),
+            KeyCode::Down => self.move_selection(1),
+            KeyCode::PageUp => self.move_selection(-page),
+            KeyCode::PageDown => self.move_selection(page),
+            KeyCode::Home => self.move_selection(isize::MIN / 2),
+            KeyCode::End => self.move_selection(isize::MAX / 2),
+            KeyCode::Left => self.item = self.item.saturating_sub(1),
+            KeyCode::Right => {
+                if let Some(selected) = self.selected {
+                    let num_items = self.explorer.row(selected).items.len();
+                    self.item = (self.item + 1).min(num_items.saturating_sub(1));
+                }
+            }
+            KeyCode::Enter => {
+                if let Some(item) = self.selected_item().map(String::from) {
+                    self.explorer.drill(&item);
+                    self.reset_selection();
+                }
+            }
+            KeyCode::Esc | KeyCode::Backspace if self.explorer.undrill() => {
+                self.reset_selection();
+            }
+            _ => {}
+        }
+    }
+
+    fn title(&self) -> String {
+        let mut title = format!(
+            " {} of {} rows ",
+            self.explorer.len(),
+            self.explorer.num_rows()
+        );
+        if !self.explorer.drilled().is_empty() {
+            title += &format!("containing {} ", self.explorer.drilled().join(", "));
+        }
+        if let Some((column, descending)) = self.explorer.sort() {
+            title += &format!(
+                "by {} {} ",
+                self.explorer.columns()[column],
+                if descending { "\u{2193}" } else { "\u{2191}" }
+            );
+        }
+        title
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [filter_area, table_area, status_area] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Min(1),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        let editing = Style::new().add_modifier(Modifier::BOLD);
+        let filter_style = match self.mode {
+            Mode::Filter => editing,
+            _ => Style::new(),
+        };
+        frame.render_widget(
+            Paragraph::new(self.explorer.filter())
+                .style(filter_style)
+                .block(Block::bordered().title(" Filter (/) ")),
+            filter_area,
+        );
+
+        let header = Row::new(
+            self.explorer
+                .columns()
+                .iter()
+                .enumerate()
+                .map(|(i, name)| Cell::from(format!("{} {}", i + 1, name))),
+        )
+        .style(editing);
+        let widths: Vec<Constraint> = (0..self.explorer.columns().len())
+            .map(|column| {
+                if self.explorer.is_numeric(column) {
+                    Constraint::Length(12)
+                } else {
+                    Constraint::Fill(1)
+                }
+            })
+            .collect();
+        // Scrolls so the selected row is on screen, below the borders and
+        // header.
+        let height = (table_area.height as usize).saturating_sub(3).max(1);
+        if let Some(selected) = self.selected {
+            if selected < self.offset {
+                self.offset = selected;
+            } else if selected >= self.offset + height {
+                self.offset = selected + 1 - height;
+            }
+        }
+        let end = self.explorer.len().min(self.offset + height);
+        let rows: Vec<Row> = (self.offset..end)
+            .map(|index| {
+                let cells = &self.explorer.row(index).cells;
+                Row::new(cells.iter().map(|cell| Cell::from(cell.as_str())))
+            })
+            .collect();
+        let table = Table::new(rows, widths)
+            .header(header)
+            .block(Block::bordered().title(self.title()))
+            .row_highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        let mut state = TableState::default()
+            .with_selected(self.selected.map(|selected| selected - self.offset));
+        frame.render_stateful_widget(table, table_area, &mut state);
+
+        let status = match (&self.mode, &self.status, self.selected_item()) {
+            (Mode::Export(ref path), _, _) => format!("Export view to: {}", path),
+            (_, Some(ref status), _) => status.clone(),
+            (_, None, Some(item)) => format!("Item: {}  |  {}", item, HELP),
+            (_, None, None) => String::from(HELP),
+        };
+        frame.render_widget(Paragraph::new(status), status_area);
+    }
+}
+
+#[cfg(feature = "tui")]
+fn run_app(terminal: &mut DefaultTerminal, app: &mut App) -> io::Result<()> {
+    while !app.quit {
+        terminal.draw(|frame| app.draw(frame))?;
+        if let Event::Key(key) = event::read()? {
+            if key.kind == KeyEventKind::Press {
+                app.handle_key(key);
+            }
+        }
+    }
+    Ok(())
+}
+
+// Explores the rows of explorer interactively, until the user quits.
+#[cfg(feature = "tui")]
+fn explore(explorer: Explorer) -> io::Result<()> {
+    let mut terminal = ratatui::try_init()?;
+    let result = run_app(&mut terminal, &mut App::new(explorer));
+    ratatui::restore();
+    result
+}
+
+#[cfg(feature = "tui")]
+pub fn run_tui(args: &TuiArguments) -> Result<(), ArmError> {
+    let (path, explorer) = match (&args.rules_path, &args.itemsets_path) {
+        (Some(path), _) => (path, Explorer::from_rules(read_rules(path)?)),
+        (None, Some(path)) => (
+            path,
+            Explorer::from_itemsets(read_itemset_records(path, args.itemsets_format)?),
+        ),
+        (None, None) => unreachable!("--rules or --itemsets is required"),
+    };
+    explore(explorer).map_err(|e| ArmError::io(path, e))
+}
+
+#[cfg(not(feature = "tui"))]
+pub fn run_tui(args: &TuiArguments) -> Result<(), ArmError> {
+    let path = args.rules_path.as_ref().or(args.itemsets_path.as_ref());
+    Err(ArmError::io(
+        path.map_or("", |path| path.as_str()),
+        io::Error::new(
+            io::ErrorKind::Unsupported,
+            "arm tui requires building with --features tui",
+        ),
+    ))
+}
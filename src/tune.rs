@@ -0,0 +1,148 @@
+// Copyright 2018 Chris Pearce
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use cancellation::CancellationToken;
+use fptree::{fp_growth_with_progress, FPGrowthParameters, FPTree};
+use generate_rules::{count_rules, RuleParameters};
+use mining::duration_as_ms;
+use std::time::Instant;
+
+// Number of rules generated from the itemsets of fptree with at least
+// min_count.
+fn rules_at(
+    fptree: &FPTree,
+    params: &FPGrowthParameters,
+    rule_params: &RuleParameters,
+    num_transactions: u32,
+    min_count: u32,
+) -> usize {
+    let timer = Instant::now();
+    let params = FPGrowthParameters {
+        min_count,
+        max_itemset_size: params.max_itemset_size,
+        cancellation: CancellationToken::new(),
+        cross_support: params.cross_support.clone(),
+        item_min_counts: None,
+    };
+    let itemsets = fp_growth_with_progress(fptree, &params, num_transactions).itemsets;
+    let num_rules = count_rules(&itemsets, num_transactions, rule_params);
+    println!(
+        "Minimum count {} gives {} rules, found in {} ms.",
+        min_count,
+        num_rules,
+        duration_as_ms(&timer.elapsed())
+    );
+    num_rules
+}
+
+// Finds the minimum count, at least params.min_count, at which mining
+// fptree generates the number of rules nearest target_rules. Raising the
+// minimum count never adds rules, so the count is binary searched for,
+// mining the same tree each time; the tree must hold every item with at
+// least params.min_count. Returns the minimum count and its number of
+// rules.
+pub fn tune_min_count(
+    fptree: &FPTree,
+    params: &FPGrowthParameters,
+    rule_params: &RuleParameters,
+    num_transactions: u32,
+    target_rules: usize,
+) -> (u32, usize) {
+    let rules = |min_count| rules_at(fptree, params, rule_params, num_transactions, min_count);
+    let mut low = (params.min_count, rules(params.min_count));
+    if low.1 <= target_rules {
+        return low;
+    }
+    // No itemset has a count above the number of transactions.
+    let mut high = (num_transactions + 1, 0);
+    // Invariant: low gives more than target_rules, high at most.
+    while high.0 - low.0 > 1 {
+        let middle = low.0 + (high.0 - low.0) / 2;
+        let num_rules = rules(middle);
+        if num_rules <= target_rules {
+            high = (middle, num_rules);
+        } else {
+            low = (middle, num_rules);
+        }
+    }
+    if low.1 - target_rules < target_rules - high.1 {
+        low
+    } else {
+        high
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{rules_at, tune_min_count};
+    use cancellation::CancellationToken;
+    use fptree::{FPGrowthParameters, FPTree};
+    use generate_rules::RuleParameters;
+    use item::Item;
+    use rule::DEFAULT_LAPLACE_K;
+
+    #[test]
+    fn test_tune_min_count() {
+        // Item i is in transactions which are multiples of i, so higher
+        // items are rarer.
+        let mut fptree = FPTree::new();
+        for t in 1..=60u32 {
+            let transaction: Vec<Item> = (1..=6).filter(|i| t % i == 0).map(Item::from).collect();
+            fptree.insert(&transaction, 1);
+        }
+        let params = FPGrowthParameters {
+            min_count: 1,
+            max_itemset_size: None,
+            cancellation: CancellationToken::new(),
+            cross_support: None,
+            item_min_counts: None,
+        };
+        let rule_params = RuleParameters {
+            min_confidence: 0.0,
+            min_confidence_by_length: None,
+            min_lift: None,
+            max_lift: None,
+            min_confidence_lower_bound: None,
+            laplace_k: DEFAULT_LAPLACE_K,
+            min_laplace: None,
+            min_added_value: None,
+            min_gini_gain: None,
+            min_info_gain: None,
+            min_odds_ratio: None,
+            min_yules_q: None,
+            min_phi: None,
+            filter: None,
+            min_itemset_size: 0,
+            cancellation: CancellationToken::new(),
+            item_min_counts: None,
+            target_consequent: None,
+        };
+        // Indexed by minimum count.
+        let rules: Vec<usize> = (0..=61)
+            .map(|min_count| rules_at(&fptree, &params, &rule_params, 60, min_count))
+            .collect();
+        assert!(rules[1] > 20);
+
+        for &target in &[0, 5, 20, rules[1], rules[1] * 2] {
+            let (min_count, num_rules) = tune_min_count(&fptree, &params, &rule_params, 60, target);
+            assert_eq!(num_rules, rules[min_count as usize]);
+            // No other minimum count gives a number of rules nearer the
+            // target.
+            let distance = |n: usize| (n as i64 - target as i64).abs();
+            assert!(rules[1..]
+                .iter()
+                .all(|&n| distance(num_rules) <= distance(n)));
+        }
+    }
+}
@@ -101,10 +101,16 @@ where
 }
 
 #[cfg(test)]
+#[allow(
+    clippy::needless_borrow,
+    clippy::needless_borrowed_reference,
+    clippy::type_complexity,
+    clippy::useless_vec
+)]
 mod tests {
     use item::Item;
     fn to_item_vec(nums: &[u32]) -> Vec<Item> {
-        nums.iter().map(|i| Item::with_id(*i)).collect()
+        nums.iter().map(|i| Item::from(*i)).collect()
     }
 
     #[test]
@@ -139,7 +145,7 @@ mod tests {
         .map(|&(ref a, v, (ref b, ref c))| {
             (
                 to_item_vec(a),
-                Item::with_id(v),
+                Item::from(v),
                 (to_item_vec(b), to_item_vec(c)),
             )
         })
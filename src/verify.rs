@@ -0,0 +1,114 @@
+// Copyright 2018 Chris Pearce
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use fptree::ItemSet;
+use index::TransactionIndex;
+use item::Item;
+use itemizer::Itemizer;
+use output::join_items;
+use rand::seq::index::sample;
+use rand::thread_rng;
+use rule::Rule;
+use vec_sets::union;
+
+// Returns the indices of the elements to verify; either all of them, or a
+// random sample of them if a sample size is specified.
+fn indices_to_verify(len: usize, sample_size: Option<usize>) -> Vec<usize> {
+    match sample_size {
+        Some(n) if n < len => {
+            let mut indices = sample(&mut thread_rng(), len, n).into_vec();
+            indices.sort();
+            indices
+        }
+        _ => (0..len).collect(),
+    }
+}
+
+pub fn items_to_string(items: &[Item], itemizer: &Itemizer) -> String {
+    let names: Vec<&str> = items.iter().map(|&item| itemizer.str_of(item)).collect();
+    join_items(&names)
+}
+
+fn approx_eq(a: f64, b: f64) -> bool {
+    (a - b).abs() <= 1e-9 * 1.0f64.max(b.abs())
+}
+
+// Recounts the support of itemsets using the index, and prints any
+// itemsets whose count differs. Returns the number of discrepancies.
+pub fn verify_itemsets(
+    itemsets: &[ItemSet],
+    index: &dyn TransactionIndex,
+    sample_size: Option<usize>,
+    itemizer: &Itemizer,
+) -> usize {
+    let mut num_discrepancies = 0;
+    for i in indices_to_verify(itemsets.len(), sample_size) {
+        let itemset = &itemsets[i];
+        let count = index.count(&itemset.items) as u32;
+        if count != itemset.count {
+            println!(
+                "Itemset [{}] reported count {} but recounted {}.",
+                items_to_string(&itemset.items, itemizer),
+                itemset.count,
+                count
+            );
+            num_discrepancies += 1;
+        }
+    }
+    num_discrepancies
+}
+
+// Recomputes the support, confidence, and lift of rules using the index,
+// and prints any rules whose stats differ. Returns the number of
+// discrepancies.
+pub fn verify_rules(
+    rules: &[Vec<Rule>],
+    index: &dyn TransactionIndex,
+    sample_size: Option<usize>,
+    itemizer: &Itemizer,
+) -> usize {
+    let rules: Vec<&Rule> = rules.iter().flat_map(|chunk| chunk.iter()).collect();
+    let n = index.transaction_count() as f64;
+    let mut num_discrepancies = 0;
+    for i in indices_to_verify(rules.len(), sample_size) {
+        let rule = rules[i];
+        let both = union(&rule.antecedent, &rule.consequent);
+        let count = index.count(&both);
+        let support = count as f64 / n;
+        let antecedent_support = index.count(&rule.antecedent) as f64 / n;
+        let consequent_support = index.count(&rule.consequent) as f64 / n;
+        let confidence = support / antecedent_support;
+        let lift = confidence / consequent_support;
+        if count as u32 != rule.count
+            || !approx_eq(rule.support, support)
+            || !approx_eq(rule.confidence, confidence)
+            || !approx_eq(rule.lift, lift)
+        {
+            println!(
+                "Rule {} => {} reported (confidence={}, lift={}, support={}) \
+                 but recounted (confidence={}, lift={}, support={}).",
+                items_to_string(&rule.antecedent, itemizer),
+                items_to_string(&rule.consequent, itemizer),
+                rule.confidence,
+                rule.lift,
+                rule.support,
+                confidence,
+                lift,
+                support
+            );
+            num_discrepancies += 1;
+        }
+    }
+    num_discrepancies
+}